@@ -0,0 +1,2 @@
+-- Soft-delete flag for topic mappings; archived topics are hidden from lookups
+ALTER TABLE topic_mappings ADD COLUMN archived INTEGER NOT NULL DEFAULT 0;
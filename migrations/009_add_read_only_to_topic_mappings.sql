@@ -0,0 +1,2 @@
+-- Observer flag for topic mappings; read-only topics mirror output but ignore prompts
+ALTER TABLE topic_mappings ADD COLUMN read_only INTEGER NOT NULL DEFAULT 0;
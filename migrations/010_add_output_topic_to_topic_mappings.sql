@@ -0,0 +1,2 @@
+-- Optional topic that receives the agent's output instead of the input topic
+ALTER TABLE topic_mappings ADD COLUMN output_topic_id INTEGER;
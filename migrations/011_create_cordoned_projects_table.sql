@@ -0,0 +1,5 @@
+-- Projects under maintenance; their instances are not spawned or restarted
+CREATE TABLE IF NOT EXISTS cordoned_projects (
+    project TEXT PRIMARY KEY,
+    cordoned_at INTEGER NOT NULL
+);
@@ -0,0 +1,2 @@
+-- Modification time of the project's system prompt file last sent to the session
+ALTER TABLE topic_mappings ADD COLUMN system_prompt_mtime INTEGER;
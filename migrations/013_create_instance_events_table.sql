@@ -0,0 +1,11 @@
+-- Instance lifecycle events (spawned, stopped, crashed, restarted) for /stats
+
+CREATE TABLE IF NOT EXISTS instance_events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    instance_id TEXT NOT NULL,
+    event TEXT NOT NULL,
+    timestamp INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_instance_events_timestamp ON instance_events(timestamp);
+CREATE INDEX IF NOT EXISTS idx_instance_events_instance_id ON instance_events(instance_id);
@@ -0,0 +1,17 @@
+-- Response ratings collected through the 👍/👎 buttons (COLLECT_FEEDBACK).
+-- A row is created when the buttons are posted and rated when one is pressed.
+
+CREATE TABLE IF NOT EXISTS feedback (
+    chat_id INTEGER NOT NULL,
+    prompt_message_id INTEGER NOT NULL,
+    session_id TEXT NOT NULL,
+    message_id TEXT NOT NULL,
+    rating TEXT,
+    user_id INTEGER,
+    created_at INTEGER NOT NULL,
+    rated_at INTEGER,
+    PRIMARY KEY (chat_id, prompt_message_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_feedback_rated_at ON feedback(rated_at);
+CREATE INDEX IF NOT EXISTS idx_feedback_message_id ON feedback(message_id);
@@ -0,0 +1,9 @@
+-- Topics that were mid-response at shutdown; reattached on the next start
+CREATE TABLE IF NOT EXISTS pending_generations (
+    chat_id INTEGER NOT NULL,
+    topic_id INTEGER NOT NULL,
+    session_id TEXT NOT NULL,
+    last_message_id INTEGER,
+    saved_at INTEGER NOT NULL,
+    PRIMARY KEY (chat_id, topic_id)
+);
@@ -0,0 +1,9 @@
+-- Output handed to Telegram but not yet confirmed sent; replayed on start
+CREATE TABLE IF NOT EXISTS outbound_queue (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    chat_id INTEGER NOT NULL,
+    topic_id INTEGER NOT NULL,
+    text TEXT NOT NULL,
+    reply_to INTEGER,
+    queued_at INTEGER NOT NULL
+);
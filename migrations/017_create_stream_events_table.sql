@@ -0,0 +1,14 @@
+-- Per-topic conversation log (prompts sent from Telegram and the agent's
+-- streamed output), read back by /export
+
+CREATE TABLE IF NOT EXISTS stream_events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    chat_id INTEGER NOT NULL,
+    topic_id INTEGER NOT NULL,
+    session_id TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    content TEXT NOT NULL,
+    timestamp INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_stream_events_topic ON stream_events(chat_id, topic_id);
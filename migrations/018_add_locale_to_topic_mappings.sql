@@ -0,0 +1,2 @@
+-- Language code set with /lang; responses in the topic are asked to use it
+ALTER TABLE topic_mappings ADD COLUMN locale TEXT;
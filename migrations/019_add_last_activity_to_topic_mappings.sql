@@ -0,0 +1,2 @@
+-- When a prompt was last sent from the topic, in seconds since the epoch
+ALTER TABLE topic_mappings ADD COLUMN last_activity_at INTEGER;
@@ -0,0 +1,2 @@
+-- When the instance was started, in seconds since the epoch
+ALTER TABLE instances ADD COLUMN started_at INTEGER;
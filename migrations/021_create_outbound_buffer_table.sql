@@ -0,0 +1,10 @@
+-- Response text batched for a topic but not yet handed to the outbound queue;
+-- formatted and sent on start if the bot stopped before flushing it
+CREATE TABLE IF NOT EXISTS outbound_buffer (
+    chat_id INTEGER NOT NULL,
+    topic_id INTEGER NOT NULL,
+    text TEXT NOT NULL,
+    reply_to INTEGER,
+    updated_at INTEGER NOT NULL,
+    PRIMARY KEY (chat_id, topic_id)
+);
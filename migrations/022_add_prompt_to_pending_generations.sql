@@ -0,0 +1,2 @@
+-- The prompt a pending generation answers, resent when its response is lost
+ALTER TABLE pending_generations ADD COLUMN prompt TEXT;
@@ -0,0 +1,100 @@
+//! Request body size limit (`API_MAX_BODY_BYTES`).
+//!
+//! Bodies are rejected up front when `Content-Length` is over the limit and
+//! otherwise read incrementally, so an oversized chunked upload is cut off as
+//! soon as it crosses the limit instead of being buffered in full.
+
+use futures::{Stream, StreamExt};
+use std::fmt::Display;
+
+/// Errors reading a request body.
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+pub enum BodyError {
+    #[error("Request body exceeds the {limit} byte limit")]
+    TooLarge { limit: usize },
+    #[error("Failed to read request body: {0}")]
+    Read(String),
+}
+
+impl BodyError {
+    /// HTTP status code for this error: 413 for oversized bodies, 400 otherwise.
+    pub fn status_code(&self) -> u16 {
+        match self {
+            Self::TooLarge { .. } => 413,
+            Self::Read(_) => 400,
+        }
+    }
+}
+
+/// Reject a request whose declared `Content-Length` is over `limit`.
+pub fn check_content_length(content_length: Option<u64>, limit: usize) -> Result<(), BodyError> {
+    match content_length {
+        Some(len) if len > limit as u64 => Err(BodyError::TooLarge { limit }),
+        _ => Ok(()),
+    }
+}
+
+/// Collect a body stream, failing with [`BodyError::TooLarge`] as soon as more
+/// than `limit` bytes have been received.
+pub async fn read_body_limited<S, B, E>(mut body: S, limit: usize) -> Result<Vec<u8>, BodyError>
+where
+    S: Stream<Item = Result<B, E>> + Unpin,
+    B: AsRef<[u8]>,
+    E: Display,
+{
+    let mut buf = Vec::new();
+    while let Some(chunk) = body.next().await {
+        let chunk = chunk.map_err(|e| BodyError::Read(e.to_string()))?;
+        let chunk = chunk.as_ref();
+        if buf.len() + chunk.len() > limit {
+            return Err(BodyError::TooLarge { limit });
+        }
+        buf.extend_from_slice(chunk);
+    }
+    Ok(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::stream;
+
+    fn chunks(sizes: &[usize]) -> impl Stream<Item = Result<Vec<u8>, String>> + Unpin {
+        stream::iter(sizes.iter().map(|n| Ok(vec![b'x'; *n])).collect::<Vec<_>>())
+    }
+
+    #[tokio::test]
+    async fn test_oversized_body_returns_413() {
+        let err = read_body_limited(chunks(&[600, 600]), 1024)
+            .await
+            .unwrap_err();
+        assert_eq!(err, BodyError::TooLarge { limit: 1024 });
+        assert_eq!(err.status_code(), 413);
+    }
+
+    #[tokio::test]
+    async fn test_body_within_limit_is_read() {
+        let body = read_body_limited(chunks(&[512, 512]), 1024).await.unwrap();
+        assert_eq!(body.len(), 1024);
+    }
+
+    #[tokio::test]
+    async fn test_stream_error_returns_400() {
+        let body = stream::iter(vec![Ok(vec![1u8]), Err("connection reset".to_string())]);
+        let err = read_body_limited(body, 1024).await.unwrap_err();
+        assert_eq!(err.status_code(), 400);
+        assert!(err.to_string().contains("connection reset"));
+    }
+
+    #[test]
+    fn test_content_length_precheck() {
+        assert!(check_content_length(None, 1024).is_ok());
+        assert!(check_content_length(Some(1024), 1024).is_ok());
+        assert_eq!(
+            check_content_length(Some(1025), 1024)
+                .unwrap_err()
+                .status_code(),
+            413
+        );
+    }
+}
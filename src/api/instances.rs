@@ -0,0 +1,320 @@
+//! Instance management endpoints (`/api/instances`).
+
+use crate::orchestrator::manager::InstanceManager;
+use crate::types::instance::InstanceState;
+use serde::Serialize;
+use std::sync::Arc;
+use tracing::{debug, warn};
+
+/// Log lines returned when the request has no `tail` parameter.
+pub const DEFAULT_LOG_TAIL: usize = 200;
+
+/// Largest `tail` a request may ask for; larger values are capped.
+pub const MAX_LOG_TAIL: usize = 2000;
+
+/// Shared state for API handlers.
+#[derive(Clone)]
+pub struct AppState {
+    pub instance_manager: Arc<InstanceManager>,
+}
+
+/// Errors from instance endpoints.
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+pub enum InstanceApiError {
+    #[error("Instance not found: {0}")]
+    NotFound(String),
+    /// The cause is logged server-side and never sent to the client
+    #[error("Instance request failed")]
+    Internal,
+}
+
+impl InstanceApiError {
+    /// HTTP status code for this error: 404 for unknown instances, 500 otherwise.
+    pub fn status_code(&self) -> u16 {
+        match self {
+            Self::NotFound(_) => 404,
+            Self::Internal => 500,
+        }
+    }
+}
+
+/// Response body for `GET /api/instances/{id}`.
+#[derive(Debug, Clone, Serialize)]
+pub struct InstanceDetail {
+    pub id: String,
+    pub state: InstanceState,
+    pub project_path: String,
+    pub port: u16,
+    pub topic_id: i32,
+    /// Unix timestamps of the last start and stop
+    pub started_at: Option<i64>,
+    pub stopped_at: Option<i64>,
+    /// Whether the instance is running in this process; `false` means the
+    /// details come from the store only
+    pub live: bool,
+    /// Seconds since the instance last saw activity, if it is tracked
+    pub last_activity_secs: Option<u64>,
+}
+
+/// `GET /api/instances/{id}`: the instance's record with its live state.
+/// 404 when the instance is neither running nor stored.
+pub async fn get_instance(state: &AppState, id: &str) -> Result<InstanceDetail, InstanceApiError> {
+    debug!(instance_id = %id, "API get instance");
+    let live = state.instance_manager.get_instance(id).await.is_some();
+    let info = match state.instance_manager.get_instance_by_id(id).await {
+        Ok(Some(info)) => info,
+        Ok(None) => return Err(InstanceApiError::NotFound(id.to_string())),
+        Err(e) => {
+            warn!(instance_id = %id, error = %format!("{:#}", e), "API get instance failed");
+            return Err(InstanceApiError::Internal);
+        }
+    };
+    let last_activity_secs = state
+        .instance_manager
+        .activity_snapshot()
+        .await
+        .get(id)
+        .map(|idle| idle.as_secs());
+    Ok(InstanceDetail {
+        id: info.id,
+        state: info.state,
+        project_path: info.project_path,
+        port: info.port,
+        topic_id: info.topic_id,
+        started_at: info.started_at,
+        stopped_at: info.stopped_at,
+        live,
+        last_activity_secs,
+    })
+}
+
+/// `DELETE /api/instances/{id}`: stop the instance's container, release its
+/// port and delete its record. Success maps to 204 No Content.
+pub async fn delete_instance(state: &AppState, id: &str) -> Result<(), InstanceApiError> {
+    debug!(instance_id = %id, "API delete instance");
+    match state.instance_manager.remove_instance(id).await {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(InstanceApiError::NotFound(id.to_string())),
+        Err(e) => {
+            warn!(instance_id = %id, error = %format!("{:#}", e), "API delete instance failed");
+            Err(InstanceApiError::Internal)
+        }
+    }
+}
+
+/// Response body for `GET /api/instances/{id}/logs`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct InstanceLogs {
+    pub instance_id: String,
+    /// Number of lines requested after capping
+    pub tail: usize,
+    pub lines: Vec<String>,
+}
+
+impl InstanceLogs {
+    /// Plain-text body for clients that ask for `text/plain`.
+    pub fn to_plain_text(&self) -> String {
+        let mut text = self.lines.join("\n");
+        if !text.is_empty() {
+            text.push('\n');
+        }
+        text
+    }
+}
+
+/// Effective line count for a `tail` query parameter: the default when
+/// absent, capped at [`MAX_LOG_TAIL`].
+pub fn log_tail(tail: Option<usize>) -> usize {
+    tail.unwrap_or(DEFAULT_LOG_TAIL).min(MAX_LOG_TAIL)
+}
+
+/// `GET /api/instances/{id}/logs?tail=N`: the last lines of the instance's
+/// container output. 404 when the instance or its container is gone.
+pub async fn instance_logs(
+    state: &AppState,
+    id: &str,
+    tail: Option<usize>,
+) -> Result<InstanceLogs, InstanceApiError> {
+    let tail = log_tail(tail);
+    debug!(instance_id = %id, tail = tail, "API instance logs");
+    match state.instance_manager.instance_logs(id, tail).await {
+        Ok(Some(logs)) => Ok(InstanceLogs {
+            instance_id: id.to_string(),
+            tail,
+            lines: logs.lines().map(str::to_string).collect(),
+        }),
+        Ok(None) => Err(InstanceApiError::NotFound(id.to_string())),
+        Err(e) => {
+            warn!(instance_id = %id, error = %format!("{:#}", e), "API instance logs failed");
+            Err(InstanceApiError::Internal)
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::orchestrator::container::mock::{MockAction, MockRuntime};
+    use crate::orchestrator::port_pool::PortPool;
+    use crate::orchestrator::store::OrchestratorStore;
+    use crate::test_utils::test_config;
+    use crate::types::instance::InstanceInfo;
+    use tempfile::TempDir;
+
+    pub(crate) async fn create_test_state() -> (AppState, Arc<MockRuntime>, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        let config = test_config(temp_dir.path());
+        let store = OrchestratorStore::new(&config.orchestrator_db_path)
+            .await
+            .unwrap();
+        store
+            .save_instance(
+                &InstanceInfo {
+                    id: "inst_api".to_string(),
+                    state: InstanceState::Stopped,
+                    project_path: "/test/api".to_string(),
+                    port: 4101,
+                    pid: None,
+                    container_id: Some("container-api".to_string()),
+                    started_at: Some(1_700_000_000),
+                    stopped_at: None,
+                    topic_id: 7,
+                },
+                None,
+            )
+            .await
+            .unwrap();
+        let runtime = Arc::new(MockRuntime::new());
+        let manager = InstanceManager::new(config, store, PortPool::new(4100, 10), runtime.clone())
+            .await
+            .unwrap();
+        let state = AppState {
+            instance_manager: Arc::new(manager),
+        };
+        (state, runtime, temp_dir)
+    }
+
+    #[tokio::test]
+    async fn test_delete_instance_stops_container_and_removes_record() {
+        let (state, runtime, temp_dir) = create_test_state().await;
+
+        delete_instance(&state, "inst_api").await.unwrap();
+
+        let actions = runtime.recorded_actions();
+        assert!(actions
+            .iter()
+            .any(|a| matches!(a, MockAction::StopContainer { id, .. } if id == "container-api")));
+        assert!(actions
+            .iter()
+            .any(|a| matches!(a, MockAction::RemoveContainer { id, .. } if id == "container-api")));
+        let store = OrchestratorStore::new(&temp_dir.path().join("orchestrator.db"))
+            .await
+            .unwrap();
+        assert!(store.get_instance("inst_api").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_get_instance_returns_stored_record() {
+        let (state, _runtime, _temp_dir) = create_test_state().await;
+
+        let detail = get_instance(&state, "inst_api").await.unwrap();
+        assert_eq!(detail.id, "inst_api");
+        assert_eq!(detail.state, InstanceState::Stopped);
+        assert!(!detail.live);
+        assert_eq!(detail.last_activity_secs, None);
+
+        state.instance_manager.record_activity("inst_api").await;
+        let detail = get_instance(&state, "inst_api").await.unwrap();
+        assert_eq!(detail.last_activity_secs, Some(0));
+        let json = serde_json::to_value(&detail).unwrap();
+        assert_eq!(json["id"], "inst_api");
+        assert_eq!(json["topic_id"], 7);
+        assert_eq!(json["live"], false);
+        assert!(json.get("container_id").is_none());
+        assert!(json.get("pid").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_get_unknown_instance_returns_404() {
+        let (state, _runtime, _temp_dir) = create_test_state().await;
+
+        let err = get_instance(&state, "inst_missing").await.unwrap_err();
+        assert_eq!(err, InstanceApiError::NotFound("inst_missing".to_string()));
+        assert_eq!(err.status_code(), 404);
+    }
+
+    #[tokio::test]
+    async fn test_delete_unknown_instance_returns_404() {
+        let (state, runtime, _temp_dir) = create_test_state().await;
+
+        let err = delete_instance(&state, "inst_missing").await.unwrap_err();
+        assert_eq!(err, InstanceApiError::NotFound("inst_missing".to_string()));
+        assert_eq!(err.status_code(), 404);
+        assert!(runtime.recorded_actions().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_instance_logs_returns_tail() {
+        let (state, runtime, _temp_dir) = create_test_state().await;
+        *runtime.logs_result.lock().unwrap() = Ok(Some("listening on 8080\nready\n".to_string()));
+
+        let logs = instance_logs(&state, "inst_api", Some(50)).await.unwrap();
+
+        assert_eq!(logs.instance_id, "inst_api");
+        assert_eq!(logs.tail, 50);
+        assert_eq!(logs.lines, vec!["listening on 8080", "ready"]);
+        assert_eq!(logs.to_plain_text(), "listening on 8080\nready\n");
+        let json = serde_json::to_value(&logs).unwrap();
+        assert_eq!(json["lines"][1], "ready");
+        assert!(runtime.recorded_actions().iter().any(|a| matches!(
+            a,
+            MockAction::ContainerLogs { id, tail: 50 } if id == "container-api"
+        )));
+    }
+
+    #[tokio::test]
+    async fn test_instance_logs_caps_tail() {
+        let (state, runtime, _temp_dir) = create_test_state().await;
+
+        let logs = instance_logs(&state, "inst_api", Some(1_000_000))
+            .await
+            .unwrap();
+        assert_eq!(logs.tail, MAX_LOG_TAIL);
+        assert_eq!(
+            instance_logs(&state, "inst_api", None).await.unwrap().tail,
+            DEFAULT_LOG_TAIL
+        );
+        assert!(runtime.recorded_actions().iter().any(|a| matches!(
+            a,
+            MockAction::ContainerLogs { tail, .. } if *tail == MAX_LOG_TAIL
+        )));
+    }
+
+    #[tokio::test]
+    async fn test_instance_logs_failure_hides_cause() {
+        let (state, runtime, _temp_dir) = create_test_state().await;
+        *runtime.logs_result.lock().unwrap() =
+            Err("docker socket /var/run/docker.sock".to_string());
+
+        let err = instance_logs(&state, "inst_api", None).await.unwrap_err();
+        assert_eq!(err, InstanceApiError::Internal);
+        assert_eq!(err.status_code(), 500);
+        assert_eq!(err.to_string(), "Instance request failed");
+    }
+
+    #[tokio::test]
+    async fn test_instance_logs_missing_instance_or_container_returns_404() {
+        let (state, runtime, _temp_dir) = create_test_state().await;
+
+        let err = instance_logs(&state, "inst_missing", None)
+            .await
+            .unwrap_err();
+        assert_eq!(err.status_code(), 404);
+        assert!(runtime.recorded_actions().is_empty());
+
+        // The record exists but its container is gone
+        *runtime.logs_result.lock().unwrap() = Ok(None);
+        let err = instance_logs(&state, "inst_api", None).await.unwrap_err();
+        assert_eq!(err, InstanceApiError::NotFound("inst_api".to_string()));
+    }
+}
@@ -0,0 +1,8 @@
+//! External HTTP API.
+//!
+//! Request handling in `instances` and `body_limit` is framework-independent;
+//! `server` maps it onto the axum routes served on `API_HOST:API_PORT`.
+
+pub mod body_limit;
+pub mod instances;
+pub mod server;
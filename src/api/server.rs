@@ -0,0 +1,444 @@
+//! HTTP server for the `/api` routes.
+//!
+//! Every route except `/api/health` requires `Authorization: Bearer <API_KEY>`
+//! when `API_KEY` is set. Request bodies are capped at `API_MAX_BODY_BYTES`
+//! before any handler runs.
+
+use crate::api::body_limit::{check_content_length, read_body_limited, BodyError};
+use crate::api::instances::{self, AppState, InstanceApiError, InstanceDetail};
+use crate::config::Config;
+use axum::body::Body;
+use axum::extract::{Path, Query, Request, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::Deserialize;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, warn};
+
+/// Credentials and limits applied to every request.
+#[derive(Debug, Clone)]
+pub struct ApiSettings {
+    /// Bearer token required on all routes but `/api/health`; `None` leaves
+    /// them open
+    pub api_key: Option<String>,
+    /// Largest request body accepted, in bytes
+    pub max_body_bytes: usize,
+}
+
+impl ApiSettings {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            api_key: config.api_key.clone(),
+            max_body_bytes: config.api_max_body_bytes,
+        }
+    }
+}
+
+/// JSON `{"error": ...}` response with status `status`.
+fn error_response(status: u16, message: String) -> Response {
+    let status = StatusCode::from_u16(status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+    (status, Json(serde_json::json!({ "error": message }))).into_response()
+}
+
+impl IntoResponse for InstanceApiError {
+    fn into_response(self) -> Response {
+        error_response(self.status_code(), self.to_string())
+    }
+}
+
+impl IntoResponse for BodyError {
+    fn into_response(self) -> Response {
+        error_response(self.status_code(), self.to_string())
+    }
+}
+
+/// `GET /api/health`: component report, 200 when healthy and 503 otherwise.
+async fn health(State(state): State<AppState>) -> Response {
+    let report = state.instance_manager.health_report().await;
+    let status =
+        StatusCode::from_u16(report.status_code()).unwrap_or(StatusCode::SERVICE_UNAVAILABLE);
+    (status, Json(report)).into_response()
+}
+
+async fn get_instance(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<InstanceDetail>, InstanceApiError> {
+    instances::get_instance(&state, &id).await.map(Json)
+}
+
+async fn delete_instance(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, InstanceApiError> {
+    instances::delete_instance(&state, &id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Debug, Deserialize)]
+struct LogsQuery {
+    tail: Option<usize>,
+}
+
+/// Whether the client asked for plain text rather than JSON.
+fn wants_plain_text(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::ACCEPT)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|accept| accept.contains("text/plain") && !accept.contains("json"))
+}
+
+async fn instance_logs(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(query): Query<LogsQuery>,
+    headers: HeaderMap,
+) -> Result<Response, InstanceApiError> {
+    let logs = instances::instance_logs(&state, &id, query.tail).await?;
+    if wants_plain_text(&headers) {
+        Ok((
+            [(header::CONTENT_TYPE, "text/plain; charset=utf-8")],
+            logs.to_plain_text(),
+        )
+            .into_response())
+    } else {
+        Ok(Json(logs).into_response())
+    }
+}
+
+/// Compare API keys without stopping at the first differing byte.
+fn keys_match(given: &str, expected: &str) -> bool {
+    given.len() == expected.len()
+        && given
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+async fn require_api_key(State(key): State<Arc<str>>, request: Request, next: Next) -> Response {
+    let token = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    match token {
+        Some(token) if keys_match(token, &key) => next.run(request).await,
+        _ => {
+            debug!(path = %request.uri().path(), "Rejecting API request without a valid key");
+            error_response(401, "Missing or invalid API key".to_string())
+        }
+    }
+}
+
+/// Reject bodies over `limit`: up front from `Content-Length`, otherwise
+/// while reading, so a chunked upload is cut off once it crosses the limit.
+async fn limit_body(State(limit): State<usize>, request: Request, next: Next) -> Response {
+    let content_length = request
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<u64>().ok());
+    if let Err(e) = check_content_length(content_length, limit) {
+        return e.into_response();
+    }
+
+    let (parts, body) = request.into_parts();
+    match read_body_limited(body.into_data_stream(), limit).await {
+        Ok(bytes) => {
+            next.run(Request::from_parts(parts, Body::from(bytes)))
+                .await
+        }
+        Err(e) => e.into_response(),
+    }
+}
+
+/// The `/api` routes with authentication and the body limit applied.
+pub fn create_router(state: AppState, settings: &ApiSettings) -> Router {
+    let mut protected = Router::new()
+        .route(
+            "/api/instances/{id}",
+            get(get_instance).delete(delete_instance),
+        )
+        .route("/api/instances/{id}/logs", get(instance_logs));
+    if let Some(key) = &settings.api_key {
+        protected = protected.route_layer(middleware::from_fn_with_state(
+            Arc::<str>::from(key.as_str()),
+            require_api_key,
+        ));
+    }
+
+    Router::new()
+        .route("/api/health", get(health))
+        .merge(protected)
+        .layer(middleware::from_fn_with_state(
+            settings.max_body_bytes,
+            limit_body,
+        ))
+        .with_state(state)
+}
+
+/// Bind `API_HOST:API_PORT` and serve the API in the background until
+/// `shutdown` is cancelled; in-flight requests are finished first. Returns
+/// the bound address and the server task to await, or `None` when `API_PORT`
+/// is 0.
+pub async fn spawn(
+    config: &Config,
+    state: AppState,
+    shutdown: CancellationToken,
+) -> std::io::Result<Option<(SocketAddr, JoinHandle<()>)>> {
+    if config.api_port == 0 {
+        return Ok(None);
+    }
+    let listener = TcpListener::bind(SocketAddr::new(config.api_host, config.api_port)).await?;
+    let addr = listener.local_addr()?;
+    let app = create_router(state, &ApiSettings::from_config(config));
+    let handle = tokio::spawn(async move {
+        if let Err(e) = axum::serve(listener, app)
+            .with_graceful_shutdown(shutdown.cancelled_owned())
+            .await
+        {
+            warn!(error = %e, "API server stopped");
+        }
+        debug!("API server shut down");
+    });
+    Ok(Some((addr, handle)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::instances::tests::create_test_state;
+    use crate::orchestrator::container::mock::{MockAction, MockRuntime};
+
+    const KEY: &str = "test-api-key";
+
+    /// Serve the router on an ephemeral port and return its base URL.
+    async fn serve(state: AppState, settings: ApiSettings) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = create_router(state, &settings);
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        format!("http://{}", addr)
+    }
+
+    async fn serve_with_key(
+        max_body_bytes: usize,
+    ) -> (String, Arc<MockRuntime>, tempfile::TempDir) {
+        let (state, runtime, temp_dir) = create_test_state().await;
+        let url = serve(
+            state,
+            ApiSettings {
+                api_key: Some(KEY.to_string()),
+                max_body_bytes,
+            },
+        )
+        .await;
+        (url, runtime, temp_dir)
+    }
+
+    #[test]
+    fn test_keys_match() {
+        assert!(keys_match("secret", "secret"));
+        assert!(!keys_match("secreT", "secret"));
+        assert!(!keys_match("secret2", "secret"));
+        assert!(!keys_match("", "secret"));
+    }
+
+    #[tokio::test]
+    async fn test_health_is_open_and_reports_components() {
+        let (url, _runtime, _temp_dir) = serve_with_key(1024).await;
+
+        let response = reqwest::get(format!("{}/api/health", url)).await.unwrap();
+
+        assert_eq!(response.status(), 200);
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body["healthy"], true);
+        assert_eq!(body["database"]["ok"], true);
+    }
+
+    #[tokio::test]
+    async fn test_instance_routes_require_api_key() {
+        let (url, runtime, _temp_dir) = serve_with_key(1024).await;
+        let client = reqwest::Client::new();
+        let logs_url = format!("{}/api/instances/inst_api/logs", url);
+
+        let response = client.get(&logs_url).send().await.unwrap();
+        assert_eq!(response.status(), 401);
+        let response = client
+            .get(&logs_url)
+            .bearer_auth("wrong-key")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 401);
+        let response = client
+            .delete(format!("{}/api/instances/inst_api", url))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 401);
+        assert!(runtime.recorded_actions().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_get_instance_route() {
+        let (url, _runtime, _temp_dir) = serve_with_key(1024).await;
+        let client = reqwest::Client::new();
+
+        let response = client
+            .get(format!("{}/api/instances/inst_api", url))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 401);
+
+        let response = client
+            .get(format!("{}/api/instances/inst_api", url))
+            .bearer_auth(KEY)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body["id"], "inst_api");
+        assert_eq!(body["topic_id"], 7);
+        assert_eq!(body["started_at"], 1_700_000_000);
+        assert_eq!(body["live"], false);
+
+        let response = client
+            .get(format!("{}/api/instances/inst_missing", url))
+            .bearer_auth(KEY)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 404);
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body["error"], "Instance not found: inst_missing");
+    }
+
+    #[tokio::test]
+    async fn test_instance_logs_route_serves_json_and_plain_text() {
+        let (url, runtime, _temp_dir) = serve_with_key(1024).await;
+        *runtime.logs_result.lock().unwrap() = Ok(Some("booting\nready\n".to_string()));
+        let client = reqwest::Client::new();
+        let logs_url = format!("{}/api/instances/inst_api/logs?tail=5", url);
+
+        let response = client.get(&logs_url).bearer_auth(KEY).send().await.unwrap();
+        assert_eq!(response.status(), 200);
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body["tail"], 5);
+        assert_eq!(body["lines"][1], "ready");
+
+        let response = client
+            .get(&logs_url)
+            .bearer_auth(KEY)
+            .header(header::ACCEPT, "text/plain")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.text().await.unwrap(), "booting\nready\n");
+
+        let response = client
+            .get(format!("{}/api/instances/inst_missing/logs", url))
+            .bearer_auth(KEY)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 404);
+    }
+
+    #[tokio::test]
+    async fn test_delete_instance_route() {
+        let (url, runtime, _temp_dir) = serve_with_key(1024).await;
+        let client = reqwest::Client::new();
+        let instance_url = format!("{}/api/instances/inst_api", url);
+
+        let response = client
+            .delete(&instance_url)
+            .bearer_auth(KEY)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 204);
+        assert!(runtime
+            .recorded_actions()
+            .iter()
+            .any(|a| matches!(a, MockAction::RemoveContainer { id, .. } if id == "container-api")));
+
+        let response = client
+            .delete(&instance_url)
+            .bearer_auth(KEY)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 404);
+    }
+
+    #[tokio::test]
+    async fn test_oversized_body_returns_413() {
+        let (url, _runtime, _temp_dir) = serve_with_key(1024).await;
+        let client = reqwest::Client::new();
+        let instance_url = format!("{}/api/instances/inst_api", url);
+
+        // Declared with Content-Length
+        let response = client
+            .delete(&instance_url)
+            .bearer_auth(KEY)
+            .body(vec![b'x'; 2048])
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 413);
+
+        // Chunked, without Content-Length
+        let chunks: Vec<Result<Vec<u8>, std::io::Error>> =
+            vec![Ok(vec![b'x'; 600]), Ok(vec![b'x'; 600])];
+        let response = client
+            .delete(&instance_url)
+            .bearer_auth(KEY)
+            .body(reqwest::Body::wrap_stream(futures::stream::iter(chunks)))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 413);
+
+        // Within the limit the request reaches the handler
+        let response = client
+            .delete(&instance_url)
+            .bearer_auth(KEY)
+            .body(vec![b'x'; 512])
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 204);
+    }
+
+    #[tokio::test]
+    async fn test_routes_are_open_without_api_key() {
+        let (state, _runtime, _temp_dir) = create_test_state().await;
+        let url = serve(
+            state,
+            ApiSettings {
+                api_key: None,
+                max_body_bytes: 1024,
+            },
+        )
+        .await;
+
+        let response = reqwest::get(format!("{}/api/instances/inst_missing/logs", url))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 404);
+    }
+}
@@ -0,0 +1,61 @@
+//! Jitter for exponential backoff delays.
+//!
+//! Instances that crash together would otherwise restart and reconnect in
+//! lockstep. Each delay is scaled by a random factor in
+//! `[1 - JITTER_FRACTION, 1 + JITTER_FRACTION]`.
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+/// Maximum relative deviation applied to a delay (±25%).
+pub const JITTER_FRACTION: f64 = 0.25;
+
+/// Scale `delay` by the jitter factor for `sample`, a value in `[0, 1)`.
+/// Pass [`random_sample`] in production and a fixed value in tests.
+pub fn jittered(delay: Duration, sample: f64) -> Duration {
+    let sample = sample.clamp(0.0, 1.0);
+    let factor = 1.0 - JITTER_FRACTION + 2.0 * JITTER_FRACTION * sample;
+    delay.mul_f64(factor)
+}
+
+/// Uniform sample in `[0, 1)`. Uses std's randomly keyed hasher, which is
+/// plenty for spreading out retries.
+pub fn random_sample() -> f64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u128(
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos(),
+    );
+    (hasher.finish() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_jittered_bounds() {
+        let delay = Duration::from_secs(8);
+        assert_eq!(jittered(delay, 0.0), Duration::from_secs(6));
+        assert_eq!(jittered(delay, 0.5), Duration::from_secs(8));
+        assert_eq!(jittered(delay, 1.0), Duration::from_secs(10));
+        // Out-of-range samples are clamped
+        assert_eq!(jittered(delay, -3.0), Duration::from_secs(6));
+        assert_eq!(jittered(delay, 7.0), Duration::from_secs(10));
+    }
+
+    #[test]
+    fn test_random_jitter_stays_within_bounds() {
+        let delay = Duration::from_millis(1000);
+        for _ in 0..1000 {
+            let sample = random_sample();
+            assert!((0.0..1.0).contains(&sample));
+            let jittered = jittered(delay, sample);
+            assert!(jittered >= Duration::from_millis(750));
+            assert!(jittered <= Duration::from_millis(1250));
+        }
+    }
+}
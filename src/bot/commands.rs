@@ -1,3 +1,4 @@
+use teloxide::types::Message;
 use teloxide::utils::command::BotCommands;
 
 /// Bot commands for oc-outpost
@@ -8,7 +9,9 @@ use teloxide::utils::command::BotCommands;
 )]
 pub enum Command {
     /// Create new project and session
-    #[command(description = "create new project and session - Usage: /new <project_name>")]
+    #[command(
+        description = "create new project and session - Usage: /new <project_name> [--from <session_id>]"
+    )]
     New(String),
 
     /// List all sessions
@@ -20,25 +23,140 @@ pub enum Command {
     Projects,
 
     /// Close topic and clean up
-    #[command(description = "close topic and clean up")]
-    Close,
+    #[command(description = "close topic and clean up - Usage: /close [force]")]
+    Close(String),
+
+    /// Archive topic and stop instance
+    #[command(description = "archive topic and stop instance")]
+    Archive,
 
     /// Show session info
     #[command(description = "show current session info")]
     Session,
 
+    /// Send the topic's conversation as a Markdown file
+    #[command(description = "download this topic's conversation as a Markdown file")]
+    Export,
+
+    /// Replay the last prompt in a fresh session
+    #[command(
+        rename = "retry_clean",
+        description = "start a fresh session and replay your last prompt"
+    )]
+    RetryClean,
+
+    /// Send one prompt to two models and post both responses
+    #[command(
+        description = "compare two models on one prompt - Usage: /compare <provider/modelA> <provider/modelB> <prompt>"
+    )]
+    Compare(String),
+
+    /// Re-bind the topic to a different project
+    #[command(description = "re-bind this topic to a different project")]
+    Switch,
+
+    /// Toggle read-only observer mode for the topic
+    #[command(description = "toggle read-only observer mode - Usage: /observe [on|off]")]
+    Observe(String),
+
+    /// Route agent output to another topic
+    #[command(description = "send agent output to another topic - Usage: /output <topic_id>|off")]
+    Output(String),
+
+    /// Set the language responses in the topic should use
+    #[command(description = "set the response language - Usage: /lang <code>|off")]
+    Lang(String),
+
+    /// Restart the topic's instance, resetting its circuit breaker
+    #[command(description = "restart this topic's instance after repeated crashes")]
+    Restart,
+
+    /// Time a health-check round-trip to the topic's instance
+    #[command(description = "measure the round-trip latency to this topic's instance")]
+    Ping,
+
     /// Show orchestrator status
     #[command(description = "show orchestrator status")]
     Status,
 
+    /// Show aggregate usage over time
+    #[command(description = "show usage statistics - Usage: /stats [days]")]
+    Stats(String),
+
+    /// Put a project into maintenance (admins only)
+    #[command(
+        description = "stop a project and block new instances (admins) - Usage: /cordon [project]"
+    )]
+    Cordon(String),
+
+    /// Take a project out of maintenance (admins only)
+    #[command(description = "resume a cordoned project (admins) - Usage: /uncordon <project>")]
+    Uncordon(String),
+
+    /// Reload hot-reloadable config (admins only)
+    #[command(description = "reload hot-reloadable config without restarting (admins)")]
+    Reload,
+
+    /// Send this chat's topic mappings as a JSON file (admins only)
+    #[command(
+        rename = "export_mappings",
+        description = "download this chat's topic mappings as a JSON file (admins)"
+    )]
+    ExportMappings,
+
+    /// Import this chat's topic mappings from an exported JSON file (admins only)
+    #[command(
+        rename = "import_mappings",
+        description = "import topic mappings - send the exported file with this as its caption"
+    )]
+    ImportMappings,
+
+    /// Show SSE subscription diagnostics
+    #[command(description = "show stream diagnostics - Usage: /debug [stream]")]
+    Debug(String),
+
+    /// Show the caller's ids and access status
+    #[command(description = "show your user, chat and topic ids and access status")]
+    Whoami,
+
     /// Show help
     #[command(description = "display this help text")]
     Help,
 }
 
+/// Parse a command from a message's text or, for media, its caption.
+///
+/// teloxide's `filter_command` only looks at the text, so `/new myproj`
+/// sent as a photo caption would otherwise be forwarded as a prompt.
+pub fn parse_message_command(msg: &Message, bot_name: &str) -> Option<Command> {
+    let text = msg.text().or_else(|| msg.caption())?;
+    Command::parse(text, bot_name).ok()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::ops::ControlFlow;
+    use teloxide::dispatching::DpHandlerDescription;
+    use teloxide::dptree::{self, case};
+
+    fn photo_message(caption: &str) -> Message {
+        serde_json::from_value(serde_json::json!({
+            "message_id": 1,
+            "date": 0,
+            "chat": {"id": -1001234567890_i64, "type": "supergroup", "title": "Test"},
+            "from": {"id": 7, "is_bot": false, "first_name": "Tester"},
+            "photo": [{
+                "file_id": "photo-1",
+                "file_unique_id": "u1",
+                "width": 90,
+                "height": 90,
+                "file_size": 1000
+            }],
+            "caption": caption
+        }))
+        .unwrap()
+    }
 
     #[test]
     fn test_parse_new_command() {
@@ -55,7 +173,46 @@ mod tests {
     #[test]
     fn test_parse_close_command() {
         let cmd = Command::parse("/close", "bot").unwrap();
-        assert_eq!(cmd, Command::Close);
+        assert_eq!(cmd, Command::Close(String::new()));
+    }
+
+    #[test]
+    fn test_parse_close_force_command() {
+        let cmd = Command::parse("/close force", "bot").unwrap();
+        assert_eq!(cmd, Command::Close("force".to_string()));
+    }
+
+    #[test]
+    fn test_parse_retry_clean_command() {
+        let cmd = Command::parse("/retry_clean", "bot").unwrap();
+        assert_eq!(cmd, Command::RetryClean);
+    }
+
+    #[test]
+    fn test_parse_mappings_commands() {
+        assert_eq!(
+            Command::parse("/export_mappings", "bot").unwrap(),
+            Command::ExportMappings
+        );
+        assert_eq!(
+            Command::parse("/import_mappings", "bot").unwrap(),
+            Command::ImportMappings
+        );
+    }
+
+    #[test]
+    fn test_parse_compare_command() {
+        let cmd = Command::parse("/compare model-a model-b explain this", "bot").unwrap();
+        assert_eq!(
+            cmd,
+            Command::Compare("model-a model-b explain this".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_archive_command() {
+        let cmd = Command::parse("/archive", "bot").unwrap();
+        assert_eq!(cmd, Command::Archive);
     }
 
     #[test]
@@ -82,6 +239,66 @@ mod tests {
         assert_eq!(cmd, Command::Help);
     }
 
+    #[test]
+    fn test_parse_whoami_command() {
+        let cmd = Command::parse("/whoami", "bot").unwrap();
+        assert_eq!(cmd, Command::Whoami);
+    }
+
+    #[test]
+    fn test_parse_switch_command() {
+        let cmd = Command::parse("/switch", "bot").unwrap();
+        assert_eq!(cmd, Command::Switch);
+    }
+
+    #[test]
+    fn test_parse_cordon_commands() {
+        let cmd = Command::parse("/cordon api", "bot").unwrap();
+        assert_eq!(cmd, Command::Cordon("api".to_string()));
+
+        let cmd = Command::parse("/uncordon api", "bot").unwrap();
+        assert_eq!(cmd, Command::Uncordon("api".to_string()));
+    }
+
+    #[test]
+    fn test_parse_debug_command() {
+        let cmd = Command::parse("/debug", "bot").unwrap();
+        assert_eq!(cmd, Command::Debug(String::new()));
+
+        let cmd = Command::parse("/debug stream", "bot").unwrap();
+        assert_eq!(cmd, Command::Debug("stream".to_string()));
+    }
+
+    #[test]
+    fn test_parse_observe_command() {
+        let cmd = Command::parse("/observe", "bot").unwrap();
+        assert_eq!(cmd, Command::Observe(String::new()));
+
+        let cmd = Command::parse("/observe off", "bot").unwrap();
+        assert_eq!(cmd, Command::Observe("off".to_string()));
+    }
+
+    #[test]
+    fn test_parse_output_command() {
+        let cmd = Command::parse("/output 42", "bot").unwrap();
+        assert_eq!(cmd, Command::Output("42".to_string()));
+    }
+
+    #[test]
+    fn test_parse_reload_command() {
+        let cmd = Command::parse("/reload", "bot").unwrap();
+        assert_eq!(cmd, Command::Reload);
+    }
+
+    #[test]
+    fn test_parse_stats_command() {
+        let cmd = Command::parse("/stats", "bot").unwrap();
+        assert_eq!(cmd, Command::Stats(String::new()));
+
+        let cmd = Command::parse("/stats 30", "bot").unwrap();
+        assert_eq!(cmd, Command::Stats("30".to_string()));
+    }
+
     #[test]
     fn test_command_descriptions() {
         let descriptions = Command::descriptions();
@@ -98,4 +315,46 @@ mod tests {
         let result = Command::parse("/invalid", "bot");
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_parse_message_command_from_caption() {
+        let msg = photo_message("/new myproj");
+        assert_eq!(
+            parse_message_command(&msg, "outpost_bot"),
+            Some(Command::New("myproj".to_string()))
+        );
+
+        let msg = photo_message("/new@outpost_bot myproj");
+        assert_eq!(
+            parse_message_command(&msg, "outpost_bot"),
+            Some(Command::New("myproj".to_string()))
+        );
+
+        // Addressed to another bot, or not a command at all
+        assert_eq!(
+            parse_message_command(&photo_message("/new@other_bot myproj"), "outpost_bot"),
+            None
+        );
+        assert_eq!(
+            parse_message_command(&photo_message("what is in this screenshot?"), "outpost_bot"),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_captioned_command_is_dispatched() {
+        let handler: dptree::Handler<'_, String, DpHandlerDescription> = dptree::entry()
+            .filter_map(|msg: Message| parse_message_command(&msg, "outpost_bot"))
+            .branch(case![Command::New(name)].endpoint(|name: String| async move { name }));
+
+        let result = handler
+            .dispatch(dptree::deps![photo_message("/new myproj")])
+            .await;
+        assert_eq!(result, ControlFlow::Break("myproj".to_string()));
+
+        let result = handler
+            .dispatch(dptree::deps![photo_message("just a photo")])
+            .await;
+        assert!(matches!(result, ControlFlow::Continue(_)));
+    }
 }
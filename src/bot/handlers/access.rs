@@ -0,0 +1,69 @@
+//! Per-user access control within whitelisted chats.
+//!
+//! `TELEGRAM_ALLOWED_USERS` narrows who may drive the bot inside the chats in
+//! `TELEGRAM_CHAT_IDS`. An empty list allows every member. Admin commands are
+//! further limited to `TELEGRAM_ADMIN_USERS`.
+
+use crate::config::Config;
+use crate::types::error::{OutpostError, Result};
+use teloxide::prelude::*;
+use teloxide::types::User;
+use tracing::warn;
+
+/// Reply sent to users who are not in `TELEGRAM_ALLOWED_USERS`.
+pub const UNAUTHORIZED_MESSAGE: &str =
+    "Sorry, you are not allowed to use this bot. Ask an admin to add your user id.";
+
+/// Whether `from` may use the bot. Updates without a sender (e.g. channel
+/// posts) are only allowed when no allowlist is configured.
+pub fn is_allowed_sender(config: &Config, from: Option<&User>) -> bool {
+    match from {
+        Some(user) => config.is_allowed_user(user.id.0 as i64),
+        None => config.telegram_allowed_users.is_empty(),
+    }
+}
+
+/// Reply sent when a user outside `TELEGRAM_ADMIN_USERS` runs an admin
+/// command.
+pub const ADMIN_ONLY_MESSAGE: &str = "Only bot admins (TELEGRAM_ADMIN_USERS) can use this command.";
+
+/// Whether `from` may run admin commands. Updates without a sender never may.
+pub fn is_admin_sender(config: &Config, from: Option<&User>) -> bool {
+    from.is_some_and(|user| config.is_admin(user.id.0 as i64))
+}
+
+/// Log and politely reject a message from a user outside the allowlist.
+pub async fn reject_unauthorized_message(bot: Bot, msg: Message) -> Result<()> {
+    warn!(
+        chat_id = msg.chat.id.0,
+        topic_id = ?msg.thread_id.map(|t| t.0 .0),
+        sender_id = ?msg.from.as_ref().map(|u| u.id.0),
+        sender_username = ?msg.from.as_ref().and_then(|u| u.username.clone()),
+        "Rejecting message from user not in TELEGRAM_ALLOWED_USERS"
+    );
+
+    let mut request = bot.send_message(msg.chat.id, UNAUTHORIZED_MESSAGE);
+    if let Some(thread_id) = msg.thread_id {
+        request = request.message_thread_id(thread_id);
+    }
+    request
+        .await
+        .map_err(|e| OutpostError::telegram_error(e.to_string()))?;
+    Ok(())
+}
+
+/// Log and reject a callback query from a user outside the allowlist.
+pub async fn reject_unauthorized_callback(bot: Bot, q: CallbackQuery) -> Result<()> {
+    warn!(
+        sender_id = q.from.id.0,
+        sender_username = ?q.from.username,
+        callback_data = ?q.data,
+        "Rejecting callback from user not in TELEGRAM_ALLOWED_USERS"
+    );
+
+    bot.answer_callback_query(q.id)
+        .text(UNAUTHORIZED_MESSAGE)
+        .await
+        .map_err(|e| OutpostError::telegram_error(e.to_string()))?;
+    Ok(())
+}
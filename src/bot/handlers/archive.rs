@@ -0,0 +1,82 @@
+//! /archive command handler
+//!
+//! Archives the current topic: the instance is stopped and the mapping is
+//! soft-deleted, so the next message in the topic offers the project
+//! selection keyboard again.
+
+use crate::bot::{BotState, Command};
+use crate::project_config::{command_enabled, COMMAND_DISABLED_MESSAGE};
+use crate::types::error::{OutpostError, Result};
+use std::path::Path;
+use std::sync::Arc;
+use teloxide::prelude::*;
+use teloxide::types::{MessageId, ThreadId};
+use tracing::{debug, warn};
+
+fn get_topic_id(msg: &Message) -> Result<i32> {
+    let thread_id = msg.thread_id.ok_or_else(|| {
+        OutpostError::telegram_error("This command must be used in a forum topic")
+    })?;
+
+    if thread_id.0 .0 == 1 {
+        return Err(OutpostError::telegram_error(
+            "Cannot archive the General topic",
+        ));
+    }
+
+    Ok(thread_id.0 .0)
+}
+
+/// Handle /archive command
+pub async fn handle_archive(
+    bot: Bot,
+    msg: Message,
+    _cmd: Command,
+    state: Arc<BotState>,
+) -> Result<()> {
+    debug!(
+        chat_id = msg.chat.id.0,
+        topic_id = ?msg.thread_id.map(|t| t.0 .0),
+        sender_id = ?msg.from.as_ref().map(|u| u.id.0),
+        "Handling /archive"
+    );
+
+    let topic_id = get_topic_id(&msg)?;
+
+    let mapping = state
+        .topic_store
+        .get_mapping(msg.chat.id.0, topic_id)
+        .await
+        .map_err(|e| OutpostError::database_error(e.to_string()))?
+        .ok_or_else(|| OutpostError::telegram_error("No active connection in this topic"))?;
+
+    if !command_enabled(Path::new(&mapping.project_path), "archive") {
+        bot.send_message(msg.chat.id, COMMAND_DISABLED_MESSAGE)
+            .message_thread_id(ThreadId(MessageId(topic_id)))
+            .await
+            .map_err(|e| OutpostError::telegram_error(e.to_string()))?;
+        return Ok(());
+    }
+
+    if let Some(instance_id) = &mapping.instance_id {
+        if let Err(e) = state.instance_manager.stop_instance(instance_id).await {
+            warn!(instance_id = %instance_id, error = %e, "Failed to stop instance during archive");
+        }
+    }
+
+    state
+        .topic_store
+        .archive_mapping(msg.chat.id.0, topic_id)
+        .await
+        .map_err(|e| OutpostError::database_error(e.to_string()))?;
+
+    bot.send_message(
+        msg.chat.id,
+        "Topic archived. Send a message here to link it to a project again.",
+    )
+    .message_thread_id(ThreadId(MessageId(topic_id)))
+    .await
+    .map_err(|e| OutpostError::telegram_error(e.to_string()))?;
+
+    Ok(())
+}
@@ -1,13 +1,33 @@
 use crate::bot::BotState;
 use crate::git::worktree::{create_worktree, is_git_repo, sanitize_branch_name};
+use crate::integration::Integration;
+use crate::opencode::stream_handler::StreamHandler;
+use crate::project_config::validate_project;
 use crate::types::error::{OutpostError, Result};
 use crate::types::forum::TopicMapping;
+use std::path::PathBuf;
 use std::sync::Arc;
 use teloxide::prelude::*;
 use teloxide::types::{InlineKeyboardMarkup, MessageId, ThreadId};
 use tracing::{debug, info, warn};
 
-pub async fn dispatch_callback(bot: Bot, q: CallbackQuery, state: Arc<BotState>) -> Result<()> {
+/// Callback prefix for linking an unmapped topic to a project.
+pub const PROJECT_CALLBACK_PREFIX: &str = "proj";
+
+/// Callback prefix for re-binding a mapped topic via `/switch`.
+pub const SWITCH_CALLBACK_PREFIX: &str = "switch";
+
+pub async fn dispatch_callback(
+    bot: Bot,
+    q: CallbackQuery,
+    state: Arc<BotState>,
+    stream_handler: Arc<StreamHandler>,
+    integration: Arc<Integration>,
+) -> Result<()> {
+    if !state.config().is_allowed_user(q.from.id.0 as i64) {
+        return crate::bot::handlers::access::reject_unauthorized_callback(bot, q).await;
+    }
+
     let data = match q.data.as_deref() {
         Some(d) => d,
         None => {
@@ -24,6 +44,10 @@ pub async fn dispatch_callback(bot: Bot, q: CallbackQuery, state: Arc<BotState>)
         crate::bot::handlers::close::handle_close_callback(bot, q, state).await
     } else if data.starts_with("proj:") {
         handle_project_selection_callback(bot, q, state).await
+    } else if data.starts_with("switch:") {
+        handle_switch_callback(bot, q, state, stream_handler, integration).await
+    } else if data.starts_with("fb:") {
+        crate::bot::handlers::feedback::handle_feedback_callback(bot, q, state).await
     } else {
         warn!(callback_data = %data, "Unknown callback prefix");
         let _ = bot.answer_callback_query(q.id).text("Unknown action").await;
@@ -32,8 +56,12 @@ pub async fn dispatch_callback(bot: Bot, q: CallbackQuery, state: Arc<BotState>)
 }
 
 fn parse_project_callback_data(data: &str) -> Result<(i32, String)> {
+    parse_prefixed_project_callback_data(data, PROJECT_CALLBACK_PREFIX)
+}
+
+fn parse_prefixed_project_callback_data(data: &str, prefix: &str) -> Result<(i32, String)> {
     let parts: Vec<&str> = data.splitn(3, ':').collect();
-    if parts.len() != 3 || parts[0] != "proj" {
+    if parts.len() != 3 || parts[0] != prefix {
         return Err(OutpostError::telegram_error(
             "Invalid project callback data format",
         ));
@@ -53,6 +81,60 @@ fn parse_project_callback_data(data: &str) -> Result<(i32, String)> {
     Ok((topic_id, project_name))
 }
 
+/// Resolve `project_name` under the project base, creating a worktree for git
+/// repositories. Problems are reported in the topic and yield `None`.
+async fn prepare_project_path(
+    bot: &Bot,
+    chat_id: ChatId,
+    topic_id: i32,
+    state: &BotState,
+    project_name: &str,
+) -> Result<Option<(PathBuf, Option<String>)>> {
+    let project_path = state.config().project_base_path.join(project_name);
+    if !project_path.is_dir() {
+        bot.send_message(chat_id, format!("Directory '{}' not found.", project_name))
+            .message_thread_id(ThreadId(MessageId(topic_id)))
+            .await
+            .map_err(|e| OutpostError::telegram_error(e.to_string()))?;
+        return Ok(None);
+    }
+
+    if state.config().validate_project {
+        if let Some(warning) = validate_project(&project_path).warning(project_name) {
+            bot.send_message(chat_id, warning)
+                .message_thread_id(ThreadId(MessageId(topic_id)))
+                .await
+                .map_err(|e| OutpostError::telegram_error(e.to_string()))?;
+        }
+    }
+
+    let mut worktree_branch = None;
+    let effective_project_path = if is_git_repo(&project_path) {
+        let sanitized = sanitize_branch_name(project_name);
+        worktree_branch = Some(format!("wt/{}", sanitized));
+        match create_worktree(
+            &project_path,
+            project_name,
+            &state.config().project_base_path,
+        )
+        .await
+        {
+            Ok(path) => path,
+            Err(e) => {
+                bot.send_message(chat_id, format!("Failed to create worktree: {}", e))
+                    .message_thread_id(ThreadId(MessageId(topic_id)))
+                    .await
+                    .map_err(|e| OutpostError::telegram_error(e.to_string()))?;
+                return Ok(None);
+            }
+        }
+    } else {
+        project_path
+    };
+
+    Ok(Some((effective_project_path, worktree_branch)))
+}
+
 async fn handle_project_selection_callback(
     bot: Bot,
     q: CallbackQuery,
@@ -110,37 +192,10 @@ async fn handle_project_selection_callback(
         return Ok(());
     }
 
-    let project_path = state.config.project_base_path.join(&project_name);
-    if !project_path.is_dir() {
-        bot.send_message(chat_id, format!("Directory '{}' not found.", project_name))
-            .message_thread_id(ThreadId(MessageId(topic_id)))
-            .await
-            .map_err(|e| OutpostError::telegram_error(e.to_string()))?;
+    let Some((effective_project_path, worktree_branch)) =
+        prepare_project_path(&bot, chat_id, topic_id, &state, &project_name).await?
+    else {
         return Ok(());
-    }
-
-    let mut worktree_branch = None;
-    let effective_project_path = if is_git_repo(&project_path) {
-        let sanitized = sanitize_branch_name(&project_name);
-        worktree_branch = Some(format!("wt/{}", sanitized));
-        match create_worktree(
-            &project_path,
-            &project_name,
-            &state.config.project_base_path,
-        )
-        .await
-        {
-            Ok(path) => path,
-            Err(e) => {
-                bot.send_message(chat_id, format!("Failed to create worktree: {}", e))
-                    .message_thread_id(ThreadId(MessageId(topic_id)))
-                    .await
-                    .map_err(|e| OutpostError::telegram_error(e.to_string()))?;
-                return Ok(());
-            }
-        }
-    } else {
-        project_path.clone()
     };
 
     let instance_lock = state
@@ -166,6 +221,11 @@ async fn handle_project_selection_callback(
         session_id: None,
         instance_id: Some(instance_id.clone()),
         topic_name_updated: false,
+        read_only: false,
+        output_topic_id: None,
+        system_prompt_mtime: None,
+        locale: None,
+        last_activity_at: None,
         created_at: now,
         updated_at: now,
     };
@@ -209,6 +269,182 @@ async fn handle_project_selection_callback(
     Ok(())
 }
 
+/// Point `mapping` at a new project and instance. The old session belongs to
+/// the previous project, so it is cleared and the topic name is refreshed on
+/// the next response.
+fn rebind_mapping(mapping: &mut TopicMapping, project_path: String, instance_id: String, now: i64) {
+    mapping.project_path = project_path;
+    mapping.instance_id = Some(instance_id);
+    mapping.session_id = None;
+    mapping.topic_name_updated = false;
+    mapping.updated_at = now;
+}
+
+async fn handle_switch_callback(
+    bot: Bot,
+    q: CallbackQuery,
+    state: Arc<BotState>,
+    stream_handler: Arc<StreamHandler>,
+    integration: Arc<Integration>,
+) -> Result<()> {
+    let data = q
+        .data
+        .as_deref()
+        .ok_or_else(|| OutpostError::telegram_error("No callback data"))?;
+
+    let (topic_id, project_name) =
+        parse_prefixed_project_callback_data(data, SWITCH_CALLBACK_PREFIX)?;
+
+    let chat_id = q
+        .message
+        .as_ref()
+        .map(|m| m.chat().id)
+        .ok_or_else(|| OutpostError::telegram_error("No message in callback"))?;
+
+    debug!(
+        topic_id = topic_id,
+        project = %project_name,
+        chat_id = chat_id.0,
+        "Handling project switch callback"
+    );
+
+    let _ = bot
+        .answer_callback_query(q.id.clone())
+        .text(format!("Switching to '{}'...", project_name))
+        .await;
+
+    if let Some(ref message) = q.message {
+        let _ = bot
+            .edit_message_text(
+                chat_id,
+                message.id(),
+                format!("Switching to project: {}...", project_name),
+            )
+            .reply_markup(InlineKeyboardMarkup::new(Vec::<
+                Vec<teloxide::types::InlineKeyboardButton>,
+            >::new()))
+            .await;
+    }
+
+    let Some(mut mapping) = state
+        .topic_store
+        .get_mapping(chat_id.0, topic_id)
+        .await
+        .map_err(|e| OutpostError::database_error(e.to_string()))?
+    else {
+        bot.send_message(chat_id, "No active connection in this topic")
+            .message_thread_id(ThreadId(MessageId(topic_id)))
+            .await
+            .map_err(|e| OutpostError::telegram_error(e.to_string()))?;
+        return Ok(());
+    };
+
+    let Some((effective_project_path, worktree_branch)) =
+        prepare_project_path(&bot, chat_id, topic_id, &state, &project_name).await?
+    else {
+        return Ok(());
+    };
+
+    let new_path = effective_project_path.to_string_lossy().to_string();
+    if new_path == mapping.project_path {
+        bot.send_message(
+            chat_id,
+            format!("This topic is already using '{}'.", project_name),
+        )
+        .message_thread_id(ThreadId(MessageId(topic_id)))
+        .await
+        .map_err(|e| OutpostError::telegram_error(e.to_string()))?;
+        return Ok(());
+    }
+
+    // Stop forwarding the old session before the topic moves on, so the new
+    // session gets a fresh subscription
+    integration.stop_stream(topic_id).await;
+    if let Some(old_session_id) = mapping.session_id.as_deref() {
+        stream_handler.unsubscribe(old_session_id).await;
+    }
+
+    // Other topics on the old project keep using its instance; otherwise it
+    // would sit idle until the reaper, so stop it now
+    let project_mappings = state
+        .topic_store
+        .count_mappings_by_project(&mapping.project_path)
+        .await
+        .map_err(|e| OutpostError::database_error(e.to_string()))?;
+    if let Some(old_instance_id) = mapping.instance_id.as_deref() {
+        if project_mappings > 1 {
+            debug!(
+                instance_id = %old_instance_id,
+                other_topics = project_mappings - 1,
+                "Previous instance still in use, leaving it running"
+            );
+        } else if let Err(e) = state.instance_manager.stop_instance(old_instance_id).await {
+            warn!(
+                instance_id = %old_instance_id,
+                error = %e,
+                "Failed to stop previous instance during switch"
+            );
+        }
+    }
+
+    let instance_lock = state
+        .instance_manager
+        .get_or_create(&effective_project_path, topic_id)
+        .await
+        .map_err(|e| OutpostError::io_error(format!("Failed to spawn instance: {}", e)))?;
+
+    let inst = instance_lock.lock().await;
+    let instance_id = inst.id().to_string();
+    let port = inst.port();
+    drop(inst);
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_err(|e| OutpostError::io_error(e.to_string()))?
+        .as_secs() as i64;
+
+    let old_path = mapping.project_path.clone();
+    rebind_mapping(&mut mapping, new_path, instance_id.clone(), now);
+    state
+        .topic_store
+        .save_mapping(&mapping)
+        .await
+        .map_err(|e| OutpostError::database_error(e.to_string()))?;
+
+    let worktree_info = worktree_branch
+        .as_ref()
+        .map(|branch| format!("\nWorktree branch: {}", branch))
+        .unwrap_or_default();
+
+    let confirmation = format!(
+        "Switched this topic to '{}'.\n\n\
+         Path: {}{}\n\
+         Instance: {}\n\
+         Port: {}\n\n\
+         The previous session was left behind; your next message starts a new one.",
+        project_name,
+        effective_project_path.display(),
+        worktree_info,
+        instance_id,
+        port
+    );
+
+    bot.send_message(chat_id, confirmation)
+        .message_thread_id(ThreadId(MessageId(topic_id)))
+        .await
+        .map_err(|e| OutpostError::telegram_error(e.to_string()))?;
+
+    info!(
+        topic_id = topic_id,
+        from = %old_path,
+        to = %mapping.project_path,
+        instance_id = %instance_id,
+        "Topic switched to another project"
+    );
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -283,4 +519,54 @@ mod tests {
         assert_eq!(topic_id, 2147483647);
         assert_eq!(name, "project");
     }
+
+    #[test]
+    fn test_switch_prefix_detected() {
+        let (topic_id, name) =
+            parse_prefixed_project_callback_data("switch:42:other-project", SWITCH_CALLBACK_PREFIX)
+                .unwrap();
+        assert_eq!(topic_id, 42);
+        assert_eq!(name, "other-project");
+        assert!(parse_prefixed_project_callback_data(
+            "proj:42:other-project",
+            SWITCH_CALLBACK_PREFIX
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_rebind_mapping_resets_session_state() {
+        let mut mapping = TopicMapping {
+            topic_id: 42,
+            chat_id: -1001234567890,
+            project_path: "/projects/old".to_string(),
+            session_id: Some("ses_old".to_string()),
+            instance_id: Some("inst_old".to_string()),
+            topic_name_updated: true,
+            read_only: true,
+            output_topic_id: Some(77),
+            system_prompt_mtime: None,
+            locale: None,
+            last_activity_at: None,
+            created_at: 1000,
+            updated_at: 1000,
+        };
+
+        rebind_mapping(
+            &mut mapping,
+            "/projects/new".to_string(),
+            "inst_new".to_string(),
+            2000,
+        );
+
+        assert_eq!(mapping.project_path, "/projects/new");
+        assert_eq!(mapping.instance_id.as_deref(), Some("inst_new"));
+        assert_eq!(mapping.session_id, None);
+        assert!(!mapping.topic_name_updated);
+        assert_eq!(mapping.updated_at, 2000);
+        // Topic-level settings survive the switch
+        assert_eq!(mapping.created_at, 1000);
+        assert!(mapping.read_only);
+        assert_eq!(mapping.output_topic_id, Some(77));
+    }
 }
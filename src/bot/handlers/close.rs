@@ -1,11 +1,18 @@
 use crate::bot::{BotState, Command};
+use crate::opencode::stream_handler::{ConnectionState, StreamHandler};
+use crate::project_config::{command_enabled, COMMAND_DISABLED_MESSAGE};
+use crate::telegram::image_cache::cleanup_image_cache;
 use crate::types::error::{OutpostError, Result};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Duration;
 use teloxide::prelude::*;
 use teloxide::types::{InlineKeyboardButton, InlineKeyboardMarkup, MessageId, ThreadId};
 use tracing::{debug, warn};
 
+/// Sessions active within this window require confirmation before closing.
+const CLOSE_CONFIRM_ACTIVITY_WINDOW: Duration = Duration::from_secs(10 * 60);
+
 fn get_topic_id(msg: &Message) -> Result<i32> {
     let thread_id = msg.thread_id.ok_or_else(|| {
         OutpostError::telegram_error("This command must be used in a forum topic")
@@ -20,11 +27,27 @@ fn get_topic_id(msg: &Message) -> Result<i32> {
     Ok(thread_id.0 .0)
 }
 
+/// Parse `/close` arguments, returning whether `force` was given.
+fn parse_force_flag(args: &str) -> Result<bool> {
+    match args.trim() {
+        "" => Ok(false),
+        "force" => Ok(true),
+        _ => Err(OutpostError::telegram_error("Usage: /close [force]")),
+    }
+}
+
+/// Whether closing should be confirmed first: the session is streaming or
+/// was active recently.
+fn requires_confirmation(idle: Option<Duration>, has_active_stream: bool) -> bool {
+    has_active_stream || idle.is_some_and(|d| d < CLOSE_CONFIRM_ACTIVITY_WINDOW)
+}
+
 pub async fn handle_close(
     bot: Bot,
     msg: Message,
-    _cmd: Command,
+    cmd: Command,
     state: Arc<BotState>,
+    stream_handler: Arc<StreamHandler>,
 ) -> Result<()> {
     debug!(
         chat_id = msg.chat.id.0,
@@ -33,15 +56,59 @@ pub async fn handle_close(
         "Handling /close"
     );
 
+    let args = match cmd {
+        Command::Close(args) => args,
+        _ => return Err(OutpostError::config_error("Invalid command type")),
+    };
+    let force = parse_force_flag(&args)?;
     let topic_id = get_topic_id(&msg)?;
 
-    let _mapping = state
+    let mapping = state
         .topic_store
         .get_mapping(msg.chat.id.0, topic_id)
         .await
         .map_err(|e| OutpostError::database_error(e.to_string()))?
         .ok_or_else(|| OutpostError::telegram_error("No active connection in this topic"))?;
 
+    if !command_enabled(Path::new(&mapping.project_path), "close") {
+        bot.send_message(msg.chat.id, COMMAND_DISABLED_MESSAGE)
+            .message_thread_id(ThreadId(MessageId(topic_id)))
+            .await
+            .map_err(|e| OutpostError::telegram_error(e.to_string()))?;
+        return Ok(());
+    }
+
+    let idle = match &mapping.instance_id {
+        Some(instance_id) => state
+            .instance_manager
+            .activity_snapshot()
+            .await
+            .get(instance_id)
+            .copied(),
+        None => None,
+    };
+    let has_active_stream = mapping.session_id.as_ref().is_some_and(|session_id| {
+        stream_handler
+            .subscription_status()
+            .iter()
+            .any(|s| &s.session_id == session_id && s.state == ConnectionState::Connected)
+    });
+    debug!(
+        force = force,
+        idle_secs = ?idle.map(|d| d.as_secs()),
+        has_active_stream = has_active_stream,
+        "Close confirmation check"
+    );
+
+    if force || !requires_confirmation(idle, has_active_stream) {
+        bot.send_message(msg.chat.id, "Closing topic... ⏳")
+            .message_thread_id(ThreadId(MessageId(topic_id)))
+            .await
+            .map_err(|e| OutpostError::telegram_error(e.to_string()))?;
+        close_topic(&bot, msg.chat.id, topic_id, &state).await;
+        return Ok(());
+    }
+
     let keyboard = InlineKeyboardMarkup::new(vec![vec![
         InlineKeyboardButton::callback("✅ Confirm", format!("close:{}:confirm", topic_id)),
         InlineKeyboardButton::callback("❌ Cancel", format!("close:{}:cancel", topic_id)),
@@ -49,7 +116,7 @@ pub async fn handle_close(
 
     bot.send_message(
         msg.chat.id,
-        "Are you sure you want to close this topic? This will stop the instance, remove the worktree, and delete this topic.",
+        "This session is still active. Closing will stop the instance, remove the worktree, delete this topic, and its context will be lost. Are you sure?",
     )
     .message_thread_id(ThreadId(MessageId(topic_id)))
     .reply_markup(keyboard)
@@ -112,57 +179,68 @@ pub async fn handle_close_callback(bot: Bot, q: CallbackQuery, state: Arc<BotSta
             ));
         };
 
-        let mapping = state
-            .topic_store
-            .get_mapping(chat_id.0, topic_id)
-            .await
-            .ok()
-            .flatten();
+        close_topic(&bot, chat_id, topic_id, &state).await;
+    }
 
-        if let Some(mapping) = mapping {
-            if let Some(instance_id) = &mapping.instance_id {
-                if let Err(e) = state.instance_manager.stop_instance(instance_id).await {
-                    warn!(instance_id = %instance_id, error = %e, "Failed to stop instance during close");
-                }
+    Ok(())
+}
+
+/// Stop the instance, remove the worktree, delete the mapping and the topic.
+async fn close_topic(bot: &Bot, chat_id: ChatId, topic_id: i32, state: &BotState) {
+    let mapping = state
+        .topic_store
+        .get_mapping(chat_id.0, topic_id)
+        .await
+        .ok()
+        .flatten();
+
+    if let Some(mapping) = mapping {
+        if let Some(instance_id) = &mapping.instance_id {
+            if let Err(e) = state.instance_manager.stop_instance(instance_id).await {
+                warn!(instance_id = %instance_id, error = %e, "Failed to stop instance during close");
             }
+        }
 
-            let project_path = PathBuf::from(&mapping.project_path);
-            if mapping.project_path.contains("/.worktrees/") {
-                if let Some(worktrees_dir) =
-                    project_path.ancestors().find(|p| p.ends_with(".worktrees"))
-                {
-                    if let Some(repo_root) = worktrees_dir.parent() {
+        let project_path = PathBuf::from(&mapping.project_path);
+        if let Err(e) =
+            cleanup_image_cache(&project_path, state.config().image_cache_retention).await
+        {
+            warn!(error = %e, "Failed to clean image cache during close");
+        }
+
+        if mapping.project_path.contains("/.worktrees/") {
+            if let Some(worktrees_dir) =
+                project_path.ancestors().find(|p| p.ends_with(".worktrees"))
+            {
+                if let Some(repo_root) = worktrees_dir.parent() {
+                    if let Err(e) =
+                        crate::git::worktree::remove_worktree(repo_root, &project_path).await
+                    {
+                        warn!(error = %e, "Failed to remove worktree during close");
+                    }
+                    if let Some(wt_name) = project_path.file_name().and_then(|n| n.to_str()) {
+                        let branch_name = format!("wt/{}", wt_name);
                         if let Err(e) =
-                            crate::git::worktree::remove_worktree(repo_root, &project_path).await
+                            crate::git::worktree::delete_branch(repo_root, &branch_name).await
                         {
-                            warn!(error = %e, "Failed to remove worktree during close");
-                        }
-                        if let Some(wt_name) = project_path.file_name().and_then(|n| n.to_str()) {
-                            let branch_name = format!("wt/{}", wt_name);
-                            if let Err(e) =
-                                crate::git::worktree::delete_branch(repo_root, &branch_name).await
-                            {
-                                warn!(error = %e, "Failed to delete branch during close");
-                            }
+                            warn!(error = %e, "Failed to delete branch during close");
                         }
                     }
                 }
             }
         }
+    }
 
-        if let Err(e) = state.topic_store.delete_mapping(chat_id.0, topic_id).await {
-            warn!(error = %e, "Failed to delete topic mapping during close");
-        }
-
-        if let Err(e) = bot
-            .delete_forum_topic(chat_id, ThreadId(MessageId(topic_id)))
-            .await
-        {
-            warn!(error = %e, "Failed to delete forum topic during close");
-        }
+    if let Err(e) = state.topic_store.delete_mapping(chat_id.0, topic_id).await {
+        warn!(error = %e, "Failed to delete topic mapping during close");
     }
 
-    Ok(())
+    if let Err(e) = bot
+        .delete_forum_topic(chat_id, ThreadId(MessageId(topic_id)))
+        .await
+    {
+        warn!(error = %e, "Failed to delete forum topic during close");
+    }
 }
 
 #[cfg(test)]
@@ -184,6 +262,7 @@ mod tests {
             telegram_bot_token: "test_token".to_string(),
             telegram_chat_ids: vec![-1001234567890],
             telegram_allowed_users: vec![],
+            telegram_admin_users: vec![],
             handle_general_topic: true,
             opencode_path: PathBuf::from("opencode"),
             opencode_max_instances: 10,
@@ -202,6 +281,56 @@ mod tests {
             opencode_config_path: PathBuf::from("/tmp/oc-config"),
             container_port: 8080,
             env_passthrough: vec![],
+            topic_name_strategy: crate::config::TopicNameStrategy::Leaf,
+            show_usage: false,
+            duplicate_response_window: Duration::from_secs(10),
+            container_dns: vec![],
+            container_dns_search: vec![],
+            fallback_model: None,
+            max_topics_per_chat: 50,
+            transcription_url: None,
+            transcription_api_key: None,
+            transcription_model: "whisper-1".to_string(),
+            audio_mode: crate::config::AudioMode::Transcribe,
+            permission_timeout: Duration::from_secs(600),
+            opencode_request_timeout: Duration::from_secs(30),
+            resurrection_timeout: Duration::from_secs(30),
+            resurrection_wake_delay: Duration::from_secs(3),
+            show_file_edits: false,
+            allowed_upload_mime: vec![],
+            dedup_expiry: Duration::from_secs(30),
+            max_reconnect_attempts: 5,
+            base_reconnect_delay: Duration::from_secs(1),
+            max_reconnect_delay: Duration::from_secs(16),
+            max_resume_age: Duration::ZERO,
+            max_concurrent_downloads: 4,
+            max_message_parts: 10,
+            api_max_body_bytes: 1_048_576,
+            api_port: 0,
+            api_host: [127, 0, 0, 1].into(),
+            api_key: None,
+            image_cache_retention: Duration::from_secs(86400),
+            slow_start_nudge: Duration::from_secs(15),
+            rewrite_workspace_paths: false,
+            opencode_socket_path: None,
+            health_path: "/global/health".to_string(),
+            health_check: Default::default(),
+            collect_feedback: false,
+            container_extra_args: vec![],
+            response_filters: vec![],
+            reply_to_prompts: false,
+            preferred_photo_size: crate::config::PhotoSizePreference::Largest,
+            max_image_dimension: 1280,
+            max_image_bytes: 1_048_576,
+            default_image_prompt: None,
+            validate_project: false,
+            max_response_chars: 0,
+            collapse_repeated_tools: false,
+            stream_mode: crate::config::StreamMode::Batched,
+            durable_outbound: false,
+            keep_stopped_containers: false,
+            container_memory_mb: 0,
+            workspace_mount: "/workspace".to_string(),
         };
 
         let orchestrator_store = OrchestratorStore::new(&config.orchestrator_db_path)
@@ -281,6 +410,29 @@ mod tests {
             .contains("must be used in a forum topic"));
     }
 
+    #[test]
+    fn test_parse_force_flag() {
+        assert!(!parse_force_flag("").unwrap());
+        assert!(!parse_force_flag("  ").unwrap());
+        assert!(parse_force_flag("force").unwrap());
+        assert!(parse_force_flag("now").is_err());
+    }
+
+    #[test]
+    fn test_requires_confirmation_for_active_session() {
+        assert!(requires_confirmation(None, true));
+        assert!(requires_confirmation(Some(Duration::from_secs(30)), false));
+    }
+
+    #[test]
+    fn test_no_confirmation_for_idle_session() {
+        assert!(!requires_confirmation(None, false));
+        assert!(!requires_confirmation(
+            Some(CLOSE_CONFIRM_ACTIVITY_WINDOW + Duration::from_secs(1)),
+            false
+        ));
+    }
+
     #[test]
     fn test_close_callback_data_format() {
         let confirm_data = "close:123:confirm";
@@ -350,6 +502,11 @@ mod tests {
             session_id: Some("ses_test".to_string()),
             instance_id: Some("inst_test".to_string()),
             topic_name_updated: false,
+            read_only: false,
+            output_topic_id: None,
+            system_prompt_mtime: None,
+            locale: None,
+            last_activity_at: None,
             created_at: now,
             updated_at: now,
         };
@@ -0,0 +1,292 @@
+//! /compare command handler
+//!
+//! Sends one prompt to two models at once, each in its own transient session
+//! on the topic's instance, and posts both responses labeled with their
+//! model. The transient sessions are deleted once the responses are in, so
+//! the topic's own session is left untouched.
+
+use crate::bot::{BotState, Command};
+use crate::opencode::{MessageResponse, OpenCodeClient};
+use crate::project_config::{command_enabled, COMMAND_DISABLED_MESSAGE};
+use crate::telegram::markdown::split_message;
+use crate::types::error::{OutpostError, Result};
+use crate::types::opencode::{MessagePart, ModelRef};
+use std::path::Path;
+use std::sync::Arc;
+use teloxide::prelude::*;
+use teloxide::types::{MessageId, ThreadId};
+use tracing::{debug, warn};
+
+const USAGE: &str = "Usage: /compare <provider/modelA> <provider/modelB> <prompt>";
+
+/// Telegram's message length limit
+const MAX_MESSAGE_LEN: usize = 4096;
+
+/// Labels for the two compared responses, in argument order.
+const LABELS: [&str; 2] = ["A", "B"];
+
+fn get_topic_id(msg: &Message) -> Result<i32> {
+    let thread_id = msg.thread_id.ok_or_else(|| {
+        OutpostError::telegram_error("This command must be used in a forum topic")
+    })?;
+
+    if thread_id.0 .0 == 1 {
+        return Err(OutpostError::telegram_error(
+            "Cannot compare models in the General topic",
+        ));
+    }
+
+    Ok(thread_id.0 .0)
+}
+
+/// Parsed `/compare` arguments.
+#[derive(Debug, PartialEq)]
+struct CompareRequest {
+    models: [String; 2],
+    prompt: String,
+}
+
+/// Split `<modelA> <modelB> <prompt>`. Both models must be `provider/model`;
+/// the prompt keeps its inner formatting.
+fn parse_compare_args(args: &str) -> Option<CompareRequest> {
+    let (model_a, rest) = args.trim().split_once(char::is_whitespace)?;
+    let (model_b, prompt) = rest.trim_start().split_once(char::is_whitespace)?;
+    let prompt = prompt.trim();
+    if prompt.is_empty() || ModelRef::parse(model_a).is_none() || ModelRef::parse(model_b).is_none()
+    {
+        return None;
+    }
+    Some(CompareRequest {
+        models: [model_a.to_string(), model_b.to_string()],
+        prompt: prompt.to_string(),
+    })
+}
+
+/// Text parts of a response, joined by newlines.
+fn response_text(response: &MessageResponse) -> String {
+    response
+        .message
+        .content
+        .iter()
+        .filter_map(|part| match part {
+            MessagePart::Text { text } => Some(text.as_str()),
+            MessagePart::File(_) => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Ask `model` for a response in a transient session, deleting the session
+/// afterwards whether or not the prompt succeeded.
+async fn ask_model(
+    client: &OpenCodeClient,
+    project_path: &Path,
+    model: &str,
+    prompt: &str,
+) -> anyhow::Result<String> {
+    let session = client.create_session(project_path).await?;
+    debug!(session_id = %session.id, model = %model, "Created transient session for /compare");
+
+    let result = client
+        .send_message_with_model(&session.id, prompt, Some(model))
+        .await;
+
+    if let Err(e) = client.delete_session(&session.id).await {
+        warn!(session_id = %session.id, error = %e, "Failed to delete transient /compare session");
+    }
+
+    Ok(response_text(&result?))
+}
+
+/// Send the prompt to both models in parallel. Results are in argument order.
+async fn compare_models(
+    client: &OpenCodeClient,
+    project_path: &Path,
+    request: &CompareRequest,
+) -> [anyhow::Result<String>; 2] {
+    let [model_a, model_b] = &request.models;
+    let (a, b) = tokio::join!(
+        ask_model(client, project_path, model_a, &request.prompt),
+        ask_model(client, project_path, model_b, &request.prompt),
+    );
+    [a, b]
+}
+
+/// Format one model's response under its label.
+fn format_labeled_response(label: &str, model: &str, result: &anyhow::Result<String>) -> String {
+    match result {
+        Ok(text) if text.trim().is_empty() => {
+            format!("🤖 {} · {}\n\n(empty response)", label, model)
+        }
+        Ok(text) => format!("🤖 {} · {}\n\n{}", label, model, text),
+        Err(e) => format!("⚠️ {} · {} failed: {}", label, model, e),
+    }
+}
+
+/// Handle /compare command
+pub async fn handle_compare(
+    bot: Bot,
+    msg: Message,
+    cmd: Command,
+    state: Arc<BotState>,
+) -> Result<()> {
+    debug!(
+        chat_id = msg.chat.id.0,
+        topic_id = ?msg.thread_id.map(|t| t.0 .0),
+        sender_id = ?msg.from.as_ref().map(|u| u.id.0),
+        "Handling /compare"
+    );
+
+    let args = match cmd {
+        Command::Compare(args) => args,
+        _ => return Err(OutpostError::telegram_error("Invalid command")),
+    };
+
+    let topic_id = get_topic_id(&msg)?;
+    let reply = |text: String| {
+        bot.send_message(msg.chat.id, text)
+            .message_thread_id(ThreadId(MessageId(topic_id)))
+    };
+
+    let mapping = state
+        .topic_store
+        .get_mapping(msg.chat.id.0, topic_id)
+        .await
+        .map_err(|e| OutpostError::database_error(e.to_string()))?
+        .ok_or_else(|| OutpostError::telegram_error("No active connection in this topic"))?;
+
+    let project_path = Path::new(&mapping.project_path);
+    if !command_enabled(project_path, "compare") {
+        reply(COMMAND_DISABLED_MESSAGE.to_string())
+            .await
+            .map_err(|e| OutpostError::telegram_error(e.to_string()))?;
+        return Ok(());
+    }
+
+    let Some(request) = parse_compare_args(&args) else {
+        reply(USAGE.to_string())
+            .await
+            .map_err(|e| OutpostError::telegram_error(e.to_string()))?;
+        return Ok(());
+    };
+
+    let instance_id = mapping
+        .instance_id
+        .clone()
+        .ok_or_else(|| OutpostError::telegram_error("No instance for this topic"))?;
+    let instance = state
+        .orchestrator_store
+        .get_instance(&instance_id)
+        .await
+        .map_err(|e| OutpostError::database_error(e.to_string()))?
+        .ok_or_else(|| OutpostError::telegram_error("Instance not found"))?;
+    let client = OpenCodeClient::for_port(&state.config(), instance.port)
+        .map_err(|e| OutpostError::config_error(e.to_string()))?;
+
+    reply(format!(
+        "⚖️ Comparing {} and {}...",
+        request.models[0], request.models[1]
+    ))
+    .await
+    .map_err(|e| OutpostError::telegram_error(e.to_string()))?;
+
+    let results = compare_models(&client, project_path, &request).await;
+    for ((label, model), result) in LABELS.iter().zip(&request.models).zip(&results) {
+        let text = format_labeled_response(label, model, result);
+        for chunk in split_message(&text, MAX_MESSAGE_LEN) {
+            reply(chunk)
+                .await
+                .map_err(|e| OutpostError::telegram_error(e.to_string()))?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{body_partial_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn prompt_response(text: &str) -> ResponseTemplate {
+        ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "message": {"role": "assistant", "content": [{"type": "text", "text": text}]},
+            "metadata": {"id": "msg-1", "role": "assistant", "model": null}
+        }))
+    }
+
+    #[test]
+    fn test_parse_compare_args() {
+        let request =
+            parse_compare_args("  openai/gpt-4o  anthropic/claude  explain\n  this ").unwrap();
+        assert_eq!(
+            request.models,
+            ["openai/gpt-4o".to_string(), "anthropic/claude".to_string()]
+        );
+        assert_eq!(request.prompt, "explain\n  this");
+
+        assert!(parse_compare_args("").is_none());
+        assert!(parse_compare_args("openai/a openai/b").is_none());
+        assert!(parse_compare_args("openai/a openai/b   ").is_none());
+        assert!(parse_compare_args("model-a openai/b say hi").is_none());
+    }
+
+    #[test]
+    fn test_format_labeled_response() {
+        let ok = format_labeled_response("A", "model-a", &Ok("hello".to_string()));
+        assert_eq!(ok, "🤖 A · model-a\n\nhello");
+
+        let empty = format_labeled_response("B", "model-b", &Ok("  ".to_string()));
+        assert!(empty.contains("(empty response)"));
+
+        let failed = format_labeled_response("B", "model-b", &Err(anyhow::anyhow!("HTTP 500")));
+        assert_eq!(failed, "⚠️ B · model-b failed: HTTP 500");
+    }
+
+    #[tokio::test]
+    async fn test_compare_models_dispatches_to_both_and_cleans_up() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/session"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": "ses_cmp",
+                "title": null,
+                "created": 1640000000,
+                "updated": 1640000000
+            })))
+            .expect(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/session/ses_cmp/prompt"))
+            .and(body_partial_json(serde_json::json!({
+                "model": {"providerID": "openai", "modelID": "model-a"}
+            })))
+            .respond_with(prompt_response("answer from A"))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/session/ses_cmp/prompt"))
+            .and(body_partial_json(serde_json::json!({
+                "model": {"providerID": "anthropic", "modelID": "model-b"}
+            })))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&server)
+            .await;
+        Mock::given(method("DELETE"))
+            .and(path("/session/ses_cmp"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let client = OpenCodeClient::with_retry(&server.uri(), 0, std::time::Duration::ZERO);
+        let request = parse_compare_args("openai/model-a anthropic/model-b say hi").unwrap();
+        let [a, b] = compare_models(&client, Path::new("/workspace"), &request).await;
+
+        assert_eq!(a.unwrap(), "answer from A");
+        assert!(b.is_err());
+    }
+}
@@ -0,0 +1,111 @@
+//! /cordon command handler
+//!
+//! Cordoning a project puts it into maintenance: its running instances are
+//! stopped, they are not spawned or restarted again, and prompts in its topics
+//! get a maintenance reply until the project is uncordoned (see `uncordon`).
+//! Limited to `TELEGRAM_ADMIN_USERS`.
+
+use crate::bot::handlers::access::{is_admin_sender, ADMIN_ONLY_MESSAGE};
+use crate::bot::{BotState, Command};
+use crate::types::error::{OutpostError, Result};
+use std::sync::Arc;
+use teloxide::prelude::*;
+use tracing::debug;
+
+fn format_cordoned_list(projects: &[String]) -> String {
+    if projects.is_empty() {
+        "No projects are cordoned.\n\nUsage: /cordon <project>".to_string()
+    } else {
+        format!(
+            "Cordoned projects:\n{}\n\nUse /uncordon <project> to resume one.",
+            projects
+                .iter()
+                .map(|p| format!("- {}", p))
+                .collect::<Vec<_>>()
+                .join("\n")
+        )
+    }
+}
+
+fn format_cordon_result(project: &str, stopped: &[String]) -> String {
+    let mut output = format!(
+        "Project '{}' cordoned. Its instances will not be started until /uncordon {}.",
+        project, project
+    );
+    if !stopped.is_empty() {
+        output.push_str(&format!("\nStopped: {}", stopped.join(", ")));
+    }
+    output
+}
+
+/// Handle /cordon command
+pub async fn handle_cordon(
+    bot: Bot,
+    msg: Message,
+    cmd: Command,
+    state: Arc<BotState>,
+) -> Result<()> {
+    debug!(
+        chat_id = msg.chat.id.0,
+        topic_id = ?msg.thread_id.map(|t| t.0 .0),
+        sender_id = ?msg.from.as_ref().map(|u| u.id.0),
+        "Handling /cordon"
+    );
+    if !is_admin_sender(&state.config(), msg.from.as_ref()) {
+        bot.send_message(msg.chat.id, ADMIN_ONLY_MESSAGE)
+            .await
+            .map_err(|e| OutpostError::telegram_error(e.to_string()))?;
+        return Ok(());
+    }
+
+    let project = match cmd {
+        Command::Cordon(project) => project.trim().to_string(),
+        _ => return Err(OutpostError::config_error("Invalid command type")),
+    };
+
+    let output = if project.is_empty() {
+        let projects = state
+            .instance_manager
+            .cordoned_projects()
+            .await
+            .map_err(|e| OutpostError::database_error(e.to_string()))?;
+        format_cordoned_list(&projects)
+    } else {
+        let stopped = state
+            .instance_manager
+            .cordon_project(&project)
+            .await
+            .map_err(|e| OutpostError::database_error(e.to_string()))?;
+        format_cordon_result(&project, &stopped)
+    };
+
+    bot.send_message(msg.chat.id, output)
+        .await
+        .map_err(|e| OutpostError::telegram_error(e.to_string()))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_cordoned_list() {
+        assert!(format_cordoned_list(&[]).contains("No projects are cordoned"));
+
+        let output = format_cordoned_list(&["api".to_string(), "web".to_string()]);
+        assert!(output.contains("- api\n- web"));
+        assert!(output.contains("/uncordon <project>"));
+    }
+
+    #[test]
+    fn test_format_cordon_result() {
+        let output = format_cordon_result("api", &[]);
+        assert!(output.contains("Project 'api' cordoned"));
+        assert!(!output.contains("Stopped"));
+
+        let output = format_cordon_result("api", &["inst_1".to_string()]);
+        assert!(output.contains("Stopped: inst_1"));
+    }
+}
@@ -0,0 +1,180 @@
+//! /debug command handler
+
+use crate::bot::{BotState, Command};
+use crate::opencode::stream_handler::{ConnectionState, StreamHandler, SubscriptionStatus};
+use crate::types::error::{OutpostError, Result};
+use std::sync::Arc;
+use teloxide::prelude::*;
+use tracing::debug;
+
+fn connection_state_label(state: ConnectionState) -> &'static str {
+    match state {
+        ConnectionState::Connecting => "connecting",
+        ConnectionState::Connected => "connected",
+        ConnectionState::Reconnecting => "reconnecting",
+        ConnectionState::Failed => "failed",
+    }
+}
+
+/// Which section of diagnostics to show.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DebugSection {
+    All,
+    Stream,
+}
+
+/// Parse `/debug [stream]` arguments.
+fn parse_debug_args(args: &str) -> Result<DebugSection> {
+    match args.trim() {
+        "" => Ok(DebugSection::All),
+        "stream" => Ok(DebugSection::Stream),
+        other => Err(OutpostError::telegram_error(format!(
+            "Unknown debug section '{}'. Usage: /debug [stream]",
+            other
+        ))),
+    }
+}
+
+/// Format SSE subscription diagnostics for display
+fn format_debug_output(subscriptions: &[SubscriptionStatus]) -> String {
+    let mut output = String::from("Debug Info\n\n");
+
+    output.push_str(&format!("SSE Subscriptions: {}\n", subscriptions.len()));
+    for sub in subscriptions {
+        output.push_str(&format!(
+            "- {} ({}, {} reconnects)\n",
+            sub.session_id,
+            connection_state_label(sub.state),
+            sub.reconnect_attempts
+        ));
+    }
+
+    output
+}
+
+/// Format per-subscription event counters for `/debug stream`
+fn format_stream_metrics(subscriptions: &[SubscriptionStatus]) -> String {
+    let mut output = String::from("Stream Metrics\n\n");
+
+    if subscriptions.is_empty() {
+        output.push_str("No active subscriptions\n");
+    }
+    for sub in subscriptions {
+        let last_event = match sub.last_event_age() {
+            Some(age) => format!("{}s ago", age.as_secs()),
+            None => "never".to_string(),
+        };
+        output.push_str(&format!(
+            "- {}: {} events, {} bytes, {:.2} events/s, last event {}\n",
+            sub.session_id,
+            sub.events_received,
+            sub.bytes_received,
+            sub.events_per_sec(),
+            last_event
+        ));
+    }
+
+    output
+}
+
+/// Handle /debug command
+pub async fn handle_debug(
+    bot: Bot,
+    msg: Message,
+    cmd: Command,
+    _state: Arc<BotState>,
+    stream_handler: Arc<StreamHandler>,
+) -> Result<()> {
+    debug!(
+        chat_id = msg.chat.id.0,
+        topic_id = ?msg.thread_id.map(|t| t.0 .0),
+        sender_id = ?msg.from.as_ref().map(|u| u.id.0),
+        "Handling /debug"
+    );
+    let section = match cmd {
+        Command::Debug(args) => parse_debug_args(&args)?,
+        _ => return Err(OutpostError::config_error("Invalid command type")),
+    };
+    let subscriptions = stream_handler.subscription_status();
+    debug!(
+        subscription_count = subscriptions.len(),
+        "Subscription status fetched"
+    );
+
+    let output = match section {
+        DebugSection::All => format!(
+            "{}\n{}",
+            format_debug_output(&subscriptions),
+            format_stream_metrics(&subscriptions)
+        ),
+        DebugSection::Stream => format_stream_metrics(&subscriptions),
+    };
+    bot.send_message(msg.chat.id, output)
+        .await
+        .map_err(|e| OutpostError::telegram_error(e.to_string()))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_format_debug_output_empty() {
+        let output = format_debug_output(&[]);
+        assert!(output.contains("SSE Subscriptions: 0"));
+    }
+
+    fn status(session_id: &str, state: ConnectionState, reconnects: u32) -> SubscriptionStatus {
+        SubscriptionStatus {
+            session_id: session_id.to_string(),
+            state,
+            reconnect_attempts: reconnects,
+            events_received: 0,
+            bytes_received: 0,
+            subscribed_at: Instant::now(),
+            last_event_at: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_debug_args() {
+        assert_eq!(parse_debug_args("").unwrap(), DebugSection::All);
+        assert_eq!(parse_debug_args(" stream ").unwrap(), DebugSection::Stream);
+        assert!(parse_debug_args("bogus")
+            .unwrap_err()
+            .to_string()
+            .contains("Usage: /debug [stream]"));
+    }
+
+    #[test]
+    fn test_format_stream_metrics() {
+        assert!(format_stream_metrics(&[]).contains("No active subscriptions"));
+
+        let mut busy = status("ses_a", ConnectionState::Connected, 0);
+        busy.events_received = 20;
+        busy.bytes_received = 4096;
+        busy.subscribed_at = Instant::now() - Duration::from_secs(10);
+        busy.last_event_at = Some(Instant::now() - Duration::from_secs(3));
+        let idle = status("ses_b", ConnectionState::Connecting, 0);
+
+        let output = format_stream_metrics(&[busy, idle]);
+        assert!(output.contains("- ses_a: 20 events, 4096 bytes, 2.00 events/s, last event 3s ago"));
+        assert!(output.contains("- ses_b: 0 events, 0 bytes, 0.00 events/s, last event never"));
+    }
+
+    #[test]
+    fn test_format_debug_output_lists_subscriptions() {
+        let subscriptions = vec![
+            status("ses_a", ConnectionState::Connected, 0),
+            status("ses_b", ConnectionState::Reconnecting, 3),
+        ];
+
+        let output = format_debug_output(&subscriptions);
+        assert!(output.contains("SSE Subscriptions: 2"));
+        assert!(output.contains("- ses_a (connected, 0 reconnects)"));
+        assert!(output.contains("- ses_b (reconnecting, 3 reconnects)"));
+    }
+}
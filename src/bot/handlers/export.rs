@@ -0,0 +1,274 @@
+//! /export command handler
+//!
+//! Sends the topic's conversation as a Markdown file, built from the stream
+//! event log in the log DB: prompts sent from Telegram, the agent's text,
+//! tool calls with their results, and session errors.
+
+use crate::bot::handlers::session::format_timestamp;
+use crate::bot::{BotState, Command};
+use crate::db::log_store::{LoggedStreamEvent, StreamEventKind};
+use crate::project_config::{command_enabled, COMMAND_DISABLED_MESSAGE};
+use crate::types::error::{OutpostError, Result};
+use std::path::Path;
+use std::sync::Arc;
+use teloxide::prelude::*;
+use teloxide::types::{InputFile, MessageId, ThreadId};
+use tracing::{debug, warn};
+
+/// Tool results longer than this many characters are cut in the transcript.
+const MAX_EXPORTED_RESULT_CHARS: usize = 2000;
+
+fn get_topic_id(msg: &Message) -> Result<i32> {
+    let thread_id = msg.thread_id.ok_or_else(|| {
+        OutpostError::telegram_error("This command must be used in a forum topic")
+    })?;
+
+    // General topic has ThreadId(MessageId(1))
+    if thread_id.0 .0 == 1 {
+        return Err(OutpostError::telegram_error(
+            "This command must be used in a forum topic",
+        ));
+    }
+
+    Ok(thread_id.0 .0)
+}
+
+/// Wrap `content` in a code fence longer than any backtick run inside it.
+fn fenced(content: &str, lang: &str) -> String {
+    let longest_run = content.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest_run.max(2) + 1);
+    format!("{}{}\n{}\n{}", fence, lang, content.trim_end(), fence)
+}
+
+/// Format a topic's stream event log as a Markdown transcript.
+///
+/// Everything the agent produced after a prompt goes under one response
+/// heading, with consecutive text chunks joined into one paragraph. A change
+/// of session is marked with a rule.
+fn format_transcript(project_path: &str, events: &[LoggedStreamEvent]) -> String {
+    let mut blocks = vec![format!("# Transcript: {}", project_path)];
+    let mut session: Option<&str> = None;
+    let mut in_response = false;
+    let mut in_text = false;
+
+    for event in events {
+        if session.is_some_and(|id| id != event.session_id) {
+            blocks.push(format!("---\n\n_New session: {}_", event.session_id));
+            in_response = false;
+        }
+        session = Some(&event.session_id);
+
+        let time = format_timestamp(event.timestamp / 1000);
+        if event.kind == StreamEventKind::Prompt {
+            blocks.push(format!("## Prompt ({} UTC)\n\n{}", time, event.content));
+            in_response = false;
+            in_text = false;
+            continue;
+        }
+        if !in_response {
+            blocks.push(format!("## Response ({} UTC)", time));
+            in_response = true;
+            in_text = false;
+        }
+
+        match event.kind {
+            StreamEventKind::Text => {
+                match blocks.last_mut() {
+                    Some(block) if in_text => block.push_str(&event.content),
+                    _ => blocks.push(event.content.clone()),
+                }
+                in_text = true;
+                continue;
+            }
+            StreamEventKind::Tool => {
+                let (name, args) = event
+                    .content
+                    .split_once('\n')
+                    .unwrap_or((event.content.as_str(), ""));
+                let args = serde_json::from_str::<serde_json::Value>(args)
+                    .ok()
+                    .and_then(|value| serde_json::to_string_pretty(&value).ok())
+                    .unwrap_or_else(|| args.to_string());
+                let mut block = format!("**Tool:** `{}`", name);
+                if !args.trim().is_empty() {
+                    block.push_str("\n\n");
+                    block.push_str(&fenced(&args, "json"));
+                }
+                blocks.push(block);
+            }
+            StreamEventKind::ToolResult => {
+                let mut result: String = event
+                    .content
+                    .chars()
+                    .take(MAX_EXPORTED_RESULT_CHARS)
+                    .collect();
+                if result.len() < event.content.len() {
+                    result.push_str("\n...");
+                }
+                blocks.push(format!("**Result:**\n\n{}", fenced(&result, "")));
+            }
+            StreamEventKind::Error => {
+                blocks.push(format!("**Error:** {}", event.content));
+            }
+            StreamEventKind::Prompt => unreachable!("prompts are handled above"),
+        }
+        in_text = false;
+    }
+
+    let blocks: Vec<&str> = blocks.iter().map(|block| block.trim_end()).collect();
+    format!("{}\n", blocks.join("\n\n"))
+}
+
+/// Name of the uploaded file, after the project directory.
+fn transcript_file_name(project_path: &str) -> String {
+    let name = Path::new(project_path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "topic".to_string());
+    format!("{}-transcript.md", name)
+}
+
+/// Handle /export command
+pub async fn handle_export(
+    bot: Bot,
+    msg: Message,
+    _cmd: Command,
+    state: Arc<BotState>,
+) -> Result<()> {
+    debug!(
+        chat_id = msg.chat.id.0,
+        topic_id = ?msg.thread_id.map(|t| t.0 .0),
+        sender_id = ?msg.from.as_ref().map(|u| u.id.0),
+        "Handling /export"
+    );
+    let topic_id = get_topic_id(&msg)?;
+    let chat_id = msg.chat.id;
+
+    let mapping = state
+        .topic_store
+        .get_mapping(chat_id.0, topic_id)
+        .await
+        .map_err(|e| OutpostError::database_error(e.to_string()))?
+        .ok_or_else(|| OutpostError::telegram_error("No active connection in this topic"))?;
+
+    if !command_enabled(Path::new(&mapping.project_path), "export") {
+        bot.send_message(chat_id, COMMAND_DISABLED_MESSAGE)
+            .message_thread_id(ThreadId(MessageId(topic_id)))
+            .await
+            .map_err(|e| OutpostError::telegram_error(e.to_string()))?;
+        return Ok(());
+    }
+
+    let events = match state.log_store.as_ref() {
+        Some(log_store) => log_store
+            .stream_events(chat_id.0, topic_id)
+            .await
+            .map_err(|e| OutpostError::database_error(e.to_string()))?,
+        None => Vec::new(),
+    };
+    if events.is_empty() {
+        bot.send_message(
+            chat_id,
+            "No conversation history to export in this topic yet.",
+        )
+        .message_thread_id(ThreadId(MessageId(topic_id)))
+        .await
+        .map_err(|e| OutpostError::telegram_error(e.to_string()))?;
+        return Ok(());
+    }
+    debug!(
+        topic_id = topic_id,
+        events = events.len(),
+        "Exporting transcript"
+    );
+
+    let transcript = format_transcript(&mapping.project_path, &events);
+    let path = std::env::temp_dir().join(format!(
+        "oc-outpost-transcript-{}-{}.md",
+        topic_id,
+        uuid::Uuid::new_v4().simple()
+    ));
+    tokio::fs::write(&path, transcript)
+        .await
+        .map_err(|e| OutpostError::io_error(e.to_string()))?;
+
+    let sent = bot
+        .send_document(
+            chat_id,
+            InputFile::file(&path).file_name(transcript_file_name(&mapping.project_path)),
+        )
+        .message_thread_id(ThreadId(MessageId(topic_id)))
+        .await;
+    if let Err(e) = tokio::fs::remove_file(&path).await {
+        warn!(path = %path.display(), error = %e, "Failed to remove exported transcript");
+    }
+    sent.map_err(|e| OutpostError::telegram_error(e.to_string()))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(session_id: &str, kind: StreamEventKind, content: &str) -> LoggedStreamEvent {
+        LoggedStreamEvent {
+            session_id: session_id.to_string(),
+            kind,
+            content: content.to_string(),
+            timestamp: 1_700_000_000_000,
+        }
+    }
+
+    #[test]
+    fn test_format_transcript() {
+        let events = vec![
+            event("ses_1", StreamEventKind::Prompt, "Fix the build"),
+            event("ses_1", StreamEventKind::Text, "Looking at "),
+            event("ses_1", StreamEventKind::Text, "the error."),
+            event(
+                "ses_1",
+                StreamEventKind::Tool,
+                "read_file\n{\"filePath\":\"src/main.rs\"}",
+            ),
+            event("ses_1", StreamEventKind::ToolResult, "fn main() {}"),
+            event("ses_1", StreamEventKind::Text, "Fixed."),
+            event("ses_2", StreamEventKind::Prompt, "Thanks"),
+            event("ses_2", StreamEventKind::Error, "rate limited"),
+        ];
+
+        let transcript = format_transcript("/projects/api", &events);
+
+        assert_eq!(
+            transcript,
+            "# Transcript: /projects/api\n\n\
+             ## Prompt (2023-11-14 22:13:20 UTC)\n\n\
+             Fix the build\n\n\
+             ## Response (2023-11-14 22:13:20 UTC)\n\n\
+             Looking at the error.\n\n\
+             **Tool:** `read_file`\n\n\
+             ```json\n{\n  \"filePath\": \"src/main.rs\"\n}\n```\n\n\
+             **Result:**\n\n\
+             ```\nfn main() {}\n```\n\n\
+             Fixed.\n\n\
+             ---\n\n\
+             _New session: ses_2_\n\n\
+             ## Prompt (2023-11-14 22:13:20 UTC)\n\n\
+             Thanks\n\n\
+             ## Response (2023-11-14 22:13:20 UTC)\n\n\
+             **Error:** rate limited\n"
+        );
+    }
+
+    #[test]
+    fn test_fenced_outlasts_backticks_in_content() {
+        assert_eq!(fenced("a ``` b", ""), "````\na ``` b\n````");
+        assert_eq!(fenced("plain", "json"), "```json\nplain\n```");
+    }
+
+    #[test]
+    fn test_transcript_file_name() {
+        assert_eq!(transcript_file_name("/projects/api"), "api-transcript.md");
+        assert_eq!(transcript_file_name("/"), "topic-transcript.md");
+    }
+}
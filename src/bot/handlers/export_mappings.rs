@@ -0,0 +1,71 @@
+//! /export_mappings command handler
+//!
+//! Back up or migrate the topic → project → session mappings of a chat:
+//! sends the chat's mappings as a JSON file that `/import_mappings` reads
+//! back. Limited to `TELEGRAM_ADMIN_USERS` and never crosses chats.
+
+use crate::bot::handlers::access::{is_admin_sender, ADMIN_ONLY_MESSAGE};
+use crate::bot::{BotState, Command};
+use crate::types::error::{OutpostError, Result};
+use std::sync::Arc;
+use teloxide::prelude::*;
+use teloxide::types::InputFile;
+use tracing::{debug, info};
+
+/// File name of the `/export_mappings` document.
+const EXPORT_FILE_NAME: &str = "topic-mappings.json";
+
+/// Send `text` in the chat, in the command's topic if it has one.
+async fn reply(bot: &Bot, msg: &Message, text: String) -> Result<()> {
+    let mut request = bot.send_message(msg.chat.id, text);
+    if let Some(thread_id) = msg.thread_id {
+        request = request.message_thread_id(thread_id);
+    }
+    request
+        .await
+        .map_err(|e| OutpostError::telegram_error(e.to_string()))?;
+    Ok(())
+}
+
+/// Handle /export_mappings command
+pub async fn handle_export_mappings(
+    bot: Bot,
+    msg: Message,
+    _cmd: Command,
+    state: Arc<BotState>,
+) -> Result<()> {
+    debug!(
+        chat_id = msg.chat.id.0,
+        topic_id = ?msg.thread_id.map(|t| t.0 .0),
+        sender_id = ?msg.from.as_ref().map(|u| u.id.0),
+        "Handling /export_mappings"
+    );
+    if !is_admin_sender(&state.config(), msg.from.as_ref()) {
+        return reply(&bot, &msg, ADMIN_ONLY_MESSAGE.to_string()).await;
+    }
+
+    let mappings = state
+        .topic_store
+        .export_chat(msg.chat.id.0)
+        .await
+        .map_err(|e| OutpostError::database_error(e.to_string()))?;
+    if mappings.is_empty() {
+        return reply(&bot, &msg, "No topic mappings to export.".to_string()).await;
+    }
+    let json =
+        serde_json::to_vec_pretty(&mappings).map_err(|e| OutpostError::io_error(e.to_string()))?;
+
+    let mut request = bot.send_document(
+        msg.chat.id,
+        InputFile::memory(json).file_name(EXPORT_FILE_NAME),
+    );
+    if let Some(thread_id) = msg.thread_id {
+        request = request.message_thread_id(thread_id);
+    }
+    request
+        .await
+        .map_err(|e| OutpostError::telegram_error(e.to_string()))?;
+    info!(count = mappings.len(), "Topic mappings exported");
+
+    Ok(())
+}
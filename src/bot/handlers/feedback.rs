@@ -0,0 +1,176 @@
+//! 👍/👎 feedback on completed responses.
+//!
+//! With `COLLECT_FEEDBACK` enabled, a small prompt with rating buttons is
+//! posted after each completed assistant message. The prompt is recorded in
+//! the log DB so a button press can be tied back to the session and message;
+//! totals are shown by `/stats`.
+
+use crate::bot::BotState;
+use crate::db::log_store::FeedbackRating;
+use crate::types::error::{OutpostError, Result};
+use std::sync::Arc;
+use teloxide::prelude::*;
+use teloxide::types::{InlineKeyboardButton, InlineKeyboardMarkup, MessageId, ThreadId};
+use tracing::{debug, warn};
+
+/// Callback data prefix for the rating buttons.
+pub const FEEDBACK_CALLBACK_PREFIX: &str = "fb";
+
+const FEEDBACK_PROMPT: &str = "How was this response?";
+
+fn feedback_keyboard() -> InlineKeyboardMarkup {
+    InlineKeyboardMarkup::new(vec![vec![
+        InlineKeyboardButton::callback("👍", format!("{}:up", FEEDBACK_CALLBACK_PREFIX)),
+        InlineKeyboardButton::callback("👎", format!("{}:down", FEEDBACK_CALLBACK_PREFIX)),
+    ]])
+}
+
+/// Parse `fb:up` / `fb:down` callback data.
+fn parse_feedback_callback_data(data: &str) -> Result<FeedbackRating> {
+    let (prefix, rating) = data
+        .split_once(':')
+        .ok_or_else(|| OutpostError::telegram_error("Invalid feedback callback data format"))?;
+    if prefix != FEEDBACK_CALLBACK_PREFIX {
+        return Err(OutpostError::telegram_error(
+            "Invalid feedback callback data format",
+        ));
+    }
+    rating
+        .parse()
+        .map_err(|_| OutpostError::telegram_error("Invalid rating in callback data"))
+}
+
+fn format_rated_message(rating: FeedbackRating) -> String {
+    let emoji = match rating {
+        FeedbackRating::Up => "👍",
+        FeedbackRating::Down => "👎",
+    };
+    format!("Rated {} - thanks for the feedback!", emoji)
+}
+
+/// Post the rating buttons for OpenCode message `message_id` in `thread_id`.
+/// Does nothing when feedback collection is off or there is no log DB.
+pub async fn send_feedback_prompt(
+    bot: &Bot,
+    chat_id: ChatId,
+    thread_id: i32,
+    session_id: &str,
+    message_id: &str,
+    state: &BotState,
+) -> Result<()> {
+    if !state.config().collect_feedback {
+        return Ok(());
+    }
+    let Some(log_store) = state.log_store.as_ref() else {
+        return Ok(());
+    };
+    if log_store
+        .has_feedback_prompt(message_id)
+        .await
+        .map_err(|e| OutpostError::database_error(e.to_string()))?
+    {
+        return Ok(());
+    }
+
+    debug!(topic_id = thread_id, session_id = %session_id, message_id = %message_id, "Sending feedback prompt");
+    let sent = bot
+        .send_message(chat_id, FEEDBACK_PROMPT)
+        .message_thread_id(ThreadId(MessageId(thread_id)))
+        .reply_markup(feedback_keyboard())
+        .await
+        .map_err(|e| OutpostError::telegram_error(e.to_string()))?;
+
+    log_store
+        .record_feedback_prompt(chat_id.0, sent.id.0, session_id, message_id)
+        .await
+        .map_err(|e| OutpostError::database_error(e.to_string()))
+}
+
+/// Handle a 👍/👎 button press.
+pub async fn handle_feedback_callback(
+    bot: Bot,
+    q: CallbackQuery,
+    state: Arc<BotState>,
+) -> Result<()> {
+    debug!(callback_data = ?q.data, "Handling feedback callback");
+    let data = q
+        .data
+        .as_deref()
+        .ok_or_else(|| OutpostError::telegram_error("No callback data"))?;
+    let rating = parse_feedback_callback_data(data)?;
+
+    let (Some(message), Some(log_store)) = (q.message.as_ref(), state.log_store.as_ref()) else {
+        let _ = bot
+            .answer_callback_query(q.id)
+            .text("Feedback is not available.")
+            .await;
+        return Ok(());
+    };
+    let chat_id = message.chat().id;
+    let prompt_id = message.id();
+
+    let recorded = log_store
+        .rate_feedback(chat_id.0, prompt_id.0, q.from.id.0 as i64, rating)
+        .await
+        .map_err(|e| OutpostError::database_error(e.to_string()))?;
+    if !recorded {
+        warn!(
+            chat_id = chat_id.0,
+            prompt_message_id = prompt_id.0,
+            "Feedback for unknown prompt"
+        );
+        let _ = bot
+            .answer_callback_query(q.id)
+            .text("This response can no longer be rated.")
+            .await;
+        return Ok(());
+    }
+
+    let _ = bot.answer_callback_query(q.id).await;
+    // Keep the buttons so the rating can still be changed
+    let _ = bot
+        .edit_message_text(chat_id, prompt_id, format_rated_message(rating))
+        .reply_markup(feedback_keyboard())
+        .await;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_feedback_callback_data() {
+        assert_eq!(
+            parse_feedback_callback_data("fb:up").unwrap(),
+            FeedbackRating::Up
+        );
+        assert_eq!(
+            parse_feedback_callback_data("fb:down").unwrap(),
+            FeedbackRating::Down
+        );
+        assert!(parse_feedback_callback_data("fb:meh").is_err());
+        assert!(parse_feedback_callback_data("perm:up").is_err());
+        assert!(parse_feedback_callback_data("fb").is_err());
+    }
+
+    #[test]
+    fn test_feedback_keyboard_callback_data() {
+        let keyboard = feedback_keyboard();
+        let data: Vec<String> = keyboard.inline_keyboard[0]
+            .iter()
+            .map(|button| match &button.kind {
+                teloxide::types::InlineKeyboardButtonKind::CallbackData(data) => data.clone(),
+                other => panic!("unexpected button kind: {:?}", other),
+            })
+            .collect();
+        assert_eq!(data, vec!["fb:up", "fb:down"]);
+    }
+
+    #[test]
+    fn test_format_rated_message() {
+        assert!(format_rated_message(FeedbackRating::Up).contains("👍"));
+        assert!(format_rated_message(FeedbackRating::Down).contains("👎"));
+    }
+}
@@ -15,13 +15,32 @@ fn format_general_help() -> String {
     "OpenCode Telegram Bot\n\n\
      Commands:\n\
      /new <name> - Create new project topic\n\
+     /new <name> --from <session> - Fork a session into a new topic\n\
      /sessions - List active sessions\n\
      /projects - List available projects\n\
      /status - Show bot status\n\
+     /stats [days] - Show usage statistics\n\
+     /cordon [project] - Put a project into maintenance\n\
+     /uncordon <project> - Resume a cordoned project\n\
+     /reload - Reload hot-reloadable config\n\
+     /export_mappings - Download all topic mappings as JSON\n\
+     /import_mappings - Import mappings from an exported file\n\
+     /debug [stream] - Show stream diagnostics\n\
+     /whoami - Show your ids and access status\n\
      /help - This help\n\n\
      In a topic:\n\
      /session - Show session info\n\
-     /close - Close topic and stop instance"
+     /export - Download the conversation as Markdown\n\
+     /retry_clean - Replay last prompt in a fresh session\n\
+     /compare <modelA> <modelB> <prompt> - Compare two models on one prompt\n\
+     /switch - Re-bind topic to a different project\n\
+     /observe [on|off] - Toggle read-only observer mode\n\
+     /output <topic_id>|off - Send agent output to another topic\n\
+     /lang <code>|off - Set the response language\n\
+     /restart - Restart the instance after repeated crashes\n\
+     /ping - Measure latency to the instance\n\
+     /close - Close topic and stop instance\n\
+     /archive - Archive topic and stop instance"
         .to_string()
 }
 
@@ -29,7 +48,17 @@ fn format_general_help() -> String {
 fn format_topic_help() -> String {
     "Topic Commands:\n\n\
      /session - Show session info\n\
-     /close - Close topic and stop instance\n\n\
+     /export - Download the conversation as Markdown\n\
+     /retry_clean - Replay last prompt in a fresh session\n\
+     /compare <modelA> <modelB> <prompt> - Compare two models on one prompt\n\
+     /switch - Re-bind topic to a different project\n\
+     /observe [on|off] - Toggle read-only observer mode\n\
+     /output <topic_id>|off - Send agent output to another topic\n\
+     /lang <code>|off - Set the response language\n\
+     /restart - Restart the instance after repeated crashes\n\
+     /ping - Measure latency to the instance\n\
+     /close - Close topic and stop instance\n\
+     /archive - Archive topic and stop instance\n\n\
      Use /help in General topic for all commands."
         .to_string()
 }
@@ -82,15 +111,36 @@ mod tests {
         // Verify commands section
         assert!(help.contains("Commands:"));
         assert!(help.contains("/new <name> - Create new project topic"));
+        assert!(help.contains("/new <name> --from <session> - Fork a session into a new topic"));
         assert!(help.contains("/sessions - List active sessions"));
         assert!(help.contains("/projects - List available projects"));
         assert!(help.contains("/status - Show bot status"));
+        assert!(help.contains("/stats [days] - Show usage statistics"));
+        assert!(help.contains("/cordon [project] - Put a project into maintenance"));
+        assert!(help.contains("/uncordon <project> - Resume a cordoned project"));
+        assert!(help.contains("/reload - Reload hot-reloadable config"));
+        assert!(help.contains("/export_mappings - Download all topic mappings as JSON"));
+        assert!(help.contains("/import_mappings - Import mappings from an exported file"));
+        assert!(help.contains("/debug [stream] - Show stream diagnostics"));
+        assert!(help.contains("/whoami - Show your ids and access status"));
         assert!(help.contains("/help - This help"));
 
         // Verify topic commands section
         assert!(help.contains("In a topic:"));
         assert!(help.contains("/session - Show session info"));
+        assert!(help.contains("/export - Download the conversation as Markdown"));
+        assert!(help.contains("/retry_clean - Replay last prompt in a fresh session"));
+        assert!(
+            help.contains("/compare <modelA> <modelB> <prompt> - Compare two models on one prompt")
+        );
+        assert!(help.contains("/switch - Re-bind topic to a different project"));
+        assert!(help.contains("/observe [on|off] - Toggle read-only observer mode"));
+        assert!(help.contains("/output <topic_id>|off - Send agent output to another topic"));
+        assert!(help.contains("/lang <code>|off - Set the response language"));
+        assert!(help.contains("/restart - Restart the instance after repeated crashes"));
+        assert!(help.contains("/ping - Measure latency to the instance"));
         assert!(help.contains("/close - Close topic and stop instance"));
+        assert!(help.contains("/archive - Archive topic and stop instance"));
 
         // Verify removed commands are absent
         assert!(!help.contains("/connect"));
@@ -108,7 +158,19 @@ mod tests {
 
         // Verify topic commands
         assert!(help.contains("/session - Show session info"));
+        assert!(help.contains("/export - Download the conversation as Markdown"));
+        assert!(help.contains("/retry_clean - Replay last prompt in a fresh session"));
+        assert!(
+            help.contains("/compare <modelA> <modelB> <prompt> - Compare two models on one prompt")
+        );
+        assert!(help.contains("/switch - Re-bind topic to a different project"));
+        assert!(help.contains("/observe [on|off] - Toggle read-only observer mode"));
+        assert!(help.contains("/output <topic_id>|off - Send agent output to another topic"));
+        assert!(help.contains("/lang <code>|off - Set the response language"));
+        assert!(help.contains("/restart - Restart the instance after repeated crashes"));
+        assert!(help.contains("/ping - Measure latency to the instance"));
         assert!(help.contains("/close - Close topic and stop instance"));
+        assert!(help.contains("/archive - Archive topic and stop instance"));
 
         // Verify reference to general help
         assert!(help.contains("Use /help in General topic for all commands."));
@@ -118,6 +180,7 @@ mod tests {
         assert!(!help.contains("/sessions"));
         assert!(!help.contains("/projects"));
         assert!(!help.contains("/status"));
+        assert!(!help.contains("/stats"));
 
         // Verify removed commands are absent
         assert!(!help.contains("/connect"));
@@ -0,0 +1,174 @@
+//! /import_mappings command handler
+//!
+//! Restores mappings saved by `/export_mappings`: sending that file back with
+//! `/import_mappings` as its caption (or replying to it with the command)
+//! upserts them into this bot's store. Limited to `TELEGRAM_ADMIN_USERS` and
+//! never crosses chats.
+
+use crate::bot::handlers::access::{is_admin_sender, ADMIN_ONLY_MESSAGE};
+use crate::bot::{BotState, Command};
+use crate::forum::MappingImport;
+use crate::types::error::{OutpostError, Result};
+use crate::types::forum::TopicMapping;
+use std::sync::Arc;
+use teloxide::net::Download;
+use teloxide::prelude::*;
+use tracing::{debug, info};
+
+/// Largest file `/import_mappings` downloads.
+const MAX_IMPORT_BYTES: u32 = 5 * 1024 * 1024;
+
+/// Parse an exported mappings file.
+fn parse_mappings(data: &[u8]) -> Result<Vec<TopicMapping>> {
+    serde_json::from_slice(data).map_err(|e| {
+        OutpostError::telegram_error(format!(
+            "Not a valid mappings file (expected the JSON from /export_mappings): {}",
+            e
+        ))
+    })
+}
+
+fn format_import_result(result: &MappingImport) -> String {
+    let mut output = format!("Imported {} topic mapping(s).", result.imported);
+    if !result.conflicts.is_empty() {
+        output.push_str(&format!(
+            "\n\nReplaced {} existing mapping(s) bound to a different project or session:",
+            result.conflicts.len()
+        ));
+        for conflict in &result.conflicts {
+            output.push_str(&format!(
+                "\n- chat {} topic {}: {} -> {}",
+                conflict.chat_id,
+                conflict.topic_id,
+                conflict.existing_project,
+                conflict.imported_project
+            ));
+        }
+    }
+    output
+}
+
+/// Send `text` in the chat, in the command's topic if it has one.
+async fn reply(bot: &Bot, msg: &Message, text: String) -> Result<()> {
+    let mut request = bot.send_message(msg.chat.id, text);
+    if let Some(thread_id) = msg.thread_id {
+        request = request.message_thread_id(thread_id);
+    }
+    request
+        .await
+        .map_err(|e| OutpostError::telegram_error(e.to_string()))?;
+    Ok(())
+}
+
+/// Handle /import_mappings command
+pub async fn handle_import_mappings(
+    bot: Bot,
+    msg: Message,
+    _cmd: Command,
+    state: Arc<BotState>,
+) -> Result<()> {
+    debug!(
+        chat_id = msg.chat.id.0,
+        topic_id = ?msg.thread_id.map(|t| t.0 .0),
+        sender_id = ?msg.from.as_ref().map(|u| u.id.0),
+        "Handling /import_mappings"
+    );
+    if !is_admin_sender(&state.config(), msg.from.as_ref()) {
+        return reply(&bot, &msg, ADMIN_ONLY_MESSAGE.to_string()).await;
+    }
+
+    let document = msg
+        .document()
+        .or_else(|| msg.reply_to_message().and_then(|m| m.document()));
+    let Some(document) = document else {
+        return reply(
+            &bot,
+            &msg,
+            "Send the file from /export_mappings with /import_mappings as its caption, or reply to it with /import_mappings.".to_string(),
+        )
+        .await;
+    };
+    if document.file.size > MAX_IMPORT_BYTES {
+        let output = format!(
+            "Mappings file is too large ({} bytes, limit {}).",
+            document.file.size, MAX_IMPORT_BYTES
+        );
+        return reply(&bot, &msg, output).await;
+    }
+
+    let file = bot
+        .get_file(document.file.id.clone())
+        .await
+        .map_err(|e| OutpostError::telegram_error(e.to_string()))?;
+    let mut data: Vec<u8> = Vec::with_capacity(file.size as usize);
+    bot.download_file(&file.path, &mut data)
+        .await
+        .map_err(|e| OutpostError::telegram_error(e.to_string()))?;
+
+    let output = match parse_mappings(&data) {
+        Ok(mappings) => match state
+            .topic_store
+            .import(msg.chat.id.0, &state.config().project_base_path, &mappings)
+            .await
+        {
+            Ok(result) => {
+                info!(
+                    imported = result.imported,
+                    conflicts = result.conflicts.len(),
+                    "Topic mappings imported"
+                );
+                format_import_result(&result)
+            }
+            Err(e) => format!("Import failed: {}", e),
+        },
+        Err(e) => e.to_string(),
+    };
+    reply(&bot, &msg, output).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::forum::MappingConflict;
+
+    #[test]
+    fn test_parse_mappings() {
+        let mappings = parse_mappings(
+            br#"[{"topic_id": 7, "chat_id": -1001234567890, "project_path": "/p",
+                  "session_id": "ses_1", "instance_id": null, "topic_name_updated": true,
+                  "created_at": 1, "updated_at": 2}]"#,
+        )
+        .unwrap();
+        assert_eq!(mappings.len(), 1);
+        assert_eq!(mappings[0].session_id.as_deref(), Some("ses_1"));
+        assert!(!mappings[0].read_only);
+
+        assert!(parse_mappings(b"{\"topic_id\": 7}")
+            .unwrap_err()
+            .to_string()
+            .contains("Not a valid mappings file"));
+    }
+
+    #[test]
+    fn test_format_import_result() {
+        assert_eq!(
+            format_import_result(&MappingImport {
+                imported: 2,
+                conflicts: vec![],
+            }),
+            "Imported 2 topic mapping(s)."
+        );
+
+        let output = format_import_result(&MappingImport {
+            imported: 1,
+            conflicts: vec![MappingConflict {
+                chat_id: -100,
+                topic_id: 5,
+                existing_project: "/old".to_string(),
+                imported_project: "/new".to_string(),
+            }],
+        });
+        assert!(output.contains("Replaced 1 existing mapping(s)"));
+        assert!(output.contains("chat -100 topic 5: /old -> /new"));
+    }
+}
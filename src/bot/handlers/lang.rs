@@ -0,0 +1,173 @@
+//! /lang command handler
+//!
+//! Sets the language responses in a topic should use. The code is stored in
+//! the topic mapping and sent to OpenCode as a system instruction when a
+//! session is created for the topic, and again with the next prompt after
+//! the code changes or the instance is resurrected.
+
+use crate::bot::{BotState, Command};
+use crate::integration::Integration;
+use crate::project_config::{command_enabled, COMMAND_DISABLED_MESSAGE};
+use crate::types::error::{OutpostError, Result};
+use std::path::Path;
+use std::sync::Arc;
+use teloxide::prelude::*;
+use teloxide::types::{MessageId, ThreadId};
+use tracing::debug;
+
+fn get_topic_id(msg: &Message) -> Result<i32> {
+    let thread_id = msg.thread_id.ok_or_else(|| {
+        OutpostError::telegram_error("This command must be used in a forum topic")
+    })?;
+
+    Ok(thread_id.0 .0)
+}
+
+/// System instruction asking the session to respond in `locale`.
+pub fn locale_instruction(locale: &str) -> String {
+    format!(
+        "Respond in the language with code \"{}\" unless the user explicitly asks for another language.",
+        locale
+    )
+}
+
+/// What `/lang` was asked to do.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum LangAction {
+    Show,
+    Set(Option<String>),
+}
+
+/// Parse `/lang [<code>|off]`. Codes are BCP 47 style tags such as `de`,
+/// `pt-BR` or `zh-Hant`; `_` is accepted as a separator.
+fn parse_lang_args(args: &str) -> Result<LangAction> {
+    let args = args.trim();
+    if args.is_empty() {
+        return Ok(LangAction::Show);
+    }
+    if args.eq_ignore_ascii_case("off") {
+        return Ok(LangAction::Set(None));
+    }
+
+    let code = args.replace('_', "-");
+    let mut subtags = code.split('-');
+    let language = subtags.next().unwrap_or_default();
+    let valid = (2..=3).contains(&language.len())
+        && language.chars().all(|c| c.is_ascii_alphabetic())
+        && subtags
+            .all(|s| (2..=8).contains(&s.len()) && s.chars().all(|c| c.is_ascii_alphanumeric()));
+    if !valid {
+        return Err(OutpostError::telegram_error(format!(
+            "Invalid language code '{}'. Usage: /lang <code>|off, e.g. /lang de or /lang pt-BR",
+            args
+        )));
+    }
+    Ok(LangAction::Set(Some(code)))
+}
+
+fn format_lang_setting(locale: Option<&str>) -> String {
+    match locale {
+        Some(locale) => format!("Responses in this topic are requested in \"{}\".", locale),
+        None => "No response language is set for this topic.".to_string(),
+    }
+}
+
+/// Handle /lang command
+pub async fn handle_lang(
+    bot: Bot,
+    msg: Message,
+    cmd: Command,
+    state: Arc<BotState>,
+    integration: Arc<Integration>,
+) -> Result<()> {
+    debug!(
+        chat_id = msg.chat.id.0,
+        topic_id = ?msg.thread_id.map(|t| t.0 .0),
+        sender_id = ?msg.from.as_ref().map(|u| u.id.0),
+        "Handling /lang"
+    );
+
+    let args = match cmd {
+        Command::Lang(args) => args,
+        _ => return Err(OutpostError::config_error("Invalid command type")),
+    };
+    let topic_id = get_topic_id(&msg)?;
+
+    let mapping = state
+        .topic_store
+        .get_mapping(msg.chat.id.0, topic_id)
+        .await
+        .map_err(|e| OutpostError::database_error(e.to_string()))?
+        .ok_or_else(|| OutpostError::telegram_error("No active connection in this topic"))?;
+
+    if !command_enabled(Path::new(&mapping.project_path), "lang") {
+        bot.send_message(msg.chat.id, COMMAND_DISABLED_MESSAGE)
+            .message_thread_id(ThreadId(MessageId(topic_id)))
+            .await
+            .map_err(|e| OutpostError::telegram_error(e.to_string()))?;
+        return Ok(());
+    }
+
+    let locale = match parse_lang_args(&args)? {
+        LangAction::Show => mapping.locale,
+        LangAction::Set(locale) => {
+            state
+                .topic_store
+                .set_locale(msg.chat.id.0, topic_id, locale.as_deref())
+                .await
+                .map_err(|e| OutpostError::database_error(e.to_string()))?;
+            if locale.is_some() {
+                integration.reapply_locale(topic_id).await;
+            }
+            debug!(topic_id = topic_id, locale = ?locale, "Topic locale updated");
+            locale
+        }
+    };
+
+    bot.send_message(msg.chat.id, format_lang_setting(locale.as_deref()))
+        .message_thread_id(ThreadId(MessageId(topic_id)))
+        .await
+        .map_err(|e| OutpostError::telegram_error(e.to_string()))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_lang_args() {
+        assert_eq!(parse_lang_args("").unwrap(), LangAction::Show);
+        assert_eq!(
+            parse_lang_args(" de ").unwrap(),
+            LangAction::Set(Some("de".to_string()))
+        );
+        assert_eq!(
+            parse_lang_args("pt_BR").unwrap(),
+            LangAction::Set(Some("pt-BR".to_string()))
+        );
+        assert_eq!(parse_lang_args("Off").unwrap(), LangAction::Set(None));
+    }
+
+    #[test]
+    fn test_parse_lang_args_rejects_invalid_codes() {
+        for args in ["german please", "d", "de-", "1a", "en-US-x!"] {
+            assert!(
+                parse_lang_args(args)
+                    .unwrap_err()
+                    .to_string()
+                    .contains("Usage: /lang"),
+                "{} should be rejected",
+                args
+            );
+        }
+    }
+
+    #[test]
+    fn test_locale_instruction_names_code() {
+        assert!(locale_instruction("fr").contains("\"fr\""));
+        assert!(format_lang_setting(Some("fr")).contains("\"fr\""));
+        assert!(format_lang_setting(None).contains("No response language"));
+    }
+}
@@ -1,19 +1,59 @@
+pub mod access;
+pub mod archive;
 pub mod callbacks;
 pub mod close;
+pub mod compare;
+pub mod cordon;
+pub mod debug;
+pub mod export;
+pub mod export_mappings;
+pub mod feedback;
 pub mod help;
+pub mod import_mappings;
+pub mod lang;
 pub mod new;
+pub mod observe;
+pub mod output;
 pub mod permissions;
+pub mod ping;
 pub mod projects;
+pub mod reload;
+pub mod restart;
+pub mod retry_clean;
 pub mod session;
 pub mod sessions;
+pub mod stats;
 pub mod status;
+pub mod switch;
+pub mod uncordon;
+pub mod whoami;
 
+pub use access::{is_allowed_sender, reject_unauthorized_message};
+pub use archive::handle_archive;
 pub use callbacks::dispatch_callback;
 pub use close::handle_close;
+pub use compare::handle_compare;
+pub use cordon::handle_cordon;
+pub use debug::handle_debug;
+pub use export::handle_export;
+pub use export_mappings::handle_export_mappings;
+pub use feedback::send_feedback_prompt;
 pub use help::handle_help;
+pub use import_mappings::handle_import_mappings;
+pub use lang::handle_lang;
 pub use new::handle_new;
+pub use observe::handle_observe;
+pub use output::handle_output;
 pub use permissions::handle_permission_request;
+pub use ping::handle_ping;
 pub use projects::handle_projects;
+pub use reload::handle_reload;
+pub use restart::handle_restart;
+pub use retry_clean::handle_retry_clean;
 pub use session::handle_session;
 pub use sessions::handle_sessions;
+pub use stats::handle_stats;
 pub use status::handle_status;
+pub use switch::handle_switch;
+pub use uncordon::handle_uncordon;
+pub use whoami::handle_whoami;
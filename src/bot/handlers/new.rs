@@ -1,10 +1,111 @@
 use crate::bot::{BotState, Command};
 use crate::git::worktree::{create_worktree, is_git_repo, sanitize_branch_name};
+use crate::opencode::OpenCodeClient;
+use crate::project_config::validate_project;
 use crate::types::error::{OutpostError, Result};
 use crate::types::forum::TopicMapping;
+use std::path::Path;
 use std::sync::Arc;
 use teloxide::prelude::*;
-use tracing::debug;
+use tracing::{debug, warn};
+
+/// Arguments of `/new <project_name> [--from <session_id>]`
+#[derive(Debug, PartialEq)]
+struct NewArgs {
+    name: String,
+    from_session: Option<String>,
+}
+
+/// Parse `/new` arguments. `--from <session_id>` may appear before or after
+/// the project name.
+fn parse_new_args(args: &str) -> Result<NewArgs> {
+    let mut name = None;
+    let mut from_session = None;
+    let mut tokens = args.split_whitespace();
+    while let Some(token) = tokens.next() {
+        if token == "--from" {
+            let session = tokens.next().ok_or_else(|| {
+                OutpostError::config_error(
+                    "--from requires a session ID: /new <project_name> --from <session_id>",
+                )
+            })?;
+            from_session = Some(session.to_string());
+        } else if name.is_none() {
+            name = Some(token.to_string());
+        } else {
+            return Err(OutpostError::config_error(format!(
+                "Unexpected argument '{}'. Usage: /new <project_name> [--from <session_id>]",
+                token
+            )));
+        }
+    }
+
+    Ok(NewArgs {
+        name: name.unwrap_or_default(),
+        from_session,
+    })
+}
+
+/// Check that the session being forked is tracked by a topic in the requesting
+/// chat, which must be whitelisted, and lives under `PROJECT_BASE_PATH`.
+/// Returns a user-facing reason on failure.
+fn check_fork_source<'a>(
+    chat_ids: &[i64],
+    chat_id: i64,
+    project_base_path: &Path,
+    source: Option<&'a TopicMapping>,
+    session_id: &str,
+) -> std::result::Result<&'a TopicMapping, String> {
+    let Some(source) = source else {
+        return Err(format!(
+            "Session '{}' not found. Use /sessions to see known sessions.",
+            session_id
+        ));
+    };
+    if source.chat_id != chat_id
+        || !chat_ids.contains(&source.chat_id)
+        || !Path::new(&source.project_path).starts_with(project_base_path)
+    {
+        return Err(format!(
+            "Session '{}' does not belong to an allowed project.",
+            session_id
+        ));
+    }
+    if source.instance_id.is_none() {
+        return Err(format!(
+            "Session '{}' has no OpenCode instance to fork from.",
+            session_id
+        ));
+    }
+    Ok(source)
+}
+
+/// Topic mapping for a newly created topic, attached to the session created
+/// (or forked) for it. A topic without a session gets one on its first message.
+fn new_topic_mapping(
+    chat_id: i64,
+    topic_id: i32,
+    project_path: &Path,
+    instance_id: String,
+    session_id: Option<String>,
+    now: i64,
+) -> TopicMapping {
+    TopicMapping {
+        topic_id,
+        chat_id,
+        project_path: project_path.to_string_lossy().to_string(),
+        session_id,
+        instance_id: Some(instance_id),
+        topic_name_updated: false,
+        read_only: false,
+        output_topic_id: None,
+        system_prompt_mtime: None,
+        locale: None,
+        last_activity_at: None,
+        created_at: now,
+        updated_at: now,
+    }
+}
 
 /// Validate project name according to rules:
 /// - Length: 1-50 characters
@@ -33,6 +134,18 @@ fn validate_project_name(name: &str) -> Result<()> {
     Ok(())
 }
 
+/// Message to show when a chat already has `max_topics` mapped topics.
+/// A limit of 0 disables the check.
+fn topic_limit_message(existing: usize, max_topics: usize) -> Option<String> {
+    if max_topics == 0 || existing < max_topics {
+        return None;
+    }
+    Some(format!(
+        "Topic limit reached: this chat already has {} of {} allowed topics. Use /close or /archive on an unused topic first.",
+        existing, max_topics
+    ))
+}
+
 /// Check if message is in General topic (thread_id is None or ThreadId(MessageId(1)))
 fn is_general_topic(msg: &Message) -> bool {
     msg.thread_id.is_none() || (msg.thread_id.map(|id| id.0) == Some(teloxide::types::MessageId(1)))
@@ -40,6 +153,9 @@ fn is_general_topic(msg: &Message) -> bool {
 
 /// Handle /new command - create new project and session
 ///
+/// With `--from <session_id>` the new topic is attached to a fork of an
+/// existing session in that session's project instead (see [`handle_new_fork`]).
+///
 /// Steps:
 /// 1. Extract and validate project name
 /// 2. Check if General topic (reject if HANDLE_GENERAL_TOPIC=false)
@@ -57,11 +173,12 @@ pub async fn handle_new(bot: Bot, msg: Message, cmd: Command, state: Arc<BotStat
     );
 
     // Extract project name from command
-    let name = match cmd {
-        Command::New(n) => n,
+    let args = match cmd {
+        Command::New(args) => args,
         _ => return Err(OutpostError::config_error("Invalid command type")),
     };
-    debug!(name = %name, "Project name extracted from command");
+    let NewArgs { name, from_session } = parse_new_args(&args)?;
+    debug!(name = %name, from_session = ?from_session, "Project name extracted from command");
 
     // Validate project name
     validate_project_name(&name)?;
@@ -70,10 +187,10 @@ pub async fn handle_new(bot: Bot, msg: Message, cmd: Command, state: Arc<BotStat
     // Check if in General topic
     debug!(
         is_general = is_general_topic(&msg),
-        handle_general = state.config.handle_general_topic,
+        handle_general = state.config().handle_general_topic,
         "General topic check"
     );
-    if is_general_topic(&msg) && !state.config.handle_general_topic {
+    if is_general_topic(&msg) && !state.config().handle_general_topic {
         bot.send_message(
             msg.chat.id,
             "Cannot create projects in General topic. Please create a forum topic first.",
@@ -83,7 +200,30 @@ pub async fn handle_new(bot: Bot, msg: Message, cmd: Command, state: Arc<BotStat
         return Ok(());
     }
 
-    let project_path = state.config.project_base_path.join(&name);
+    let existing_topics = state
+        .topic_store
+        .count_mappings_by_chat(msg.chat.id.0)
+        .await
+        .map_err(|e| OutpostError::database_error(e.to_string()))?;
+    debug!(
+        existing_topics = existing_topics,
+        max_topics = state.config().max_topics_per_chat,
+        "Topic limit check"
+    );
+    if let Some(limit_msg) =
+        topic_limit_message(existing_topics, state.config().max_topics_per_chat)
+    {
+        bot.send_message(msg.chat.id, limit_msg)
+            .await
+            .map_err(|e| OutpostError::telegram_error(e.to_string()))?;
+        return Ok(());
+    }
+
+    if let Some(source_session) = from_session {
+        return handle_new_fork(&bot, &msg, &state, &name, &source_session).await;
+    }
+
+    let project_path = state.config().project_base_path.join(&name);
     debug!(project_path = %project_path.display(), "Resolved project path");
 
     debug!(project_path = %project_path.display(), exists = project_path.exists(), "Project directory existence check");
@@ -93,7 +233,7 @@ pub async fn handle_new(bot: Bot, msg: Message, cmd: Command, state: Arc<BotStat
             format!(
                 "Directory '{}' not found under {}. Use /projects to see available directories.",
                 name,
-                state.config.project_base_path.display()
+                state.config().project_base_path.display()
             ),
         )
         .await
@@ -101,11 +241,19 @@ pub async fn handle_new(bot: Bot, msg: Message, cmd: Command, state: Arc<BotStat
         return Ok(());
     }
 
+    if state.config().validate_project {
+        if let Some(warning) = validate_project(&project_path).warning(&name) {
+            bot.send_message(msg.chat.id, warning)
+                .await
+                .map_err(|e| OutpostError::telegram_error(e.to_string()))?;
+        }
+    }
+
     let mut worktree_branch = None;
     let effective_project_path = if is_git_repo(&project_path) {
         let sanitized = sanitize_branch_name(&name);
         worktree_branch = Some(format!("wt/{}", sanitized));
-        match create_worktree(&project_path, &name, &state.config.project_base_path).await {
+        match create_worktree(&project_path, &name, &state.config().project_base_path).await {
             Ok(path) => path,
             Err(e) => {
                 bot.send_message(msg.chat.id, format!("Failed to create worktree: {}", e))
@@ -167,18 +315,28 @@ pub async fn handle_new(bot: Bot, msg: Message, cmd: Command, state: Arc<BotStat
         .map_err(|e| OutpostError::io_error(e.to_string()))?
         .as_secs() as i64;
 
+    // Create the session up front so the topic is bound before its first
+    // message. If that fails, the first message creates one instead.
+    let client = OpenCodeClient::for_port(&state.config(), port)
+        .map_err(|e| OutpostError::config_error(e.to_string()))?;
+    let session_id = match client.create_session(&effective_project_path).await {
+        Ok(session) => Some(session.id),
+        Err(e) => {
+            warn!(topic_id = topic_id, error = %e, "Failed to create session for new topic, deferring to first message");
+            None
+        }
+    };
+
     // Create and save TopicMapping with real instance_id
-    let mapping = TopicMapping {
+    let mapping = new_topic_mapping(
+        msg.chat.id.0,
         topic_id,
-        chat_id: msg.chat.id.0,
-        project_path: effective_project_path.to_string_lossy().to_string(),
-        session_id: None,
-        instance_id: Some(instance_id.clone()),
-        topic_name_updated: false,
-        created_at: now,
-        updated_at: now,
-    };
-    debug!(topic_id = mapping.topic_id, instance_id = ?mapping.instance_id, project_path = %mapping.project_path, "TopicMapping created, session_id will be set when OpenCode session starts");
+        &effective_project_path,
+        instance_id.clone(),
+        session_id,
+        now,
+    );
+    debug!(topic_id = mapping.topic_id, instance_id = ?mapping.instance_id, session_id = ?mapping.session_id, project_path = %mapping.project_path, "TopicMapping created");
     state
         .topic_store
         .save_mapping(&mapping)
@@ -220,6 +378,120 @@ pub async fn handle_new(bot: Bot, msg: Message, cmd: Command, state: Arc<BotStat
     Ok(())
 }
 
+/// Create a topic attached to a fork of `source_session`.
+///
+/// The fork shares the source topic's project directory and OpenCode instance,
+/// so no new worktree is created. The session is forked before the topic is
+/// created, so a failed fork leaves no empty topic behind.
+async fn handle_new_fork(
+    bot: &Bot,
+    msg: &Message,
+    state: &BotState,
+    name: &str,
+    source_session: &str,
+) -> Result<()> {
+    let source = state
+        .topic_store
+        .get_mapping_by_session(source_session)
+        .await
+        .map_err(|e| OutpostError::database_error(e.to_string()))?;
+    let source = match check_fork_source(
+        &state.config().telegram_chat_ids,
+        msg.chat.id.0,
+        &state.config().project_base_path,
+        source.as_ref(),
+        source_session,
+    ) {
+        Ok(source) => source,
+        Err(reason) => {
+            bot.send_message(msg.chat.id, reason)
+                .await
+                .map_err(|e| OutpostError::telegram_error(e.to_string()))?;
+            return Ok(());
+        }
+    };
+    let project_path = Path::new(&source.project_path);
+
+    // The source session is stored in the data directory of the source
+    // topic, so resolve the instance that mounts it
+    state
+        .instance_manager
+        .get_or_create(project_path, source.topic_id)
+        .await
+        .map_err(|e| OutpostError::io_error(format!("Failed to spawn instance: {}", e)))?;
+    let info = state
+        .orchestrator_store
+        .get_instance_by_path(&source.project_path)
+        .await
+        .map_err(|e| OutpostError::database_error(e.to_string()))?
+        .ok_or_else(|| OutpostError::io_error("Instance created but not found in store"))?;
+
+    let client = OpenCodeClient::for_port(&state.config(), info.port)
+        .map_err(|e| OutpostError::config_error(e.to_string()))?;
+    let forked = match client.fork_session(source_session).await {
+        Ok(session) => session,
+        Err(e) => {
+            bot.send_message(msg.chat.id, format!("Failed to fork session: {}", e))
+                .await
+                .map_err(|e| OutpostError::telegram_error(e.to_string()))?;
+            return Err(OutpostError::opencode_api_error(e.to_string()));
+        }
+    };
+    debug!(source_session = %source_session, forked_session = %forked.id, "Session forked for new topic");
+
+    let forum_topic = match bot.create_forum_topic(msg.chat.id, name).await {
+        Ok(topic) => topic,
+        Err(e) => {
+            if let Err(delete_err) = client.delete_session(&forked.id).await {
+                warn!(session_id = %forked.id, error = %delete_err, "Failed to delete fork after topic creation failed");
+            }
+            return Err(OutpostError::telegram_error(format!(
+                "Failed to create forum topic: {}",
+                e
+            )));
+        }
+    };
+    let topic_id = forum_topic.thread_id.0 .0;
+    debug!(topic_id = topic_id, source_session = %source_session, "Forum topic created for fork");
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_err(|e| OutpostError::io_error(e.to_string()))?
+        .as_secs() as i64;
+    let mapping = new_topic_mapping(
+        msg.chat.id.0,
+        topic_id,
+        project_path,
+        info.id.clone(),
+        Some(forked.id.clone()),
+        now,
+    );
+    state
+        .topic_store
+        .save_mapping(&mapping)
+        .await
+        .map_err(|e| OutpostError::database_error(e.to_string()))?;
+
+    let confirmation = format!(
+        "🌱 Forked session {} into {}\n\n\
+         📁 Path: {}\n\
+         🆔 Instance: {}\n\
+         🔌 Port: {}\n\n\
+         Send a message here to continue from the forked context.",
+        source_session,
+        forked.id,
+        project_path.display(),
+        info.id,
+        info.port
+    );
+    bot.send_message(msg.chat.id, confirmation)
+        .message_thread_id(forum_topic.thread_id)
+        .await
+        .map_err(|e| OutpostError::telegram_error(e.to_string()))?;
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -236,6 +508,129 @@ mod tests {
         assert!(validate_project_name("123numeric").is_ok());
     }
 
+    #[test]
+    fn test_topic_limit_message_enforces_cap() {
+        assert!(topic_limit_message(0, 3).is_none());
+        assert!(topic_limit_message(2, 3).is_none());
+
+        let msg = topic_limit_message(3, 3).unwrap();
+        assert!(msg.contains("Topic limit reached"));
+        assert!(msg.contains("3 of 3"));
+        assert!(topic_limit_message(4, 3).is_some());
+    }
+
+    #[test]
+    fn test_topic_limit_message_zero_means_unlimited() {
+        assert!(topic_limit_message(1000, 0).is_none());
+    }
+
+    #[test]
+    fn test_parse_new_args_name_only() {
+        assert_eq!(
+            parse_new_args("my-project").unwrap(),
+            NewArgs {
+                name: "my-project".to_string(),
+                from_session: None,
+            }
+        );
+        assert_eq!(parse_new_args("").unwrap().name, "");
+    }
+
+    #[test]
+    fn test_parse_new_args_with_from() {
+        let expected = NewArgs {
+            name: "my-project".to_string(),
+            from_session: Some("ses_abc".to_string()),
+        };
+        assert_eq!(
+            parse_new_args("my-project --from ses_abc").unwrap(),
+            expected
+        );
+        assert_eq!(
+            parse_new_args("--from ses_abc   my-project").unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_parse_new_args_rejects_bad_input() {
+        let err = parse_new_args("my-project --from").unwrap_err();
+        assert!(err.to_string().contains("--from requires a session ID"));
+
+        let err = parse_new_args("my-project extra").unwrap_err();
+        assert!(err.to_string().contains("Unexpected argument 'extra'"));
+    }
+
+    fn source_mapping(chat_id: i64, project_path: &str) -> TopicMapping {
+        TopicMapping {
+            topic_id: 7,
+            chat_id,
+            project_path: project_path.to_string(),
+            session_id: Some("ses_src".to_string()),
+            instance_id: Some("inst-1".to_string()),
+            topic_name_updated: true,
+            read_only: false,
+            output_topic_id: None,
+            system_prompt_mtime: None,
+            locale: None,
+            last_activity_at: None,
+            created_at: 0,
+            updated_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_check_fork_source() {
+        let base = Path::new("/projects");
+        let chats = [-100];
+
+        let valid = source_mapping(-100, "/projects/.worktrees/api");
+        let source = check_fork_source(&chats, -100, base, Some(&valid), "ses_src").unwrap();
+        assert!(std::ptr::eq(source, &valid));
+
+        let err = check_fork_source(&chats, -100, base, None, "ses_missing").unwrap_err();
+        assert!(err.contains("not found"));
+
+        let other_chat = source_mapping(-200, "/projects/api");
+        let err = check_fork_source(&chats, -100, base, Some(&other_chat), "ses_src").unwrap_err();
+        assert!(err.contains("does not belong to an allowed project"));
+
+        // Whitelisted, but tracked by a topic in another chat
+        let chats = [-100, -200];
+        let err = check_fork_source(&chats, -100, base, Some(&other_chat), "ses_src").unwrap_err();
+        assert!(err.contains("does not belong to an allowed project"));
+
+        let outside = source_mapping(-100, "/etc/secret");
+        assert!(check_fork_source(&chats, -100, base, Some(&outside), "ses_src").is_err());
+
+        let mut no_instance = source_mapping(-100, "/projects/api");
+        no_instance.instance_id = None;
+        assert!(check_fork_source(&chats, -100, base, Some(&no_instance), "ses_src").is_err());
+    }
+
+    #[test]
+    fn test_new_topic_mapping_fork_vs_fresh() {
+        let path = Path::new("/projects/api");
+
+        let fresh = new_topic_mapping(-100, 42, path, "inst-1".to_string(), None, 1000);
+        assert_eq!(fresh.session_id, None);
+        assert_eq!(fresh.instance_id.as_deref(), Some("inst-1"));
+        assert_eq!(fresh.project_path, "/projects/api");
+        assert!(!fresh.topic_name_updated);
+
+        let forked = new_topic_mapping(
+            -100,
+            43,
+            path,
+            "inst-1".to_string(),
+            Some("ses_fork".to_string()),
+            1000,
+        );
+        assert_eq!(forked.session_id.as_deref(), Some("ses_fork"));
+        assert_eq!(forked.topic_id, 43);
+        assert_eq!(forked.created_at, 1000);
+    }
+
     #[test]
     fn test_validate_project_name_empty() {
         let result = validate_project_name("");
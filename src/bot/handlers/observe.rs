@@ -0,0 +1,125 @@
+//! /observe command handler
+//!
+//! Toggles observer (read-only) mode for the current topic. Prompts sent in
+//! an observed topic are ignored, while the session's output keeps streaming.
+
+use crate::bot::{BotState, Command};
+use crate::project_config::{command_enabled, COMMAND_DISABLED_MESSAGE};
+use crate::types::error::{OutpostError, Result};
+use std::path::Path;
+use std::sync::Arc;
+use teloxide::prelude::*;
+use teloxide::types::{MessageId, ThreadId};
+use tracing::debug;
+
+fn get_topic_id(msg: &Message) -> Result<i32> {
+    let thread_id = msg.thread_id.ok_or_else(|| {
+        OutpostError::telegram_error("This command must be used in a forum topic")
+    })?;
+
+    Ok(thread_id.0 .0)
+}
+
+/// Parse `/observe [on|off]`; no argument toggles the current setting.
+fn parse_observe_args(args: &str, current: bool) -> Result<bool> {
+    match args.trim().to_lowercase().as_str() {
+        "" => Ok(!current),
+        "on" => Ok(true),
+        "off" => Ok(false),
+        other => Err(OutpostError::telegram_error(format!(
+            "Unknown argument '{}'. Usage: /observe [on|off]",
+            other
+        ))),
+    }
+}
+
+fn format_observe_reply(read_only: bool) -> &'static str {
+    if read_only {
+        "Observer mode on. Messages in this topic are no longer sent to the agent; its output is still shown here."
+    } else {
+        "Observer mode off. Messages in this topic are sent to the agent again."
+    }
+}
+
+/// Handle /observe command
+pub async fn handle_observe(
+    bot: Bot,
+    msg: Message,
+    cmd: Command,
+    state: Arc<BotState>,
+) -> Result<()> {
+    debug!(
+        chat_id = msg.chat.id.0,
+        topic_id = ?msg.thread_id.map(|t| t.0 .0),
+        sender_id = ?msg.from.as_ref().map(|u| u.id.0),
+        "Handling /observe"
+    );
+
+    let args = match cmd {
+        Command::Observe(args) => args,
+        _ => return Err(OutpostError::config_error("Invalid command type")),
+    };
+    let topic_id = get_topic_id(&msg)?;
+
+    let mapping = state
+        .topic_store
+        .get_mapping(msg.chat.id.0, topic_id)
+        .await
+        .map_err(|e| OutpostError::database_error(e.to_string()))?
+        .ok_or_else(|| OutpostError::telegram_error("No active connection in this topic"))?;
+
+    if !command_enabled(Path::new(&mapping.project_path), "observe") {
+        bot.send_message(msg.chat.id, COMMAND_DISABLED_MESSAGE)
+            .message_thread_id(ThreadId(MessageId(topic_id)))
+            .await
+            .map_err(|e| OutpostError::telegram_error(e.to_string()))?;
+        return Ok(());
+    }
+
+    let read_only = parse_observe_args(&args, mapping.read_only)?;
+    state
+        .topic_store
+        .set_read_only(msg.chat.id.0, topic_id, read_only)
+        .await
+        .map_err(|e| OutpostError::database_error(e.to_string()))?;
+    debug!(
+        topic_id = topic_id,
+        read_only = read_only,
+        "Observer mode updated"
+    );
+
+    bot.send_message(msg.chat.id, format_observe_reply(read_only))
+        .message_thread_id(ThreadId(MessageId(topic_id)))
+        .await
+        .map_err(|e| OutpostError::telegram_error(e.to_string()))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_observe_args_toggles_without_argument() {
+        assert!(parse_observe_args("", false).unwrap());
+        assert!(!parse_observe_args("  ", true).unwrap());
+    }
+
+    #[test]
+    fn test_parse_observe_args_explicit() {
+        assert!(parse_observe_args("on", true).unwrap());
+        assert!(parse_observe_args("ON", false).unwrap());
+        assert!(!parse_observe_args("off", true).unwrap());
+        assert!(parse_observe_args("maybe", false)
+            .unwrap_err()
+            .to_string()
+            .contains("Usage: /observe [on|off]"));
+    }
+
+    #[test]
+    fn test_format_observe_reply() {
+        assert!(format_observe_reply(true).contains("Observer mode on"));
+        assert!(format_observe_reply(false).contains("Observer mode off"));
+    }
+}
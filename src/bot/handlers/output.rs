@@ -0,0 +1,165 @@
+//! /output command handler
+//!
+//! Routes the agent's output for the current topic to a separate topic, so
+//! prompts stay in one topic and the (verbose) responses land in another.
+//! Errors and permission prompts stay in the input topic.
+
+use crate::bot::{BotState, Command};
+use crate::project_config::{command_enabled, COMMAND_DISABLED_MESSAGE};
+use crate::types::error::{OutpostError, Result};
+use std::path::Path;
+use std::sync::Arc;
+use teloxide::prelude::*;
+use teloxide::types::{MessageId, ThreadId};
+use tracing::debug;
+
+fn get_topic_id(msg: &Message) -> Result<i32> {
+    let thread_id = msg.thread_id.ok_or_else(|| {
+        OutpostError::telegram_error("This command must be used in a forum topic")
+    })?;
+
+    Ok(thread_id.0 .0)
+}
+
+/// What `/output` was asked to do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OutputAction {
+    Show,
+    Set(Option<i32>),
+}
+
+/// Parse `/output [<topic_id>|off]`.
+fn parse_output_args(args: &str, topic_id: i32) -> Result<OutputAction> {
+    let args = args.trim();
+    if args.is_empty() {
+        return Ok(OutputAction::Show);
+    }
+    if args.eq_ignore_ascii_case("off") {
+        return Ok(OutputAction::Set(None));
+    }
+
+    let target: i32 = args.parse().map_err(|_| {
+        OutpostError::telegram_error(format!(
+            "Invalid topic '{}'. Usage: /output <topic_id>|off",
+            args
+        ))
+    })?;
+    if target <= 1 {
+        return Err(OutpostError::telegram_error(
+            "Output cannot be sent to the General topic",
+        ));
+    }
+    if target == topic_id {
+        return Ok(OutputAction::Set(None));
+    }
+    Ok(OutputAction::Set(Some(target)))
+}
+
+fn format_output_setting(output_topic_id: Option<i32>) -> String {
+    match output_topic_id {
+        Some(id) => format!("Agent output for this topic is sent to topic {}.", id),
+        None => "Agent output is shown in this topic.".to_string(),
+    }
+}
+
+/// Handle /output command
+pub async fn handle_output(
+    bot: Bot,
+    msg: Message,
+    cmd: Command,
+    state: Arc<BotState>,
+) -> Result<()> {
+    debug!(
+        chat_id = msg.chat.id.0,
+        topic_id = ?msg.thread_id.map(|t| t.0 .0),
+        sender_id = ?msg.from.as_ref().map(|u| u.id.0),
+        "Handling /output"
+    );
+
+    let args = match cmd {
+        Command::Output(args) => args,
+        _ => return Err(OutpostError::config_error("Invalid command type")),
+    };
+    let topic_id = get_topic_id(&msg)?;
+
+    let mapping = state
+        .topic_store
+        .get_mapping(msg.chat.id.0, topic_id)
+        .await
+        .map_err(|e| OutpostError::database_error(e.to_string()))?
+        .ok_or_else(|| OutpostError::telegram_error("No active connection in this topic"))?;
+
+    if !command_enabled(Path::new(&mapping.project_path), "output") {
+        bot.send_message(msg.chat.id, COMMAND_DISABLED_MESSAGE)
+            .message_thread_id(ThreadId(MessageId(topic_id)))
+            .await
+            .map_err(|e| OutpostError::telegram_error(e.to_string()))?;
+        return Ok(());
+    }
+
+    let output_topic_id = match parse_output_args(&args, topic_id)? {
+        OutputAction::Show => mapping.output_topic_id,
+        OutputAction::Set(output_topic_id) => {
+            state
+                .topic_store
+                .set_output_topic(msg.chat.id.0, topic_id, output_topic_id)
+                .await
+                .map_err(|e| OutpostError::database_error(e.to_string()))?;
+            debug!(
+                topic_id = topic_id,
+                output_topic_id = ?output_topic_id,
+                "Output topic updated"
+            );
+            output_topic_id
+        }
+    };
+
+    bot.send_message(msg.chat.id, format_output_setting(output_topic_id))
+        .message_thread_id(ThreadId(MessageId(topic_id)))
+        .await
+        .map_err(|e| OutpostError::telegram_error(e.to_string()))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_output_args() {
+        assert_eq!(parse_output_args("", 10).unwrap(), OutputAction::Show);
+        assert_eq!(
+            parse_output_args(" 42 ", 10).unwrap(),
+            OutputAction::Set(Some(42))
+        );
+        assert_eq!(
+            parse_output_args("OFF", 10).unwrap(),
+            OutputAction::Set(None)
+        );
+        // Pointing a topic at itself is the same as turning routing off
+        assert_eq!(
+            parse_output_args("10", 10).unwrap(),
+            OutputAction::Set(None)
+        );
+    }
+
+    #[test]
+    fn test_parse_output_args_rejects_invalid_topics() {
+        assert!(parse_output_args("logs", 10)
+            .unwrap_err()
+            .to_string()
+            .contains("Usage: /output"));
+        assert!(parse_output_args("1", 10)
+            .unwrap_err()
+            .to_string()
+            .contains("General topic"));
+        assert!(parse_output_args("-5", 10).is_err());
+    }
+
+    #[test]
+    fn test_format_output_setting() {
+        assert!(format_output_setting(Some(42)).contains("sent to topic 42"));
+        assert!(format_output_setting(None).contains("shown in this topic"));
+    }
+}
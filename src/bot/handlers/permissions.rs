@@ -1,10 +1,87 @@
 use crate::bot::BotState;
 use crate::opencode::OpenCodeClient;
 use crate::types::error::{OutpostError, Result};
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use teloxide::prelude::*;
-use teloxide::types::{InlineKeyboardButton, InlineKeyboardMarkup};
-use tracing::debug;
+use teloxide::types::{InlineKeyboardButton, InlineKeyboardMarkup, MessageId};
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+/// Permission requests awaiting a user decision, keyed by permission id.
+///
+/// Each entry owns the task that auto-denies the request once its timeout
+/// elapses, if a timeout is configured. Resolving the permission first cancels
+/// that task.
+#[derive(Debug, Default)]
+pub struct PendingPermissions {
+    timeouts: Mutex<HashMap<String, Option<JoinHandle<()>>>>,
+}
+
+impl PendingPermissions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run `on_timeout` after `timeout` unless `permission_id` is cancelled first.
+    pub fn track<F>(self: &Arc<Self>, permission_id: &str, timeout: Duration, on_timeout: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let pending = Arc::clone(self);
+        let id = permission_id.to_string();
+        let handle = tokio::spawn(async move {
+            tokio::time::sleep(timeout).await;
+            let expired = pending.timeouts.lock().unwrap().remove(&id).is_some();
+            if expired {
+                on_timeout.await;
+            }
+        });
+
+        self.insert(permission_id, Some(handle));
+    }
+
+    /// Record `permission_id` as awaiting a decision without a timeout.
+    pub fn register(&self, permission_id: &str) {
+        self.insert(permission_id, None);
+    }
+
+    fn insert(&self, permission_id: &str, handle: Option<JoinHandle<()>>) {
+        if let Some(Some(previous)) = self
+            .timeouts
+            .lock()
+            .unwrap()
+            .insert(permission_id.to_string(), handle)
+        {
+            previous.abort();
+        }
+    }
+
+    /// Resolve `permission_id`, cancelling its timeout. Returns whether it was
+    /// still pending.
+    pub fn cancel(&self, permission_id: &str) -> bool {
+        match self.timeouts.lock().unwrap().remove(permission_id) {
+            Some(handle) => {
+                if let Some(handle) = handle {
+                    handle.abort();
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Number of permissions still awaiting a decision.
+    pub fn len(&self) -> usize {
+        self.timeouts.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
 
 /// Format permission request message
 fn format_permission_message(description: &str) -> String {
@@ -14,18 +91,20 @@ fn format_permission_message(description: &str) -> String {
     )
 }
 
-/// Create inline keyboard with Allow/Deny buttons
+/// Create inline keyboard with Allow/Deny/Cancel buttons
 fn create_inline_keyboard(session_id: &str, permission_id: &str) -> InlineKeyboardMarkup {
     let allow_data = format!("perm:{}:{}:allow", session_id, permission_id);
     let deny_data = format!("perm:{}:{}:deny", session_id, permission_id);
+    let cancel_data = format!("perm:{}:{}:cancel", session_id, permission_id);
 
     InlineKeyboardMarkup::new(vec![vec![
         InlineKeyboardButton::callback("Allow", allow_data),
         InlineKeyboardButton::callback("Deny", deny_data),
+        InlineKeyboardButton::callback("Cancel", cancel_data),
     ]])
 }
 
-/// Parse callback data format: perm:{session_id}:{permission_id}:{allow|deny}
+/// Parse callback data format: perm:{session_id}:{permission_id}:{allow|deny|cancel}
 fn parse_callback_data(data: &str) -> Result<(String, String, String)> {
     let parts: Vec<&str> = data.split(':').collect();
     if parts.len() != 4 || parts[0] != "perm" {
@@ -39,7 +118,70 @@ fn parse_callback_data(data: &str) -> Result<(String, String, String)> {
     ))
 }
 
+/// Text shown in place of the buttons when a request times out
+fn format_timeout_message(timeout: Duration) -> String {
+    format!(
+        "⏱ No response within {}s - ❌ Denied automatically",
+        timeout.as_secs()
+    )
+}
+
+/// Build an OpenCode client for the instance serving `session_id`.
+async fn permission_client(state: &BotState, session_id: &str) -> Result<OpenCodeClient> {
+    let mapping = state
+        .topic_store
+        .get_mapping_by_session(session_id)
+        .await
+        .map_err(|e| OutpostError::telegram_error(format!("Failed to look up session: {}", e)))?
+        .ok_or_else(|| OutpostError::telegram_error("Session not found"))?;
+    debug!(session_id = %session_id, instance_id = ?mapping.instance_id, "Session mapping found for permission");
+
+    let instance_id = mapping
+        .instance_id
+        .ok_or_else(|| OutpostError::telegram_error("Instance ID not found for session"))?;
+
+    // Look up the instance port from the orchestrator store
+    let instance = state
+        .orchestrator_store
+        .get_instance(&instance_id)
+        .await
+        .map_err(|e| OutpostError::telegram_error(format!("Failed to look up instance: {}", e)))?
+        .ok_or_else(|| OutpostError::telegram_error("Instance not found"))?;
+
+    OpenCodeClient::for_port(&state.config(), instance.port)
+        .map_err(|e| OutpostError::config_error(e.to_string()))
+}
+
+/// Deny a permission that nobody answered and note it on the request message.
+async fn auto_deny_permission(
+    bot: &Bot,
+    state: &BotState,
+    chat_id: ChatId,
+    message_id: MessageId,
+    session_id: &str,
+    permission_id: &str,
+) -> Result<()> {
+    let client = permission_client(state, session_id).await?;
+    client
+        .reply_permission(session_id, permission_id, false)
+        .await
+        .map_err(|e| OutpostError::opencode_api_error(e.to_string()))?;
+
+    bot.edit_message_text(
+        chat_id,
+        message_id,
+        format_timeout_message(state.config().permission_timeout),
+    )
+    .await
+    .map_err(|e| OutpostError::telegram_error(e.to_string()))?;
+
+    Ok(())
+}
+
 /// Handle permission request from OpenCode
+///
+/// Unless `PERMISSION_TIMEOUT_MS` is 0, the request is denied automatically
+/// when the user does not answer in time.
 pub async fn handle_permission_request(
     bot: Bot,
     chat_id: ChatId,
@@ -47,23 +189,48 @@ pub async fn handle_permission_request(
     session_id: &str,
     permission_id: &str,
     description: &str,
+    state: Arc<BotState>,
 ) -> Result<()> {
     debug!(topic_id = thread_id, permission_id = %permission_id, "Sending permission request to Telegram");
     let message = format_permission_message(description);
     let keyboard = create_inline_keyboard(session_id, permission_id);
 
-    bot.send_message(chat_id, message)
-        .message_thread_id(teloxide::types::ThreadId(teloxide::types::MessageId(
-            thread_id,
-        )))
+    let sent = bot
+        .send_message(chat_id, message)
+        .message_thread_id(teloxide::types::ThreadId(MessageId(thread_id)))
         .reply_markup(keyboard)
         .await
         .map_err(|e| OutpostError::telegram_error(e.to_string()))?;
 
+    let timeout = state.config().permission_timeout;
+    if timeout.is_zero() {
+        state.pending_permissions.register(permission_id);
+        return Ok(());
+    }
+
+    let pending = Arc::clone(&state.pending_permissions);
+    let session_id = session_id.to_string();
+    let id = permission_id.to_string();
+    pending.track(permission_id, timeout, async move {
+        info!(
+            session_id = %session_id,
+            permission_id = %id,
+            timeout_secs = timeout.as_secs(),
+            "Permission request timed out, denying"
+        );
+        if let Err(e) = auto_deny_permission(&bot, &state, chat_id, sent.id, &session_id, &id).await
+        {
+            warn!(permission_id = %id, error = ?e, "Failed to auto-deny permission");
+        }
+    });
+
     Ok(())
 }
 
-/// Handle permission callback (Allow/Deny button click)
+/// Handle permission callback (Allow/Deny/Cancel button click)
+///
+/// Cancel dismisses a stale request by denying it. Cancelling a request that
+/// was already answered only acknowledges the click.
 pub async fn handle_permission_callback(
     bot: Bot,
     q: CallbackQuery,
@@ -78,29 +245,20 @@ pub async fn handle_permission_callback(
     debug!(session_id = %session_id, permission_id = %permission_id, action = %action, "Parsed permission callback data");
 
     let allow = action == "allow";
+    let cancel = action == "cancel";
 
-    // Look up the instance port from the session_id
-    let mapping = state
-        .topic_store
-        .get_mapping_by_session(&session_id)
-        .await
-        .map_err(|e| OutpostError::telegram_error(format!("Failed to look up session: {}", e)))?
-        .ok_or_else(|| OutpostError::telegram_error("Session not found"))?;
-    debug!(session_id = %session_id, instance_id = ?mapping.instance_id, "Session mapping found for permission");
-
-    let instance_id = mapping
-        .instance_id
-        .ok_or_else(|| OutpostError::telegram_error("Instance ID not found for session"))?;
-
-    // Look up the instance port from the orchestrator store
-    let instance = state
-        .orchestrator_store
-        .get_instance(&instance_id)
-        .await
-        .map_err(|e| OutpostError::telegram_error(format!("Failed to look up instance: {}", e)))?
-        .ok_or_else(|| OutpostError::telegram_error("Instance not found"))?;
+    if state.pending_permissions.cancel(&permission_id) {
+        debug!(permission_id = %permission_id, "Cancelled permission timeout");
+    } else if cancel {
+        debug!(permission_id = %permission_id, "Permission already answered, nothing to cancel");
+        bot.answer_callback_query(q.id)
+            .text("This request was already answered")
+            .await
+            .map_err(|e| OutpostError::telegram_error(e.to_string()))?;
+        return Ok(());
+    }
 
-    let client = OpenCodeClient::new(&format!("http://localhost:{}", instance.port));
+    let client = permission_client(&state, &session_id).await?;
     client
         .reply_permission(&session_id, &permission_id, allow)
         .await
@@ -108,7 +266,13 @@ pub async fn handle_permission_callback(
     debug!(session_id = %session_id, permission_id = %permission_id, allow = allow, "Permission reply sent to OpenCode");
 
     if let Some(message) = q.message {
-        let result_text = if allow { "✅ Allowed" } else { "❌ Denied" };
+        let result_text = if cancel {
+            "🚫 Cancelled"
+        } else if allow {
+            "✅ Allowed"
+        } else {
+            "❌ Denied"
+        };
         let chat_id = message.chat().id;
         let message_id = message.id();
         bot.edit_message_text(chat_id, message_id, result_text)
@@ -126,6 +290,72 @@ pub async fn handle_permission_callback(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    #[tokio::test]
+    async fn test_pending_permission_times_out() {
+        let pending = Arc::new(PendingPermissions::new());
+        let fired = Arc::new(AtomicBool::new(false));
+
+        let fired_clone = Arc::clone(&fired);
+        pending.track("perm_1", Duration::from_millis(20), async move {
+            fired_clone.store(true, Ordering::SeqCst);
+        });
+        assert_eq!(pending.len(), 1);
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(fired.load(Ordering::SeqCst));
+        assert!(pending.is_empty());
+        assert!(!pending.cancel("perm_1"));
+    }
+
+    #[tokio::test]
+    async fn test_pending_permission_cancelled_by_user() {
+        let pending = Arc::new(PendingPermissions::new());
+        let fired = Arc::new(AtomicBool::new(false));
+
+        let fired_clone = Arc::clone(&fired);
+        pending.track("perm_1", Duration::from_millis(20), async move {
+            fired_clone.store(true, Ordering::SeqCst);
+        });
+        assert!(pending.cancel("perm_1"));
+        assert!(pending.is_empty());
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(!fired.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn test_pending_permission_cancel_only_affects_its_id() {
+        let pending = Arc::new(PendingPermissions::new());
+        let fired = Arc::new(AtomicBool::new(false));
+
+        let fired_clone = Arc::clone(&fired);
+        pending.track("perm_2", Duration::from_millis(20), async move {
+            fired_clone.store(true, Ordering::SeqCst);
+        });
+        assert!(!pending.cancel("perm_1"));
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(fired.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn test_pending_permission_registered_without_timeout() {
+        let pending = Arc::new(PendingPermissions::new());
+        pending.register("perm_1");
+        assert_eq!(pending.len(), 1);
+
+        assert!(pending.cancel("perm_1"));
+        assert!(!pending.cancel("perm_1"));
+    }
+
+    #[test]
+    fn test_format_timeout_message() {
+        let msg = format_timeout_message(Duration::from_secs(600));
+        assert!(msg.contains("600s"));
+        assert!(msg.contains("Denied automatically"));
+    }
 
     #[test]
     fn test_format_permission_message() {
@@ -139,7 +369,7 @@ mod tests {
     fn test_create_inline_keyboard() {
         let keyboard = create_inline_keyboard("ses_123", "perm_456");
         assert_eq!(keyboard.inline_keyboard.len(), 1);
-        assert_eq!(keyboard.inline_keyboard[0].len(), 2);
+        assert_eq!(keyboard.inline_keyboard[0].len(), 3);
     }
 
     #[test]
@@ -160,6 +390,13 @@ mod tests {
         assert_eq!(action, "deny");
     }
 
+    #[test]
+    fn test_parse_callback_data_cancel() {
+        let (_, perm, action) = parse_callback_data("perm:ses_123:perm_456:cancel").unwrap();
+        assert_eq!(perm, "perm_456");
+        assert_eq!(action, "cancel");
+    }
+
     #[test]
     fn test_parse_callback_data_invalid_format() {
         let data = "invalid:data";
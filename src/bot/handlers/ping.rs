@@ -0,0 +1,131 @@
+//! /ping command handler
+//!
+//! Times a health-check round-trip to the topic's instance, waking it up
+//! first if it was stopped, to help tell a slow agent from a slow instance.
+
+use crate::bot::{BotState, Command};
+use crate::integration::Integration;
+use crate::project_config::{command_enabled, COMMAND_DISABLED_MESSAGE};
+use crate::types::error::{OutpostError, Result};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use teloxide::prelude::*;
+use teloxide::types::{MessageId, ThreadId};
+use tracing::debug;
+
+fn get_topic_id(msg: &Message) -> Result<i32> {
+    let thread_id = msg.thread_id.ok_or_else(|| {
+        OutpostError::telegram_error("This command must be used in a forum topic")
+    })?;
+
+    Ok(thread_id.0 .0)
+}
+
+fn format_ping(instance_id: &str, port: u16, latency: Duration, healthy: bool) -> String {
+    let headline = if healthy {
+        format!("🏓 Pong in {} ms", latency.as_millis())
+    } else {
+        format!(
+            "⚠️ Instance answered unhealthy after {} ms",
+            latency.as_millis()
+        )
+    };
+    format!("{}\nInstance: {} (port {})", headline, instance_id, port)
+}
+
+fn format_ping_failure(reason: &str) -> String {
+    format!("❌ Could not reach the instance: {}", reason)
+}
+
+/// Handle /ping command
+pub async fn handle_ping(
+    bot: Bot,
+    msg: Message,
+    _cmd: Command,
+    state: Arc<BotState>,
+    integration: Arc<Integration>,
+) -> Result<()> {
+    debug!(
+        chat_id = msg.chat.id.0,
+        topic_id = ?msg.thread_id.map(|t| t.0 .0),
+        sender_id = ?msg.from.as_ref().map(|u| u.id.0),
+        "Handling /ping"
+    );
+    let topic_id = get_topic_id(&msg)?;
+
+    let mut mapping = state
+        .topic_store
+        .get_mapping(msg.chat.id.0, topic_id)
+        .await
+        .map_err(|e| OutpostError::database_error(e.to_string()))?
+        .ok_or_else(|| OutpostError::telegram_error("No active connection in this topic"))?;
+
+    if !command_enabled(Path::new(&mapping.project_path), "ping") {
+        bot.send_message(msg.chat.id, COMMAND_DISABLED_MESSAGE)
+            .message_thread_id(ThreadId(MessageId(topic_id)))
+            .await
+            .map_err(|e| OutpostError::telegram_error(e.to_string()))?;
+        return Ok(());
+    }
+
+    let text = match integration
+        .get_port_or_resurrect(&bot, msg.chat.id, topic_id, &mut mapping)
+        .await
+    {
+        Err(e) => format_ping_failure(&e.to_string()),
+        Ok(_) => match state
+            .instance_manager
+            .get_instance_by_path(Path::new(&mapping.project_path))
+            .await
+        {
+            None => format_ping_failure("no instance is running for this project"),
+            Some(instance) => {
+                let inst = instance.lock().await;
+                let started = Instant::now();
+                let result = inst.health_check().await;
+                let latency = started.elapsed();
+                debug!(
+                    instance_id = %inst.id(),
+                    latency_ms = latency.as_millis() as u64,
+                    result = ?result,
+                    "Ping round-trip"
+                );
+                match result {
+                    Ok(healthy) => format_ping(inst.id(), inst.port(), latency, healthy),
+                    Err(e) => format_ping_failure(&e.to_string()),
+                }
+            }
+        },
+    };
+
+    bot.send_message(msg.chat.id, text)
+        .message_thread_id(ThreadId(MessageId(topic_id)))
+        .await
+        .map_err(|e| OutpostError::telegram_error(e.to_string()))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_ping_includes_latency_and_instance() {
+        let text = format_ping("inst_abc", 4100, Duration::from_millis(42), true);
+        assert_eq!(text, "🏓 Pong in 42 ms\nInstance: inst_abc (port 4100)");
+
+        let text = format_ping("inst_abc", 4100, Duration::from_millis(5003), false);
+        assert!(text.contains("unhealthy after 5003 ms"));
+        assert!(text.contains("inst_abc (port 4100)"));
+    }
+
+    #[test]
+    fn test_format_ping_failure() {
+        assert_eq!(
+            format_ping_failure("Session wake-up timed out"),
+            "❌ Could not reach the instance: Session wake-up timed out"
+        );
+    }
+}
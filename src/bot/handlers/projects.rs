@@ -1,25 +1,77 @@
 //! /projects command handler
 //!
 //! Lists all available project directories under PROJECT_BASE_PATH.
-//! Displays directories in alphabetical order.
+//! Displays directories in alphabetical order, each marked with the state
+//! and port of its OpenCode instance.
 
 use crate::bot::{BotState, Command};
-use crate::types::error::Result;
+use crate::orchestrator::store::OrchestratorStore;
+use crate::types::error::{OutpostError, Result};
+use crate::types::instance::InstanceState;
+use std::path::Path;
 use std::sync::Arc;
 use teloxide::prelude::*;
 use tracing::debug;
 
+/// A project directory and its instance, if one was ever started.
+#[derive(Debug, Clone, PartialEq)]
+struct ProjectStatus {
+    name: String,
+    state: Option<InstanceState>,
+    port: Option<u16>,
+}
+
+impl ProjectStatus {
+    fn marker(&self) -> String {
+        match (&self.state, self.port) {
+            (Some(InstanceState::Running), Some(port)) => {
+                format!("🟢 running on port {}", port)
+            }
+            (Some(InstanceState::Starting), Some(port)) => {
+                format!("🟡 starting on port {}", port)
+            }
+            _ => "⚪ stopped".to_string(),
+        }
+    }
+}
+
+/// Look up the instance for each project directory under `base_path`.
+async fn annotate_projects(
+    store: &OrchestratorStore,
+    base_path: &Path,
+    dirs: Vec<String>,
+) -> Result<Vec<ProjectStatus>> {
+    let mut projects = Vec::with_capacity(dirs.len());
+    for name in dirs {
+        let path = base_path.join(&name).display().to_string();
+        let instance = store
+            .get_instance_by_path(&path)
+            .await
+            .map_err(|e| OutpostError::database_error(e.to_string()))?;
+        projects.push(ProjectStatus {
+            name,
+            state: instance.as_ref().map(|i| i.state.clone()),
+            port: instance.map(|i| i.port),
+        });
+    }
+    Ok(projects)
+}
+
 /// Format project list for display
-fn format_projects(dirs: Vec<String>, base_path: &str) -> String {
-    if dirs.is_empty() {
+fn format_projects(projects: &[ProjectStatus], base_path: &str) -> String {
+    if projects.is_empty() {
         format!("No projects found in `{}`", base_path)
     } else {
-        let list = dirs
+        let running = projects
             .iter()
-            .map(|d| format!("• `{}`", d))
+            .filter(|p| p.state == Some(InstanceState::Running))
+            .count();
+        let list = projects
+            .iter()
+            .map(|p| format!("• `{}` {}", p.name, p.marker()))
             .collect::<Vec<_>>()
             .join("\n");
-        format!("📁 Available Projects\n\n{}", list)
+        format!("📁 Available Projects ({} running)\n\n{}", running, list)
     }
 }
 
@@ -58,14 +110,15 @@ pub async fn handle_projects(
         "Handling /projects"
     );
 
-    let base_path = &state.config.project_base_path;
+    let base_path = &state.config().project_base_path;
     let dirs = list_project_dirs(base_path);
+    let projects = annotate_projects(&state.orchestrator_store, base_path, dirs).await?;
     let base_path_str = base_path.display().to_string();
-    let output = format_projects(dirs, &base_path_str);
+    let output = format_projects(&projects, &base_path_str);
 
     bot.send_message(msg.chat.id, output)
         .await
-        .map_err(|e| crate::types::error::OutpostError::telegram_error(e.to_string()))?;
+        .map_err(|e| OutpostError::telegram_error(e.to_string()))?;
 
     Ok(())
 }
@@ -73,9 +126,21 @@ pub async fn handle_projects(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::types::instance::InstanceInfo;
     use std::path::PathBuf;
     use tempfile::TempDir;
 
+    fn stopped(names: &[&str]) -> Vec<ProjectStatus> {
+        names
+            .iter()
+            .map(|name| ProjectStatus {
+                name: name.to_string(),
+                state: None,
+                port: None,
+            })
+            .collect()
+    }
+
     #[test]
     fn test_list_project_dirs_with_subdirs() {
         let dir = TempDir::new().unwrap();
@@ -123,25 +188,21 @@ mod tests {
 
     #[test]
     fn test_format_projects_empty() {
-        let output = format_projects(vec![], "/home/user/projects");
+        let output = format_projects(&[], "/home/user/projects");
         assert_eq!(output, "No projects found in `/home/user/projects`");
     }
 
     #[test]
     fn test_format_projects_single() {
-        let output = format_projects(vec!["my-project".to_string()], "/home/user/projects");
+        let output = format_projects(&stopped(&["my-project"]), "/home/user/projects");
         assert!(output.contains("📁 Available Projects"));
         assert!(output.contains("• `my-project`"));
     }
 
     #[test]
     fn test_format_projects_multiple() {
-        let dirs = vec![
-            "project-a".to_string(),
-            "project-b".to_string(),
-            "project-c".to_string(),
-        ];
-        let output = format_projects(dirs, "/home/user/projects");
+        let dirs = stopped(&["project-a", "project-b", "project-c"]);
+        let output = format_projects(&dirs, "/home/user/projects");
         assert!(output.contains("📁 Available Projects"));
         assert!(output.contains("• `project-a`"));
         assert!(output.contains("• `project-b`"));
@@ -150,11 +211,51 @@ mod tests {
 
     #[test]
     fn test_format_projects_preserves_order() {
-        let dirs = vec!["alpha".to_string(), "beta".to_string(), "gamma".to_string()];
-        let output = format_projects(dirs, "/base");
+        let dirs = stopped(&["alpha", "beta", "gamma"]);
+        let output = format_projects(&dirs, "/base");
         let alpha_pos = output.find("alpha").unwrap();
         let beta_pos = output.find("beta").unwrap();
         let gamma_pos = output.find("gamma").unwrap();
         assert!(alpha_pos < beta_pos && beta_pos < gamma_pos);
     }
+
+    #[tokio::test]
+    async fn test_annotate_projects_with_seeded_instances() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = OrchestratorStore::new(&temp_dir.path().join("orch.db"))
+            .await
+            .unwrap();
+        let base = PathBuf::from("/home/user/projects");
+        for (id, name, state, port) in [
+            ("inst-alpha", "alpha", InstanceState::Running, 4100),
+            ("inst-beta", "beta", InstanceState::Stopped, 4101),
+        ] {
+            let info = InstanceInfo {
+                id: id.to_string(),
+                state,
+                project_path: base.join(name).display().to_string(),
+                port,
+                pid: None,
+                container_id: None,
+                started_at: None,
+                stopped_at: None,
+                topic_id: 0,
+            };
+            store.save_instance(&info, None).await.unwrap();
+        }
+
+        let dirs = vec!["alpha".to_string(), "beta".to_string(), "gamma".to_string()];
+        let projects = annotate_projects(&store, &base, dirs).await.unwrap();
+
+        assert_eq!(projects[0].state, Some(InstanceState::Running));
+        assert_eq!(projects[0].port, Some(4100));
+        assert_eq!(projects[1].state, Some(InstanceState::Stopped));
+        assert_eq!(projects[2].state, None);
+
+        let output = format_projects(&projects, "/home/user/projects");
+        assert!(output.contains("(1 running)"));
+        assert!(output.contains("• `alpha` 🟢 running on port 4100"));
+        assert!(output.contains("• `beta` ⚪ stopped"));
+        assert!(output.contains("• `gamma` ⚪ stopped"));
+    }
 }
@@ -0,0 +1,106 @@
+//! /reload command handler
+//!
+//! Re-reads the environment (and `.env`) and applies the hot-reloadable
+//! settings to the running bot. Instances, topics and the dispatcher are left
+//! untouched; settings outside [`HOT_RELOADABLE_SETTINGS`] still need a restart.
+//! Limited to `TELEGRAM_ADMIN_USERS`.
+
+use crate::bot::handlers::access::{is_admin_sender, ADMIN_ONLY_MESSAGE};
+use crate::bot::{BotState, Command};
+use crate::config::{Config, HOT_RELOADABLE_SETTINGS};
+use crate::types::error::{OutpostError, Result};
+use std::sync::Arc;
+use teloxide::prelude::*;
+use tracing::{debug, info, warn};
+
+/// Format the reply for a successful reload. `ignored` lists changed
+/// settings that only take effect after a restart.
+fn format_reload_result(changed: &[&str], ignored: &[&str]) -> String {
+    let mut output = if changed.is_empty() {
+        String::from("Config reloaded. No hot-reloadable settings changed.")
+    } else {
+        format!("Config reloaded. Updated: {}", changed.join(", "))
+    };
+    if !ignored.is_empty() {
+        output.push_str(&format!(
+            "\n\nChanged but need a restart (startup values kept): {}",
+            ignored.join(", ")
+        ));
+    }
+    output.push_str(&format!(
+        "\n\nHot-reloadable: {}\nOther settings require a restart.",
+        HOT_RELOADABLE_SETTINGS.join(", ")
+    ));
+    output
+}
+
+/// Handle /reload command
+pub async fn handle_reload(
+    bot: Bot,
+    msg: Message,
+    _cmd: Command,
+    state: Arc<BotState>,
+) -> Result<()> {
+    debug!(
+        chat_id = msg.chat.id.0,
+        topic_id = ?msg.thread_id.map(|t| t.0 .0),
+        sender_id = ?msg.from.as_ref().map(|u| u.id.0),
+        "Handling /reload"
+    );
+    if !is_admin_sender(&state.config(), msg.from.as_ref()) {
+        bot.send_message(msg.chat.id, ADMIN_ONLY_MESSAGE)
+            .await
+            .map_err(|e| OutpostError::telegram_error(e.to_string()))?;
+        return Ok(());
+    }
+
+    let output = match Config::reload_from_env() {
+        Ok(fresh) => {
+            let ignored = state.config().restart_required_changes(&fresh);
+            if !ignored.is_empty() {
+                warn!(settings = ?ignored, "Changed settings require a restart; keeping startup values");
+            }
+            let changed = state.config.reload(&fresh);
+            info!(changed = ?changed, "Config reloaded");
+            format_reload_result(&changed, &ignored)
+        }
+        Err(e) => {
+            warn!(error = %e, "Config reload failed; keeping current config");
+            format!("Reload failed, keeping current config: {}", e)
+        }
+    };
+
+    bot.send_message(msg.chat.id, output)
+        .await
+        .map_err(|e| OutpostError::telegram_error(e.to_string()))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_reload_result_no_changes() {
+        let output = format_reload_result(&[], &[]);
+        assert!(output.contains("No hot-reloadable settings changed"));
+        assert!(output.contains("OPENCODE_IDLE_TIMEOUT_MS"));
+        assert!(!output.contains("need a restart"));
+    }
+
+    #[test]
+    fn test_format_reload_result_lists_changes() {
+        let output = format_reload_result(&["SHOW_USAGE", "FALLBACK_MODEL"], &[]);
+        assert!(output.contains("Updated: SHOW_USAGE, FALLBACK_MODEL"));
+        assert!(output.contains("require a restart"));
+    }
+
+    #[test]
+    fn test_format_reload_result_lists_restart_required() {
+        let output = format_reload_result(&[], &["API_PORT", "PROJECT_BASE_PATH"]);
+        assert!(output.contains(
+            "Changed but need a restart (startup values kept): API_PORT, PROJECT_BASE_PATH"
+        ));
+    }
+}
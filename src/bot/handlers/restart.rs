@@ -0,0 +1,103 @@
+//! /restart command handler
+//!
+//! Stops the topic's instance and resets its circuit breaker, so the next
+//! message starts a fresh instance even after repeated crashes.
+
+use crate::bot::{BotState, Command};
+use crate::integration::Integration;
+use crate::project_config::{command_enabled, COMMAND_DISABLED_MESSAGE};
+use crate::types::error::{OutpostError, Result};
+use std::path::Path;
+use std::sync::Arc;
+use teloxide::prelude::*;
+use teloxide::types::{MessageId, ThreadId};
+use tracing::{debug, warn};
+
+fn get_topic_id(msg: &Message) -> Result<i32> {
+    let thread_id = msg.thread_id.ok_or_else(|| {
+        OutpostError::telegram_error("This command must be used in a forum topic")
+    })?;
+
+    Ok(thread_id.0 .0)
+}
+
+fn format_restart_result(was_tripped: bool) -> String {
+    let mut text = String::from("🔄 Instance restarted.");
+    if was_tripped {
+        text.push_str(" It was in a failed state after repeated crashes and has been reset.");
+    }
+    text.push_str(" The next message starts a fresh instance.");
+    text
+}
+
+/// Handle /restart command
+pub async fn handle_restart(
+    bot: Bot,
+    msg: Message,
+    _cmd: Command,
+    state: Arc<BotState>,
+    integration: Arc<Integration>,
+) -> Result<()> {
+    debug!(
+        chat_id = msg.chat.id.0,
+        topic_id = ?msg.thread_id.map(|t| t.0 .0),
+        sender_id = ?msg.from.as_ref().map(|u| u.id.0),
+        "Handling /restart"
+    );
+    let topic_id = get_topic_id(&msg)?;
+
+    let mapping = state
+        .topic_store
+        .get_mapping(msg.chat.id.0, topic_id)
+        .await
+        .map_err(|e| OutpostError::database_error(e.to_string()))?
+        .ok_or_else(|| OutpostError::telegram_error("No active connection in this topic"))?;
+
+    let project_path = Path::new(&mapping.project_path);
+    if !command_enabled(project_path, "restart") {
+        bot.send_message(msg.chat.id, COMMAND_DISABLED_MESSAGE)
+            .message_thread_id(ThreadId(MessageId(topic_id)))
+            .await
+            .map_err(|e| OutpostError::telegram_error(e.to_string()))?;
+        return Ok(());
+    }
+
+    let was_tripped = state.instance_manager.reset_circuit(project_path).await;
+    if let Some(instance) = state
+        .instance_manager
+        .get_instance_by_path(project_path)
+        .await
+    {
+        let instance_id = instance.lock().await.id().to_string();
+        if let Err(e) = state.instance_manager.stop_instance(&instance_id).await {
+            warn!(instance_id = %instance_id, error = %e, "Failed to stop instance for /restart");
+        }
+    }
+    integration.stop_stream(topic_id).await;
+    debug!(
+        topic_id = topic_id,
+        was_tripped = was_tripped,
+        "Instance restarted"
+    );
+
+    bot.send_message(msg.chat.id, format_restart_result(was_tripped))
+        .message_thread_id(ThreadId(MessageId(topic_id)))
+        .await
+        .map_err(|e| OutpostError::telegram_error(e.to_string()))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_restart_result() {
+        assert_eq!(
+            format_restart_result(false),
+            "🔄 Instance restarted. The next message starts a fresh instance."
+        );
+        assert!(format_restart_result(true).contains("failed state"));
+    }
+}
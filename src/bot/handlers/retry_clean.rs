@@ -0,0 +1,298 @@
+//! /retry_clean command handler
+//!
+//! Starts a fresh OpenCode session for the current topic and replays the
+//! user's last prompt into it, for when a session's context got confused.
+
+use crate::bot::handlers::lang::locale_instruction;
+use crate::bot::{BotState, Command};
+use crate::integration::Integration;
+use crate::opencode::stream_handler::StreamHandler;
+use crate::opencode::OpenCodeClient;
+use crate::project_config::{command_enabled, COMMAND_DISABLED_MESSAGE};
+use crate::types::error::{OutpostError, Result};
+use crate::types::opencode::{Message as OpenCodeMessage, MessagePart};
+use std::path::Path;
+use std::sync::Arc;
+use teloxide::prelude::*;
+use teloxide::types::{MessageId, ThreadId};
+use tracing::{debug, info};
+
+fn get_topic_id(msg: &Message) -> Result<i32> {
+    let thread_id = msg.thread_id.ok_or_else(|| {
+        OutpostError::telegram_error("This command must be used in a forum topic")
+    })?;
+
+    if thread_id.0 .0 == 1 {
+        return Err(OutpostError::telegram_error(
+            "Cannot retry in the General topic",
+        ));
+    }
+
+    Ok(thread_id.0 .0)
+}
+
+/// Parts of the most recent user message, if any.
+fn last_user_prompt(messages: &[OpenCodeMessage]) -> Option<Vec<MessagePart>> {
+    messages
+        .iter()
+        .rev()
+        .find(|m| m.role == "user" && !m.content.is_empty())
+        .map(|m| m.content.clone())
+}
+
+/// Create a fresh session, with the topic's `/lang` instruction if any, and
+/// replay the last user prompt of `old_session_id` into it. Returns the new
+/// session id, or `None` when there is nothing to replay (no session is
+/// created in that case).
+async fn replay_in_clean_session(
+    client: &OpenCodeClient,
+    stream_handler: &StreamHandler,
+    old_session_id: &str,
+    project_path: &Path,
+    locale: Option<&str>,
+) -> anyhow::Result<Option<String>> {
+    let messages = client.list_messages(old_session_id).await?;
+    let Some(parts) = last_user_prompt(&messages) else {
+        return Ok(None);
+    };
+
+    let system = locale.map(locale_instruction);
+    let session = client
+        .create_session_with_system(project_path, system.as_deref())
+        .await?;
+    debug!(
+        old_session_id = %old_session_id,
+        new_session_id = %session.id,
+        parts_count = parts.len(),
+        "Replaying last prompt into clean session"
+    );
+
+    for part in &parts {
+        if let MessagePart::Text { text } = part {
+            stream_handler.mark_from_telegram(&session.id, text);
+        }
+    }
+    client.send_message_parts_async(&session.id, parts).await?;
+
+    Ok(Some(session.id))
+}
+
+/// Handle /retry_clean command
+pub async fn handle_retry_clean(
+    bot: Bot,
+    msg: Message,
+    _cmd: Command,
+    state: Arc<BotState>,
+    stream_handler: Arc<StreamHandler>,
+    integration: Arc<Integration>,
+) -> Result<()> {
+    debug!(
+        chat_id = msg.chat.id.0,
+        topic_id = ?msg.thread_id.map(|t| t.0 .0),
+        sender_id = ?msg.from.as_ref().map(|u| u.id.0),
+        "Handling /retry_clean"
+    );
+
+    let topic_id = get_topic_id(&msg)?;
+
+    let mut mapping = state
+        .topic_store
+        .get_mapping(msg.chat.id.0, topic_id)
+        .await
+        .map_err(|e| OutpostError::database_error(e.to_string()))?
+        .ok_or_else(|| OutpostError::telegram_error("No active connection in this topic"))?;
+
+    if !command_enabled(Path::new(&mapping.project_path), "retry_clean") {
+        bot.send_message(msg.chat.id, COMMAND_DISABLED_MESSAGE)
+            .message_thread_id(ThreadId(MessageId(topic_id)))
+            .await
+            .map_err(|e| OutpostError::telegram_error(e.to_string()))?;
+        return Ok(());
+    }
+
+    let old_session_id = mapping.session_id.clone().ok_or_else(|| {
+        OutpostError::session_not_found(format!("No session for topic {}", topic_id))
+    })?;
+
+    let instance_id = mapping
+        .instance_id
+        .clone()
+        .ok_or_else(|| OutpostError::telegram_error("No instance for this topic"))?;
+    let instance = state
+        .orchestrator_store
+        .get_instance(&instance_id)
+        .await
+        .map_err(|e| OutpostError::database_error(e.to_string()))?
+        .ok_or_else(|| OutpostError::telegram_error("Instance not found"))?;
+    let client = OpenCodeClient::for_port(&state.config(), instance.port)
+        .map_err(|e| OutpostError::config_error(e.to_string()))?;
+
+    let new_session_id = replay_in_clean_session(
+        &client,
+        &stream_handler,
+        &old_session_id,
+        Path::new(&mapping.project_path),
+        mapping.locale.as_deref(),
+    )
+    .await
+    .map_err(|e| OutpostError::opencode_api_error(e.to_string()))?;
+
+    let Some(new_session_id) = new_session_id else {
+        bot.send_message(msg.chat.id, "No previous prompt to retry in this session.")
+            .message_thread_id(ThreadId(MessageId(topic_id)))
+            .await
+            .map_err(|e| OutpostError::telegram_error(e.to_string()))?;
+        return Ok(());
+    };
+
+    mapping.session_id = Some(new_session_id.clone());
+    mapping.updated_at = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64;
+    state
+        .topic_store
+        .save_mapping(&mapping)
+        .await
+        .map_err(|e| OutpostError::database_error(e.to_string()))?;
+
+    info!(
+        topic_id = topic_id,
+        old_session_id = %old_session_id,
+        new_session_id = %new_session_id,
+        "Topic moved to clean session"
+    );
+
+    // Follow the new session's stream instead of the old one
+    integration.stop_stream(topic_id).await;
+    stream_handler.unsubscribe(&old_session_id).await;
+    integration
+        .ensure_stream_subscription(bot.clone(), msg.chat.id, topic_id, &mapping)
+        .await?;
+
+    bot.send_message(
+        msg.chat.id,
+        format!(
+            "🔄 Started clean session {} and replayed your last prompt.",
+            new_session_id
+        ),
+    )
+    .message_thread_id(ThreadId(MessageId(topic_id)))
+    .await
+    .map_err(|e| OutpostError::telegram_error(e.to_string()))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{body_string_contains, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn message(role: &str, text: &str) -> OpenCodeMessage {
+        OpenCodeMessage {
+            role: role.to_string(),
+            content: vec![MessagePart::Text {
+                text: text.to_string(),
+            }],
+        }
+    }
+
+    #[test]
+    fn test_last_user_prompt_picks_most_recent_user_message() {
+        let messages = vec![
+            message("user", "first prompt"),
+            message("assistant", "first answer"),
+            message("user", "second prompt"),
+            message("assistant", "second answer"),
+        ];
+
+        let parts = last_user_prompt(&messages).unwrap();
+        assert_eq!(parts.len(), 1);
+        match &parts[0] {
+            MessagePart::Text { text } => assert_eq!(text, "second prompt"),
+            _ => panic!("Expected text part"),
+        }
+    }
+
+    #[test]
+    fn test_last_user_prompt_none_without_user_messages() {
+        assert!(last_user_prompt(&[]).is_none());
+        assert!(last_user_prompt(&[message("assistant", "hello")]).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_replay_routes_last_prompt_to_new_session() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/session/ses_old/message"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!([
+                {"role": "user", "content": [{"type": "text", "text": "refactor the parser"}]},
+                {"role": "assistant", "content": [{"type": "text", "text": "done, kind of"}]}
+            ])))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/session"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": "ses_new",
+                "title": null,
+                "created": 1640000000,
+                "updated": 1640000000
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/session/ses_new/prompt_async"))
+            .and(body_string_contains("refactor the parser"))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = OpenCodeClient::new(&server.uri());
+        let stream_handler = StreamHandler::new(client.clone(), Default::default());
+        let new_session = replay_in_clean_session(
+            &client,
+            &stream_handler,
+            "ses_old",
+            Path::new("/workspace"),
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(new_session.as_deref(), Some("ses_new"));
+    }
+
+    #[tokio::test]
+    async fn test_replay_without_prompt_creates_no_session() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/session/ses_old/message"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!([])))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(500))
+            .expect(0)
+            .mount(&server)
+            .await;
+
+        let client = OpenCodeClient::new(&server.uri());
+        let stream_handler = StreamHandler::new(client.clone(), Default::default());
+        let new_session = replay_in_clean_session(
+            &client,
+            &stream_handler,
+            "ses_old",
+            Path::new("/workspace"),
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert!(new_session.is_none());
+    }
+}
@@ -1,11 +1,14 @@
 //! /session command handler
 
 use crate::bot::{BotState, Command};
+use crate::project_config::{command_enabled, COMMAND_DISABLED_MESSAGE};
 use crate::types::error::{OutpostError, Result};
 use crate::types::forum::TopicMapping;
 use crate::types::instance::InstanceInfo;
+use std::path::Path;
 use std::sync::Arc;
 use teloxide::prelude::*;
+use teloxide::types::{MessageId, ThreadId};
 use tracing::debug;
 
 /// Extract topic_id from message, ensuring it's not the General topic
@@ -25,7 +28,7 @@ fn get_topic_id(msg: &Message) -> Result<i32> {
 }
 
 /// Format timestamp (Unix seconds) to readable format
-fn format_timestamp(timestamp: i64) -> String {
+pub(crate) fn format_timestamp(timestamp: i64) -> String {
     // Convert Unix timestamp to a basic date format
     // This is a simple implementation without external dependencies
     let days_since_epoch = timestamp / 86400;
@@ -137,6 +140,14 @@ pub async fn handle_session(
         .await
         .map_err(|e| OutpostError::database_error(e.to_string()))?
         .ok_or_else(|| OutpostError::telegram_error("No active connection in this topic"))?;
+
+    if !command_enabled(Path::new(&mapping.project_path), "session") {
+        bot.send_message(msg.chat.id, COMMAND_DISABLED_MESSAGE)
+            .message_thread_id(ThreadId(MessageId(topic_id)))
+            .await
+            .map_err(|e| OutpostError::telegram_error(e.to_string()))?;
+        return Ok(());
+    }
     debug!(topic_id = topic_id, session_id = ?mapping.session_id, instance_id = ?mapping.instance_id, "Mapping found for session info");
 
     // Get instance info if available
@@ -178,6 +189,11 @@ mod tests {
             session_id: Some("ses_abc123456".to_string()),
             instance_id: Some("inst_001".to_string()),
             topic_name_updated: false,
+            read_only: false,
+            output_topic_id: None,
+            system_prompt_mtime: None,
+            locale: None,
+            last_activity_at: None,
             created_at: 1640000000,
             updated_at: 1640000100,
         };
@@ -213,6 +229,11 @@ mod tests {
             session_id: None,
             instance_id: None,
             topic_name_updated: false,
+            read_only: false,
+            output_topic_id: None,
+            system_prompt_mtime: None,
+            locale: None,
+            last_activity_at: None,
             created_at: 1640000000,
             updated_at: 1640000100,
         };
@@ -236,6 +257,11 @@ mod tests {
             session_id: Some("ses_xyz789".to_string()),
             instance_id: Some("inst_002".to_string()),
             topic_name_updated: true,
+            read_only: false,
+            output_topic_id: None,
+            system_prompt_mtime: None,
+            locale: None,
+            last_activity_at: None,
             created_at: 1650000000,
             updated_at: 1650000200,
         };
@@ -268,6 +294,11 @@ mod tests {
             session_id: Some("ses_ext123".to_string()),
             instance_id: Some("inst_ext".to_string()),
             topic_name_updated: false,
+            read_only: false,
+            output_topic_id: None,
+            system_prompt_mtime: None,
+            locale: None,
+            last_activity_at: None,
             created_at: 1660000000,
             updated_at: 1660000300,
         };
@@ -301,6 +332,11 @@ mod tests {
             session_id: Some("ses_docker".to_string()),
             instance_id: Some("inst_docker".to_string()),
             topic_name_updated: false,
+            read_only: false,
+            output_topic_id: None,
+            system_prompt_mtime: None,
+            locale: None,
+            last_activity_at: None,
             created_at: 1660000000,
             updated_at: 1660000300,
         };
@@ -1,9 +1,13 @@
 //! /sessions command handler
 
 use crate::bot::{BotState, Command};
-use crate::types::error::Result;
+use crate::types::error::{OutpostError, Result};
+use crate::types::forum::TopicMapping;
+use crate::types::instance::{InstanceInfo, InstanceState};
+use std::collections::HashMap;
 use std::path::Path;
 use std::sync::Arc;
+use std::time::Duration;
 use teloxide::prelude::*;
 use tracing::debug;
 
@@ -13,8 +17,26 @@ const MAX_SESSIONS_PER_PAGE: usize = 10;
 struct SessionInfo {
     name: String,
     path: String,
-    session_id: String,
-    port: u16,
+    session_id: Option<String>,
+    state: Option<InstanceState>,
+    port: Option<u16>,
+    idle_secs: Option<u64>,
+}
+
+impl SessionInfo {
+    fn is_running(&self) -> bool {
+        matches!(self.state, Some(InstanceState::Running))
+    }
+}
+
+fn state_emoji(state: Option<&InstanceState>) -> &'static str {
+    match state {
+        Some(InstanceState::Running) => "🟢",
+        Some(InstanceState::Starting) => "🟡",
+        Some(InstanceState::Stopping) => "🟠",
+        Some(InstanceState::Error) => "🔴",
+        Some(InstanceState::Stopped) | None => "⚪",
+    }
 }
 
 fn format_sessions(sessions: &[SessionInfo]) -> String {
@@ -22,13 +44,33 @@ fn format_sessions(sessions: &[SessionInfo]) -> String {
         return "No active sessions found.".to_string();
     }
 
-    let mut output = format!("Active Sessions ({})\n\n", sessions.len());
+    let running = sessions.iter().filter(|s| s.is_running()).count();
+    let mut output = format!("Sessions ({}, {} running)\n\n", sessions.len(), running);
 
     for session in sessions.iter().take(MAX_SESSIONS_PER_PAGE) {
+        let state = session
+            .state
+            .as_ref()
+            .map(|s| format!("{:?}", s))
+            .unwrap_or_else(|| "Stopped".to_string());
         output.push_str(&format!(
-            "{}\n{}\n{}\nPort: {}\n\n",
-            session.name, session.path, session.session_id, session.port
+            "{} {} ({})\n{}\n",
+            state_emoji(session.state.as_ref()),
+            session.name,
+            state,
+            session.path
         ));
+        if let Some(session_id) = &session.session_id {
+            output.push_str(&format!("{}\n", session_id));
+        }
+        match session.port {
+            Some(port) => output.push_str(&format!("Port: {}\n", port)),
+            None => output.push_str("Port: -\n"),
+        }
+        if let Some(idle_secs) = session.idle_secs {
+            output.push_str(&format!("Last activity: {}s ago\n", idle_secs));
+        }
+        output.push('\n');
     }
 
     if sessions.len() > MAX_SESSIONS_PER_PAGE {
@@ -39,39 +81,67 @@ fn format_sessions(sessions: &[SessionInfo]) -> String {
     output
 }
 
-async fn get_sessions(state: &BotState) -> Result<Vec<SessionInfo>> {
-    let instances = state
-        .orchestrator_store
-        .get_all_instances()
-        .await
-        .map_err(|e| {
-            crate::types::error::OutpostError::database_error(format!(
-                "Failed to list instances: {}",
-                e
-            ))
-        })?;
-
-    let sessions = instances
+/// Join topic mappings with persisted instance state and in-memory activity.
+///
+/// Instances are matched by the mapping's `instance_id`, falling back to the
+/// project path. Running sessions are listed first, then by project name.
+fn join_sessions(
+    mappings: Vec<TopicMapping>,
+    instances: &[InstanceInfo],
+    activity: &HashMap<String, Duration>,
+) -> Vec<SessionInfo> {
+    let mut sessions: Vec<SessionInfo> = mappings
         .into_iter()
-        .filter(|info| {
-            matches!(
-                info.state,
-                crate::types::instance::InstanceState::Running
-                    | crate::types::instance::InstanceState::Starting
-            )
-        })
-        .map(|info| {
-            let name = extract_project_name(&info.project_path);
+        .map(|mapping| {
+            let instance = mapping
+                .instance_id
+                .as_deref()
+                .and_then(|id| instances.iter().find(|i| i.id == id))
+                .or_else(|| {
+                    instances
+                        .iter()
+                        .find(|i| i.project_path == mapping.project_path)
+                });
+            let idle_secs = instance
+                .and_then(|i| activity.get(&i.id))
+                .map(|d| d.as_secs());
+
             SessionInfo {
-                name,
-                path: info.project_path,
-                session_id: info.id.to_string(),
-                port: info.port,
+                name: extract_project_name(&mapping.project_path),
+                path: mapping.project_path,
+                session_id: mapping.session_id,
+                state: instance.map(|i| i.state.clone()),
+                port: instance.map(|i| i.port),
+                idle_secs,
             }
         })
         .collect();
 
-    Ok(sessions)
+    sessions.sort_by(|a, b| {
+        b.is_running()
+            .cmp(&a.is_running())
+            .then_with(|| a.name.cmp(&b.name))
+    });
+
+    sessions
+}
+
+async fn get_sessions(state: &BotState, chat_id: i64) -> Result<Vec<SessionInfo>> {
+    let mappings = state
+        .topic_store
+        .get_mappings_by_chat(chat_id)
+        .await
+        .map_err(|e| OutpostError::database_error(format!("Failed to list mappings: {}", e)))?;
+
+    let instances = state
+        .orchestrator_store
+        .get_all_instances()
+        .await
+        .map_err(|e| OutpostError::database_error(format!("Failed to list instances: {}", e)))?;
+
+    let activity = state.instance_manager.activity_snapshot().await;
+
+    Ok(join_sessions(mappings, &instances, &activity))
 }
 
 fn extract_project_name(path: &str) -> String {
@@ -94,13 +164,15 @@ pub async fn handle_sessions(
         sender_id = ?msg.from.as_ref().map(|u| u.id.0),
         "Handling /sessions"
     );
-    let sessions = get_sessions(&state).await.unwrap_or_default();
+    let sessions = get_sessions(&state, msg.chat.id.0)
+        .await
+        .unwrap_or_default();
     debug!(count = sessions.len(), "Sessions retrieved");
     let output = format_sessions(&sessions);
 
     bot.send_message(msg.chat.id, output)
         .await
-        .map_err(|e| crate::types::error::OutpostError::telegram_error(e.to_string()))?;
+        .map_err(|e| OutpostError::telegram_error(e.to_string()))?;
 
     Ok(())
 }
@@ -108,6 +180,57 @@ pub async fn handle_sessions(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::forum::TopicStore;
+    use crate::orchestrator::store::OrchestratorStore;
+    use tempfile::TempDir;
+
+    fn make_session(name: &str, state: Option<InstanceState>, port: Option<u16>) -> SessionInfo {
+        SessionInfo {
+            name: name.to_string(),
+            path: format!("/home/user/{}", name),
+            session_id: Some(format!("ses_{}", name)),
+            state,
+            port,
+            idle_secs: None,
+        }
+    }
+
+    fn make_mapping(topic_id: i32, project_path: &str, instance_id: Option<&str>) -> TopicMapping {
+        TopicMapping {
+            topic_id,
+            chat_id: -1001234567890,
+            project_path: project_path.to_string(),
+            session_id: Some(format!("ses_{}", topic_id)),
+            instance_id: instance_id.map(String::from),
+            topic_name_updated: false,
+            read_only: false,
+            output_topic_id: None,
+            system_prompt_mtime: None,
+            locale: None,
+            last_activity_at: None,
+            created_at: 1000,
+            updated_at: 1000,
+        }
+    }
+
+    fn make_instance(
+        id: &str,
+        project_path: &str,
+        state: InstanceState,
+        port: u16,
+    ) -> InstanceInfo {
+        InstanceInfo {
+            id: id.to_string(),
+            state,
+            project_path: project_path.to_string(),
+            port,
+            pid: None,
+            container_id: None,
+            started_at: None,
+            stopped_at: None,
+            topic_id: 0,
+        }
+    }
 
     #[test]
     fn test_format_empty_list() {
@@ -117,63 +240,174 @@ mod tests {
 
     #[test]
     fn test_format_single_session() {
-        let sessions = vec![SessionInfo {
-            name: "my-project".to_string(),
-            path: "/home/user/my-project".to_string(),
-            session_id: "ses_abc123".to_string(),
-            port: 4100,
-        }];
+        let mut session = make_session("my-project", Some(InstanceState::Running), Some(4100));
+        session.idle_secs = Some(42);
 
-        let output = format_sessions(&sessions);
-        assert!(output.contains("Active Sessions (1)"));
-        assert!(output.contains("my-project"));
+        let output = format_sessions(&[session]);
+        assert!(output.contains("Sessions (1, 1 running)"));
+        assert!(output.contains("🟢 my-project (Running)"));
         assert!(output.contains("/home/user/my-project"));
-        assert!(output.contains("ses_abc123"));
+        assert!(output.contains("ses_my-project"));
         assert!(output.contains("Port: 4100"));
+        assert!(output.contains("Last activity: 42s ago"));
     }
 
     #[test]
     fn test_format_multiple_sessions() {
         let sessions = vec![
-            SessionInfo {
-                name: "project1".to_string(),
-                path: "/home/user/project1".to_string(),
-                session_id: "ses_abc123".to_string(),
-                port: 4100,
-            },
-            SessionInfo {
-                name: "project2".to_string(),
-                path: "/home/user/project2".to_string(),
-                session_id: "ses_def456".to_string(),
-                port: 4101,
-            },
+            make_session("project1", Some(InstanceState::Running), Some(4100)),
+            make_session("project2", None, None),
         ];
 
         let output = format_sessions(&sessions);
-        assert!(output.contains("Active Sessions (2)"));
+        assert!(output.contains("Sessions (2, 1 running)"));
         assert!(output.contains("project1"));
-        assert!(output.contains("project2"));
+        assert!(output.contains("⚪ project2 (Stopped)"));
+        assert!(output.contains("Port: -"));
     }
 
     #[test]
     fn test_pagination_many_sessions() {
         let sessions: Vec<SessionInfo> = (0..15)
-            .map(|i| SessionInfo {
-                name: format!("project{}", i),
-                path: format!("/home/user/project{}", i),
-                session_id: format!("ses_{}", i),
-                port: 4100 + i as u16,
+            .map(|i| {
+                make_session(
+                    &format!("project{}", i),
+                    Some(InstanceState::Running),
+                    Some(4100 + i as u16),
+                )
             })
             .collect();
 
         let output = format_sessions(&sessions);
-        assert!(output.contains("Active Sessions (15)"));
+        assert!(output.contains("Sessions (15, 15 running)"));
         assert!(output.contains("... and 5 more"));
         assert!(output.contains("project0"));
         assert!(output.contains("project9"));
         assert!(!output.contains("project10"));
     }
 
+    #[test]
+    fn test_state_emoji() {
+        assert_eq!(state_emoji(Some(&InstanceState::Running)), "🟢");
+        assert_eq!(state_emoji(Some(&InstanceState::Starting)), "🟡");
+        assert_eq!(state_emoji(Some(&InstanceState::Error)), "🔴");
+        assert_eq!(state_emoji(Some(&InstanceState::Stopped)), "⚪");
+        assert_eq!(state_emoji(None), "⚪");
+    }
+
+    #[test]
+    fn test_join_sessions_matches_by_path_when_instance_id_missing() {
+        let mappings = vec![make_mapping(1, "/home/user/alpha", None)];
+        let instances = vec![make_instance(
+            "inst-alpha",
+            "/home/user/alpha",
+            InstanceState::Running,
+            4100,
+        )];
+
+        let sessions = join_sessions(mappings, &instances, &HashMap::new());
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].port, Some(4100));
+        assert_eq!(sessions[0].state, Some(InstanceState::Running));
+        assert_eq!(sessions[0].idle_secs, None);
+    }
+
+    #[tokio::test]
+    async fn test_join_sessions_with_seeded_stores() {
+        let temp_dir = TempDir::new().unwrap();
+        let topic_store = TopicStore::new(&temp_dir.path().join("topics.db"))
+            .await
+            .unwrap();
+        let orchestrator_store = OrchestratorStore::new(&temp_dir.path().join("orch.db"))
+            .await
+            .unwrap();
+
+        topic_store
+            .save_mapping(&make_mapping(1, "/home/user/zeta", Some("inst-zeta")))
+            .await
+            .unwrap();
+        topic_store
+            .save_mapping(&make_mapping(2, "/home/user/alpha", Some("inst-alpha")))
+            .await
+            .unwrap();
+        topic_store
+            .save_mapping(&make_mapping(3, "/home/user/beta", None))
+            .await
+            .unwrap();
+
+        orchestrator_store
+            .save_instance(
+                &make_instance("inst-zeta", "/home/user/zeta", InstanceState::Running, 4101),
+                None,
+            )
+            .await
+            .unwrap();
+        orchestrator_store
+            .save_instance(
+                &make_instance(
+                    "inst-alpha",
+                    "/home/user/alpha",
+                    InstanceState::Stopped,
+                    4100,
+                ),
+                None,
+            )
+            .await
+            .unwrap();
+
+        let mappings = topic_store
+            .get_mappings_by_chat(-1001234567890)
+            .await
+            .unwrap();
+        let instances = orchestrator_store.get_all_instances().await.unwrap();
+        let activity = HashMap::from([("inst-zeta".to_string(), Duration::from_secs(17))]);
+
+        let sessions = join_sessions(mappings, &instances, &activity);
+        let names: Vec<&str> = sessions.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["zeta", "alpha", "beta"]);
+
+        assert_eq!(sessions[0].state, Some(InstanceState::Running));
+        assert_eq!(sessions[0].port, Some(4101));
+        assert_eq!(sessions[0].idle_secs, Some(17));
+        assert_eq!(sessions[1].state, Some(InstanceState::Stopped));
+        assert_eq!(sessions[2].state, None);
+
+        let output = format_sessions(&sessions);
+        assert!(output.contains("Sessions (3, 1 running)"));
+        assert!(output.contains("🟢 zeta (Running)"));
+        assert!(output.contains("Last activity: 17s ago"));
+        assert!(output.find("zeta").unwrap() < output.find("alpha").unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_archived_mappings_hidden_from_sessions() {
+        let temp_dir = TempDir::new().unwrap();
+        let topic_store = TopicStore::new(&temp_dir.path().join("topics.db"))
+            .await
+            .unwrap();
+
+        topic_store
+            .save_mapping(&make_mapping(1, "/home/user/kept", None))
+            .await
+            .unwrap();
+        topic_store
+            .save_mapping(&make_mapping(2, "/home/user/archived", None))
+            .await
+            .unwrap();
+        topic_store
+            .archive_mapping(-1001234567890, 2)
+            .await
+            .unwrap();
+
+        let mappings = topic_store
+            .get_mappings_by_chat(-1001234567890)
+            .await
+            .unwrap();
+        let sessions = join_sessions(mappings, &[], &HashMap::new());
+        let names: Vec<&str> = sessions.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["kept"]);
+    }
+
     #[test]
     fn test_extract_project_name() {
         assert_eq!(extract_project_name("/home/user/my-project"), "my-project");
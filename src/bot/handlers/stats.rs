@@ -0,0 +1,171 @@
+//! /stats command handler
+//!
+//! Summarizes bot runs, instance lifecycle events and response feedback
+//! recorded in the log DB over the last N days (default 7).
+
+use crate::bot::{BotState, Command};
+use crate::db::log_store::UsageStats;
+use crate::types::error::{OutpostError, Result};
+use std::sync::Arc;
+use std::time::Duration;
+use teloxide::prelude::*;
+use tracing::debug;
+
+/// Window used when `/stats` is called without an argument.
+const DEFAULT_STATS_DAYS: u64 = 7;
+
+/// Parse the optional day count argument.
+fn parse_stats_days(args: &str) -> std::result::Result<u64, String> {
+    let args = args.trim();
+    if args.is_empty() {
+        return Ok(DEFAULT_STATS_DAYS);
+    }
+    match args.parse::<u64>() {
+        Ok(days) if days > 0 => Ok(days),
+        _ => Err("Usage: /stats [days]".to_string()),
+    }
+}
+
+/// Format a duration as e.g. "2h 15m", or "45s" below a minute.
+fn format_lifetime(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    let hours = secs / 3600;
+    let minutes = (secs % 3600) / 60;
+    if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else if minutes > 0 {
+        format!("{}m", minutes)
+    } else {
+        format!("{}s", secs)
+    }
+}
+
+fn format_stats_output(stats: &UsageStats, days: u64) -> String {
+    let mut output = format!(
+        "Usage (last {} day{})\n\n",
+        days,
+        if days == 1 { "" } else { "s" }
+    );
+    output.push_str(&format!("Bot runs: {}\n", stats.runs));
+    output.push_str(&format!("Instances spawned: {}\n", stats.spawned));
+    output.push_str(&format!("  Restarts: {}\n", stats.restarted));
+    output.push_str(&format!("  Crashes: {}\n", stats.crashed));
+    output.push_str(&format!("  Stops: {}\n", stats.stopped));
+    output.push_str(&format!(
+        "Avg instance lifetime: {}\n",
+        stats
+            .avg_instance_lifetime
+            .map(format_lifetime)
+            .unwrap_or_else(|| "n/a".to_string())
+    ));
+    output.push_str(&format!(
+        "Feedback: 👍 {} / 👎 {}\n",
+        stats.feedback_up, stats.feedback_down
+    ));
+    output
+}
+
+/// Handle /stats command
+pub async fn handle_stats(
+    bot: Bot,
+    msg: Message,
+    cmd: Command,
+    state: Arc<BotState>,
+) -> Result<()> {
+    debug!(
+        chat_id = msg.chat.id.0,
+        topic_id = ?msg.thread_id.map(|t| t.0 .0),
+        sender_id = ?msg.from.as_ref().map(|u| u.id.0),
+        "Handling /stats"
+    );
+    let chat_id = msg.chat.id;
+
+    let args = match cmd {
+        Command::Stats(args) => args,
+        _ => return Err(OutpostError::telegram_error("Invalid command")),
+    };
+    let days = match parse_stats_days(&args) {
+        Ok(days) => days,
+        Err(usage) => {
+            bot.send_message(chat_id, usage)
+                .await
+                .map_err(|e| OutpostError::telegram_error(e.to_string()))?;
+            return Ok(());
+        }
+    };
+
+    let Some(log_store) = state.log_store.as_ref() else {
+        bot.send_message(chat_id, "Usage stats are not available.")
+            .await
+            .map_err(|e| OutpostError::telegram_error(e.to_string()))?;
+        return Ok(());
+    };
+
+    let now_ms = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_err(|e| OutpostError::io_error(e.to_string()))?
+        .as_millis() as i64;
+    let since = now_ms - Duration::from_secs(days * 86400).as_millis() as i64;
+    let stats = log_store
+        .usage_stats(since)
+        .await
+        .map_err(|e| OutpostError::database_error(e.to_string()))?;
+
+    bot.send_message(chat_id, format_stats_output(&stats, days))
+        .await
+        .map_err(|e| OutpostError::telegram_error(e.to_string()))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_stats_days() {
+        assert_eq!(parse_stats_days(""), Ok(7));
+        assert_eq!(parse_stats_days(" 30 "), Ok(30));
+        assert!(parse_stats_days("0").is_err());
+        assert!(parse_stats_days("week").is_err());
+    }
+
+    #[test]
+    fn test_format_lifetime() {
+        assert_eq!(format_lifetime(Duration::from_secs(8100)), "2h 15m");
+        assert_eq!(format_lifetime(Duration::from_secs(900)), "15m");
+        assert_eq!(format_lifetime(Duration::from_secs(42)), "42s");
+    }
+
+    #[test]
+    fn test_format_stats_output() {
+        let stats = UsageStats {
+            runs: 2,
+            spawned: 5,
+            stopped: 3,
+            crashed: 1,
+            restarted: 1,
+            avg_instance_lifetime: Some(Duration::from_secs(3900)),
+            feedback_up: 4,
+            feedback_down: 1,
+        };
+        let output = format_stats_output(&stats, 7);
+
+        assert!(output.contains("Usage (last 7 days)"));
+        assert!(output.contains("Bot runs: 2"));
+        assert!(output.contains("Instances spawned: 5"));
+        assert!(output.contains("  Restarts: 1"));
+        assert!(output.contains("  Crashes: 1"));
+        assert!(output.contains("  Stops: 3"));
+        assert!(output.contains("Avg instance lifetime: 1h 5m"));
+        assert!(output.contains("Feedback: 👍 4 / 👎 1"));
+    }
+
+    #[test]
+    fn test_format_stats_output_without_lifetimes() {
+        let output = format_stats_output(&UsageStats::default(), 1);
+
+        assert!(output.contains("Usage (last 1 day)"));
+        assert!(output.contains("Avg instance lifetime: n/a"));
+    }
+}
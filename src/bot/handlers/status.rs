@@ -1,6 +1,8 @@
 //! /status command handler
 
 use crate::bot::{BotState, Command};
+use crate::integration::Integration;
+use crate::orchestrator::manager::ManagerStatus;
 use crate::types::error::{OutpostError, Result};
 use std::sync::Arc;
 use teloxide::prelude::*;
@@ -20,18 +22,35 @@ fn format_uptime(seconds: u64) -> String {
 
 /// Format status output for display
 fn format_status_output(
-    total_count: usize,
-    port_used: usize,
+    status: &ManagerStatus,
     port_total: usize,
+    active_streams: usize,
     uptime_seconds: u64,
 ) -> String {
+    let port_used = port_total.saturating_sub(status.available_ports);
     let mut output = String::from("Orchestrator Status\n\n");
 
-    output.push_str(&format!("Active Instances: {}\n", total_count));
-    output.push('\n');
-    output.push_str(&format!("Port Pool: {}/{} used\n", port_used, port_total));
     output.push_str(&format!("Uptime: {}\n", format_uptime(uptime_seconds)));
-    output.push_str("Health: Healthy\n");
+    output.push('\n');
+    output.push_str(&format!("Instances: {}\n", status.total_instances));
+    output.push_str(&format!("  Running: {}\n", status.running_instances));
+    output.push_str(&format!("  Stopped: {}\n", status.stopped_instances));
+    output.push_str(&format!("  Error: {}\n", status.error_instances));
+    output.push('\n');
+    output.push_str(&format!(
+        "Port Pool: {}/{} used ({} available)\n",
+        port_used, port_total, status.available_ports
+    ));
+    output.push_str(&format!("Active Streams: {}\n", active_streams));
+
+    if status.error_instances > 0 {
+        output.push_str(&format!(
+            "Health: Degraded ({} in error state)\n",
+            status.error_instances
+        ));
+    } else {
+        output.push_str("Health: Healthy\n");
+    }
 
     output
 }
@@ -42,6 +61,7 @@ pub async fn handle_status(
     msg: Message,
     _cmd: Command,
     state: Arc<BotState>,
+    integration: Arc<Integration>,
 ) -> Result<()> {
     debug!(
         chat_id = msg.chat.id.0,
@@ -51,32 +71,22 @@ pub async fn handle_status(
     );
     let chat_id = msg.chat.id;
 
-    // Get orchestrator store
-    let all_instances = state
-        .orchestrator_store
-        .get_all_instances()
-        .await
-        .map_err(|e| OutpostError::database_error(e.to_string()))?;
-
-    // Count instances
-    let total_count = all_instances.len();
-
-    debug!(total_count = total_count, "Instance count");
-
-    // Get port pool usage from InstanceManager
+    // Instance breakdown and port pool usage from InstanceManager
     let manager_status = state.instance_manager.get_status().await;
     debug!(
+        total_instances = manager_status.total_instances,
+        running_instances = manager_status.running_instances,
         available_ports = manager_status.available_ports,
         "Manager status fetched"
     );
-    let port_total = state.config.opencode_port_pool_size as usize;
-    let port_used = port_total - manager_status.available_ports;
+    let port_total = state.config().opencode_port_pool_size as usize;
+    let active_streams = integration.active_stream_count().await;
 
     // Calculate uptime (from bot start time)
     let uptime_seconds = state.bot_start_time.elapsed().as_secs();
 
     // Format and send message
-    let output = format_status_output(total_count, port_used, port_total, uptime_seconds);
+    let output = format_status_output(&manager_status, port_total, active_streams, uptime_seconds);
 
     bot.send_message(chat_id, output)
         .await
@@ -117,41 +127,93 @@ mod tests {
         assert_eq!(formatted, "1h 0m");
     }
 
+    fn seeded_status(
+        running: usize,
+        stopped: usize,
+        error: usize,
+        available: usize,
+    ) -> ManagerStatus {
+        ManagerStatus {
+            total_instances: running + stopped + error,
+            running_instances: running,
+            stopped_instances: stopped,
+            error_instances: error,
+            available_ports: available,
+        }
+    }
+
     #[test]
     fn test_format_status_output_basic() {
-        let output = format_status_output(3, 4, 100, 8100);
+        let output = format_status_output(&seeded_status(2, 1, 0, 96), 100, 2, 8100);
 
         assert!(output.contains("Orchestrator Status"));
-        assert!(output.contains("Active Instances: 3"));
-        assert!(output.contains("Port Pool: 4/100 used"));
         assert!(output.contains("Uptime: 2h 15m"));
+        assert!(output.contains("Instances: 3"));
+        assert!(output.contains("  Running: 2"));
+        assert!(output.contains("  Stopped: 1"));
+        assert!(output.contains("  Error: 0"));
+        assert!(output.contains("Port Pool: 4/100 used (96 available)"));
+        assert!(output.contains("Active Streams: 2"));
         assert!(output.contains("Health: Healthy"));
     }
 
     #[test]
     fn test_format_status_output_no_instances() {
-        let output = format_status_output(0, 0, 100, 300);
+        let output = format_status_output(&seeded_status(0, 0, 0, 100), 100, 0, 300);
 
-        assert!(output.contains("Active Instances: 0"));
-        assert!(output.contains("Port Pool: 0/100 used"));
+        assert!(output.contains("Instances: 0"));
+        assert!(output.contains("Port Pool: 0/100 used (100 available)"));
+        assert!(output.contains("Active Streams: 0"));
         assert!(output.contains("Uptime: 5m"));
     }
 
     #[test]
     fn test_format_status_output_all_active() {
-        let output = format_status_output(10, 10, 100, 3600);
+        let output = format_status_output(&seeded_status(10, 0, 0, 90), 100, 10, 3600);
 
-        assert!(output.contains("Active Instances: 10"));
-        assert!(output.contains("Port Pool: 10/100 used"));
+        assert!(output.contains("Instances: 10"));
+        assert!(output.contains("  Running: 10"));
+        assert!(output.contains("Port Pool: 10/100 used (90 available)"));
         assert!(output.contains("Uptime: 1h 0m"));
     }
 
     #[test]
-    fn test_format_status_output_mixed() {
-        let output = format_status_output(5, 8, 100, 7200);
+    fn test_format_status_output_reports_errors_as_degraded() {
+        let output = format_status_output(&seeded_status(3, 3, 2, 92), 100, 1, 7200);
+
+        assert!(output.contains("Instances: 8"));
+        assert!(output.contains("  Error: 2"));
+        assert!(output.contains("Port Pool: 8/100 used (92 available)"));
+        assert!(output.contains("Health: Degraded (2 in error state)"));
+        assert!(!output.contains("Health: Healthy"));
+    }
 
-        assert!(output.contains("Active Instances: 5"));
-        assert!(output.contains("Port Pool: 8/100 used"));
-        assert!(output.contains("Uptime: 2h 0m"));
+    #[tokio::test]
+    async fn test_status_reflects_manager_state() {
+        use crate::orchestrator::container::{mock::MockRuntime, ContainerRuntime};
+        use crate::orchestrator::manager::InstanceManager;
+        use crate::orchestrator::port_pool::PortPool;
+        use crate::orchestrator::store::OrchestratorStore;
+        use crate::test_utils::test_config;
+        use tempfile::TempDir;
+
+        let temp_dir = TempDir::new().unwrap();
+        let config = test_config(temp_dir.path());
+        let store = OrchestratorStore::new(&config.orchestrator_db_path)
+            .await
+            .unwrap();
+        let port_pool = PortPool::new(4100, 10);
+        port_pool.allocate().await.unwrap();
+        port_pool.allocate().await.unwrap();
+        let runtime: Arc<dyn ContainerRuntime> = Arc::new(MockRuntime::new());
+        let manager = InstanceManager::new(Arc::new(config.clone()), store, port_pool, runtime)
+            .await
+            .unwrap();
+
+        let status = manager.get_status().await;
+        let output = format_status_output(&status, config.opencode_port_pool_size as usize, 0, 60);
+        assert!(output.contains("Instances: 0"));
+        assert!(output.contains("Port Pool: 2/10 used (8 available)"));
+        assert!(output.contains("Uptime: 1m"));
     }
 }
@@ -0,0 +1,79 @@
+//! /switch command handler
+//!
+//! Re-binds the current topic to a different project. The project keyboard
+//! is shown here; the rebind itself happens in the `switch:` callback.
+
+use crate::bot::handlers::callbacks::SWITCH_CALLBACK_PREFIX;
+use crate::bot::{BotState, Command};
+use crate::integration::Integration;
+use crate::project_config::{command_enabled, COMMAND_DISABLED_MESSAGE};
+use crate::types::error::{OutpostError, Result};
+use std::path::Path;
+use std::sync::Arc;
+use teloxide::prelude::*;
+use teloxide::types::{MessageId, ThreadId};
+use tracing::debug;
+
+/// Reply when the agent is still working on a prompt in this topic.
+const SWITCH_BUSY_MESSAGE: &str =
+    "The agent is still generating a response. Wait for it to finish before using /switch.";
+
+fn get_topic_id(msg: &Message) -> Result<i32> {
+    let thread_id = msg.thread_id.ok_or_else(|| {
+        OutpostError::telegram_error("This command must be used in a forum topic")
+    })?;
+
+    Ok(thread_id.0 .0)
+}
+
+/// Handle /switch command
+pub async fn handle_switch(
+    bot: Bot,
+    msg: Message,
+    _cmd: Command,
+    state: Arc<BotState>,
+    integration: Arc<Integration>,
+) -> Result<()> {
+    debug!(
+        chat_id = msg.chat.id.0,
+        topic_id = ?msg.thread_id.map(|t| t.0 .0),
+        sender_id = ?msg.from.as_ref().map(|u| u.id.0),
+        "Handling /switch"
+    );
+
+    let topic_id = get_topic_id(&msg)?;
+
+    let mapping = state
+        .topic_store
+        .get_mapping(msg.chat.id.0, topic_id)
+        .await
+        .map_err(|e| OutpostError::database_error(e.to_string()))?
+        .ok_or_else(|| OutpostError::telegram_error("No active connection in this topic"))?;
+
+    if !command_enabled(Path::new(&mapping.project_path), "switch") {
+        bot.send_message(msg.chat.id, COMMAND_DISABLED_MESSAGE)
+            .message_thread_id(ThreadId(MessageId(topic_id)))
+            .await
+            .map_err(|e| OutpostError::telegram_error(e.to_string()))?;
+        return Ok(());
+    }
+
+    if integration.is_generating(topic_id).await {
+        debug!(topic_id = topic_id, "Refusing /switch while generating");
+        bot.send_message(msg.chat.id, SWITCH_BUSY_MESSAGE)
+            .message_thread_id(ThreadId(MessageId(topic_id)))
+            .await
+            .map_err(|e| OutpostError::telegram_error(e.to_string()))?;
+        return Ok(());
+    }
+
+    integration
+        .send_project_selection_keyboard(
+            &bot,
+            msg.chat.id,
+            topic_id,
+            SWITCH_CALLBACK_PREFIX,
+            "Select the project to switch this topic to:",
+        )
+        .await
+}
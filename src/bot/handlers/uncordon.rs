@@ -0,0 +1,61 @@
+//! /uncordon command handler
+//!
+//! Takes a project out of maintenance so new messages start its instance
+//! again. Limited to `TELEGRAM_ADMIN_USERS`.
+
+use crate::bot::handlers::access::{is_admin_sender, ADMIN_ONLY_MESSAGE};
+use crate::bot::{BotState, Command};
+use crate::types::error::{OutpostError, Result};
+use std::sync::Arc;
+use teloxide::prelude::*;
+use tracing::debug;
+
+/// Handle /uncordon command
+pub async fn handle_uncordon(
+    bot: Bot,
+    msg: Message,
+    cmd: Command,
+    state: Arc<BotState>,
+) -> Result<()> {
+    debug!(
+        chat_id = msg.chat.id.0,
+        topic_id = ?msg.thread_id.map(|t| t.0 .0),
+        sender_id = ?msg.from.as_ref().map(|u| u.id.0),
+        "Handling /uncordon"
+    );
+    if !is_admin_sender(&state.config(), msg.from.as_ref()) {
+        bot.send_message(msg.chat.id, ADMIN_ONLY_MESSAGE)
+            .await
+            .map_err(|e| OutpostError::telegram_error(e.to_string()))?;
+        return Ok(());
+    }
+
+    let project = match cmd {
+        Command::Uncordon(project) => project.trim().to_string(),
+        _ => return Err(OutpostError::config_error("Invalid command type")),
+    };
+    if project.is_empty() {
+        return Err(OutpostError::telegram_error("Usage: /uncordon <project>"));
+    }
+
+    let removed = state
+        .instance_manager
+        .uncordon_project(&project)
+        .await
+        .map_err(|e| OutpostError::database_error(e.to_string()))?;
+
+    let output = if removed {
+        format!(
+            "Project '{}' uncordoned. New messages will start its instance again.",
+            project
+        )
+    } else {
+        format!("Project '{}' is not cordoned.", project)
+    };
+
+    bot.send_message(msg.chat.id, output)
+        .await
+        .map_err(|e| OutpostError::telegram_error(e.to_string()))?;
+
+    Ok(())
+}
@@ -0,0 +1,155 @@
+//! /whoami command handler
+//!
+//! Reports the caller's ids and whether they pass `TELEGRAM_ALLOWED_USERS` and
+//! `TELEGRAM_CHAT_IDS`. Answered in every chat and for every user, so people
+//! can discover the ids to put in those lists.
+
+use crate::bot::handlers::access::is_allowed_sender;
+use crate::bot::{BotState, Command};
+use crate::types::error::{OutpostError, Result};
+use std::sync::Arc;
+use teloxide::prelude::*;
+use tracing::debug;
+
+fn yes_no(value: bool) -> &'static str {
+    if value {
+        "yes"
+    } else {
+        "no"
+    }
+}
+
+/// Render the /whoami reply for `msg`.
+fn format_whoami(msg: &Message, allowed_user: bool, whitelisted_chat: bool) -> String {
+    let user_id = msg
+        .from
+        .as_ref()
+        .map(|u| u.id.0.to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+    let username = msg
+        .from
+        .as_ref()
+        .and_then(|u| u.username.as_ref())
+        .map(|name| format!("@{}", name))
+        .unwrap_or_else(|| "none".to_string());
+    let topic_id = msg
+        .thread_id
+        .map(|t| t.0 .0.to_string())
+        .unwrap_or_else(|| "none".to_string());
+
+    let mut output = format!(
+        "User ID: {}\n\
+         Username: {}\n\
+         Chat ID: {}\n\
+         Topic ID: {}\n\
+         Allowed user: {}\n\
+         Whitelisted chat: {}",
+        user_id,
+        username,
+        msg.chat.id.0,
+        topic_id,
+        yes_no(allowed_user),
+        yes_no(whitelisted_chat),
+    );
+    if !whitelisted_chat {
+        output.push_str(
+            "\n\n⚠️ This chat is not in TELEGRAM_CHAT_IDS. \
+             The bot ignores everything here except /whoami.",
+        );
+    } else if !allowed_user {
+        output.push_str(
+            "\n\n⚠️ You are not in TELEGRAM_ALLOWED_USERS. \
+             Ask an admin to add your user ID.",
+        );
+    }
+    output
+}
+
+/// Handle /whoami command
+pub async fn handle_whoami(
+    bot: Bot,
+    msg: Message,
+    _cmd: Command,
+    state: Arc<BotState>,
+) -> Result<()> {
+    debug!(
+        chat_id = msg.chat.id.0,
+        topic_id = ?msg.thread_id.map(|t| t.0 .0),
+        sender_id = ?msg.from.as_ref().map(|u| u.id.0),
+        "Handling /whoami"
+    );
+    let config = state.config();
+    let allowed_user = is_allowed_sender(&config, msg.from.as_ref());
+    let whitelisted_chat = config.is_whitelisted_chat(msg.chat.id.0);
+
+    let mut request = bot.send_message(
+        msg.chat.id,
+        format_whoami(&msg, allowed_user, whitelisted_chat),
+    );
+    if let Some(thread_id) = msg.thread_id {
+        request = request.message_thread_id(thread_id);
+    }
+    request
+        .await
+        .map_err(|e| OutpostError::telegram_error(e.to_string()))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_message(thread_id: Option<i32>) -> Message {
+        let mut msg = serde_json::json!({
+            "message_id": 100,
+            "date": 1640000000,
+            "chat": {
+                "id": -1001234567890_i64,
+                "type": "supergroup",
+                "title": "Test Group"
+            },
+            "from": {
+                "id": 424242,
+                "is_bot": false,
+                "first_name": "Ada",
+                "username": "ada"
+            },
+            "text": "/whoami"
+        });
+        if let Some(tid) = thread_id {
+            msg["message_thread_id"] = serde_json::json!(tid);
+        }
+        serde_json::from_value(msg).unwrap()
+    }
+
+    #[test]
+    fn test_format_whoami_fields() {
+        let output = format_whoami(&make_message(Some(42)), true, true);
+
+        assert!(output.contains("User ID: 424242"));
+        assert!(output.contains("Username: @ada"));
+        assert!(output.contains("Chat ID: -1001234567890"));
+        assert!(output.contains("Topic ID: 42"));
+        assert!(output.contains("Allowed user: yes"));
+        assert!(output.contains("Whitelisted chat: yes"));
+        assert!(!output.contains("⚠️"));
+    }
+
+    #[test]
+    fn test_format_whoami_outside_whitelisted_chat() {
+        let output = format_whoami(&make_message(None), false, false);
+
+        assert!(output.contains("Topic ID: none"));
+        assert!(output.contains("Allowed user: no"));
+        assert!(output.contains("Whitelisted chat: no"));
+        assert!(output.contains("not in TELEGRAM_CHAT_IDS"));
+    }
+
+    #[test]
+    fn test_format_whoami_user_not_allowed() {
+        let output = format_whoami(&make_message(Some(42)), false, true);
+
+        assert!(output.contains("not in TELEGRAM_ALLOWED_USERS"));
+    }
+}
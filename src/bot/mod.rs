@@ -2,9 +2,13 @@ mod commands;
 pub mod handlers;
 mod state;
 
-pub use commands::Command;
+pub use commands::{parse_message_command, Command};
 pub use handlers::{
-    dispatch_callback, handle_close, handle_help, handle_new, handle_permission_request,
-    handle_projects, handle_session, handle_sessions, handle_status,
+    dispatch_callback, handle_archive, handle_close, handle_compare, handle_cordon, handle_debug,
+    handle_export, handle_export_mappings, handle_help, handle_import_mappings, handle_lang,
+    handle_new, handle_observe, handle_output, handle_permission_request, handle_ping,
+    handle_projects, handle_reload, handle_restart, handle_retry_clean, handle_session,
+    handle_sessions, handle_stats, handle_status, handle_switch, handle_uncordon, handle_whoami,
+    is_allowed_sender, reject_unauthorized_message, send_feedback_prompt,
 };
 pub use state::BotState;
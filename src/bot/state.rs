@@ -1,4 +1,6 @@
-use crate::config::Config;
+use crate::bot::handlers::permissions::PendingPermissions;
+use crate::config::{Config, SharedConfig};
+use crate::db::log_store::LogStore;
 use crate::forum::TopicStore;
 use crate::orchestrator::manager::InstanceManager;
 use crate::orchestrator::store::OrchestratorStore;
@@ -8,27 +10,44 @@ use std::time::Instant;
 pub struct BotState {
     pub orchestrator_store: Arc<OrchestratorStore>,
     pub topic_store: Arc<TopicStore>,
-    pub config: Arc<Config>,
+    pub config: SharedConfig,
     pub instance_manager: Arc<InstanceManager>,
     pub bot_start_time: Instant,
+    pub pending_permissions: Arc<PendingPermissions>,
+    /// Run and instance event history, for `/stats`.
+    pub log_store: Option<LogStore>,
 }
 
 impl BotState {
     pub fn new(
         orchestrator_store: OrchestratorStore,
         topic_store: TopicStore,
-        config: Config,
+        config: impl Into<SharedConfig>,
         instance_manager: InstanceManager,
         bot_start_time: Instant,
     ) -> Self {
         Self {
             orchestrator_store: Arc::new(orchestrator_store),
             topic_store: Arc::new(topic_store),
-            config: Arc::new(config),
+            config: config.into(),
             instance_manager: Arc::new(instance_manager),
             bot_start_time,
+            pending_permissions: Arc::new(PendingPermissions::new()),
+            log_store: None,
         }
     }
+
+    /// Attach the log store used by `/stats`.
+    pub fn with_log_store(mut self, log_store: LogStore) -> Self {
+        self.log_store = Some(log_store);
+        self
+    }
+
+    /// Snapshot of the current configuration. Hold on to the returned value
+    /// only for the duration of one operation so `/reload` changes are seen.
+    pub fn config(&self) -> Arc<Config> {
+        self.config.get()
+    }
 }
 
 #[cfg(test)]
@@ -47,6 +66,7 @@ mod tests {
             telegram_bot_token: "test_token".to_string(),
             telegram_chat_ids: vec![-1001234567890],
             telegram_allowed_users: vec![],
+            telegram_admin_users: vec![],
             handle_general_topic: true,
             opencode_path: PathBuf::from("opencode"),
             opencode_max_instances: 10,
@@ -65,6 +85,56 @@ mod tests {
             opencode_config_path: PathBuf::from("/tmp/oc-config"),
             container_port: 8080,
             env_passthrough: vec![],
+            topic_name_strategy: crate::config::TopicNameStrategy::Leaf,
+            show_usage: false,
+            duplicate_response_window: Duration::from_secs(10),
+            container_dns: vec![],
+            container_dns_search: vec![],
+            fallback_model: None,
+            max_topics_per_chat: 50,
+            transcription_url: None,
+            transcription_api_key: None,
+            transcription_model: "whisper-1".to_string(),
+            audio_mode: crate::config::AudioMode::Transcribe,
+            permission_timeout: Duration::from_secs(600),
+            opencode_request_timeout: Duration::from_secs(30),
+            resurrection_timeout: Duration::from_secs(30),
+            resurrection_wake_delay: Duration::from_secs(3),
+            show_file_edits: false,
+            allowed_upload_mime: vec![],
+            dedup_expiry: Duration::from_secs(30),
+            max_reconnect_attempts: 5,
+            base_reconnect_delay: Duration::from_secs(1),
+            max_reconnect_delay: Duration::from_secs(16),
+            max_resume_age: Duration::ZERO,
+            max_concurrent_downloads: 4,
+            max_message_parts: 10,
+            api_max_body_bytes: 1_048_576,
+            api_port: 0,
+            api_host: [127, 0, 0, 1].into(),
+            api_key: None,
+            image_cache_retention: Duration::from_secs(86400),
+            slow_start_nudge: Duration::from_secs(15),
+            rewrite_workspace_paths: false,
+            opencode_socket_path: None,
+            health_path: "/global/health".to_string(),
+            health_check: Default::default(),
+            collect_feedback: false,
+            container_extra_args: vec![],
+            response_filters: vec![],
+            reply_to_prompts: false,
+            preferred_photo_size: crate::config::PhotoSizePreference::Largest,
+            max_image_dimension: 1280,
+            max_image_bytes: 1_048_576,
+            default_image_prompt: None,
+            validate_project: false,
+            max_response_chars: 0,
+            collapse_repeated_tools: false,
+            stream_mode: crate::config::StreamMode::Batched,
+            durable_outbound: false,
+            keep_stopped_containers: false,
+            container_memory_mb: 0,
+            workspace_mount: "/workspace".to_string(),
         };
         (config, temp_dir)
     }
@@ -99,8 +169,8 @@ mod tests {
             bot_start_time,
         );
 
-        assert_eq!(state.config.telegram_chat_ids, vec![-1001234567890]);
-        assert_eq!(state.config.opencode_max_instances, 10);
+        assert_eq!(state.config().telegram_chat_ids, vec![-1001234567890]);
+        assert_eq!(state.config().opencode_max_instances, 10);
     }
 
     #[tokio::test]
@@ -168,10 +238,10 @@ mod tests {
             bot_start_time,
         );
 
-        let config_clone = Arc::clone(&state.config);
+        let config_clone = Arc::clone(&state.config());
         assert_eq!(
             config_clone.telegram_chat_ids,
-            state.config.telegram_chat_ids
+            state.config().telegram_chat_ids
         );
     }
 }
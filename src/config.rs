@@ -1,41 +1,203 @@
+use crate::orchestrator::container::DEFAULT_WORKSPACE_MOUNT;
+use crate::types::instance::{HealthCheck, HealthMethod, DEFAULT_HEALTH_PATH};
+use crate::types::opencode::ModelRef;
 use anyhow::{anyhow, Result};
+use std::net::IpAddr;
 use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
 use std::time::Duration;
 use tracing::debug;
 
+/// How a forum topic name is derived from its project path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TopicNameStrategy {
+    /// Last path component (`backend`)
+    Leaf,
+    /// Last two path components (`repos/backend`)
+    LastTwo,
+    /// Path relative to `project_base_path` (`team/repos/backend`)
+    FullRelative,
+}
+
+impl std::str::FromStr for TopicNameStrategy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "leaf" => Ok(Self::Leaf),
+            "last_two" => Ok(Self::LastTwo),
+            "full_relative" => Ok(Self::FullRelative),
+            _ => Err(anyhow!(
+                "TOPIC_NAME_STRATEGY must be one of 'leaf', 'last_two', 'full_relative'"
+            )),
+        }
+    }
+}
+
+/// Which size of a Telegram photo is downloaded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PhotoSizePreference {
+    /// The original, largest size
+    Largest,
+    /// The largest size within `MAX_IMAGE_DIMENSION` and `MAX_IMAGE_BYTES`
+    Bounded,
+}
+
+impl std::str::FromStr for PhotoSizePreference {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "largest" => Ok(Self::Largest),
+            "bounded" => Ok(Self::Bounded),
+            _ => Err(anyhow!(
+                "PREFERRED_PHOTO_SIZE must be one of 'largest', 'bounded'"
+            )),
+        }
+    }
+}
+
+/// How voice and audio messages are handled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AudioMode {
+    /// Transcribe voice messages and send the transcript as text
+    Transcribe,
+    /// Forward the audio file to models that accept audio input
+    Passthrough,
+    /// Drop voice and audio messages
+    Ignore,
+}
+
+impl std::str::FromStr for AudioMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "transcribe" => Ok(Self::Transcribe),
+            "passthrough" => Ok(Self::Passthrough),
+            "ignore" => Ok(Self::Ignore),
+            _ => Err(anyhow!(
+                "AUDIO_MODE must be one of 'transcribe', 'passthrough', 'ignore'"
+            )),
+        }
+    }
+}
+
+/// How agent text is forwarded to Telegram.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamMode {
+    /// Send text as it arrives, batched to respect Telegram's rate limits
+    Batched,
+    /// Hold all text until the message completes or the session goes idle,
+    /// then send it at once
+    Final,
+}
+
+impl std::str::FromStr for StreamMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "batched" => Ok(Self::Batched),
+            "final" => Ok(Self::Final),
+            _ => Err(anyhow!("STREAM_MODE must be one of 'batched', 'final'")),
+        }
+    }
+}
+
+/// Upload MIME types accepted when `ALLOWED_UPLOAD_MIME` is unset
+const DEFAULT_ALLOWED_UPLOAD_MIME: &str = "text/*,image/*,application/json,application/xml,application/x-yaml,application/yaml,application/toml,application/javascript,application/pdf";
+
 /// Configuration for oc-outpost loaded from environment variables
 #[derive(Debug, Clone)]
 pub struct Config {
-    // Telegram (4 fields)
+    // Telegram (14 fields)
     pub telegram_bot_token: String,
     pub telegram_chat_ids: Vec<i64>,
     pub telegram_allowed_users: Vec<i64>,
+    pub telegram_admin_users: Vec<i64>,
     pub handle_general_topic: bool,
-
-    // OpenCode (8 fields)
+    pub topic_name_strategy: TopicNameStrategy,
+    pub max_topics_per_chat: usize,
+    pub allowed_upload_mime: Vec<String>,
+    pub max_concurrent_downloads: usize,
+    pub max_message_parts: usize,
+    pub preferred_photo_size: PhotoSizePreference,
+    pub max_image_dimension: u32,
+    pub max_image_bytes: u32,
+    pub default_image_prompt: Option<String>,
+
+    // Output (12 fields)
+    pub show_usage: bool,
+    pub duplicate_response_window: Duration,
+    pub show_file_edits: bool,
+    pub slow_start_nudge: Duration,
+    pub rewrite_workspace_paths: bool,
+    pub collect_feedback: bool,
+    pub response_filters: Vec<String>,
+    pub reply_to_prompts: bool,
+    pub max_response_chars: usize,
+    pub collapse_repeated_tools: bool,
+    pub stream_mode: StreamMode,
+    pub durable_outbound: bool,
+
+    // Transcription (4 fields)
+    pub transcription_url: Option<String>,
+    pub transcription_api_key: Option<String>,
+    pub transcription_model: String,
+    pub audio_mode: AudioMode,
+
+    // OpenCode (20 fields)
     pub opencode_path: PathBuf,
     pub opencode_max_instances: usize,
     pub opencode_idle_timeout: Duration,
     pub opencode_port_start: u16,
     pub opencode_port_pool_size: u16,
     pub opencode_health_check_interval: Duration,
+    pub health_path: String,
+    pub health_check: HealthCheck,
     pub opencode_startup_timeout: Duration,
     pub opencode_data_path: PathBuf,
-
-    // Storage (3 fields)
+    pub opencode_socket_path: Option<PathBuf>,
+    pub fallback_model: Option<String>,
+    pub permission_timeout: Duration,
+    pub opencode_request_timeout: Duration,
+    pub resurrection_timeout: Duration,
+    pub resurrection_wake_delay: Duration,
+    pub dedup_expiry: Duration,
+    pub max_reconnect_attempts: u32,
+    pub base_reconnect_delay: Duration,
+    pub max_reconnect_delay: Duration,
+    pub max_resume_age: Duration,
+
+    // Storage (4 fields)
     pub orchestrator_db_path: PathBuf,
     pub topic_db_path: PathBuf,
     pub log_db_path: PathBuf,
+    pub image_cache_retention: Duration,
 
-    // Project (2 fields)
+    // Project (3 fields)
     pub project_base_path: PathBuf,
     pub auto_create_project_dirs: bool,
+    pub validate_project: bool,
 
-    // Docker (4 fields)
+    // Docker (11 fields)
     pub docker_image: String,
     pub opencode_config_path: PathBuf,
     pub container_port: u16,
     pub env_passthrough: Vec<String>,
+    pub container_dns: Vec<String>,
+    pub container_dns_search: Vec<String>,
+    pub container_extra_args: Vec<String>,
+    pub keep_stopped_containers: bool,
+    pub container_memory_mb: u64,
+    pub workspace_mount: String,
+
+    // API (4 fields)
+    pub api_max_body_bytes: usize,
+    pub api_port: u16,
+    pub api_host: IpAddr,
+    pub api_key: Option<String>,
 }
 
 impl Config {
@@ -44,6 +206,13 @@ impl Config {
         Self::from_env_inner(true)
     }
 
+    /// Re-read configuration for `/reload`. Values in `.env` override the
+    /// process environment so that edits to the file take effect.
+    pub fn reload_from_env() -> Result<Self> {
+        dotenvy::dotenv_override().ok();
+        Self::from_env_inner(false)
+    }
+
     #[cfg(test)]
     pub fn from_env_no_dotenv() -> Result<Self> {
         Self::from_env_inner(false)
@@ -79,11 +248,154 @@ impl Config {
             })
             .collect::<Result<Vec<_>>>()?;
 
+        let telegram_admin_users = std::env::var("TELEGRAM_ADMIN_USERS")
+            .unwrap_or_default()
+            .split(',')
+            .filter(|s| !s.trim().is_empty())
+            .map(|s| {
+                s.trim()
+                    .parse::<i64>()
+                    .map_err(|_| anyhow!("TELEGRAM_ADMIN_USERS contains invalid integer"))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
         let handle_general_topic = std::env::var("HANDLE_GENERAL_TOPIC")
             .unwrap_or_else(|_| "true".to_string())
             .parse::<bool>()
             .map_err(|_| anyhow!("HANDLE_GENERAL_TOPIC must be 'true' or 'false'"))?;
 
+        let topic_name_strategy = std::env::var("TOPIC_NAME_STRATEGY")
+            .unwrap_or_else(|_| "leaf".to_string())
+            .parse::<TopicNameStrategy>()?;
+
+        let max_topics_per_chat = std::env::var("MAX_TOPICS_PER_CHAT")
+            .unwrap_or_else(|_| "50".to_string())
+            .parse::<usize>()
+            .map_err(|_| anyhow!("MAX_TOPICS_PER_CHAT must be a valid integer"))?;
+
+        let allowed_upload_mime = std::env::var("ALLOWED_UPLOAD_MIME")
+            .unwrap_or_else(|_| DEFAULT_ALLOWED_UPLOAD_MIME.to_string())
+            .split(',')
+            .filter(|s| !s.trim().is_empty())
+            .map(|s| s.trim().to_lowercase())
+            .collect::<Vec<_>>();
+
+        let max_concurrent_downloads = std::env::var("MAX_CONCURRENT_DOWNLOADS")
+            .unwrap_or_else(|_| "4".to_string())
+            .parse::<usize>()
+            .ok()
+            .filter(|n| *n > 0)
+            .ok_or_else(|| anyhow!("MAX_CONCURRENT_DOWNLOADS must be a positive integer"))?;
+
+        let max_message_parts = std::env::var("MAX_MESSAGE_PARTS")
+            .unwrap_or_else(|_| "10".to_string())
+            .parse::<usize>()
+            .ok()
+            .filter(|n| *n > 0)
+            .ok_or_else(|| anyhow!("MAX_MESSAGE_PARTS must be a positive integer"))?;
+
+        let preferred_photo_size = std::env::var("PREFERRED_PHOTO_SIZE")
+            .unwrap_or_else(|_| "largest".to_string())
+            .parse::<PhotoSizePreference>()?;
+
+        let max_image_dimension = std::env::var("MAX_IMAGE_DIMENSION")
+            .unwrap_or_else(|_| "1280".to_string())
+            .parse::<u32>()
+            .map_err(|_| anyhow!("MAX_IMAGE_DIMENSION must be a valid integer"))?;
+
+        let max_image_bytes = std::env::var("MAX_IMAGE_BYTES")
+            .unwrap_or_else(|_| "1048576".to_string())
+            .parse::<u32>()
+            .map_err(|_| anyhow!("MAX_IMAGE_BYTES must be a valid integer"))?;
+
+        // Set but empty disables the prompt
+        let default_image_prompt = match std::env::var("DEFAULT_IMAGE_PROMPT") {
+            Ok(s) => Some(s.trim().to_string()).filter(|s| !s.is_empty()),
+            Err(_) => Some("Describe these images.".to_string()),
+        };
+
+        let show_usage = std::env::var("SHOW_USAGE")
+            .unwrap_or_else(|_| "false".to_string())
+            .parse::<bool>()
+            .map_err(|_| anyhow!("SHOW_USAGE must be 'true' or 'false'"))?;
+
+        let duplicate_response_window = Duration::from_millis(
+            std::env::var("DUPLICATE_RESPONSE_WINDOW_MS")
+                .unwrap_or_else(|_| "10000".to_string())
+                .parse::<u64>()
+                .map_err(|_| anyhow!("DUPLICATE_RESPONSE_WINDOW_MS must be a valid integer"))?,
+        );
+
+        let show_file_edits = std::env::var("SHOW_FILE_EDITS")
+            .unwrap_or_else(|_| "false".to_string())
+            .parse::<bool>()
+            .map_err(|_| anyhow!("SHOW_FILE_EDITS must be true or false"))?;
+
+        let slow_start_nudge = Duration::from_millis(
+            std::env::var("SLOW_START_NUDGE_MS")
+                .unwrap_or_else(|_| "15000".to_string())
+                .parse::<u64>()
+                .map_err(|_| anyhow!("SLOW_START_NUDGE_MS must be a valid integer"))?,
+        );
+
+        let rewrite_workspace_paths = std::env::var("REWRITE_WORKSPACE_PATHS")
+            .unwrap_or_else(|_| "false".to_string())
+            .parse::<bool>()
+            .map_err(|_| anyhow!("REWRITE_WORKSPACE_PATHS must be true or false"))?;
+
+        let collect_feedback = std::env::var("COLLECT_FEEDBACK")
+            .unwrap_or_else(|_| "false".to_string())
+            .parse::<bool>()
+            .map_err(|_| anyhow!("COLLECT_FEEDBACK must be true or false"))?;
+
+        let response_filters = std::env::var("RESPONSE_FILTERS")
+            .unwrap_or_default()
+            .split(',')
+            .filter(|s| !s.trim().is_empty())
+            .map(|s| s.trim().to_string())
+            .collect::<Vec<_>>();
+
+        let reply_to_prompts = std::env::var("REPLY_TO_PROMPTS")
+            .unwrap_or_else(|_| "false".to_string())
+            .parse::<bool>()
+            .map_err(|_| anyhow!("REPLY_TO_PROMPTS must be true or false"))?;
+
+        let max_response_chars = std::env::var("MAX_RESPONSE_CHARS")
+            .unwrap_or_else(|_| "20000".to_string())
+            .parse::<usize>()
+            .map_err(|_| anyhow!("MAX_RESPONSE_CHARS must be a valid integer"))?;
+
+        let collapse_repeated_tools = std::env::var("COLLAPSE_REPEATED_TOOLS")
+            .unwrap_or_else(|_| "false".to_string())
+            .parse::<bool>()
+            .map_err(|_| anyhow!("COLLAPSE_REPEATED_TOOLS must be true or false"))?;
+
+        let stream_mode = std::env::var("STREAM_MODE")
+            .unwrap_or_else(|_| "batched".to_string())
+            .parse::<StreamMode>()?;
+
+        let durable_outbound = std::env::var("DURABLE_OUTBOUND")
+            .unwrap_or_else(|_| "false".to_string())
+            .parse::<bool>()
+            .map_err(|_| anyhow!("DURABLE_OUTBOUND must be true or false"))?;
+
+        let transcription_url = std::env::var("TRANSCRIPTION_URL")
+            .ok()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty());
+
+        let transcription_api_key = std::env::var("TRANSCRIPTION_API_KEY")
+            .ok()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty());
+
+        let transcription_model =
+            std::env::var("TRANSCRIPTION_MODEL").unwrap_or_else(|_| "whisper-1".to_string());
+
+        let audio_mode = std::env::var("AUDIO_MODE")
+            .unwrap_or_else(|_| "transcribe".to_string())
+            .parse::<AudioMode>()?;
+
         let opencode_path = PathBuf::from(
             std::env::var("OPENCODE_PATH").unwrap_or_else(|_| "opencode".to_string()),
         );
@@ -119,6 +431,54 @@ impl Config {
                 })?,
         );
 
+        let health_path = std::env::var("OPENCODE_HEALTH_PATH")
+            .ok()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .map(|s| {
+                if s.starts_with('/') {
+                    s
+                } else {
+                    format!("/{}", s)
+                }
+            })
+            .unwrap_or_else(|| DEFAULT_HEALTH_PATH.to_string());
+
+        let health_method = match std::env::var("OPENCODE_HEALTH_METHOD") {
+            Ok(s) if !s.trim().is_empty() => s
+                .trim()
+                .parse::<HealthMethod>()
+                .map_err(|_| anyhow!("OPENCODE_HEALTH_METHOD must be one of 'GET', 'HEAD'"))?,
+            _ => HealthMethod::default(),
+        };
+        let health_expect_status = std::env::var("HEALTH_EXPECT_STATUS")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(|s| {
+                s.parse::<u16>()
+                    .ok()
+                    .filter(|code| (100..600).contains(code))
+                    .ok_or_else(|| {
+                        anyhow!("HEALTH_EXPECT_STATUS must be a comma-separated list of HTTP status codes")
+                    })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let health_expect_body = std::env::var("HEALTH_EXPECT_BODY")
+            .ok()
+            .filter(|s| !s.trim().is_empty());
+        if health_method == HealthMethod::Head && health_expect_body.is_some() {
+            return Err(anyhow!(
+                "HEALTH_EXPECT_BODY cannot be used with OPENCODE_HEALTH_METHOD=HEAD"
+            ));
+        }
+        let health_check = HealthCheck {
+            method: health_method,
+            expect_status: health_expect_status,
+            expect_body: health_expect_body,
+        };
+
         let opencode_startup_timeout = Duration::from_millis(
             std::env::var("OPENCODE_STARTUP_TIMEOUT_MS")
                 .unwrap_or_else(|_| "60000".to_string())
@@ -131,6 +491,85 @@ impl Config {
         let opencode_data_path =
             PathBuf::from(shellexpand::tilde(&opencode_data_path_raw).into_owned());
 
+        let opencode_socket_path = std::env::var("OPENCODE_SOCKET_PATH")
+            .ok()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .map(|s| PathBuf::from(shellexpand::tilde(&s).into_owned()));
+
+        let fallback_model = std::env::var("FALLBACK_MODEL")
+            .ok()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty());
+        if let Some(model) = &fallback_model {
+            if ModelRef::parse(model).is_none() {
+                return Err(anyhow!("FALLBACK_MODEL must be provider/model"));
+            }
+        }
+
+        let permission_timeout = Duration::from_millis(
+            std::env::var("PERMISSION_TIMEOUT_MS")
+                .unwrap_or_else(|_| "600000".to_string())
+                .parse::<u64>()
+                .map_err(|_| anyhow!("PERMISSION_TIMEOUT_MS must be a valid integer"))?,
+        );
+
+        let opencode_request_timeout = Duration::from_millis(
+            std::env::var("OPENCODE_REQUEST_TIMEOUT_MS")
+                .unwrap_or_else(|_| "30000".to_string())
+                .parse::<u64>()
+                .map_err(|_| anyhow!("OPENCODE_REQUEST_TIMEOUT_MS must be a valid integer"))?,
+        );
+
+        let resurrection_timeout = std::env::var("RESURRECTION_TIMEOUT_MS")
+            .unwrap_or_else(|_| "30000".to_string())
+            .parse::<u64>()
+            .ok()
+            .filter(|ms| *ms > 0)
+            .map(Duration::from_millis)
+            .ok_or_else(|| anyhow!("RESURRECTION_TIMEOUT_MS must be a positive integer"))?;
+
+        let resurrection_wake_delay = Duration::from_millis(
+            std::env::var("RESURRECTION_WAKE_DELAY_MS")
+                .unwrap_or_else(|_| "3000".to_string())
+                .parse::<u64>()
+                .map_err(|_| anyhow!("RESURRECTION_WAKE_DELAY_MS must be a valid integer"))?,
+        );
+
+        let dedup_expiry = Duration::from_secs(
+            std::env::var("DEDUP_EXPIRY_SECS")
+                .unwrap_or_else(|_| "30".to_string())
+                .parse::<u64>()
+                .map_err(|_| anyhow!("DEDUP_EXPIRY_SECS must be a valid integer"))?,
+        );
+
+        let max_reconnect_attempts = std::env::var("MAX_RECONNECT_ATTEMPTS")
+            .unwrap_or_else(|_| "5".to_string())
+            .parse::<u32>()
+            .map_err(|_| anyhow!("MAX_RECONNECT_ATTEMPTS must be a valid integer"))?;
+
+        let base_reconnect_delay = Duration::from_secs(
+            std::env::var("BASE_RECONNECT_DELAY_SECS")
+                .unwrap_or_else(|_| "1".to_string())
+                .parse::<u64>()
+                .map_err(|_| anyhow!("BASE_RECONNECT_DELAY_SECS must be a valid integer"))?,
+        );
+
+        let max_reconnect_delay = Duration::from_secs(
+            std::env::var("MAX_RECONNECT_DELAY_SECS")
+                .unwrap_or_else(|_| "16".to_string())
+                .parse::<u64>()
+                .map_err(|_| anyhow!("MAX_RECONNECT_DELAY_SECS must be a valid integer"))?,
+        );
+
+        let max_resume_age = Duration::from_secs(
+            std::env::var("MAX_RESUME_AGE_DAYS")
+                .unwrap_or_else(|_| "0".to_string())
+                .parse::<u64>()
+                .map_err(|_| anyhow!("MAX_RESUME_AGE_DAYS must be a valid integer"))?
+                .saturating_mul(24 * 60 * 60),
+        );
+
         let orchestrator_db_path = PathBuf::from(
             std::env::var("ORCHESTRATOR_DB_PATH")
                 .unwrap_or_else(|_| "./data/orchestrator.db".to_string()),
@@ -144,6 +583,13 @@ impl Config {
             std::env::var("LOG_DB_PATH").unwrap_or_else(|_| "./data/logs.db".to_string()),
         );
 
+        let image_cache_retention = Duration::from_secs(
+            std::env::var("IMAGE_CACHE_RETENTION_SECS")
+                .unwrap_or_else(|_| "86400".to_string())
+                .parse::<u64>()
+                .map_err(|_| anyhow!("IMAGE_CACHE_RETENTION_SECS must be a valid integer"))?,
+        );
+
         let project_base_path = std::env::var("PROJECT_BASE_PATH")
             .map_err(|_| anyhow!("PROJECT_BASE_PATH is required but not set"))?;
         let project_base_path = PathBuf::from(shellexpand::tilde(&project_base_path).into_owned());
@@ -153,6 +599,11 @@ impl Config {
             .parse::<bool>()
             .map_err(|_| anyhow!("AUTO_CREATE_PROJECT_DIRS must be 'true' or 'false'"))?;
 
+        let validate_project = std::env::var("VALIDATE_PROJECT")
+            .unwrap_or_else(|_| "false".to_string())
+            .parse::<bool>()
+            .map_err(|_| anyhow!("VALIDATE_PROJECT must be 'true' or 'false'"))?;
+
         let docker_image = std::env::var("OPENCODE_DOCKER_IMAGE")
             .unwrap_or_else(|_| "ghcr.io/sst/opencode".to_string());
 
@@ -173,6 +624,76 @@ impl Config {
             .map(|s| s.trim().to_string())
             .collect::<Vec<_>>();
 
+        let container_dns = std::env::var("CONTAINER_DNS")
+            .unwrap_or_default()
+            .split(',')
+            .filter(|s| !s.trim().is_empty())
+            .map(|s| s.trim().to_string())
+            .collect::<Vec<_>>();
+
+        let container_dns_search = std::env::var("CONTAINER_DNS_SEARCH")
+            .unwrap_or_default()
+            .split(',')
+            .filter(|s| !s.trim().is_empty())
+            .map(|s| s.trim().to_string())
+            .collect::<Vec<_>>();
+
+        let container_extra_args = std::env::var("OPENCODE_EXTRA_ARGS")
+            .unwrap_or_default()
+            .split_whitespace()
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+
+        let keep_stopped_containers = std::env::var("KEEP_STOPPED_CONTAINERS")
+            .unwrap_or_else(|_| "false".to_string())
+            .parse::<bool>()
+            .map_err(|_| anyhow!("KEEP_STOPPED_CONTAINERS must be 'true' or 'false'"))?;
+
+        let container_memory_mb = std::env::var("CONTAINER_MEMORY_MB")
+            .unwrap_or_else(|_| "0".to_string())
+            .parse::<u64>()
+            .map_err(|_| anyhow!("CONTAINER_MEMORY_MB must be a valid integer"))?;
+
+        let workspace_mount = match std::env::var("CONTAINER_WORKSPACE_MOUNT") {
+            Ok(s) if !s.trim().is_empty() => {
+                let mount = s.trim().trim_end_matches('/');
+                if !mount.starts_with('/') {
+                    return Err(anyhow!(
+                        "CONTAINER_WORKSPACE_MOUNT must be an absolute path other than /"
+                    ));
+                }
+                mount.to_string()
+            }
+            _ => DEFAULT_WORKSPACE_MOUNT.to_string(),
+        };
+
+        let api_max_body_bytes = std::env::var("API_MAX_BODY_BYTES")
+            .unwrap_or_else(|_| "1048576".to_string())
+            .parse::<usize>()
+            .map_err(|_| anyhow!("API_MAX_BODY_BYTES must be a valid integer"))?;
+
+        // 0 turns the API server off
+        let api_port = std::env::var("API_PORT")
+            .unwrap_or_else(|_| "4200".to_string())
+            .parse::<u16>()
+            .map_err(|_| anyhow!("API_PORT must be a valid port number"))?;
+
+        let api_host = std::env::var("API_HOST")
+            .unwrap_or_else(|_| "127.0.0.1".to_string())
+            .parse::<IpAddr>()
+            .map_err(|_| anyhow!("API_HOST must be an IP address"))?;
+
+        let api_key = std::env::var("API_KEY")
+            .ok()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty());
+        if api_port != 0 && api_key.is_none() && !api_host.is_loopback() {
+            return Err(anyhow!(
+                "API_KEY is required when API_HOST ({}) is not a loopback address",
+                api_host
+            ));
+        }
+
         debug!(
             opencode_path = ?opencode_path,
             max_instances = opencode_max_instances,
@@ -186,13 +707,64 @@ impl Config {
             log_db = %log_db_path.display(),
             project_base = %project_base_path.display(),
             auto_create_dirs = auto_create_project_dirs,
+            validate_project = validate_project,
             docker_image = %docker_image,
             opencode_config_path = %opencode_config_path.display(),
             container_port = container_port,
+            keep_stopped_containers = keep_stopped_containers,
+            container_memory_mb = container_memory_mb,
+            workspace_mount = workspace_mount,
             env_passthrough_count = env_passthrough.len(),
             allowed_users_count = telegram_allowed_users.len(),
+            admin_users_count = telegram_admin_users.len(),
             chat_ids_count = telegram_chat_ids.len(),
             handle_general_topic = handle_general_topic,
+            topic_name_strategy = ?topic_name_strategy,
+            show_usage = show_usage,
+            duplicate_response_window = ?duplicate_response_window,
+            container_dns = ?container_dns,
+            container_dns_search = ?container_dns_search,
+            fallback_model = ?fallback_model,
+            max_topics_per_chat = max_topics_per_chat,
+            transcription_url = ?transcription_url,
+            has_transcription_api_key = transcription_api_key.is_some(),
+            transcription_model = %transcription_model,
+            audio_mode = ?audio_mode,
+            permission_timeout = ?permission_timeout,
+            opencode_request_timeout = ?opencode_request_timeout,
+            resurrection_timeout = ?resurrection_timeout,
+            resurrection_wake_delay = ?resurrection_wake_delay,
+            show_file_edits = show_file_edits,
+            allowed_upload_mime = ?allowed_upload_mime,
+            dedup_expiry = ?dedup_expiry,
+            max_reconnect_attempts,
+            base_reconnect_delay = ?base_reconnect_delay,
+            max_reconnect_delay = ?max_reconnect_delay,
+            max_resume_age = ?max_resume_age,
+            max_concurrent_downloads = max_concurrent_downloads,
+            max_message_parts = max_message_parts,
+            api_max_body_bytes = api_max_body_bytes,
+            api_port = api_port,
+            api_host = api_host,
+            has_api_key = api_key.is_some(),
+            image_cache_retention = ?image_cache_retention,
+            slow_start_nudge = ?slow_start_nudge,
+            rewrite_workspace_paths = rewrite_workspace_paths,
+            opencode_socket_path = ?opencode_socket_path,
+            health_path = %health_path,
+            health_check = ?health_check,
+            collect_feedback = collect_feedback,
+            container_extra_args = ?container_extra_args,
+            response_filters = ?response_filters,
+            reply_to_prompts = reply_to_prompts,
+            max_response_chars = max_response_chars,
+            collapse_repeated_tools = collapse_repeated_tools,
+            stream_mode = ?stream_mode,
+            durable_outbound = durable_outbound,
+            preferred_photo_size = ?preferred_photo_size,
+            max_image_dimension = max_image_dimension,
+            max_image_bytes = max_image_bytes,
+            default_image_prompt = ?default_image_prompt,
             "Config resolved from environment"
         );
 
@@ -200,57 +772,346 @@ impl Config {
             telegram_bot_token,
             telegram_chat_ids,
             telegram_allowed_users,
+            telegram_admin_users,
             handle_general_topic,
+            topic_name_strategy,
+            max_topics_per_chat,
+            allowed_upload_mime,
+            max_concurrent_downloads,
+            max_message_parts,
+            preferred_photo_size,
+            max_image_dimension,
+            max_image_bytes,
+            default_image_prompt,
+            show_usage,
+            duplicate_response_window,
+            show_file_edits,
+            slow_start_nudge,
+            rewrite_workspace_paths,
+            collect_feedback,
+            response_filters,
+            reply_to_prompts,
+            max_response_chars,
+            collapse_repeated_tools,
+            stream_mode,
+            durable_outbound,
+            transcription_url,
+            transcription_api_key,
+            transcription_model,
+            audio_mode,
             opencode_path,
             opencode_max_instances,
             opencode_idle_timeout,
             opencode_port_start,
             opencode_port_pool_size,
             opencode_health_check_interval,
+            health_path,
+            health_check,
             opencode_startup_timeout,
             opencode_data_path,
+            opencode_socket_path,
+            fallback_model,
+            permission_timeout,
+            opencode_request_timeout,
+            resurrection_timeout,
+            resurrection_wake_delay,
+            dedup_expiry,
+            max_reconnect_attempts,
+            base_reconnect_delay,
+            max_reconnect_delay,
+            max_resume_age,
             orchestrator_db_path,
             topic_db_path,
             log_db_path,
+            image_cache_retention,
             project_base_path,
             auto_create_project_dirs,
+            validate_project,
             docker_image,
             opencode_config_path,
             container_port,
             env_passthrough,
+            container_dns,
+            container_dns_search,
+            container_extra_args,
+            keep_stopped_containers,
+            container_memory_mb,
+            workspace_mount,
+            api_max_body_bytes,
+            api_port,
+            api_host,
+            api_key,
         })
     }
 
     pub fn is_whitelisted_chat(&self, chat_id: i64) -> bool {
         self.telegram_chat_ids.contains(&chat_id)
     }
+
+    /// Whether `user_id` may use the bot. An empty `TELEGRAM_ALLOWED_USERS`
+    /// allows everyone in the whitelisted chats.
+    pub fn is_allowed_user(&self, user_id: i64) -> bool {
+        self.telegram_allowed_users.is_empty() || self.telegram_allowed_users.contains(&user_id)
+    }
+
+    /// Whether `user_id` may run admin commands. Nobody can while
+    /// `TELEGRAM_ADMIN_USERS` is empty.
+    pub fn is_admin(&self, user_id: i64) -> bool {
+        self.telegram_admin_users.contains(&user_id)
+    }
+
+    /// Copy of `self` with the hot-reloadable settings taken from `fresh`
+    /// (see [`HOT_RELOADABLE_SETTINGS`]), plus the names of the settings that
+    /// changed.
+    pub fn merge_reloadable(&self, fresh: &Config) -> (Config, Vec<&'static str>) {
+        let mut merged = self.clone();
+        let mut changed = Vec::new();
+
+        macro_rules! reload {
+            ($($field:ident => $env:literal),* $(,)?) => {
+                $(
+                    if merged.$field != fresh.$field {
+                        merged.$field = fresh.$field.clone();
+                        changed.push($env);
+                    }
+                )*
+            };
+        }
+        reload!(
+            opencode_idle_timeout => "OPENCODE_IDLE_TIMEOUT_MS",
+            opencode_health_check_interval => "OPENCODE_HEALTH_CHECK_INTERVAL_MS",
+            opencode_startup_timeout => "OPENCODE_STARTUP_TIMEOUT_MS",
+            permission_timeout => "PERMISSION_TIMEOUT_MS",
+            opencode_request_timeout => "OPENCODE_REQUEST_TIMEOUT_MS",
+            resurrection_timeout => "RESURRECTION_TIMEOUT_MS",
+            resurrection_wake_delay => "RESURRECTION_WAKE_DELAY_MS",
+            duplicate_response_window => "DUPLICATE_RESPONSE_WINDOW_MS",
+            show_usage => "SHOW_USAGE",
+            show_file_edits => "SHOW_FILE_EDITS",
+            rewrite_workspace_paths => "REWRITE_WORKSPACE_PATHS",
+            collect_feedback => "COLLECT_FEEDBACK",
+            response_filters => "RESPONSE_FILTERS",
+            reply_to_prompts => "REPLY_TO_PROMPTS",
+            max_response_chars => "MAX_RESPONSE_CHARS",
+            collapse_repeated_tools => "COLLAPSE_REPEATED_TOOLS",
+            stream_mode => "STREAM_MODE",
+            max_topics_per_chat => "MAX_TOPICS_PER_CHAT",
+            allowed_upload_mime => "ALLOWED_UPLOAD_MIME",
+            default_image_prompt => "DEFAULT_IMAGE_PROMPT",
+            fallback_model => "FALLBACK_MODEL",
+            image_cache_retention => "IMAGE_CACHE_RETENTION_SECS",
+            max_resume_age => "MAX_RESUME_AGE_DAYS",
+        );
+
+        (merged, changed)
+    }
+
+    /// Names of the settings in [`RESTART_REQUIRED_SETTINGS`] that differ in
+    /// `fresh`. A reload keeps their startup values, so callers can warn that
+    /// the change was ignored.
+    pub fn restart_required_changes(&self, fresh: &Config) -> Vec<&'static str> {
+        let mut ignored = Vec::new();
+
+        macro_rules! structural {
+            ($($field:ident => $env:literal),* $(,)?) => {
+                $(
+                    if self.$field != fresh.$field {
+                        ignored.push($env);
+                    }
+                )*
+            };
+        }
+        structural!(
+            telegram_bot_token => "TELEGRAM_BOT_TOKEN",
+            opencode_port_start => "OPENCODE_PORT_START",
+            opencode_port_pool_size => "OPENCODE_PORT_POOL_SIZE",
+            container_port => "OPENCODE_CONTAINER_PORT",
+            opencode_socket_path => "OPENCODE_SOCKET_PATH",
+            orchestrator_db_path => "ORCHESTRATOR_DB_PATH",
+            topic_db_path => "TOPIC_DB_PATH",
+            log_db_path => "LOG_DB_PATH",
+            project_base_path => "PROJECT_BASE_PATH",
+            opencode_data_path => "OPENCODE_DATA_PATH",
+            api_max_body_bytes => "API_MAX_BODY_BYTES",
+            api_port => "API_PORT",
+            api_host => "API_HOST",
+            api_key => "API_KEY",
+        );
+
+        ignored
+    }
+}
+
+/// Settings that `/reload` applies without a restart. Everything else (tokens,
+/// chat IDs, ports, paths, Docker and stream settings) is read once at startup.
+pub const HOT_RELOADABLE_SETTINGS: &[&str] = &[
+    "OPENCODE_IDLE_TIMEOUT_MS",
+    "OPENCODE_HEALTH_CHECK_INTERVAL_MS",
+    "OPENCODE_STARTUP_TIMEOUT_MS",
+    "PERMISSION_TIMEOUT_MS",
+    "OPENCODE_REQUEST_TIMEOUT_MS",
+    "RESURRECTION_TIMEOUT_MS",
+    "RESURRECTION_WAKE_DELAY_MS",
+    "DUPLICATE_RESPONSE_WINDOW_MS",
+    "SHOW_USAGE",
+    "SHOW_FILE_EDITS",
+    "REWRITE_WORKSPACE_PATHS",
+    "COLLECT_FEEDBACK",
+    "RESPONSE_FILTERS",
+    "REPLY_TO_PROMPTS",
+    "MAX_RESPONSE_CHARS",
+    "COLLAPSE_REPEATED_TOOLS",
+    "STREAM_MODE",
+    "MAX_TOPICS_PER_CHAT",
+    "ALLOWED_UPLOAD_MIME",
+    "DEFAULT_IMAGE_PROMPT",
+    "FALLBACK_MODEL",
+    "IMAGE_CACHE_RETENTION_SECS",
+    "MAX_RESUME_AGE_DAYS",
+];
+
+/// Ports, paths, the API server and the bot token. These are bound at startup
+/// (listeners, database pools, mounted volumes), so a reload never applies them.
+pub const RESTART_REQUIRED_SETTINGS: &[&str] = &[
+    "TELEGRAM_BOT_TOKEN",
+    "OPENCODE_PORT_START",
+    "OPENCODE_PORT_POOL_SIZE",
+    "OPENCODE_CONTAINER_PORT",
+    "OPENCODE_SOCKET_PATH",
+    "ORCHESTRATOR_DB_PATH",
+    "TOPIC_DB_PATH",
+    "LOG_DB_PATH",
+    "PROJECT_BASE_PATH",
+    "OPENCODE_DATA_PATH",
+    "API_MAX_BODY_BYTES",
+    "API_PORT",
+    "API_HOST",
+    "API_KEY",
+];
+
+/// The running configuration, shared by the bot and the instance manager.
+///
+/// Readers take a cheap snapshot with [`SharedConfig::get`]; `/reload` swaps
+/// in a new snapshot with updated hot-reloadable settings.
+#[derive(Clone, Debug)]
+pub struct SharedConfig(Arc<RwLock<Arc<Config>>>);
+
+impl SharedConfig {
+    pub fn new(config: Config) -> Self {
+        Self(Arc::new(RwLock::new(Arc::new(config))))
+    }
+
+    /// Snapshot of the current configuration.
+    pub fn get(&self) -> Arc<Config> {
+        Arc::clone(&self.0.read().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    /// Apply the hot-reloadable settings of `fresh`. Returns the names of the
+    /// settings that changed.
+    pub fn reload(&self, fresh: &Config) -> Vec<&'static str> {
+        let mut current = self.0.write().unwrap_or_else(|e| e.into_inner());
+        let (merged, changed) = current.merge_reloadable(fresh);
+        *current = Arc::new(merged);
+        changed
+    }
+}
+
+impl From<Config> for SharedConfig {
+    fn from(config: Config) -> Self {
+        Self::new(config)
+    }
+}
+
+impl From<Arc<Config>> for SharedConfig {
+    fn from(config: Arc<Config>) -> Self {
+        Self(Arc::new(RwLock::new(config)))
+    }
 }
 
 impl std::fmt::Display for Config {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "Config {{\n  telegram_bot_token: ***MASKED***,\n  telegram_chat_ids: {:?},\n  telegram_allowed_users: {:?},\n  handle_general_topic: {},\n  opencode_path: {:?},\n  opencode_max_instances: {},\n  opencode_idle_timeout: {:?},\n  opencode_port_start: {},\n  opencode_port_pool_size: {},\n  opencode_health_check_interval: {:?},\n  opencode_startup_timeout: {:?},\n  opencode_data_path: {:?},\n  orchestrator_db_path: {:?},\n  topic_db_path: {:?},\n  log_db_path: {:?},\n  project_base_path: {:?},\n  auto_create_project_dirs: {},\n  docker_image: {:?},\n  opencode_config_path: {:?},\n  container_port: {},\n  env_passthrough: {:?},\n}}",
+            "Config {{\n  telegram_bot_token: ***MASKED***,\n  telegram_chat_ids: {:?},\n  telegram_allowed_users: {:?},\n  telegram_admin_users: {:?},\n  handle_general_topic: {},\n  topic_name_strategy: {:?},\n  max_topics_per_chat: {},\n  allowed_upload_mime: {:?},\n  max_concurrent_downloads: {},\n  max_message_parts: {},\n  preferred_photo_size: {:?},\n  max_image_dimension: {},\n  max_image_bytes: {},\n  default_image_prompt: {:?},\n  show_usage: {},\n  duplicate_response_window: {:?},\n  show_file_edits: {},\n  slow_start_nudge: {:?},\n  rewrite_workspace_paths: {},\n  collect_feedback: {},\n  response_filters: {:?},\n  reply_to_prompts: {},\n  max_response_chars: {},\n  collapse_repeated_tools: {},\n  stream_mode: {:?},\n  durable_outbound: {},\n  transcription_url: {:?},\n  transcription_api_key: {},\n  transcription_model: {},\n  audio_mode: {:?},\n  opencode_path: {:?},\n  opencode_max_instances: {},\n  opencode_idle_timeout: {:?},\n  opencode_port_start: {},\n  opencode_port_pool_size: {},\n  opencode_health_check_interval: {:?},\n  health_path: {},\n  health_check: {:?},\n  opencode_startup_timeout: {:?},\n  opencode_data_path: {:?},\n  opencode_socket_path: {:?},\n  fallback_model: {:?},\n  permission_timeout: {:?},\n  opencode_request_timeout: {:?},\n  resurrection_timeout: {:?},\n  resurrection_wake_delay: {:?},\n  dedup_expiry: {:?},\n  max_reconnect_attempts: {},\n  base_reconnect_delay: {:?},\n  max_reconnect_delay: {:?},\n  max_resume_age: {:?},\n  orchestrator_db_path: {:?},\n  topic_db_path: {:?},\n  log_db_path: {:?},\n  image_cache_retention: {:?},\n  project_base_path: {:?},\n  auto_create_project_dirs: {},\n  validate_project: {},\n  docker_image: {:?},\n  opencode_config_path: {:?},\n  container_port: {},\n  env_passthrough: {:?},\n  container_dns: {:?},\n  container_dns_search: {:?},\n  container_extra_args: {:?},\n  keep_stopped_containers: {},\n  container_memory_mb: {},\n  workspace_mount: {},\n  api_max_body_bytes: {},\n  api_port: {},\n  api_host: {},\n  api_key: {},\n}}",
             self.telegram_chat_ids,
             self.telegram_allowed_users,
+            self.telegram_admin_users,
             self.handle_general_topic,
+            self.topic_name_strategy,
+            self.max_topics_per_chat,
+            self.allowed_upload_mime,
+            self.max_concurrent_downloads,
+            self.max_message_parts,
+            self.preferred_photo_size,
+            self.max_image_dimension,
+            self.max_image_bytes,
+            self.default_image_prompt,
+            self.show_usage,
+            self.duplicate_response_window,
+            self.show_file_edits,
+            self.slow_start_nudge,
+            self.rewrite_workspace_paths,
+            self.collect_feedback,
+            self.response_filters,
+            self.reply_to_prompts,
+            self.max_response_chars,
+            self.collapse_repeated_tools,
+            self.stream_mode,
+            self.durable_outbound,
+            self.transcription_url,
+            if self.transcription_api_key.is_some() {
+                "***MASKED***"
+            } else {
+                "None"
+            },
+            self.transcription_model,
+            self.audio_mode,
             self.opencode_path,
             self.opencode_max_instances,
             self.opencode_idle_timeout,
             self.opencode_port_start,
             self.opencode_port_pool_size,
             self.opencode_health_check_interval,
+            self.health_path,
+            self.health_check,
             self.opencode_startup_timeout,
             self.opencode_data_path,
+            self.opencode_socket_path,
+            self.fallback_model,
+            self.permission_timeout,
+            self.opencode_request_timeout,
+            self.resurrection_timeout,
+            self.resurrection_wake_delay,
+            self.dedup_expiry,
+            self.max_reconnect_attempts,
+            self.base_reconnect_delay,
+            self.max_reconnect_delay,
+            self.max_resume_age,
             self.orchestrator_db_path,
             self.topic_db_path,
             self.log_db_path,
+            self.image_cache_retention,
             self.project_base_path,
             self.auto_create_project_dirs,
+            self.validate_project,
             self.docker_image,
             self.opencode_config_path,
             self.container_port,
-            self.env_passthrough
+            self.env_passthrough,
+            self.container_dns,
+            self.container_dns_search,
+            self.container_extra_args,
+            self.keep_stopped_containers,
+            self.container_memory_mb,
+            self.workspace_mount,
+            self.api_max_body_bytes,
+            self.api_port,
+            self.api_host,
+            if self.api_key.is_some() {
+                "***MASKED***"
+            } else {
+                "None"
+            },
         )
     }
 }
@@ -267,6 +1128,8 @@ mod tests {
             "TELEGRAM_CHAT_IDS",
             "TELEGRAM_ALLOWED_USERS",
             "HANDLE_GENERAL_TOPIC",
+            "TOPIC_NAME_STRATEGY",
+            "SHOW_USAGE",
             "OPENCODE_PATH",
             "OPENCODE_MAX_INSTANCES",
             "OPENCODE_IDLE_TIMEOUT_MS",
@@ -284,6 +1147,57 @@ mod tests {
             "OPENCODE_CONFIG_PATH",
             "OPENCODE_CONTAINER_PORT",
             "OPENCODE_ENV_PASSTHROUGH",
+            "DUPLICATE_RESPONSE_WINDOW_MS",
+            "CONTAINER_DNS",
+            "CONTAINER_DNS_SEARCH",
+            "FALLBACK_MODEL",
+            "MAX_TOPICS_PER_CHAT",
+            "TRANSCRIPTION_URL",
+            "TRANSCRIPTION_API_KEY",
+            "TRANSCRIPTION_MODEL",
+            "PERMISSION_TIMEOUT_MS",
+            "SHOW_FILE_EDITS",
+            "ALLOWED_UPLOAD_MIME",
+            "DEDUP_EXPIRY_SECS",
+            "MAX_RECONNECT_ATTEMPTS",
+            "BASE_RECONNECT_DELAY_SECS",
+            "MAX_RECONNECT_DELAY_SECS",
+            "MAX_CONCURRENT_DOWNLOADS",
+            "API_MAX_BODY_BYTES",
+            "IMAGE_CACHE_RETENTION_SECS",
+            "SLOW_START_NUDGE_MS",
+            "REWRITE_WORKSPACE_PATHS",
+            "OPENCODE_SOCKET_PATH",
+            "OPENCODE_HEALTH_PATH",
+            "COLLECT_FEEDBACK",
+            "OPENCODE_EXTRA_ARGS",
+            "RESPONSE_FILTERS",
+            "REPLY_TO_PROMPTS",
+            "PREFERRED_PHOTO_SIZE",
+            "MAX_IMAGE_DIMENSION",
+            "MAX_IMAGE_BYTES",
+            "VALIDATE_PROJECT",
+            "MAX_RESPONSE_CHARS",
+            "KEEP_STOPPED_CONTAINERS",
+            "AUDIO_MODE",
+            "MAX_MESSAGE_PARTS",
+            "DURABLE_OUTBOUND",
+            "COLLAPSE_REPEATED_TOOLS",
+            "CONTAINER_WORKSPACE_MOUNT",
+            "MAX_RESUME_AGE_DAYS",
+            "OPENCODE_REQUEST_TIMEOUT_MS",
+            "CONTAINER_MEMORY_MB",
+            "STREAM_MODE",
+            "OPENCODE_HEALTH_METHOD",
+            "HEALTH_EXPECT_STATUS",
+            "HEALTH_EXPECT_BODY",
+            "DEFAULT_IMAGE_PROMPT",
+            "RESURRECTION_TIMEOUT_MS",
+            "RESURRECTION_WAKE_DELAY_MS",
+            "TELEGRAM_ADMIN_USERS",
+            "API_PORT",
+            "API_HOST",
+            "API_KEY",
         ] {
             std::env::remove_var(var);
         }
@@ -387,7 +1301,66 @@ mod tests {
         assert_eq!(config.log_db_path, PathBuf::from("./data/logs.db"));
         assert!(config.auto_create_project_dirs);
         assert!(config.handle_general_topic);
+        assert_eq!(config.topic_name_strategy, TopicNameStrategy::Leaf);
+        assert!(!config.show_usage);
         assert!(config.telegram_allowed_users.is_empty());
+        assert_eq!(
+            config.duplicate_response_window,
+            Duration::from_millis(10000)
+        );
+        assert!(config.container_dns.is_empty());
+        assert!(config.container_dns_search.is_empty());
+        assert!(config.fallback_model.is_none());
+        assert_eq!(config.max_topics_per_chat, 50);
+        assert!(config.transcription_url.is_none());
+        assert!(config.transcription_api_key.is_none());
+        assert_eq!(config.transcription_model, "whisper-1");
+        assert_eq!(config.permission_timeout, Duration::from_secs(600));
+        assert!(!config.show_file_edits);
+        assert!(config.allowed_upload_mime.contains(&"text/*".to_string()));
+        assert!(!config
+            .allowed_upload_mime
+            .contains(&"application/zip".to_string()));
+        assert_eq!(config.dedup_expiry, Duration::from_secs(30));
+        assert_eq!(config.max_reconnect_attempts, 5);
+        assert_eq!(config.base_reconnect_delay, Duration::from_secs(1));
+        assert_eq!(config.max_reconnect_delay, Duration::from_secs(16));
+        assert_eq!(config.max_concurrent_downloads, 4);
+        assert_eq!(config.api_max_body_bytes, 1_048_576);
+        assert_eq!(config.image_cache_retention, Duration::from_secs(86400));
+        assert_eq!(config.slow_start_nudge, Duration::from_secs(15));
+        assert!(!config.rewrite_workspace_paths);
+        assert!(config.opencode_socket_path.is_none());
+        assert_eq!(config.health_path, "/global/health");
+        assert!(!config.collect_feedback);
+        assert!(config.container_extra_args.is_empty());
+        assert!(config.response_filters.is_empty());
+        assert!(!config.reply_to_prompts);
+        assert_eq!(config.preferred_photo_size, PhotoSizePreference::Largest);
+        assert_eq!(config.max_image_dimension, 1280);
+        assert_eq!(config.max_image_bytes, 1_048_576);
+        assert!(!config.validate_project);
+        assert_eq!(config.max_response_chars, 20_000);
+        assert!(!config.keep_stopped_containers);
+        assert_eq!(config.audio_mode, AudioMode::Transcribe);
+        assert_eq!(config.max_message_parts, 10);
+        assert!(!config.durable_outbound);
+        assert!(!config.collapse_repeated_tools);
+        assert_eq!(config.workspace_mount, "/workspace");
+        assert_eq!(config.max_resume_age, Duration::ZERO);
+        assert_eq!(config.opencode_request_timeout, Duration::from_secs(30));
+        assert_eq!(config.container_memory_mb, 0);
+        assert_eq!(config.stream_mode, StreamMode::Batched);
+        assert_eq!(config.health_check, HealthCheck::default());
+        assert_eq!(
+            config.default_image_prompt.as_deref(),
+            Some("Describe these images.")
+        );
+        assert_eq!(config.resurrection_timeout, Duration::from_secs(30));
+        assert_eq!(config.resurrection_wake_delay, Duration::from_secs(3));
+        assert_eq!(config.api_port, 4200);
+        assert_eq!(config.api_host, IpAddr::from([127, 0, 0, 1]));
+        assert!(config.api_key.is_none());
         assert_eq!(config.telegram_chat_ids, vec![-1001234567890]);
         assert_eq!(config.docker_image, "ghcr.io/sst/opencode");
         assert!(!config.opencode_config_path.to_string_lossy().contains("~"));
@@ -450,6 +1423,58 @@ mod tests {
         assert_eq!(config.telegram_allowed_users, vec![123, 456, 789]);
     }
 
+    #[test]
+    #[serial]
+    fn test_telegram_admin_users_parsing() {
+        clean_config_env();
+        std::env::set_var("TELEGRAM_BOT_TOKEN", "test-token");
+        std::env::set_var("TELEGRAM_CHAT_IDS", "-1001234567890");
+        std::env::set_var("PROJECT_BASE_PATH", "~/oc-bot");
+
+        let config = Config::from_env_no_dotenv().expect("Config should parse without admins");
+        assert!(config.telegram_admin_users.is_empty());
+        assert!(!config.is_admin(123));
+
+        std::env::set_var("TELEGRAM_ADMIN_USERS", "123, 456");
+        let config = Config::from_env_no_dotenv().expect("Config should parse admin users");
+        assert_eq!(config.telegram_admin_users, vec![123, 456]);
+        assert!(config.is_admin(456));
+        assert!(!config.is_admin(789));
+
+        std::env::set_var("TELEGRAM_ADMIN_USERS", "abc");
+        assert!(Config::from_env_no_dotenv().is_err());
+        clean_config_env();
+    }
+
+    #[test]
+    #[serial]
+    fn test_api_server_settings() {
+        clean_config_env();
+        std::env::set_var("TELEGRAM_BOT_TOKEN", "test-token");
+        std::env::set_var("TELEGRAM_CHAT_IDS", "-1001234567890");
+        std::env::set_var("PROJECT_BASE_PATH", "~/oc-bot");
+        std::env::set_var("API_PORT", "4300");
+        std::env::set_var("API_KEY", " secret ");
+
+        let config = Config::from_env_no_dotenv().expect("Config should parse API settings");
+        assert_eq!(config.api_port, 4300);
+        assert_eq!(config.api_key.as_deref(), Some("secret"));
+        assert!(!config.to_string().contains("secret"));
+
+        // Listening beyond loopback needs a key, unless the server is off
+        std::env::set_var("API_HOST", "0.0.0.0");
+        assert!(Config::from_env_no_dotenv().is_ok());
+        std::env::remove_var("API_KEY");
+        let err = Config::from_env_no_dotenv().unwrap_err().to_string();
+        assert!(err.contains("API_KEY is required"));
+        std::env::set_var("API_PORT", "0");
+        assert!(Config::from_env_no_dotenv().is_ok());
+
+        std::env::set_var("API_HOST", "localhost");
+        assert!(Config::from_env_no_dotenv().is_err());
+        clean_config_env();
+    }
+
     #[test]
     #[serial]
     fn test_telegram_allowed_users_empty() {
@@ -515,6 +1540,41 @@ mod tests {
         clean_config_env();
     }
 
+    #[test]
+    #[serial]
+    fn test_zero_max_concurrent_downloads_rejected() {
+        clean_config_env();
+        std::env::set_var("TELEGRAM_BOT_TOKEN", "test-token");
+        std::env::set_var("TELEGRAM_CHAT_IDS", "-1001234567890");
+        std::env::set_var("PROJECT_BASE_PATH", "~/oc-bot");
+        std::env::set_var("MAX_CONCURRENT_DOWNLOADS", "0");
+
+        let result = Config::from_env_no_dotenv();
+        assert!(result.is_err());
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("MAX_CONCURRENT_DOWNLOADS must be a positive integer"));
+        clean_config_env();
+    }
+
+    #[test]
+    #[serial]
+    fn test_zero_max_message_parts_rejected() {
+        clean_config_env();
+        std::env::set_var("TELEGRAM_BOT_TOKEN", "test-token");
+        std::env::set_var("TELEGRAM_CHAT_IDS", "-1001234567890");
+        std::env::set_var("PROJECT_BASE_PATH", "~/oc-bot");
+        std::env::set_var("MAX_MESSAGE_PARTS", "0");
+
+        let result = Config::from_env_no_dotenv();
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("MAX_MESSAGE_PARTS must be a positive integer"));
+        clean_config_env();
+    }
+
     #[test]
     #[serial]
     fn test_telegram_chat_ids_comma_separated() {
@@ -562,12 +1622,15 @@ mod tests {
         std::env::set_var("OPENCODE_PORT_POOL_SIZE", "50");
         std::env::set_var("OPENCODE_HEALTH_CHECK_INTERVAL_MS", "45000");
         std::env::set_var("OPENCODE_STARTUP_TIMEOUT_MS", "90000");
+        std::env::set_var("OPENCODE_SOCKET_PATH", "/run/opencode.sock");
+        std::env::set_var("OPENCODE_HEALTH_PATH", "health");
         std::env::set_var("OPENCODE_DATA_PATH", "~/custom/opencode-data");
         std::env::set_var("ORCHESTRATOR_DB_PATH", "./custom/orchestrator.db");
         std::env::set_var("TOPIC_DB_PATH", "./custom/topics.db");
         std::env::set_var("LOG_DB_PATH", "./custom/logs.db");
         std::env::set_var("PROJECT_BASE_PATH", "~/projects");
         std::env::set_var("AUTO_CREATE_PROJECT_DIRS", "false");
+        std::env::set_var("VALIDATE_PROJECT", "true");
         std::env::set_var("OPENCODE_DOCKER_IMAGE", "custom/opencode:latest");
         std::env::set_var("OPENCODE_CONFIG_PATH", "~/myconfig");
         std::env::set_var("OPENCODE_CONTAINER_PORT", "9090");
@@ -602,6 +1665,11 @@ mod tests {
             Duration::from_millis(90000)
         );
         assert!(!config.opencode_data_path.to_string_lossy().contains("~"));
+        assert_eq!(
+            config.opencode_socket_path,
+            Some(PathBuf::from("/run/opencode.sock"))
+        );
+        assert_eq!(config.health_path, "/health");
         assert_eq!(
             config.orchestrator_db_path,
             PathBuf::from("./custom/orchestrator.db")
@@ -609,6 +1677,7 @@ mod tests {
         assert_eq!(config.topic_db_path, PathBuf::from("./custom/topics.db"));
         assert_eq!(config.log_db_path, PathBuf::from("./custom/logs.db"));
         assert!(!config.auto_create_project_dirs);
+        assert!(config.validate_project);
         assert_eq!(config.docker_image, "custom/opencode:latest");
         assert!(!config.opencode_config_path.to_string_lossy().contains("~"));
         assert_eq!(config.container_port, 9090);
@@ -646,6 +1715,164 @@ mod tests {
         assert!(config.env_passthrough.is_empty());
     }
 
+    #[test]
+    #[serial]
+    fn test_container_dns_parsing() {
+        clean_config_env();
+        std::env::set_var("TELEGRAM_BOT_TOKEN", "test-token");
+        std::env::set_var("TELEGRAM_CHAT_IDS", "-1001234567890");
+        std::env::set_var("PROJECT_BASE_PATH", "~/oc-bot");
+        std::env::set_var("CONTAINER_DNS", "10.0.0.2, 10.0.0.3");
+        std::env::set_var("CONTAINER_DNS_SEARCH", "corp.example.com");
+
+        let config = Config::from_env_no_dotenv().expect("Config should parse DNS settings");
+        assert_eq!(config.container_dns, vec!["10.0.0.2", "10.0.0.3"]);
+        assert_eq!(config.container_dns_search, vec!["corp.example.com"]);
+    }
+
+    #[test]
+    #[serial]
+    fn test_container_extra_args_parsing() {
+        clean_config_env();
+        std::env::set_var("TELEGRAM_BOT_TOKEN", "test-token");
+        std::env::set_var("TELEGRAM_CHAT_IDS", "-1001234567890");
+        std::env::set_var("PROJECT_BASE_PATH", "~/oc-bot");
+        std::env::set_var("OPENCODE_EXTRA_ARGS", " --log-level  debug ");
+
+        let config = Config::from_env_no_dotenv().expect("Config should parse extra args");
+        assert_eq!(config.container_extra_args, vec!["--log-level", "debug"]);
+    }
+
+    #[test]
+    #[serial]
+    fn test_keep_stopped_containers_parsing() {
+        clean_config_env();
+        std::env::set_var("TELEGRAM_BOT_TOKEN", "test-token");
+        std::env::set_var("TELEGRAM_CHAT_IDS", "-1001234567890");
+        std::env::set_var("PROJECT_BASE_PATH", "~/oc-bot");
+        std::env::set_var("KEEP_STOPPED_CONTAINERS", "true");
+
+        let config =
+            Config::from_env_no_dotenv().expect("Config should parse KEEP_STOPPED_CONTAINERS");
+        assert!(config.keep_stopped_containers);
+
+        std::env::set_var("KEEP_STOPPED_CONTAINERS", "sometimes");
+        assert!(Config::from_env_no_dotenv()
+            .unwrap_err()
+            .to_string()
+            .contains("KEEP_STOPPED_CONTAINERS must be 'true' or 'false'"));
+        clean_config_env();
+    }
+
+    #[test]
+    #[serial]
+    fn test_resurrection_timing_parsing() {
+        clean_config_env();
+        std::env::set_var("TELEGRAM_BOT_TOKEN", "test-token");
+        std::env::set_var("TELEGRAM_CHAT_IDS", "-1001234567890");
+        std::env::set_var("PROJECT_BASE_PATH", "~/oc-bot");
+        std::env::set_var("RESURRECTION_TIMEOUT_MS", "120000");
+        std::env::set_var("RESURRECTION_WAKE_DELAY_MS", "0");
+
+        let config = Config::from_env_no_dotenv().expect("Config should parse resurrection timing");
+        assert_eq!(config.resurrection_timeout, Duration::from_secs(120));
+        assert_eq!(config.resurrection_wake_delay, Duration::ZERO);
+
+        std::env::set_var("RESURRECTION_TIMEOUT_MS", "0");
+        assert!(Config::from_env_no_dotenv()
+            .unwrap_err()
+            .to_string()
+            .contains("RESURRECTION_TIMEOUT_MS must be a positive integer"));
+        clean_config_env();
+    }
+
+    #[test]
+    #[serial]
+    fn test_max_resume_age_parsing() {
+        clean_config_env();
+        std::env::set_var("TELEGRAM_BOT_TOKEN", "test-token");
+        std::env::set_var("TELEGRAM_CHAT_IDS", "-1001234567890");
+        std::env::set_var("PROJECT_BASE_PATH", "~/oc-bot");
+        std::env::set_var("MAX_RESUME_AGE_DAYS", "90");
+
+        let config = Config::from_env_no_dotenv().expect("Config should parse MAX_RESUME_AGE_DAYS");
+        assert_eq!(
+            config.max_resume_age,
+            Duration::from_secs(90 * 24 * 60 * 60)
+        );
+
+        std::env::set_var("MAX_RESUME_AGE_DAYS", "soon");
+        assert!(Config::from_env_no_dotenv()
+            .unwrap_err()
+            .to_string()
+            .contains("MAX_RESUME_AGE_DAYS must be a valid integer"));
+        clean_config_env();
+    }
+
+    #[test]
+    #[serial]
+    fn test_opencode_request_timeout_parsing() {
+        clean_config_env();
+        std::env::set_var("TELEGRAM_BOT_TOKEN", "test-token");
+        std::env::set_var("TELEGRAM_CHAT_IDS", "-1001234567890");
+        std::env::set_var("PROJECT_BASE_PATH", "~/oc-bot");
+        std::env::set_var("OPENCODE_REQUEST_TIMEOUT_MS", "0");
+
+        let config =
+            Config::from_env_no_dotenv().expect("Config should parse OPENCODE_REQUEST_TIMEOUT_MS");
+        assert_eq!(config.opencode_request_timeout, Duration::ZERO);
+
+        std::env::set_var("OPENCODE_REQUEST_TIMEOUT_MS", "slow");
+        assert!(Config::from_env_no_dotenv()
+            .unwrap_err()
+            .to_string()
+            .contains("OPENCODE_REQUEST_TIMEOUT_MS must be a valid integer"));
+        clean_config_env();
+    }
+
+    #[test]
+    #[serial]
+    fn test_container_memory_mb_parsing() {
+        clean_config_env();
+        std::env::set_var("TELEGRAM_BOT_TOKEN", "test-token");
+        std::env::set_var("TELEGRAM_CHAT_IDS", "-1001234567890");
+        std::env::set_var("PROJECT_BASE_PATH", "~/oc-bot");
+        std::env::set_var("CONTAINER_MEMORY_MB", "2048");
+
+        let config = Config::from_env_no_dotenv().expect("Config should parse CONTAINER_MEMORY_MB");
+        assert_eq!(config.container_memory_mb, 2048);
+
+        std::env::set_var("CONTAINER_MEMORY_MB", "2G");
+        assert!(Config::from_env_no_dotenv()
+            .unwrap_err()
+            .to_string()
+            .contains("CONTAINER_MEMORY_MB must be a valid integer"));
+        clean_config_env();
+    }
+
+    #[test]
+    #[serial]
+    fn test_workspace_mount_parsing() {
+        clean_config_env();
+        std::env::set_var("TELEGRAM_BOT_TOKEN", "test-token");
+        std::env::set_var("TELEGRAM_CHAT_IDS", "-1001234567890");
+        std::env::set_var("PROJECT_BASE_PATH", "~/oc-bot");
+        std::env::set_var("CONTAINER_WORKSPACE_MOUNT", " /home/user/app/ ");
+
+        let config =
+            Config::from_env_no_dotenv().expect("Config should parse CONTAINER_WORKSPACE_MOUNT");
+        assert_eq!(config.workspace_mount, "/home/user/app");
+
+        for invalid in ["app", "/"] {
+            std::env::set_var("CONTAINER_WORKSPACE_MOUNT", invalid);
+            assert!(Config::from_env_no_dotenv()
+                .unwrap_err()
+                .to_string()
+                .contains("CONTAINER_WORKSPACE_MOUNT must be an absolute path"));
+        }
+        clean_config_env();
+    }
+
     #[test]
     #[serial]
     fn test_opencode_config_path_tilde_expansion() {
@@ -674,4 +1901,323 @@ mod tests {
         assert!(config.is_whitelisted_chat(-100456));
         assert!(!config.is_whitelisted_chat(-100789));
     }
+
+    #[test]
+    #[serial]
+    fn test_is_allowed_user_allows_everyone_by_default() {
+        clean_config_env();
+        std::env::set_var("TELEGRAM_BOT_TOKEN", "test-token");
+        std::env::set_var("TELEGRAM_CHAT_IDS", "-100123");
+        std::env::set_var("PROJECT_BASE_PATH", "~/oc-bot");
+
+        let config = Config::from_env_no_dotenv().expect("Config should load");
+
+        assert!(config.is_allowed_user(1));
+        assert!(config.is_allowed_user(987654321));
+    }
+
+    #[test]
+    #[serial]
+    fn test_is_allowed_user_enforces_allowlist() {
+        clean_config_env();
+        std::env::set_var("TELEGRAM_BOT_TOKEN", "test-token");
+        std::env::set_var("TELEGRAM_CHAT_IDS", "-100123");
+        std::env::set_var("PROJECT_BASE_PATH", "~/oc-bot");
+        std::env::set_var("TELEGRAM_ALLOWED_USERS", "111,222");
+
+        let config = Config::from_env_no_dotenv().expect("Config should load");
+
+        assert!(config.is_allowed_user(111));
+        assert!(config.is_allowed_user(222));
+        assert!(!config.is_allowed_user(333));
+    }
+
+    #[test]
+    #[serial]
+    fn test_max_response_chars_parsing() {
+        clean_config_env();
+        std::env::set_var("TELEGRAM_BOT_TOKEN", "test-token");
+        std::env::set_var("TELEGRAM_CHAT_IDS", "-1001234567890");
+        std::env::set_var("PROJECT_BASE_PATH", "~/oc-bot");
+        std::env::set_var("MAX_RESPONSE_CHARS", "0");
+
+        let config = Config::from_env_no_dotenv().expect("Config should parse MAX_RESPONSE_CHARS");
+        assert_eq!(config.max_response_chars, 0);
+
+        std::env::set_var("MAX_RESPONSE_CHARS", "lots");
+        let result = Config::from_env_no_dotenv();
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("MAX_RESPONSE_CHARS must be a valid integer"));
+        clean_config_env();
+    }
+
+    #[test]
+    #[serial]
+    fn test_default_image_prompt_parsing() {
+        clean_config_env();
+        std::env::set_var("TELEGRAM_BOT_TOKEN", "test-token");
+        std::env::set_var("TELEGRAM_CHAT_IDS", "-1001234567890");
+        std::env::set_var("PROJECT_BASE_PATH", "~/oc-bot");
+
+        std::env::set_var("DEFAULT_IMAGE_PROMPT", " Explain this screenshot ");
+        let config =
+            Config::from_env_no_dotenv().expect("Config should parse DEFAULT_IMAGE_PROMPT");
+        assert_eq!(
+            config.default_image_prompt.as_deref(),
+            Some("Explain this screenshot")
+        );
+
+        // Empty disables the prompt
+        std::env::set_var("DEFAULT_IMAGE_PROMPT", "");
+        let config =
+            Config::from_env_no_dotenv().expect("Config should parse empty DEFAULT_IMAGE_PROMPT");
+        assert_eq!(config.default_image_prompt, None);
+        clean_config_env();
+    }
+
+    #[test]
+    #[serial]
+    fn test_health_check_parsing() {
+        clean_config_env();
+        std::env::set_var("TELEGRAM_BOT_TOKEN", "test-token");
+        std::env::set_var("TELEGRAM_CHAT_IDS", "-1001234567890");
+        std::env::set_var("PROJECT_BASE_PATH", "~/oc-bot");
+        std::env::set_var("OPENCODE_HEALTH_METHOD", "head");
+        std::env::set_var("HEALTH_EXPECT_STATUS", "200, 204");
+
+        let config = Config::from_env_no_dotenv().expect("Config should parse health check");
+        assert_eq!(
+            config.health_check,
+            HealthCheck {
+                method: HealthMethod::Head,
+                expect_status: vec![200, 204],
+                expect_body: None,
+            }
+        );
+
+        // HEAD responses have no body to match
+        std::env::set_var("HEALTH_EXPECT_BODY", "ok");
+        assert!(Config::from_env_no_dotenv()
+            .unwrap_err()
+            .to_string()
+            .contains("HEALTH_EXPECT_BODY cannot be used"));
+
+        std::env::set_var("OPENCODE_HEALTH_METHOD", "GET");
+        let config = Config::from_env_no_dotenv().expect("Config should parse HEALTH_EXPECT_BODY");
+        assert_eq!(config.health_check.expect_body.as_deref(), Some("ok"));
+
+        std::env::set_var("HEALTH_EXPECT_STATUS", "2xx");
+        assert!(Config::from_env_no_dotenv()
+            .unwrap_err()
+            .to_string()
+            .contains("HEALTH_EXPECT_STATUS must be"));
+
+        std::env::remove_var("HEALTH_EXPECT_STATUS");
+        std::env::set_var("OPENCODE_HEALTH_METHOD", "POST");
+        assert!(Config::from_env_no_dotenv()
+            .unwrap_err()
+            .to_string()
+            .contains("OPENCODE_HEALTH_METHOD must be one of"));
+        clean_config_env();
+    }
+
+    #[test]
+    #[serial]
+    fn test_stream_mode_parsing() {
+        clean_config_env();
+        std::env::set_var("TELEGRAM_BOT_TOKEN", "test-token");
+        std::env::set_var("TELEGRAM_CHAT_IDS", "-1001234567890");
+        std::env::set_var("PROJECT_BASE_PATH", "~/oc-bot");
+        std::env::set_var("STREAM_MODE", "final");
+
+        let config = Config::from_env_no_dotenv().expect("Config should parse STREAM_MODE");
+        assert_eq!(config.stream_mode, StreamMode::Final);
+
+        std::env::set_var("STREAM_MODE", "live");
+        assert!(Config::from_env_no_dotenv()
+            .unwrap_err()
+            .to_string()
+            .contains("STREAM_MODE must be one of"));
+        clean_config_env();
+    }
+
+    #[test]
+    #[serial]
+    fn test_audio_mode_parsing() {
+        clean_config_env();
+        std::env::set_var("TELEGRAM_BOT_TOKEN", "test-token");
+        std::env::set_var("TELEGRAM_CHAT_IDS", "-1001234567890");
+        std::env::set_var("PROJECT_BASE_PATH", "~/oc-bot");
+        std::env::set_var("AUDIO_MODE", "passthrough");
+
+        let config = Config::from_env_no_dotenv().expect("Config should parse AUDIO_MODE");
+        assert_eq!(config.audio_mode, AudioMode::Passthrough);
+
+        std::env::set_var("AUDIO_MODE", "loud");
+        let result = Config::from_env_no_dotenv();
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("AUDIO_MODE must be one of"));
+        clean_config_env();
+    }
+
+    #[test]
+    #[serial]
+    fn test_photo_size_settings_parsing() {
+        clean_config_env();
+        std::env::set_var("TELEGRAM_BOT_TOKEN", "test-token");
+        std::env::set_var("TELEGRAM_CHAT_IDS", "-1001234567890");
+        std::env::set_var("PROJECT_BASE_PATH", "~/oc-bot");
+        std::env::set_var("PREFERRED_PHOTO_SIZE", "bounded");
+        std::env::set_var("MAX_IMAGE_DIMENSION", "800");
+        std::env::set_var("MAX_IMAGE_BYTES", "200000");
+
+        let config = Config::from_env_no_dotenv().expect("Config should parse photo settings");
+        assert_eq!(config.preferred_photo_size, PhotoSizePreference::Bounded);
+        assert_eq!(config.max_image_dimension, 800);
+        assert_eq!(config.max_image_bytes, 200_000);
+
+        std::env::set_var("PREFERRED_PHOTO_SIZE", "huge");
+        let result = Config::from_env_no_dotenv();
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("PREFERRED_PHOTO_SIZE must be one of"));
+        clean_config_env();
+    }
+
+    #[test]
+    #[serial]
+    fn test_topic_name_strategy_parsing() {
+        clean_config_env();
+        std::env::set_var("TELEGRAM_BOT_TOKEN", "test-token");
+        std::env::set_var("TELEGRAM_CHAT_IDS", "-1001234567890");
+        std::env::set_var("PROJECT_BASE_PATH", "~/oc-bot");
+        std::env::set_var("TOPIC_NAME_STRATEGY", "last_two");
+
+        let config = Config::from_env_no_dotenv().expect("Config should parse strategy");
+        assert_eq!(config.topic_name_strategy, TopicNameStrategy::LastTwo);
+
+        std::env::set_var("TOPIC_NAME_STRATEGY", "full_relative");
+        let config = Config::from_env_no_dotenv().expect("Config should parse strategy");
+        assert_eq!(config.topic_name_strategy, TopicNameStrategy::FullRelative);
+
+        std::env::set_var("TOPIC_NAME_STRATEGY", "bogus");
+        let result = Config::from_env_no_dotenv();
+        assert!(result.is_err());
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("TOPIC_NAME_STRATEGY must be one of"));
+        clean_config_env();
+    }
+
+    #[test]
+    #[serial]
+    fn test_reload_updates_hot_fields_only() {
+        clean_config_env();
+        std::env::set_var("TELEGRAM_BOT_TOKEN", "test-token");
+        std::env::set_var("TELEGRAM_CHAT_IDS", "-1001234567890");
+        std::env::set_var("PROJECT_BASE_PATH", "~/oc-bot");
+        let shared = SharedConfig::new(Config::from_env_no_dotenv().unwrap());
+        let before = shared.get();
+
+        std::env::set_var("OPENCODE_IDLE_TIMEOUT_MS", "5000");
+        std::env::set_var("OPENCODE_PORT_START", "5000");
+        let fresh = Config::from_env_no_dotenv().unwrap();
+        let changed = shared.reload(&fresh);
+
+        assert_eq!(changed, vec!["OPENCODE_IDLE_TIMEOUT_MS"]);
+        let after = shared.get();
+        assert_eq!(after.opencode_idle_timeout, Duration::from_millis(5000));
+        // Structural settings keep their startup value
+        assert_eq!(after.opencode_port_start, before.opencode_port_start);
+        // Earlier snapshots are unaffected
+        assert_ne!(before.opencode_idle_timeout, after.opencode_idle_timeout);
+
+        assert!(shared.reload(&fresh).is_empty());
+        clean_config_env();
+    }
+
+    #[test]
+    #[serial]
+    fn test_merge_reloadable_covers_all_hot_settings() {
+        clean_config_env();
+        std::env::set_var("TELEGRAM_BOT_TOKEN", "test-token");
+        std::env::set_var("TELEGRAM_CHAT_IDS", "-1001234567890");
+        std::env::set_var("PROJECT_BASE_PATH", "~/oc-bot");
+        let current = Config::from_env_no_dotenv().unwrap();
+
+        std::env::set_var("OPENCODE_IDLE_TIMEOUT_MS", "5000");
+        std::env::set_var("OPENCODE_HEALTH_CHECK_INTERVAL_MS", "5000");
+        std::env::set_var("OPENCODE_STARTUP_TIMEOUT_MS", "5000");
+        std::env::set_var("PERMISSION_TIMEOUT_MS", "5000");
+        std::env::set_var("OPENCODE_REQUEST_TIMEOUT_MS", "5000");
+        std::env::set_var("RESURRECTION_TIMEOUT_MS", "90000");
+        std::env::set_var("RESURRECTION_WAKE_DELAY_MS", "1000");
+        std::env::set_var("DUPLICATE_RESPONSE_WINDOW_MS", "5000");
+        std::env::set_var("SHOW_USAGE", "true");
+        std::env::set_var("SHOW_FILE_EDITS", "true");
+        std::env::set_var("REWRITE_WORKSPACE_PATHS", "true");
+        std::env::set_var("COLLECT_FEEDBACK", "true");
+        std::env::set_var("RESPONSE_FILTERS", "<|end|>");
+        std::env::set_var("REPLY_TO_PROMPTS", "true");
+        std::env::set_var("MAX_RESPONSE_CHARS", "500");
+        std::env::set_var("COLLAPSE_REPEATED_TOOLS", "true");
+        std::env::set_var("STREAM_MODE", "final");
+        std::env::set_var("MAX_TOPICS_PER_CHAT", "3");
+        std::env::set_var("ALLOWED_UPLOAD_MIME", "text/plain");
+        std::env::set_var("DEFAULT_IMAGE_PROMPT", "What is in these images?");
+        std::env::set_var("FALLBACK_MODEL", "anthropic/claude-haiku");
+        std::env::set_var("IMAGE_CACHE_RETENTION_SECS", "60");
+        std::env::set_var("MAX_RESUME_AGE_DAYS", "30");
+        let fresh = Config::from_env_no_dotenv().unwrap();
+
+        let (_, changed) = current.merge_reloadable(&fresh);
+        assert_eq!(changed, HOT_RELOADABLE_SETTINGS);
+        clean_config_env();
+    }
+
+    #[test]
+    #[serial]
+    fn test_restart_required_changes_are_reported_not_merged() {
+        clean_config_env();
+        std::env::set_var("TELEGRAM_BOT_TOKEN", "test-token");
+        std::env::set_var("TELEGRAM_CHAT_IDS", "-1001234567890");
+        std::env::set_var("PROJECT_BASE_PATH", "~/oc-bot");
+        let current = Config::from_env_no_dotenv().unwrap();
+        assert!(current.restart_required_changes(&current).is_empty());
+
+        std::env::set_var("TELEGRAM_BOT_TOKEN", "rotated-token");
+        std::env::set_var("OPENCODE_PORT_START", "5000");
+        std::env::set_var("OPENCODE_PORT_POOL_SIZE", "10");
+        std::env::set_var("OPENCODE_CONTAINER_PORT", "9090");
+        std::env::set_var("OPENCODE_SOCKET_PATH", "/run/opencode.sock");
+        std::env::set_var("ORCHESTRATOR_DB_PATH", "./other/orchestrator.db");
+        std::env::set_var("TOPIC_DB_PATH", "./other/topics.db");
+        std::env::set_var("LOG_DB_PATH", "./other/logs.db");
+        std::env::set_var("PROJECT_BASE_PATH", "~/elsewhere");
+        std::env::set_var("OPENCODE_DATA_PATH", "~/other-data");
+        std::env::set_var("API_MAX_BODY_BYTES", "4096");
+        std::env::set_var("API_PORT", "4300");
+        std::env::set_var("API_HOST", "0.0.0.0");
+        std::env::set_var("API_KEY", "rotated-key");
+        std::env::set_var("SHOW_USAGE", "true");
+        let fresh = Config::from_env_no_dotenv().unwrap();
+
+        assert_eq!(
+            current.restart_required_changes(&fresh),
+            RESTART_REQUIRED_SETTINGS
+        );
+        let (merged, changed) = current.merge_reloadable(&fresh);
+        assert_eq!(changed, vec!["SHOW_USAGE"]);
+        assert_eq!(merged.telegram_bot_token, "test-token");
+        assert_eq!(merged.opencode_port_start, current.opencode_port_start);
+        assert_eq!(merged.topic_db_path, current.topic_db_path);
+        assert_eq!(merged.project_base_path, current.project_base_path);
+        clean_config_env();
+    }
 }
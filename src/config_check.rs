@@ -0,0 +1,327 @@
+//! `--check-config`: validate the configuration without starting the bot.
+//!
+//! Parsing in [`Config::from_env`] catches malformed values; the checks here
+//! cover what only shows up later at runtime: missing directories, a port pool
+//! that runs past 65535, and an unreachable container runtime.
+
+use crate::config::Config;
+use crate::orchestrator::container::ContainerRuntime;
+use std::fmt;
+use std::path::Path;
+
+/// Outcome of a single check.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckItem {
+    pub name: &'static str,
+    pub ok: bool,
+    pub detail: String,
+}
+
+impl CheckItem {
+    fn pass(name: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            ok: true,
+            detail: detail.into(),
+        }
+    }
+
+    fn fail(name: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            ok: false,
+            detail: detail.into(),
+        }
+    }
+}
+
+/// All check results, printed one per line.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConfigReport {
+    pub items: Vec<CheckItem>,
+}
+
+impl ConfigReport {
+    /// Whether every check passed.
+    pub fn ok(&self) -> bool {
+        self.items.iter().all(|item| item.ok)
+    }
+
+    /// Failed checks only.
+    pub fn failures(&self) -> impl Iterator<Item = &CheckItem> {
+        self.items.iter().filter(|item| !item.ok)
+    }
+}
+
+impl fmt::Display for ConfigReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for item in &self.items {
+            let mark = if item.ok { "ok  " } else { "FAIL" };
+            writeln!(f, "[{}] {}: {}", mark, item.name, item.detail)?;
+        }
+        let failed = self.failures().count();
+        if failed == 0 {
+            write!(f, "Configuration OK ({} checks)", self.items.len())
+        } else {
+            write!(f, "{} of {} checks failed", failed, self.items.len())
+        }
+    }
+}
+
+/// Check that `path` is an existing directory.
+fn check_dir_exists(name: &'static str, path: &Path) -> CheckItem {
+    if path.is_dir() {
+        CheckItem::pass(name, path.display().to_string())
+    } else if path.exists() {
+        CheckItem::fail(name, format!("{} is not a directory", path.display()))
+    } else {
+        CheckItem::fail(name, format!("{} does not exist", path.display()))
+    }
+}
+
+/// Check that directory `path` exists or can be created: its nearest
+/// existing ancestor must be a writable directory.
+fn check_dir_creatable(name: &'static str, path: &Path) -> CheckItem {
+    if path.is_dir() {
+        return CheckItem::pass(name, path.display().to_string());
+    }
+    if path.exists() {
+        return CheckItem::fail(name, format!("{} is not a directory", path.display()));
+    }
+    let Some(ancestor) = path.ancestors().skip(1).find(|a| a.exists()) else {
+        return CheckItem::fail(name, format!("{} has no existing parent", path.display()));
+    };
+    let writable = std::fs::metadata(ancestor)
+        .map(|m| m.is_dir() && !m.permissions().readonly())
+        .unwrap_or(false);
+    if writable {
+        CheckItem::pass(name, format!("{} (will be created)", path.display()))
+    } else {
+        CheckItem::fail(
+            name,
+            format!(
+                "{} cannot be created: {} is not a writable directory",
+                path.display(),
+                ancestor.display()
+            ),
+        )
+    }
+}
+
+/// Check that the parent directory of file `path` exists or can be created.
+fn check_file_parent(name: &'static str, path: &Path) -> CheckItem {
+    match path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        Some(parent) => {
+            let mut item = check_dir_creatable(name, parent);
+            if item.ok {
+                item.detail = path.display().to_string();
+            }
+            item
+        }
+        None => CheckItem::pass(name, path.display().to_string()),
+    }
+}
+
+/// Filesystem checks for the configured paths.
+pub fn check_paths(config: &Config) -> Vec<CheckItem> {
+    let project_base = if config.auto_create_project_dirs {
+        check_dir_creatable("PROJECT_BASE_PATH", &config.project_base_path)
+    } else {
+        check_dir_exists("PROJECT_BASE_PATH", &config.project_base_path)
+    };
+    let mut items = vec![
+        project_base,
+        check_file_parent("ORCHESTRATOR_DB_PATH", &config.orchestrator_db_path),
+        check_file_parent("TOPIC_DB_PATH", &config.topic_db_path),
+        check_file_parent("LOG_DB_PATH", &config.log_db_path),
+        check_dir_creatable("OPENCODE_DATA_PATH", &config.opencode_data_path),
+        check_dir_exists("OPENCODE_CONFIG_PATH", &config.opencode_config_path),
+    ];
+    if let Some(socket) = &config.opencode_socket_path {
+        items.push(check_file_parent("OPENCODE_SOCKET_PATH", socket));
+    }
+    items
+}
+
+/// Port range checks.
+pub fn check_ports(config: &Config) -> Vec<CheckItem> {
+    let start = config.opencode_port_start;
+    let size = config.opencode_port_pool_size;
+    let pool = if start == 0 {
+        CheckItem::fail("OPENCODE_PORT_START", "port 0 is not a usable port")
+    } else if size == 0 {
+        CheckItem::fail("OPENCODE_PORT_POOL_SIZE", "port pool is empty")
+    } else {
+        let end = u32::from(start) + u32::from(size) - 1;
+        if end > u32::from(u16::MAX) {
+            CheckItem::fail(
+                "OPENCODE_PORT_POOL_SIZE",
+                format!("pool {}..={} runs past port {}", start, end, u16::MAX),
+            )
+        } else {
+            CheckItem::pass("OPENCODE_PORT_POOL_SIZE", format!("{}..={}", start, end))
+        }
+    };
+
+    let instances = if config.opencode_max_instances > usize::from(size) {
+        CheckItem::fail(
+            "OPENCODE_MAX_INSTANCES",
+            format!(
+                "{} instances need more ports than the pool's {}",
+                config.opencode_max_instances, size
+            ),
+        )
+    } else {
+        CheckItem::pass(
+            "OPENCODE_MAX_INSTANCES",
+            config.opencode_max_instances.to_string(),
+        )
+    };
+
+    let container_port = if config.container_port == 0 {
+        CheckItem::fail("OPENCODE_CONTAINER_PORT", "port 0 is not a usable port")
+    } else {
+        CheckItem::pass("OPENCODE_CONTAINER_PORT", config.container_port.to_string())
+    };
+
+    vec![pool, instances, container_port]
+}
+
+/// Check that the container runtime answers. `runtime` holds the connection
+/// error when no runtime client could be created.
+pub async fn check_runtime(
+    runtime: std::result::Result<&dyn ContainerRuntime, String>,
+) -> CheckItem {
+    let result = match runtime {
+        Ok(runtime) => runtime
+            .list_containers_by_prefix("oc-")
+            .await
+            .map_err(|e| format!("{:#}", e)),
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(containers) => CheckItem::pass(
+            "Docker",
+            format!("reachable, {} oc- containers", containers.len()),
+        ),
+        Err(e) => CheckItem::fail("Docker", e),
+    }
+}
+
+/// Run every check against `config`.
+pub async fn validate(
+    config: &Config,
+    runtime: std::result::Result<&dyn ContainerRuntime, String>,
+) -> ConfigReport {
+    let mut items = check_paths(config);
+    items.extend(check_ports(config));
+    items.push(check_runtime(runtime).await);
+    ConfigReport { items }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::orchestrator::container::mock::MockRuntime;
+    use crate::test_utils::test_config;
+    use tempfile::TempDir;
+
+    fn create_test_config(temp_dir: &TempDir) -> Config {
+        let config_dir = temp_dir.path().join("oc-config");
+        std::fs::create_dir(&config_dir).unwrap();
+        let mut config = test_config(temp_dir.path());
+        config.opencode_port_pool_size = 100;
+        config.opencode_data_path = temp_dir.path().join("data");
+        config.orchestrator_db_path = temp_dir.path().join("db/orchestrator.db");
+        config.topic_db_path = temp_dir.path().join("db/topics.db");
+        config.log_db_path = temp_dir.path().join("db/logs.db");
+        config.project_base_path = temp_dir.path().join("projects");
+        config.opencode_config_path = config_dir;
+        config
+    }
+
+    fn failed_names(report: &ConfigReport) -> Vec<&'static str> {
+        report.failures().map(|item| item.name).collect()
+    }
+
+    #[tokio::test]
+    async fn test_good_config_passes() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = create_test_config(&temp_dir);
+        let runtime = MockRuntime::new();
+
+        let report = validate(&config, Ok(&runtime)).await;
+
+        assert!(report.ok(), "unexpected failures:\n{}", report);
+        assert!(report.to_string().ends_with("Configuration OK (10 checks)"));
+    }
+
+    #[tokio::test]
+    async fn test_missing_project_base_without_auto_create_fails() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = create_test_config(&temp_dir);
+        config.auto_create_project_dirs = false;
+
+        let report = validate(&config, Ok(&MockRuntime::new())).await;
+
+        assert_eq!(failed_names(&report), vec!["PROJECT_BASE_PATH"]);
+        assert!(report.to_string().contains("does not exist"));
+    }
+
+    #[tokio::test]
+    async fn test_missing_opencode_config_dir_fails() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = create_test_config(&temp_dir);
+        config.opencode_config_path = temp_dir.path().join("nope");
+
+        let report = validate(&config, Ok(&MockRuntime::new())).await;
+
+        assert_eq!(failed_names(&report), vec!["OPENCODE_CONFIG_PATH"]);
+    }
+
+    #[test]
+    fn test_port_pool_overflow_fails() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = create_test_config(&temp_dir);
+        config.opencode_port_start = 65530;
+        config.opencode_port_pool_size = 10;
+
+        let items = check_ports(&config);
+        let pool = items
+            .iter()
+            .find(|i| i.name == "OPENCODE_PORT_POOL_SIZE")
+            .unwrap();
+        assert!(!pool.ok);
+        assert!(pool.detail.contains("65530..=65539"));
+
+        // The pool is also too small for the instance limit
+        let instances = items
+            .iter()
+            .find(|i| i.name == "OPENCODE_MAX_INSTANCES")
+            .unwrap();
+        config.opencode_max_instances = 11;
+        assert!(instances.ok);
+        assert!(
+            !check_ports(&config)
+                .iter()
+                .find(|i| i.name == "OPENCODE_MAX_INSTANCES")
+                .unwrap()
+                .ok
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_docker_fails() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = create_test_config(&temp_dir);
+        let runtime = MockRuntime::new().with_list_result(Err("connection refused".to_string()));
+
+        let report = validate(&config, Ok(&runtime)).await;
+        assert_eq!(failed_names(&report), vec!["Docker"]);
+        assert!(report.to_string().contains("connection refused"));
+
+        let report = validate(&config, Err("Failed to connect to Docker".to_string())).await;
+        assert_eq!(failed_names(&report), vec!["Docker"]);
+        assert!(report.to_string().ends_with("1 of 10 checks failed"));
+    }
+}
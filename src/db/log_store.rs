@@ -1,11 +1,143 @@
 use anyhow::Result;
 use sqlx::sqlite::SqlitePool;
 use std::path::Path;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tracing::debug;
 
 use super::init_log_db;
 
+/// One tracing event queued for [`LogStore::insert_logs`].
+#[derive(Debug, Clone)]
+pub struct LogRecord {
+    pub timestamp: i64,
+    pub sequence: i64,
+    pub level: &'static str,
+    pub target: String,
+    pub message: String,
+    pub fields: Option<String>,
+}
+
+/// Instance lifecycle event recorded for `/stats`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InstanceEvent {
+    /// A container was started for the instance (including restarts).
+    Spawned,
+    /// The instance was stopped on request or after its idle timeout.
+    Stopped,
+    /// The health check found the instance's container dead.
+    Crashed,
+    /// The instance was replaced after a crash or a failed state.
+    Restarted,
+}
+
+impl InstanceEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Spawned => "spawned",
+            Self::Stopped => "stopped",
+            Self::Crashed => "crashed",
+            Self::Restarted => "restarted",
+        }
+    }
+}
+
+/// Rating given with the feedback buttons under a response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeedbackRating {
+    Up,
+    Down,
+}
+
+impl FeedbackRating {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Up => "up",
+            Self::Down => "down",
+        }
+    }
+}
+
+impl std::str::FromStr for FeedbackRating {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "up" => Ok(Self::Up),
+            "down" => Ok(Self::Down),
+            _ => Err(anyhow::anyhow!("Unknown feedback rating: {}", s)),
+        }
+    }
+}
+
+/// Kind of entry in a topic's stream event log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamEventKind {
+    /// A prompt sent from Telegram.
+    Prompt,
+    /// A chunk of the agent's response text.
+    Text,
+    /// A tool call, as the tool name and its arguments.
+    Tool,
+    /// Output of a tool call.
+    ToolResult,
+    /// A session error reported by OpenCode.
+    Error,
+}
+
+impl StreamEventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Prompt => "prompt",
+            Self::Text => "text",
+            Self::Tool => "tool",
+            Self::ToolResult => "tool_result",
+            Self::Error => "error",
+        }
+    }
+}
+
+impl std::str::FromStr for StreamEventKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "prompt" => Ok(Self::Prompt),
+            "text" => Ok(Self::Text),
+            "tool" => Ok(Self::Tool),
+            "tool_result" => Ok(Self::ToolResult),
+            "error" => Ok(Self::Error),
+            _ => Err(anyhow::anyhow!("Unknown stream event kind: {}", s)),
+        }
+    }
+}
+
+/// One entry of a topic's stream event log.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoggedStreamEvent {
+    pub session_id: String,
+    pub kind: StreamEventKind,
+    pub content: String,
+    /// Unix millis.
+    pub timestamp: i64,
+}
+
+/// Aggregate usage over a time window.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct UsageStats {
+    pub runs: u64,
+    pub spawned: u64,
+    pub stopped: u64,
+    pub crashed: u64,
+    pub restarted: u64,
+    /// Mean time from spawn to stop or crash, over instances that ended in
+    /// the window. `None` if none did.
+    pub avg_instance_lifetime: Option<Duration>,
+    /// Responses rated 👍 in the window.
+    pub feedback_up: u64,
+    /// Responses rated 👎 in the window.
+    pub feedback_down: u64,
+}
+
 #[derive(Clone)]
 pub struct LogStore {
     pool: SqlitePool,
@@ -85,6 +217,251 @@ impl LogStore {
         Ok(())
     }
 
+    // NOTE: Do NOT add tracing/logging calls to this method either; it is
+    // the batched write path of DatabaseLayer.
+    /// Insert a batch of log records for `run_id` in one transaction.
+    pub async fn insert_logs(&self, run_id: &str, records: &[LogRecord]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        for record in records {
+            sqlx::query(
+                "INSERT INTO run_logs (run_id, timestamp, sequence, level, target, message, fields)
+                 VALUES (?, ?, ?, ?, ?, ?, ?)",
+            )
+            .bind(run_id)
+            .bind(record.timestamp)
+            .bind(record.sequence)
+            .bind(record.level)
+            .bind(&record.target)
+            .bind(&record.message)
+            .bind(&record.fields)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    pub async fn record_instance_event(
+        &self,
+        instance_id: &str,
+        event: InstanceEvent,
+    ) -> Result<()> {
+        debug!(instance_id = %instance_id, event = event.as_str(), "Recording instance event");
+        self.insert_instance_event(instance_id, event, now_millis())
+            .await
+    }
+
+    async fn insert_instance_event(
+        &self,
+        instance_id: &str,
+        event: InstanceEvent,
+        timestamp: i64,
+    ) -> Result<()> {
+        sqlx::query("INSERT INTO instance_events (instance_id, event, timestamp) VALUES (?, ?, ?)")
+            .bind(instance_id)
+            .bind(event.as_str())
+            .bind(timestamp)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Remember that feedback buttons for OpenCode `message_id` were posted
+    /// as Telegram message `prompt_message_id`.
+    pub async fn record_feedback_prompt(
+        &self,
+        chat_id: i64,
+        prompt_message_id: i32,
+        session_id: &str,
+        message_id: &str,
+    ) -> Result<()> {
+        debug!(
+            chat_id = chat_id,
+            prompt_message_id = prompt_message_id,
+            session_id = %session_id,
+            message_id = %message_id,
+            "Recording feedback prompt"
+        );
+        sqlx::query(
+            "INSERT OR REPLACE INTO feedback
+                (chat_id, prompt_message_id, session_id, message_id, created_at)
+             VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(chat_id)
+        .bind(prompt_message_id)
+        .bind(session_id)
+        .bind(message_id)
+        .bind(now_millis())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Whether feedback buttons were already posted for OpenCode `message_id`.
+    /// `message.updated` can fire several times for one message.
+    pub async fn has_feedback_prompt(&self, message_id: &str) -> Result<bool> {
+        let exists: bool =
+            sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM feedback WHERE message_id = ?)")
+                .bind(message_id)
+                .fetch_one(&self.pool)
+                .await?;
+        Ok(exists)
+    }
+
+    /// Store `user_id`'s rating for the response behind a feedback prompt,
+    /// replacing any earlier rating. Returns `false` if the prompt is unknown.
+    pub async fn rate_feedback(
+        &self,
+        chat_id: i64,
+        prompt_message_id: i32,
+        user_id: i64,
+        rating: FeedbackRating,
+    ) -> Result<bool> {
+        debug!(
+            chat_id = chat_id,
+            prompt_message_id = prompt_message_id,
+            user_id = user_id,
+            rating = rating.as_str(),
+            "Recording feedback rating"
+        );
+        let result = sqlx::query(
+            "UPDATE feedback SET rating = ?, user_id = ?, rated_at = ?
+             WHERE chat_id = ? AND prompt_message_id = ?",
+        )
+        .bind(rating.as_str())
+        .bind(user_id)
+        .bind(now_millis())
+        .bind(chat_id)
+        .bind(prompt_message_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Append an entry to the stream event log of a topic.
+    pub async fn record_stream_event(
+        &self,
+        chat_id: i64,
+        topic_id: i32,
+        session_id: &str,
+        kind: StreamEventKind,
+        content: &str,
+    ) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO stream_events (chat_id, topic_id, session_id, kind, content, timestamp)
+             VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(chat_id)
+        .bind(topic_id)
+        .bind(session_id)
+        .bind(kind.as_str())
+        .bind(content)
+        .bind(now_millis())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// The stream event log of a topic, oldest first. Entries of unknown
+    /// kinds are skipped.
+    pub async fn stream_events(
+        &self,
+        chat_id: i64,
+        topic_id: i32,
+    ) -> Result<Vec<LoggedStreamEvent>> {
+        let rows: Vec<(String, String, String, i64)> = sqlx::query_as(
+            "SELECT session_id, kind, content, timestamp FROM stream_events
+             WHERE chat_id = ? AND topic_id = ? ORDER BY id",
+        )
+        .bind(chat_id)
+        .bind(topic_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let events = rows
+            .into_iter()
+            .filter_map(|(session_id, kind, content, timestamp)| {
+                Some(LoggedStreamEvent {
+                    session_id,
+                    kind: kind.parse().ok()?,
+                    content,
+                    timestamp,
+                })
+            })
+            .collect::<Vec<_>>();
+        debug!(
+            chat_id = chat_id,
+            topic_id = topic_id,
+            count = events.len(),
+            "Stream events loaded"
+        );
+        Ok(events)
+    }
+
+    /// Aggregate runs, instance events and feedback since `since` (Unix millis).
+    pub async fn usage_stats(&self, since: i64) -> Result<UsageStats> {
+        let runs: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM bot_runs WHERE started_at >= ?")
+            .bind(since)
+            .fetch_one(&self.pool)
+            .await?;
+
+        let (spawned, stopped, crashed, restarted): (i64, i64, i64, i64) = sqlx::query_as(
+            "SELECT
+                COUNT(*) FILTER (WHERE event = 'spawned'),
+                COUNT(*) FILTER (WHERE event = 'stopped'),
+                COUNT(*) FILTER (WHERE event = 'crashed'),
+                COUNT(*) FILTER (WHERE event = 'restarted')
+             FROM instance_events WHERE timestamp >= ?",
+        )
+        .bind(since)
+        .fetch_one(&self.pool)
+        .await?;
+
+        // Pair each end event with the latest spawn of the same instance
+        let avg_lifetime_ms: Option<f64> = sqlx::query_scalar(
+            "SELECT AVG(e.timestamp - (
+                SELECT MAX(s.timestamp) FROM instance_events s
+                WHERE s.instance_id = e.instance_id
+                  AND s.event = 'spawned'
+                  AND s.timestamp <= e.timestamp
+             ))
+             FROM instance_events e
+             WHERE e.event IN ('stopped', 'crashed') AND e.timestamp >= ?",
+        )
+        .bind(since)
+        .fetch_one(&self.pool)
+        .await?;
+
+        let (feedback_up, feedback_down): (i64, i64) = sqlx::query_as(
+            "SELECT
+                COUNT(*) FILTER (WHERE rating = 'up'),
+                COUNT(*) FILTER (WHERE rating = 'down')
+             FROM feedback WHERE rated_at >= ?",
+        )
+        .bind(since)
+        .fetch_one(&self.pool)
+        .await?;
+
+        let stats = UsageStats {
+            runs: runs as u64,
+            spawned: spawned as u64,
+            stopped: stopped as u64,
+            crashed: crashed as u64,
+            restarted: restarted as u64,
+            avg_instance_lifetime: avg_lifetime_ms
+                .map(|ms| Duration::from_millis(ms.max(0.0) as u64)),
+            feedback_up: feedback_up as u64,
+            feedback_down: feedback_down as u64,
+        };
+        debug!(since = since, stats = ?stats, "Usage stats aggregated");
+        Ok(stats)
+    }
+
     #[cfg(test)]
     pub fn pool(&self) -> &SqlitePool {
         &self.pool
@@ -184,4 +561,187 @@ mod tests {
         .unwrap();
         assert_eq!(level, "ERROR");
     }
+
+    #[tokio::test]
+    async fn test_usage_stats_aggregates_events_in_window() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = LogStore::new(&temp_dir.path().join("logs.db"))
+            .await
+            .unwrap();
+        let hour = 3_600_000;
+
+        // Outside the window: an old run and an instance that lived 10h
+        sqlx::query(
+            "INSERT INTO bot_runs (run_id, started_at, version) VALUES ('old', 0, '0.1.0')",
+        )
+        .execute(store.pool())
+        .await
+        .unwrap();
+        store
+            .insert_instance_event("inst-old", InstanceEvent::Spawned, 0)
+            .await
+            .unwrap();
+        store
+            .insert_instance_event("inst-old", InstanceEvent::Stopped, 10 * hour)
+            .await
+            .unwrap();
+
+        let since = 100 * hour;
+        sqlx::query(
+            "INSERT INTO bot_runs (run_id, started_at, version) VALUES ('new', ?, '0.1.0')",
+        )
+        .bind(since + 1)
+        .execute(store.pool())
+        .await
+        .unwrap();
+        // inst-a: spawned, crashed after 1h, restarted as inst-b
+        // inst-b: stopped after 3h; inst-c still running
+        for (id, event, at) in [
+            ("inst-a", InstanceEvent::Spawned, since),
+            ("inst-a", InstanceEvent::Crashed, since + hour),
+            ("inst-b", InstanceEvent::Spawned, since + hour),
+            ("inst-b", InstanceEvent::Restarted, since + hour),
+            ("inst-b", InstanceEvent::Stopped, since + 4 * hour),
+            ("inst-c", InstanceEvent::Spawned, since + 5 * hour),
+        ] {
+            store.insert_instance_event(id, event, at).await.unwrap();
+        }
+
+        let stats = store.usage_stats(since).await.unwrap();
+        assert_eq!(
+            stats,
+            UsageStats {
+                runs: 1,
+                spawned: 3,
+                stopped: 1,
+                crashed: 1,
+                restarted: 1,
+                avg_instance_lifetime: Some(Duration::from_millis(2 * hour as u64)),
+                feedback_up: 0,
+                feedback_down: 0,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_usage_stats_empty_window() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = LogStore::new(&temp_dir.path().join("logs.db"))
+            .await
+            .unwrap();
+        store
+            .record_instance_event("inst-1", InstanceEvent::Spawned)
+            .await
+            .unwrap();
+
+        let stats = store.usage_stats(i64::MAX).await.unwrap();
+        assert_eq!(stats, UsageStats::default());
+
+        let stats = store.usage_stats(0).await.unwrap();
+        assert_eq!(stats.spawned, 1);
+        assert_eq!(stats.avg_instance_lifetime, None);
+    }
+
+    #[tokio::test]
+    async fn test_feedback_rating_persists_and_aggregates() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = LogStore::new(&temp_dir.path().join("logs.db"))
+            .await
+            .unwrap();
+        store
+            .record_feedback_prompt(-100, 10, "ses_1", "msg_1")
+            .await
+            .unwrap();
+        store
+            .record_feedback_prompt(-100, 11, "ses_1", "msg_2")
+            .await
+            .unwrap();
+        store
+            .record_feedback_prompt(-100, 12, "ses_2", "msg_3")
+            .await
+            .unwrap();
+        assert!(store.has_feedback_prompt("msg_2").await.unwrap());
+        assert!(!store.has_feedback_prompt("msg_4").await.unwrap());
+
+        assert!(store
+            .rate_feedback(-100, 10, 42, FeedbackRating::Up)
+            .await
+            .unwrap());
+        assert!(store
+            .rate_feedback(-100, 11, 42, FeedbackRating::Up)
+            .await
+            .unwrap());
+        // Changing one's mind replaces the earlier rating
+        assert!(store
+            .rate_feedback(-100, 11, 42, FeedbackRating::Down)
+            .await
+            .unwrap());
+        // Unknown prompt
+        assert!(!store
+            .rate_feedback(-100, 99, 42, FeedbackRating::Up)
+            .await
+            .unwrap());
+
+        let (session_id, message_id, rating, user_id): (String, String, String, i64) =
+            sqlx::query_as(
+                "SELECT session_id, message_id, rating, user_id FROM feedback
+                 WHERE chat_id = -100 AND prompt_message_id = 11",
+            )
+            .fetch_one(store.pool())
+            .await
+            .unwrap();
+        assert_eq!(session_id, "ses_1");
+        assert_eq!(message_id, "msg_2");
+        assert_eq!(rating, "down");
+        assert_eq!(user_id, 42);
+
+        let stats = store.usage_stats(0).await.unwrap();
+        assert_eq!(stats.feedback_up, 1);
+        assert_eq!(stats.feedback_down, 1);
+    }
+
+    #[tokio::test]
+    async fn test_stream_events_are_kept_per_topic_in_order() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = LogStore::new(&temp_dir.path().join("logs.db"))
+            .await
+            .unwrap();
+
+        store
+            .record_stream_event(-100, 7, "ses_1", StreamEventKind::Prompt, "hi")
+            .await
+            .unwrap();
+        store
+            .record_stream_event(-100, 8, "ses_2", StreamEventKind::Prompt, "other")
+            .await
+            .unwrap();
+        store
+            .record_stream_event(-100, 7, "ses_1", StreamEventKind::Text, "hello")
+            .await
+            .unwrap();
+
+        let events = store.stream_events(-100, 7).await.unwrap();
+        let entries: Vec<_> = events
+            .iter()
+            .map(|e| (e.kind, e.content.as_str()))
+            .collect();
+        assert_eq!(
+            entries,
+            vec![
+                (StreamEventKind::Prompt, "hi"),
+                (StreamEventKind::Text, "hello")
+            ]
+        );
+        assert!(store.stream_events(-100, 9).await.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_feedback_rating_from_str() {
+        assert_eq!("up".parse::<FeedbackRating>().unwrap(), FeedbackRating::Up);
+        assert_eq!(
+            "down".parse::<FeedbackRating>().unwrap(),
+            FeedbackRating::Down
+        );
+        assert!("meh".parse::<FeedbackRating>().is_err());
+    }
 }
@@ -1,17 +1,338 @@
 pub mod log_store;
 pub mod tracing_layer;
 
-use anyhow::Result;
-use sqlx::sqlite::SqlitePool;
+use anyhow::{bail, Result};
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool};
+use std::future::Future;
 use std::path::Path;
+use std::time::Duration;
+use tracing::warn;
+
+/// How long a connection waits on a locked database before SQLite returns
+/// `SQLITE_BUSY`. Applied to every pooled connection.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Attempts made by [`retry_busy`], including the first.
+const BUSY_RETRY_ATTEMPTS: u32 = 4;
+
+/// Delay before the first busy retry (doubles each attempt: 50ms, 100ms, 200ms).
+const BUSY_RETRY_BASE_DELAY: Duration = Duration::from_millis(50);
+
+/// Whether `err` is a transient `SQLITE_BUSY` or `SQLITE_LOCKED` error,
+/// including their extended result codes.
+pub fn is_busy(err: &sqlx::Error) -> bool {
+    err.as_database_error()
+        .and_then(|e| e.code())
+        .and_then(|code| code.parse::<i32>().ok())
+        .is_some_and(|code| matches!(code & 0xff, 5 | 6))
+}
 
-/// Initialize the orchestrator database with instances table
-pub async fn init_orchestrator_db(db_path: &Path) -> Result<SqlitePool> {
+/// Run a write, retrying with backoff while the database is busy. Other
+/// errors, and a busy error on the last attempt, are returned as is.
+pub async fn retry_busy<T, F, Fut>(mut op: F) -> std::result::Result<T, sqlx::Error>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = std::result::Result<T, sqlx::Error>>,
+{
+    let mut attempt = 1;
+    loop {
+        match op().await {
+            Err(e) if attempt < BUSY_RETRY_ATTEMPTS && is_busy(&e) => {
+                let delay = BUSY_RETRY_BASE_DELAY * 2_u32.pow(attempt - 1);
+                warn!(attempt = attempt, delay_ms = delay.as_millis() as u64, error = %e, "Database busy, retrying write");
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Open (creating if needed) the SQLite database at `db_path`.
+async fn connect(db_path: &Path) -> Result<SqlitePool> {
     if let Some(parent) = db_path.parent() {
         std::fs::create_dir_all(parent)?;
     }
-    let url = format!("sqlite:{}?mode=rwc", db_path.display());
-    let pool = SqlitePool::connect(&url).await?;
+    let options = SqliteConnectOptions::new()
+        .filename(db_path)
+        .create_if_missing(true)
+        .busy_timeout(BUSY_TIMEOUT);
+    Ok(SqlitePool::connect_with(options).await?)
+}
+
+/// A column the code relies on. Columns added after a table was first created
+/// carry the migration that adds them, so a database that missed it can be
+/// repaired at startup.
+struct ExpectedColumn {
+    name: &'static str,
+    /// Migration file name and the statement that adds the column.
+    added_by: Option<(&'static str, &'static str)>,
+}
+
+const fn column(name: &'static str) -> ExpectedColumn {
+    ExpectedColumn {
+        name,
+        added_by: None,
+    }
+}
+
+const fn added_column(
+    name: &'static str,
+    migration: &'static str,
+    statement: &'static str,
+) -> ExpectedColumn {
+    ExpectedColumn {
+        name,
+        added_by: Some((migration, statement)),
+    }
+}
+
+/// Tables and columns each database must have once its migrations have run.
+type Schema = &'static [(&'static str, &'static [ExpectedColumn])];
+
+const ORCHESTRATOR_SCHEMA: Schema = &[
+    (
+        "instances",
+        &[
+            column("id"),
+            column("project_path"),
+            column("port"),
+            column("state"),
+            column("session_id"),
+            column("created_at"),
+            column("updated_at"),
+            added_column(
+                "container_id",
+                "004_add_container_id.sql",
+                "ALTER TABLE instances ADD COLUMN container_id TEXT",
+            ),
+            added_column(
+                "topic_id",
+                "007_add_topic_id_to_instances.sql",
+                "ALTER TABLE instances ADD COLUMN topic_id INTEGER NOT NULL DEFAULT 0",
+            ),
+            added_column(
+                "started_at",
+                "020_add_started_at_to_instances.sql",
+                "ALTER TABLE instances ADD COLUMN started_at INTEGER",
+            ),
+        ],
+    ),
+    (
+        "cordoned_projects",
+        &[column("project"), column("cordoned_at")],
+    ),
+];
+
+const LOG_SCHEMA: Schema = &[
+    (
+        "bot_runs",
+        &[
+            column("run_id"),
+            column("started_at"),
+            column("stopped_at"),
+            column("version"),
+            column("config_summary"),
+        ],
+    ),
+    (
+        "run_logs",
+        &[
+            column("id"),
+            column("run_id"),
+            column("timestamp"),
+            added_column(
+                "sequence",
+                "003_create_log_tables.sql",
+                "ALTER TABLE run_logs ADD COLUMN sequence INTEGER DEFAULT 0",
+            ),
+            column("level"),
+            column("target"),
+            column("message"),
+            column("fields"),
+        ],
+    ),
+    (
+        "instance_events",
+        &[
+            column("id"),
+            column("instance_id"),
+            column("event"),
+            column("timestamp"),
+        ],
+    ),
+    (
+        "feedback",
+        &[
+            column("chat_id"),
+            column("prompt_message_id"),
+            column("session_id"),
+            column("message_id"),
+            column("rating"),
+            column("user_id"),
+            column("created_at"),
+            column("rated_at"),
+        ],
+    ),
+    (
+        "stream_events",
+        &[
+            column("id"),
+            column("chat_id"),
+            column("topic_id"),
+            column("session_id"),
+            column("kind"),
+            column("content"),
+            column("timestamp"),
+        ],
+    ),
+];
+
+const TOPICS_SCHEMA: Schema = &[
+    (
+        "topic_mappings",
+        &[
+            column("chat_id"),
+            column("topic_id"),
+            column("project_path"),
+            column("session_id"),
+            column("instance_id"),
+            column("topic_name_updated"),
+            column("created_at"),
+            column("updated_at"),
+            added_column(
+                "archived",
+                "008_add_archived_to_topic_mappings.sql",
+                "ALTER TABLE topic_mappings ADD COLUMN archived INTEGER NOT NULL DEFAULT 0",
+            ),
+            added_column(
+                "read_only",
+                "009_add_read_only_to_topic_mappings.sql",
+                "ALTER TABLE topic_mappings ADD COLUMN read_only INTEGER NOT NULL DEFAULT 0",
+            ),
+            added_column(
+                "output_topic_id",
+                "010_add_output_topic_to_topic_mappings.sql",
+                "ALTER TABLE topic_mappings ADD COLUMN output_topic_id INTEGER",
+            ),
+            added_column(
+                "system_prompt_mtime",
+                "012_add_system_prompt_mtime_to_topic_mappings.sql",
+                "ALTER TABLE topic_mappings ADD COLUMN system_prompt_mtime INTEGER",
+            ),
+            added_column(
+                "locale",
+                "018_add_locale_to_topic_mappings.sql",
+                "ALTER TABLE topic_mappings ADD COLUMN locale TEXT",
+            ),
+            added_column(
+                "last_activity_at",
+                "019_add_last_activity_to_topic_mappings.sql",
+                "ALTER TABLE topic_mappings ADD COLUMN last_activity_at INTEGER",
+            ),
+        ],
+    ),
+    (
+        "pending_generations",
+        &[
+            column("chat_id"),
+            column("topic_id"),
+            column("session_id"),
+            column("last_message_id"),
+            column("saved_at"),
+            added_column(
+                "prompt",
+                "022_add_prompt_to_pending_generations.sql",
+                "ALTER TABLE pending_generations ADD COLUMN prompt TEXT",
+            ),
+        ],
+    ),
+    (
+        "outbound_queue",
+        &[
+            column("id"),
+            column("chat_id"),
+            column("topic_id"),
+            column("text"),
+            column("reply_to"),
+            column("queued_at"),
+        ],
+    ),
+    (
+        "outbound_buffer",
+        &[
+            column("chat_id"),
+            column("topic_id"),
+            column("text"),
+            column("reply_to"),
+            column("updated_at"),
+        ],
+    ),
+];
+
+/// Column names of `table` from `PRAGMA table_info`; empty if it doesn't exist.
+async fn table_columns(pool: &SqlitePool, table: &str) -> Result<Vec<String>> {
+    let rows: Vec<(String,)> = sqlx::query_as("SELECT name FROM pragma_table_info(?)")
+        .bind(table)
+        .fetch_all(pool)
+        .await?;
+    Ok(rows.into_iter().map(|(name,)| name).collect())
+}
+
+/// Check that every table in `schema` has the columns the code queries.
+///
+/// The migrations above ignore their own errors so they can be rerun, which
+/// means a database from an older binary can come out of them still missing a
+/// column and only fail later on a query. A missing column with a known
+/// migration is added here (reporting the real error if that fails); any
+/// other missing table or column stops startup with a message naming it.
+async fn verify_schema(pool: &SqlitePool, db_path: &Path, schema: Schema) -> Result<()> {
+    for (table, columns) in schema {
+        let existing = table_columns(pool, table).await?;
+        if existing.is_empty() {
+            bail!(
+                "Database {} is missing table `{}`",
+                db_path.display(),
+                table
+            );
+        }
+        for column in columns.iter() {
+            if existing.iter().any(|name| name == column.name) {
+                continue;
+            }
+            let Some((migration, statement)) = column.added_by else {
+                bail!(
+                    "Database {} has an incompatible schema: table `{}` is missing column `{}`. \
+                     It was likely created by a different version; move it aside to recreate it.",
+                    db_path.display(),
+                    table,
+                    column.name
+                );
+            };
+            warn!(
+                table = table,
+                column = column.name,
+                migration = migration,
+                "Database column missing, applying migration"
+            );
+            if let Err(e) = sqlx::query(statement).execute(pool).await {
+                bail!(
+                    "Database {} is missing column `{}.{}` and migrations/{} failed to add it: {}",
+                    db_path.display(),
+                    table,
+                    column.name,
+                    migration,
+                    e
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Initialize the orchestrator database with instances table
+pub async fn init_orchestrator_db(db_path: &Path) -> Result<SqlitePool> {
+    let pool = connect(db_path).await?;
 
     sqlx::query("PRAGMA journal_mode=WAL;")
         .execute(&pool)
@@ -39,15 +360,19 @@ pub async fn init_orchestrator_db(db_path: &Path) -> Result<SqlitePool> {
         }
     }
 
+    let migration_011 = include_str!("../../migrations/011_create_cordoned_projects_table.sql");
+    sqlx::query(migration_011).execute(&pool).await?;
+
+    let migration_020 = include_str!("../../migrations/020_add_started_at_to_instances.sql");
+    let _ = sqlx::query(migration_020).execute(&pool).await;
+
+    verify_schema(&pool, db_path, ORCHESTRATOR_SCHEMA).await?;
+
     Ok(pool)
 }
 
 pub async fn init_log_db(db_path: &Path) -> Result<SqlitePool> {
-    if let Some(parent) = db_path.parent() {
-        std::fs::create_dir_all(parent)?;
-    }
-    let url = format!("sqlite:{}?mode=rwc", db_path.display());
-    let pool = SqlitePool::connect(&url).await?;
+    let pool = connect(db_path).await?;
 
     sqlx::query("PRAGMA journal_mode=WAL;")
         .execute(&pool)
@@ -57,21 +382,28 @@ pub async fn init_log_db(db_path: &Path) -> Result<SqlitePool> {
     sqlx::raw_sql(migration).execute(&pool).await?;
 
     // Add sequence column for log ordering (idempotent for existing DBs).
-    // tokio::spawn in DatabaseLayer causes insertion order to differ from
+    // Events from different threads can reach DatabaseLayer's writer out of
     // emission order; this monotonic counter preserves the true order.
     let _ = sqlx::query("ALTER TABLE run_logs ADD COLUMN sequence INTEGER DEFAULT 0")
         .execute(&pool)
         .await;
 
+    let migration_013 = include_str!("../../migrations/013_create_instance_events_table.sql");
+    sqlx::raw_sql(migration_013).execute(&pool).await?;
+
+    let migration_014 = include_str!("../../migrations/014_create_feedback_table.sql");
+    sqlx::raw_sql(migration_014).execute(&pool).await?;
+
+    let migration_017 = include_str!("../../migrations/017_create_stream_events_table.sql");
+    sqlx::raw_sql(migration_017).execute(&pool).await?;
+
+    verify_schema(&pool, db_path, LOG_SCHEMA).await?;
+
     Ok(pool)
 }
 
 pub async fn init_topics_db(db_path: &Path) -> Result<SqlitePool> {
-    if let Some(parent) = db_path.parent() {
-        std::fs::create_dir_all(parent)?;
-    }
-    let url = format!("sqlite:{}?mode=rwc", db_path.display());
-    let pool = SqlitePool::connect(&url).await?;
+    let pool = connect(db_path).await?;
 
     sqlx::query("PRAGMA journal_mode=WAL;")
         .execute(&pool)
@@ -83,8 +415,51 @@ pub async fn init_topics_db(db_path: &Path) -> Result<SqlitePool> {
     let migration_005 = include_str!("../../migrations/005_remove_dead_columns.sql");
     let _ = sqlx::query(migration_005).execute(&pool).await;
 
-    let migration_006 = include_str!("../../migrations/006_composite_topic_pk.sql");
-    let _ = sqlx::query(migration_006).execute(&pool).await;
+    // 006 rebuilds the table, which would drop columns added by later
+    // migrations, so only run it while the legacy single-column key remains.
+    let schema: Option<String> = sqlx::query_scalar(
+        "SELECT sql FROM sqlite_master WHERE type = 'table' AND name = 'topic_mappings'",
+    )
+    .fetch_optional(&pool)
+    .await?;
+    if !schema.is_some_and(|sql| sql.contains("PRIMARY KEY (chat_id, topic_id)")) {
+        let migration_006 = include_str!("../../migrations/006_composite_topic_pk.sql");
+        let _ = sqlx::query(migration_006).execute(&pool).await;
+    }
+
+    let migration_008 = include_str!("../../migrations/008_add_archived_to_topic_mappings.sql");
+    let _ = sqlx::query(migration_008).execute(&pool).await;
+
+    let migration_009 = include_str!("../../migrations/009_add_read_only_to_topic_mappings.sql");
+    let _ = sqlx::query(migration_009).execute(&pool).await;
+
+    let migration_010 = include_str!("../../migrations/010_add_output_topic_to_topic_mappings.sql");
+    let _ = sqlx::query(migration_010).execute(&pool).await;
+
+    let migration_012 =
+        include_str!("../../migrations/012_add_system_prompt_mtime_to_topic_mappings.sql");
+    let _ = sqlx::query(migration_012).execute(&pool).await;
+
+    let migration_015 = include_str!("../../migrations/015_create_pending_generations_table.sql");
+    sqlx::query(migration_015).execute(&pool).await?;
+
+    let migration_016 = include_str!("../../migrations/016_create_outbound_queue_table.sql");
+    sqlx::query(migration_016).execute(&pool).await?;
+
+    let migration_021 = include_str!("../../migrations/021_create_outbound_buffer_table.sql");
+    sqlx::query(migration_021).execute(&pool).await?;
+
+    let migration_022 = include_str!("../../migrations/022_add_prompt_to_pending_generations.sql");
+    let _ = sqlx::query(migration_022).execute(&pool).await;
+
+    let migration_018 = include_str!("../../migrations/018_add_locale_to_topic_mappings.sql");
+    let _ = sqlx::query(migration_018).execute(&pool).await;
+
+    let migration_019 =
+        include_str!("../../migrations/019_add_last_activity_to_topic_mappings.sql");
+    let _ = sqlx::query(migration_019).execute(&pool).await;
+
+    verify_schema(&pool, db_path, TOPICS_SCHEMA).await?;
 
     Ok(pool)
 }
@@ -120,7 +495,7 @@ mod tests {
 
         // Verify instances table exists with correct schema
         let result = sqlx::query(
-            "SELECT id, project_path, port, state, session_id, container_id, topic_id, created_at, updated_at 
+            "SELECT id, project_path, port, state, session_id, container_id, topic_id, started_at, created_at, updated_at 
              FROM instances LIMIT 0",
         )
         .fetch_optional(&pool)
@@ -197,6 +572,145 @@ mod tests {
         pool.close().await;
     }
 
+    #[tokio::test]
+    async fn test_verify_schema_applies_missing_column_migration() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("orchestrator.db");
+        let pool = connect(&db_path).await.unwrap();
+
+        // An instances table as created before 004 and 007
+        let migration = include_str!("../../migrations/001_create_instances_table.sql");
+        sqlx::raw_sql(migration).execute(&pool).await.unwrap();
+        let migration_011 = include_str!("../../migrations/011_create_cordoned_projects_table.sql");
+        sqlx::query(migration_011).execute(&pool).await.unwrap();
+        assert!(!table_columns(&pool, "instances")
+            .await
+            .unwrap()
+            .contains(&"container_id".to_string()));
+
+        verify_schema(&pool, &db_path, ORCHESTRATOR_SCHEMA)
+            .await
+            .unwrap();
+
+        let columns = table_columns(&pool, "instances").await.unwrap();
+        assert!(columns.contains(&"container_id".to_string()));
+        assert!(columns.contains(&"topic_id".to_string()));
+
+        pool.close().await;
+    }
+
+    #[tokio::test]
+    async fn test_init_fails_fast_on_missing_column() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("orchestrator.db");
+
+        // An instances table without updated_at, which no migration adds
+        let pool = connect(&db_path).await.unwrap();
+        sqlx::query(
+            "CREATE TABLE instances (id TEXT PRIMARY KEY, project_path TEXT NOT NULL, \
+             port INTEGER NOT NULL, state TEXT NOT NULL, session_id TEXT, \
+             created_at INTEGER NOT NULL)",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        pool.close().await;
+
+        let err = init_orchestrator_db(&db_path)
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("table `instances` is missing column `updated_at`"),
+            "unexpected error: {}",
+            err
+        );
+    }
+
+    #[tokio::test]
+    async fn test_init_sets_busy_timeout_on_connections() {
+        let temp_dir = TempDir::new().unwrap();
+        let pool = init_topics_db(&temp_dir.path().join("topics.db"))
+            .await
+            .unwrap();
+
+        let (timeout_ms,): (i64,) = sqlx::query_as("PRAGMA busy_timeout")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(timeout_ms, BUSY_TIMEOUT.as_millis() as i64);
+
+        pool.close().await;
+    }
+
+    #[tokio::test]
+    async fn test_retry_busy_retries_until_lock_released() {
+        use sqlx::{Connection, SqliteConnection};
+
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("orchestrator.db");
+        let pool = init_orchestrator_db(&db_path).await.unwrap();
+
+        // Hold the write lock on one connection; the writer below does not
+        // wait on it, so its first attempt fails with SQLITE_BUSY
+        let options = SqliteConnectOptions::new()
+            .filename(&db_path)
+            .busy_timeout(Duration::ZERO);
+        let mut holder = SqliteConnection::connect_with(&options).await.unwrap();
+        sqlx::query("BEGIN IMMEDIATE")
+            .execute(&mut holder)
+            .await
+            .unwrap();
+        let writer = SqlitePool::connect_with(options).await.unwrap();
+
+        let insert = "INSERT INTO cordoned_projects (project, cordoned_at) VALUES ('api', 0)";
+        let err = sqlx::query(insert).execute(&writer).await.unwrap_err();
+        assert!(is_busy(&err));
+
+        let release = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(60)).await;
+            sqlx::query("COMMIT").execute(&mut holder).await.unwrap();
+        });
+
+        let mut attempts = 0;
+        retry_busy(|| {
+            attempts += 1;
+            sqlx::query(insert).execute(&writer)
+        })
+        .await
+        .unwrap();
+        release.await.unwrap();
+        assert!(attempts > 1);
+
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM cordoned_projects")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(count, 1);
+
+        pool.close().await;
+    }
+
+    #[tokio::test]
+    async fn test_retry_busy_does_not_retry_other_errors() {
+        let temp_dir = TempDir::new().unwrap();
+        let pool = init_orchestrator_db(&temp_dir.path().join("orchestrator.db"))
+            .await
+            .unwrap();
+
+        let mut attempts = 0;
+        let err = retry_busy(|| {
+            attempts += 1;
+            sqlx::query("INSERT INTO missing_table VALUES (1)").execute(&pool)
+        })
+        .await
+        .unwrap_err();
+        assert!(!is_busy(&err));
+        assert_eq!(attempts, 1);
+
+        pool.close().await;
+    }
+
     #[tokio::test]
     async fn test_init_topics_db_creates_database() {
         let temp_dir = TempDir::new().unwrap();
@@ -1,10 +1,12 @@
 use std::collections::HashMap;
 use std::fmt::Write;
 use std::sync::atomic::{AtomicI64, Ordering};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use super::log_store::LogStore;
+use super::log_store::{LogRecord, LogStore};
 use tokio::runtime::Handle;
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
 use tracing::field::{Field, Visit};
 use tracing::{Event, Subscriber};
 use tracing_subscriber::layer::{Context, Layer};
@@ -14,20 +16,96 @@ use tracing_subscriber::registry::LookupSpan;
 // This IS the tracing layer — using tracing macros here causes infinite recursion
 // since on_event() is called for every log event, including ones from this module.
 
+/// Events buffered between `on_event` and the writer. When full, new events
+/// are dropped rather than blocking the logging thread.
+const LOG_CHANNEL_CAPACITY: usize = 8192;
+
+/// Maximum number of events written in one transaction.
+const LOG_BATCH_SIZE: usize = 256;
+
+/// How long the writer waits for a batch to fill before writing it anyway.
+const LOG_FLUSH_INTERVAL: Duration = Duration::from_millis(500);
+
 pub struct DatabaseLayer {
-    store: LogStore,
-    handle: Handle,
-    run_id: String,
+    sender: mpsc::Sender<LogRecord>,
     sequence: AtomicI64,
 }
 
+/// Stops the background writer once buffered events are written. A layer
+/// installed globally is never dropped, so this is the only way to be sure
+/// the last events reach the log DB before the process exits.
+pub struct LogWriterHandle {
+    shutdown: oneshot::Sender<()>,
+    task: JoinHandle<()>,
+}
+
+impl LogWriterHandle {
+    /// Stop accepting events, write the ones already buffered and wait for
+    /// the writer to exit.
+    pub async fn flush_and_join(self) {
+        let _ = self.shutdown.send(());
+        let _ = self.task.await;
+    }
+}
+
 impl DatabaseLayer {
-    pub fn new(store: LogStore, handle: Handle, run_id: String) -> Self {
-        Self {
-            store,
-            handle,
-            run_id,
+    /// Create the layer and spawn its background writer on `handle`. The
+    /// writer flushes what is left and exits once the layer is dropped or
+    /// the returned handle is flushed.
+    pub fn new(store: LogStore, handle: Handle, run_id: String) -> (Self, LogWriterHandle) {
+        let (sender, receiver) = mpsc::channel(LOG_CHANNEL_CAPACITY);
+        let (shutdown, shutdown_rx) = oneshot::channel();
+        let task = handle.spawn(run_log_writer(store, run_id, receiver, shutdown_rx));
+        let layer = Self {
+            sender,
             sequence: AtomicI64::new(0),
+        };
+        (layer, LogWriterHandle { shutdown, task })
+    }
+}
+
+/// Drain `receiver` into the log DB, one transaction per batch. A batch is
+/// written when it reaches `LOG_BATCH_SIZE` events or `LOG_FLUSH_INTERVAL`
+/// after its first event, whichever comes first. On `shutdown` the channel
+/// is closed and what it still holds is written before returning.
+async fn run_log_writer(
+    store: LogStore,
+    run_id: String,
+    mut receiver: mpsc::Receiver<LogRecord>,
+    mut shutdown: oneshot::Receiver<()>,
+) {
+    let mut batch = Vec::with_capacity(LOG_BATCH_SIZE);
+    let mut draining = false;
+    loop {
+        let first = tokio::select! {
+            record = receiver.recv() => record,
+            _ = &mut shutdown, if !draining => {
+                draining = true;
+                receiver.close();
+                continue;
+            }
+        };
+        let Some(first) = first else {
+            break;
+        };
+        batch.push(first);
+        let deadline = tokio::time::Instant::now() + LOG_FLUSH_INTERVAL;
+        let mut closed = false;
+        while batch.len() < LOG_BATCH_SIZE {
+            match tokio::time::timeout_at(deadline, receiver.recv()).await {
+                Ok(Some(record)) => batch.push(record),
+                Ok(None) => {
+                    closed = true;
+                    break;
+                }
+                Err(_elapsed) => break,
+            }
+        }
+
+        let _ = store.insert_logs(&run_id, &batch).await;
+        batch.clear();
+        if closed {
+            break;
         }
     }
 }
@@ -44,38 +122,24 @@ where
             .unwrap_or_default()
             .as_millis() as i64;
 
-        let seq = self.sequence.fetch_add(1, Ordering::SeqCst);
-
-        let level = metadata.level().as_str();
-        let target = metadata.target();
+        let sequence = self.sequence.fetch_add(1, Ordering::SeqCst);
 
         let mut visitor = EventVisitor::default();
         event.record(&mut visitor);
 
-        let message = visitor.message;
         let fields = if visitor.fields.is_empty() {
             None
         } else {
             serde_json::to_string(&visitor.fields).ok()
         };
 
-        let store = self.store.clone();
-        let run_id = self.run_id.clone();
-        let level = level.to_owned();
-        let target = target.to_owned();
-
-        self.handle.spawn(async move {
-            let _ = store
-                .insert_log(
-                    &run_id,
-                    timestamp,
-                    seq,
-                    &level,
-                    &target,
-                    &message,
-                    fields.as_deref(),
-                )
-                .await;
+        let _ = self.sender.try_send(LogRecord {
+            timestamp,
+            sequence,
+            level: metadata.level().as_str(),
+            target: metadata.target().to_owned(),
+            message: visitor.message,
+            fields,
         });
     }
 }
@@ -109,3 +173,79 @@ impl Visit for EventVisitor {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_batched_writer_persists_events_in_order() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = LogStore::new(&temp_dir.path().join("logs.db"))
+            .await
+            .unwrap();
+        store.create_run("run_batch", "0.1.0", None).await.unwrap();
+
+        let (layer, _writer) =
+            DatabaseLayer::new(store.clone(), Handle::current(), "run_batch".to_string());
+        let subscriber = tracing_subscriber::registry().with(layer);
+        tracing::subscriber::with_default(subscriber, || {
+            for i in 0..1000 {
+                tracing::info!(index = i, "event {}", i);
+            }
+        });
+
+        // Dropping the subscriber closes the channel; wait for the final flush
+        let mut rows: Vec<(i64, String)> = Vec::new();
+        for _ in 0..100 {
+            rows = sqlx::query_as(
+                "SELECT sequence, message FROM run_logs WHERE run_id = ? ORDER BY id",
+            )
+            .bind("run_batch")
+            .fetch_all(store.pool())
+            .await
+            .unwrap();
+            if rows.len() == 1000 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+
+        assert_eq!(rows.len(), 1000);
+        for (i, (sequence, message)) in rows.iter().enumerate() {
+            assert_eq!(*sequence, i as i64);
+            assert_eq!(message, &format!("event {}", i));
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_flush_and_join_writes_buffered_events() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = LogStore::new(&temp_dir.path().join("logs.db"))
+            .await
+            .unwrap();
+        store.create_run("run_flush", "0.1.0", None).await.unwrap();
+
+        let (layer, writer) =
+            DatabaseLayer::new(store.clone(), Handle::current(), "run_flush".to_string());
+        // Kept alive like a global subscriber, so the channel never closes
+        let dispatch = tracing::Dispatch::new(tracing_subscriber::registry().with(layer));
+        tracing::dispatcher::with_default(&dispatch, || {
+            for i in 0..300 {
+                tracing::info!("event {}", i);
+            }
+        });
+
+        writer.flush_and_join().await;
+
+        let (count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM run_logs WHERE run_id = ?")
+            .bind("run_flush")
+            .fetch_one(store.pool())
+            .await
+            .unwrap();
+        assert_eq!(count, 300);
+        drop(dispatch);
+    }
+}
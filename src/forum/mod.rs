@@ -1,3 +1,3 @@
 pub mod store;
 
-pub use store::TopicStore;
+pub use store::{MappingConflict, MappingImport, TopicStore};
@@ -1,8 +1,8 @@
-use crate::db::init_topics_db;
-use crate::types::forum::TopicMapping;
+use crate::db::{init_topics_db, retry_busy};
+use crate::types::forum::{BufferedOutput, OutboundMessage, PendingGeneration, TopicMapping};
 use anyhow::{anyhow, Result};
 use sqlx::{Row, SqlitePool};
-use std::path::Path;
+use std::path::{Component, Path};
 use std::time::Duration;
 use tracing::debug;
 
@@ -10,6 +10,84 @@ pub struct TopicStore {
     pool: SqlitePool,
 }
 
+/// Outcome of [`TopicStore::import`].
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct MappingImport {
+    /// Mappings written, new or replacing an existing one
+    pub imported: usize,
+    /// Existing mappings that pointed at a different project or session and
+    /// were replaced
+    pub conflicts: Vec<MappingConflict>,
+}
+
+/// An imported mapping that replaced a different one for the same topic.
+#[derive(Debug, PartialEq, Eq)]
+pub struct MappingConflict {
+    pub chat_id: i64,
+    pub topic_id: i32,
+    pub existing_project: String,
+    pub imported_project: String,
+}
+
+/// Insert `mapping`, or replace the one for the same chat and topic
+/// (un-archiving it).
+fn upsert_mapping(
+    mapping: &TopicMapping,
+) -> sqlx::query::Query<'_, sqlx::Sqlite, sqlx::sqlite::SqliteArguments<'_>> {
+    sqlx::query(
+        "INSERT INTO topic_mappings 
+             (topic_id, chat_id, project_path, session_id, instance_id, 
+              topic_name_updated, created_at, updated_at, read_only, output_topic_id,
+              system_prompt_mtime, locale, last_activity_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+             ON CONFLICT(chat_id, topic_id) DO UPDATE SET
+                project_path = excluded.project_path,
+                session_id = excluded.session_id,
+                instance_id = excluded.instance_id,
+                topic_name_updated = excluded.topic_name_updated,
+                read_only = excluded.read_only,
+                output_topic_id = excluded.output_topic_id,
+                system_prompt_mtime = excluded.system_prompt_mtime,
+                locale = excluded.locale,
+                last_activity_at = excluded.last_activity_at,
+                archived = 0,
+                updated_at = excluded.updated_at",
+    )
+    .bind(mapping.topic_id)
+    .bind(mapping.chat_id)
+    .bind(&mapping.project_path)
+    .bind(&mapping.session_id)
+    .bind(&mapping.instance_id)
+    .bind(if mapping.topic_name_updated { 1 } else { 0 })
+    .bind(mapping.created_at)
+    .bind(mapping.updated_at)
+    .bind(if mapping.read_only { 1 } else { 0 })
+    .bind(mapping.output_topic_id)
+    .bind(mapping.system_prompt_mtime)
+    .bind(&mapping.locale)
+    .bind(mapping.last_activity_at)
+}
+
+/// Whether `project_path` is a project under `base`: absolute, free of `..`
+/// and, once symlinks are resolved where it exists, still inside `base`.
+fn is_under_base(project_path: &str, base: &Path) -> bool {
+    let path = Path::new(project_path);
+    if !path.is_absolute()
+        || path.components().any(|c| matches!(c, Component::ParentDir))
+        || path == base
+        || !path.starts_with(base)
+    {
+        return false;
+    }
+    match std::fs::canonicalize(path) {
+        Ok(resolved) => {
+            let base = std::fs::canonicalize(base).unwrap_or_else(|_| base.to_path_buf());
+            resolved != base && resolved.starts_with(base)
+        }
+        Err(_) => true,
+    }
+}
+
 impl TopicStore {
     pub async fn new(db_path: &Path) -> Result<Self> {
         let pool = init_topics_db(db_path).await?;
@@ -19,28 +97,7 @@ impl TopicStore {
 
     pub async fn save_mapping(&self, mapping: &TopicMapping) -> Result<()> {
         debug!(topic_id = mapping.topic_id, chat_id = mapping.chat_id, session_id = ?mapping.session_id, instance_id = ?mapping.instance_id, "Saving topic mapping");
-        sqlx::query(
-            "INSERT INTO topic_mappings 
-             (topic_id, chat_id, project_path, session_id, instance_id, 
-              topic_name_updated, created_at, updated_at)
-             VALUES (?, ?, ?, ?, ?, ?, ?, ?)
-             ON CONFLICT(chat_id, topic_id) DO UPDATE SET
-                project_path = excluded.project_path,
-                session_id = excluded.session_id,
-                instance_id = excluded.instance_id,
-                topic_name_updated = excluded.topic_name_updated,
-                updated_at = excluded.updated_at",
-        )
-        .bind(mapping.topic_id)
-        .bind(mapping.chat_id)
-        .bind(&mapping.project_path)
-        .bind(&mapping.session_id)
-        .bind(&mapping.instance_id)
-        .bind(if mapping.topic_name_updated { 1 } else { 0 })
-        .bind(mapping.created_at)
-        .bind(mapping.updated_at)
-        .execute(&self.pool)
-        .await?;
+        retry_busy(|| upsert_mapping(mapping).execute(&self.pool)).await?;
 
         Ok(())
     }
@@ -53,8 +110,9 @@ impl TopicStore {
         );
         let row = sqlx::query(
             "SELECT topic_id, chat_id, project_path, session_id, instance_id,
-                    topic_name_updated, created_at, updated_at
-             FROM topic_mappings WHERE chat_id = ? AND topic_id = ?",
+                    topic_name_updated, created_at, updated_at, read_only, output_topic_id,
+                    system_prompt_mtime, locale, last_activity_at
+             FROM topic_mappings WHERE chat_id = ? AND topic_id = ? AND archived = 0",
         )
         .bind(chat_id)
         .bind(topic_id)
@@ -71,6 +129,11 @@ impl TopicStore {
                 topic_name_updated: row.get::<i32, _>(5) != 0,
                 created_at: row.get(6),
                 updated_at: row.get(7),
+                read_only: row.get::<i32, _>(8) != 0,
+                output_topic_id: row.get(9),
+                system_prompt_mtime: row.get(10),
+                locale: row.get(11),
+                last_activity_at: row.get(12),
             })),
             None => Ok(None),
         };
@@ -88,8 +151,9 @@ impl TopicStore {
         debug!(chat_id = chat_id, "Looking up mappings by chat");
         let rows = sqlx::query(
             "SELECT topic_id, chat_id, project_path, session_id, instance_id,
-                    topic_name_updated, created_at, updated_at
-             FROM topic_mappings WHERE chat_id = ?",
+                    topic_name_updated, created_at, updated_at, read_only, output_topic_id,
+                    system_prompt_mtime, locale, last_activity_at
+             FROM topic_mappings WHERE chat_id = ? AND archived = 0",
         )
         .bind(chat_id)
         .fetch_all(&self.pool)
@@ -106,6 +170,11 @@ impl TopicStore {
                 topic_name_updated: row.get::<i32, _>(5) != 0,
                 created_at: row.get(6),
                 updated_at: row.get(7),
+                read_only: row.get::<i32, _>(8) != 0,
+                output_topic_id: row.get(9),
+                system_prompt_mtime: row.get(10),
+                locale: row.get(11),
+                last_activity_at: row.get(12),
             })
             .collect();
 
@@ -117,12 +186,39 @@ impl TopicStore {
         Ok(mappings)
     }
 
-    #[allow(dead_code)]
+    /// Count active (non-archived) mappings in a chat.
+    pub async fn count_mappings_by_chat(&self, chat_id: i64) -> Result<usize> {
+        let count: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM topic_mappings WHERE chat_id = ? AND archived = 0",
+        )
+        .bind(chat_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        debug!(chat_id = chat_id, count = count, "Counted chat mappings");
+        Ok(count as usize)
+    }
+
+    /// Count active (non-archived) mappings bound to `project_path`, across
+    /// all chats.
+    pub async fn count_mappings_by_project(&self, project_path: &str) -> Result<usize> {
+        let count: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM topic_mappings WHERE project_path = ? AND archived = 0",
+        )
+        .bind(project_path)
+        .fetch_one(&self.pool)
+        .await?;
+
+        debug!(project_path = %project_path, count = count, "Counted project mappings");
+        Ok(count as usize)
+    }
+
     pub async fn get_all_mappings(&self) -> Result<Vec<TopicMapping>> {
         debug!("Looking up all mappings");
         let rows = sqlx::query(
             "SELECT topic_id, chat_id, project_path, session_id, instance_id,
-                    topic_name_updated, created_at, updated_at
+                    topic_name_updated, created_at, updated_at, read_only, output_topic_id,
+                    system_prompt_mtime, locale, last_activity_at
              FROM topic_mappings",
         )
         .fetch_all(&self.pool)
@@ -139,6 +235,11 @@ impl TopicStore {
                 topic_name_updated: row.get::<i32, _>(5) != 0,
                 created_at: row.get(6),
                 updated_at: row.get(7),
+                read_only: row.get::<i32, _>(8) != 0,
+                output_topic_id: row.get(9),
+                system_prompt_mtime: row.get(10),
+                locale: row.get(11),
+                last_activity_at: row.get(12),
             })
             .collect();
 
@@ -146,12 +247,172 @@ impl TopicStore {
         Ok(mappings)
     }
 
+    /// Active (non-archived) mappings across all chats.
+    pub async fn get_active_mappings(&self) -> Result<Vec<TopicMapping>> {
+        debug!("Looking up active mappings");
+        let rows = sqlx::query(
+            "SELECT topic_id, chat_id, project_path, session_id, instance_id,
+                    topic_name_updated, created_at, updated_at, read_only, output_topic_id,
+                    system_prompt_mtime, locale, last_activity_at
+             FROM topic_mappings WHERE archived = 0",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mappings: Vec<TopicMapping> = rows
+            .into_iter()
+            .map(|row| TopicMapping {
+                topic_id: row.get(0),
+                chat_id: row.get(1),
+                project_path: row.get(2),
+                session_id: row.get(3),
+                instance_id: row.get(4),
+                topic_name_updated: row.get::<i32, _>(5) != 0,
+                created_at: row.get(6),
+                updated_at: row.get(7),
+                read_only: row.get::<i32, _>(8) != 0,
+                output_topic_id: row.get(9),
+                system_prompt_mtime: row.get(10),
+                locale: row.get(11),
+                last_activity_at: row.get(12),
+            })
+            .collect();
+
+        debug!(count = mappings.len(), "Active mappings found");
+        Ok(mappings)
+    }
+
+    /// Active (non-archived) mappings of `chat_id`, for backups and
+    /// migrating to a new host with [`Self::import`].
+    pub async fn export_chat(&self, chat_id: i64) -> Result<Vec<TopicMapping>> {
+        debug!(chat_id = chat_id, "Exporting chat mappings");
+        let rows = sqlx::query(
+            "SELECT topic_id, chat_id, project_path, session_id, instance_id,
+                    topic_name_updated, created_at, updated_at, read_only, output_topic_id,
+                    system_prompt_mtime, locale, last_activity_at
+             FROM topic_mappings WHERE chat_id = ? AND archived = 0 ORDER BY topic_id",
+        )
+        .bind(chat_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mappings: Vec<TopicMapping> = rows
+            .into_iter()
+            .map(|row| TopicMapping {
+                topic_id: row.get(0),
+                chat_id: row.get(1),
+                project_path: row.get(2),
+                session_id: row.get(3),
+                instance_id: row.get(4),
+                topic_name_updated: row.get::<i32, _>(5) != 0,
+                created_at: row.get(6),
+                updated_at: row.get(7),
+                read_only: row.get::<i32, _>(8) != 0,
+                output_topic_id: row.get(9),
+                system_prompt_mtime: row.get(10),
+                locale: row.get(11),
+                last_activity_at: row.get(12),
+            })
+            .collect();
+
+        debug!(count = mappings.len(), "Mappings exported");
+        Ok(mappings)
+    }
+
+    /// Upsert exported mappings into `chat_id` in one transaction. All of
+    /// them are validated first: every entry must belong to `chat_id` and
+    /// point at a project under `project_base_path`, so an invalid entry
+    /// imports nothing. Existing mappings bound to a different project or
+    /// session are replaced and reported as conflicts.
+    pub async fn import(
+        &self,
+        chat_id: i64,
+        project_base_path: &Path,
+        mappings: &[TopicMapping],
+    ) -> Result<MappingImport> {
+        let mut seen = std::collections::HashSet::new();
+        for mapping in mappings {
+            if mapping.chat_id != chat_id {
+                return Err(anyhow!(
+                    "Topic {} belongs to chat {}, not this chat",
+                    mapping.topic_id,
+                    mapping.chat_id
+                ));
+            }
+            if mapping.topic_id <= 0 {
+                return Err(anyhow!(
+                    "Invalid topic id {} in chat {}",
+                    mapping.topic_id,
+                    mapping.chat_id
+                ));
+            }
+            if mapping.project_path.trim().is_empty() {
+                return Err(anyhow!(
+                    "Topic {} in chat {} has no project path",
+                    mapping.topic_id,
+                    mapping.chat_id
+                ));
+            }
+            if !is_under_base(&mapping.project_path, project_base_path) {
+                return Err(anyhow!(
+                    "Topic {} points at {}, which is outside {}",
+                    mapping.topic_id,
+                    mapping.project_path,
+                    project_base_path.display()
+                ));
+            }
+            if !seen.insert((mapping.chat_id, mapping.topic_id)) {
+                return Err(anyhow!(
+                    "Topic {} in chat {} appears more than once",
+                    mapping.topic_id,
+                    mapping.chat_id
+                ));
+            }
+        }
+
+        let mut result = MappingImport::default();
+        let mut tx = self.pool.begin().await?;
+        for mapping in mappings {
+            let existing: Option<(String, Option<String>)> = sqlx::query_as(
+                "SELECT project_path, session_id FROM topic_mappings
+                 WHERE chat_id = ? AND topic_id = ? AND archived = 0",
+            )
+            .bind(mapping.chat_id)
+            .bind(mapping.topic_id)
+            .fetch_optional(&mut *tx)
+            .await?;
+            if let Some((existing_project, existing_session)) = existing {
+                if existing_project != mapping.project_path
+                    || existing_session != mapping.session_id
+                {
+                    result.conflicts.push(MappingConflict {
+                        chat_id: mapping.chat_id,
+                        topic_id: mapping.topic_id,
+                        existing_project,
+                        imported_project: mapping.project_path.clone(),
+                    });
+                }
+            }
+            upsert_mapping(mapping).execute(&mut *tx).await?;
+            result.imported += 1;
+        }
+        tx.commit().await?;
+
+        debug!(
+            imported = result.imported,
+            conflicts = result.conflicts.len(),
+            "Mappings imported"
+        );
+        Ok(result)
+    }
+
     pub async fn get_mapping_by_session(&self, session_id: &str) -> Result<Option<TopicMapping>> {
         debug!(session_id = %session_id, "Looking up mapping by session");
         let row = sqlx::query(
             "SELECT topic_id, chat_id, project_path, session_id, instance_id,
-                    topic_name_updated, created_at, updated_at
-             FROM topic_mappings WHERE session_id = ?",
+                    topic_name_updated, created_at, updated_at, read_only, output_topic_id,
+                    system_prompt_mtime, locale, last_activity_at
+             FROM topic_mappings WHERE session_id = ? AND archived = 0",
         )
         .bind(session_id)
         .fetch_optional(&self.pool)
@@ -167,13 +428,16 @@ impl TopicStore {
                 topic_name_updated: row.get::<i32, _>(5) != 0,
                 created_at: row.get(6),
                 updated_at: row.get(7),
+                read_only: row.get::<i32, _>(8) != 0,
+                output_topic_id: row.get(9),
+                system_prompt_mtime: row.get(10),
+                locale: row.get(11),
+                last_activity_at: row.get(12),
             })),
             None => Ok(None),
         }
     }
 
-    #[allow(dead_code)]
-    // Used by future: session update feature
     pub async fn update_session(
         &self,
         chat_id: i64,
@@ -185,14 +449,14 @@ impl TopicStore {
             .duration_since(std::time::UNIX_EPOCH)?
             .as_secs() as i64;
 
-        let result = sqlx::query(
+        let result = retry_busy(|| sqlx::query(
             "UPDATE topic_mappings SET session_id = ?, updated_at = ? WHERE chat_id = ? AND topic_id = ?",
         )
         .bind(session_id)
         .bind(now)
         .bind(chat_id)
         .bind(topic_id)
-        .execute(&self.pool)
+        .execute(&self.pool))
         .await?;
 
         if result.rows_affected() == 0 {
@@ -216,13 +480,13 @@ impl TopicStore {
             .duration_since(std::time::UNIX_EPOCH)?
             .as_secs() as i64;
 
-        let result = sqlx::query(
+        let result = retry_busy(|| sqlx::query(
             "UPDATE topic_mappings SET topic_name_updated = 1, updated_at = ? WHERE chat_id = ? AND topic_id = ?",
         )
         .bind(now)
         .bind(chat_id)
         .bind(topic_id)
-        .execute(&self.pool)
+        .execute(&self.pool))
         .await?;
 
         if result.rows_affected() == 0 {
@@ -236,114 +500,508 @@ impl TopicStore {
         Ok(())
     }
 
-    pub async fn delete_mapping(&self, chat_id: i64, topic_id: i32) -> Result<()> {
+    /// Toggle observer mode for a topic.
+    pub async fn set_read_only(&self, chat_id: i64, topic_id: i32, read_only: bool) -> Result<()> {
         debug!(
             chat_id = chat_id,
             topic_id = topic_id,
-            "Deleting topic mapping"
+            read_only = read_only,
+            "Setting topic read-only flag"
         );
-        sqlx::query("DELETE FROM topic_mappings WHERE chat_id = ? AND topic_id = ?")
-            .bind(chat_id)
-            .bind(topic_id)
-            .execute(&self.pool)
-            .await?;
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs() as i64;
+
+        let result = retry_busy(|| sqlx::query(
+            "UPDATE topic_mappings SET read_only = ?, updated_at = ? WHERE chat_id = ? AND topic_id = ? AND archived = 0",
+        )
+        .bind(if read_only { 1 } else { 0 })
+        .bind(now)
+        .bind(chat_id)
+        .bind(topic_id)
+        .execute(&self.pool))
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(anyhow!(
+                "Mapping not found for chat_id {} topic_id {}",
+                chat_id,
+                topic_id
+            ));
+        }
 
         Ok(())
     }
 
-    #[allow(dead_code)]
-    // Used by future: manual cleanup and admin reporting
-    pub async fn get_stale_mappings(&self, older_than: Duration) -> Result<Vec<TopicMapping>> {
-        debug!("Looking up stale mappings");
+    /// Route the topic's agent output to `output_topic_id`, or back to the
+    /// topic itself when `None`.
+    pub async fn set_output_topic(
+        &self,
+        chat_id: i64,
+        topic_id: i32,
+        output_topic_id: Option<i32>,
+    ) -> Result<()> {
+        debug!(
+            chat_id = chat_id,
+            topic_id = topic_id,
+            output_topic_id = ?output_topic_id,
+            "Setting topic output topic"
+        );
         let now = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)?
             .as_secs() as i64;
 
-        let threshold = now - older_than.as_secs() as i64;
-
-        let rows = sqlx::query(
-            "SELECT topic_id, chat_id, project_path, session_id, instance_id,
-                    topic_name_updated, created_at, updated_at
-             FROM topic_mappings WHERE updated_at < ?",
+        let result = retry_busy(|| sqlx::query(
+            "UPDATE topic_mappings SET output_topic_id = ?, updated_at = ? WHERE chat_id = ? AND topic_id = ? AND archived = 0",
         )
-        .bind(threshold)
-        .fetch_all(&self.pool)
+        .bind(output_topic_id)
+        .bind(now)
+        .bind(chat_id)
+        .bind(topic_id)
+        .execute(&self.pool))
         .await?;
 
-        let mappings: Vec<TopicMapping> = rows
-            .into_iter()
-            .map(|row| TopicMapping {
-                topic_id: row.get(0),
-                chat_id: row.get(1),
-                project_path: row.get(2),
-                session_id: row.get(3),
-                instance_id: row.get(4),
-                topic_name_updated: row.get::<i32, _>(5) != 0,
-                created_at: row.get(6),
-                updated_at: row.get(7),
-            })
-            .collect();
+        if result.rows_affected() == 0 {
+            return Err(anyhow!(
+                "Mapping not found for chat_id {} topic_id {}",
+                chat_id,
+                topic_id
+            ));
+        }
 
-        debug!(count = mappings.len(), "Stale mappings found");
-        Ok(mappings)
+        Ok(())
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use tempfile::TempDir;
+    /// Record the modification time of the system prompt file last sent to
+    /// the topic's session.
+    pub async fn set_system_prompt_mtime(
+        &self,
+        chat_id: i64,
+        topic_id: i32,
+        mtime: Option<i64>,
+    ) -> Result<()> {
+        debug!(
+            chat_id = chat_id,
+            topic_id = topic_id,
+            mtime = ?mtime,
+            "Setting system prompt mtime"
+        );
 
-    fn create_test_mapping(topic_id: i32, chat_id: i64) -> TopicMapping {
+        retry_busy(|| sqlx::query(
+            "UPDATE topic_mappings SET system_prompt_mtime = ? WHERE chat_id = ? AND topic_id = ? AND archived = 0",
+        )
+        .bind(mtime)
+        .bind(chat_id)
+        .bind(topic_id)
+        .execute(&self.pool))
+        .await?;
+
+        Ok(())
+    }
+
+    /// Set or clear the language code responses in the topic should use.
+    pub async fn set_locale(
+        &self,
+        chat_id: i64,
+        topic_id: i32,
+        locale: Option<&str>,
+    ) -> Result<()> {
+        debug!(
+            chat_id = chat_id,
+            topic_id = topic_id,
+            locale = ?locale,
+            "Setting topic locale"
+        );
         let now = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
+            .duration_since(std::time::UNIX_EPOCH)?
             .as_secs() as i64;
 
-        TopicMapping {
-            topic_id,
-            chat_id,
-            project_path: "/test/project".to_string(),
-            session_id: None,
-            instance_id: None,
+        let result = retry_busy(|| sqlx::query(
+            "UPDATE topic_mappings SET locale = ?, updated_at = ? WHERE chat_id = ? AND topic_id = ? AND archived = 0",
+        )
+        .bind(locale)
+        .bind(now)
+        .bind(chat_id)
+        .bind(topic_id)
+        .execute(&self.pool))
+        .await?;
 
-            topic_name_updated: false,
-            created_at: now,
-            updated_at: now,
+        if result.rows_affected() == 0 {
+            return Err(anyhow!(
+                "Mapping not found for chat_id {} topic_id {}",
+                chat_id,
+                topic_id
+            ));
         }
-    }
-
-    #[tokio::test]
-    async fn test_new_creates_store_with_valid_db() {
-        let temp_dir = TempDir::new().unwrap();
-        let db_path = temp_dir.path().join("topics.db");
-
-        let _store = TopicStore::new(&db_path).await.unwrap();
 
-        // Verify database file was created
-        assert!(db_path.exists());
+        Ok(())
     }
 
-    #[tokio::test]
-    async fn test_save_mapping_inserts_new_mapping() {
-        let temp_dir = TempDir::new().unwrap();
-        let db_path = temp_dir.path().join("topics.db");
-        let store = TopicStore::new(&db_path).await.unwrap();
+    /// Record that a prompt was just sent from the topic.
+    pub async fn record_activity(&self, chat_id: i64, topic_id: i32) -> Result<()> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs() as i64;
 
-        let mapping = create_test_mapping(123, -1001234567890);
-        store.save_mapping(&mapping).await.unwrap();
+        retry_busy(|| {
+            sqlx::query(
+                "UPDATE topic_mappings SET last_activity_at = ? WHERE chat_id = ? AND topic_id = ?",
+            )
+            .bind(now)
+            .bind(chat_id)
+            .bind(topic_id)
+            .execute(&self.pool)
+        })
+        .await?;
 
-        // Verify mapping was saved
-        let retrieved = store.get_mapping(-1001234567890, 123).await.unwrap();
-        assert!(retrieved.is_some());
-        let retrieved = retrieved.unwrap();
-        assert_eq!(retrieved.topic_id, 123);
-        assert_eq!(retrieved.chat_id, -1001234567890);
-        assert_eq!(retrieved.project_path, "/test/project");
+        Ok(())
     }
 
-    #[tokio::test]
-    async fn test_save_mapping_updates_existing_mapping() {
+    /// Soft-delete a mapping. Archived mappings are excluded from lookups
+    /// until the topic is linked to a project again.
+    pub async fn archive_mapping(&self, chat_id: i64, topic_id: i32) -> Result<()> {
+        debug!(
+            chat_id = chat_id,
+            topic_id = topic_id,
+            "Archiving topic mapping"
+        );
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs() as i64;
+
+        let result = retry_busy(|| sqlx::query(
+            "UPDATE topic_mappings SET archived = 1, updated_at = ? WHERE chat_id = ? AND topic_id = ? AND archived = 0",
+        )
+        .bind(now)
+        .bind(chat_id)
+        .bind(topic_id)
+        .execute(&self.pool))
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(anyhow!(
+                "Mapping not found for chat_id {} topic_id {}",
+                chat_id,
+                topic_id
+            ));
+        }
+
+        Ok(())
+    }
+
+    pub async fn delete_mapping(&self, chat_id: i64, topic_id: i32) -> Result<()> {
+        debug!(
+            chat_id = chat_id,
+            topic_id = topic_id,
+            "Deleting topic mapping"
+        );
+        retry_busy(|| {
+            sqlx::query("DELETE FROM topic_mappings WHERE chat_id = ? AND topic_id = ?")
+                .bind(chat_id)
+                .bind(topic_id)
+                .execute(&self.pool)
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    /// Record a topic that is mid-generation, replacing its earlier marker.
+    pub async fn save_pending_generation(&self, generation: &PendingGeneration) -> Result<()> {
+        debug!(topic_id = generation.topic_id, "Saving pending generation");
+        retry_busy(|| {
+            sqlx::query(
+                "INSERT OR REPLACE INTO pending_generations
+                    (chat_id, topic_id, session_id, last_message_id, prompt, saved_at)
+                 VALUES (?, ?, ?, ?, ?, ?)",
+            )
+            .bind(generation.chat_id)
+            .bind(generation.topic_id)
+            .bind(&generation.session_id)
+            .bind(generation.last_message_id)
+            .bind(&generation.prompt)
+            .bind(generation.saved_at)
+            .execute(&self.pool)
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// Remove a topic's marker once its response has ended.
+    pub async fn delete_pending_generation(&self, chat_id: i64, topic_id: i32) -> Result<()> {
+        retry_busy(|| {
+            sqlx::query("DELETE FROM pending_generations WHERE chat_id = ? AND topic_id = ?")
+                .bind(chat_id)
+                .bind(topic_id)
+                .execute(&self.pool)
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// Load and clear the markers saved by [`Self::save_pending_generation`].
+    pub async fn take_pending_generations(&self) -> Result<Vec<PendingGeneration>> {
+        let mut tx = self.pool.begin().await?;
+        let rows = sqlx::query(
+            "SELECT chat_id, topic_id, session_id, last_message_id, prompt, saved_at
+             FROM pending_generations ORDER BY saved_at, topic_id",
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        sqlx::query("DELETE FROM pending_generations")
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        let pending: Vec<PendingGeneration> = rows
+            .into_iter()
+            .map(|row| PendingGeneration {
+                chat_id: row.get(0),
+                topic_id: row.get(1),
+                session_id: row.get(2),
+                last_message_id: row.get(3),
+                prompt: row.get(4),
+                saved_at: row.get(5),
+            })
+            .collect();
+        debug!(count = pending.len(), "Pending generations loaded");
+        Ok(pending)
+    }
+
+    /// Record output about to be sent to Telegram to `topic_id` and drop the
+    /// text buffered for `source_topic_id` it was formatted from, in one
+    /// transaction. Returns the queue id to pass to [`Self::delete_outbound`]
+    /// once the send succeeds.
+    pub async fn enqueue_outbound(
+        &self,
+        chat_id: i64,
+        source_topic_id: i32,
+        topic_id: i32,
+        text: &str,
+        reply_to: Option<i32>,
+    ) -> Result<i64> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs() as i64;
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM outbound_buffer WHERE chat_id = ? AND topic_id = ?")
+            .bind(chat_id)
+            .bind(source_topic_id)
+            .execute(&mut *tx)
+            .await?;
+        let result = sqlx::query(
+            "INSERT INTO outbound_queue (chat_id, topic_id, text, reply_to, queued_at)
+             VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(chat_id)
+        .bind(topic_id)
+        .bind(text)
+        .bind(reply_to)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(result.last_insert_rowid())
+    }
+
+    /// Remove a delivered message from the outbound queue.
+    pub async fn delete_outbound(&self, id: i64) -> Result<()> {
+        retry_busy(|| {
+            sqlx::query("DELETE FROM outbound_queue WHERE id = ?")
+                .bind(id)
+                .execute(&self.pool)
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// Messages still waiting for a confirmed send, oldest first.
+    pub async fn pending_outbound(&self) -> Result<Vec<OutboundMessage>> {
+        let rows = sqlx::query(
+            "SELECT id, chat_id, topic_id, text, reply_to, queued_at
+             FROM outbound_queue ORDER BY id",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| OutboundMessage {
+                id: row.get(0),
+                chat_id: row.get(1),
+                topic_id: row.get(2),
+                text: row.get(3),
+                reply_to: row.get(4),
+                queued_at: row.get(5),
+            })
+            .collect())
+    }
+
+    /// Record the text batched so far for a topic, replacing what was
+    /// recorded before.
+    pub async fn buffer_outbound(
+        &self,
+        chat_id: i64,
+        topic_id: i32,
+        text: &str,
+        reply_to: Option<i32>,
+    ) -> Result<()> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs() as i64;
+        retry_busy(|| {
+            sqlx::query(
+                "INSERT OR REPLACE INTO outbound_buffer (chat_id, topic_id, text, reply_to, updated_at)
+                 VALUES (?, ?, ?, ?, ?)",
+            )
+            .bind(chat_id)
+            .bind(topic_id)
+            .bind(text)
+            .bind(reply_to)
+            .bind(now)
+            .execute(&self.pool)
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// Forget a topic's batched text without queueing it.
+    pub async fn clear_outbound_buffer(&self, chat_id: i64, topic_id: i32) -> Result<()> {
+        retry_busy(|| {
+            sqlx::query("DELETE FROM outbound_buffer WHERE chat_id = ? AND topic_id = ?")
+                .bind(chat_id)
+                .bind(topic_id)
+                .execute(&self.pool)
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// Batched text left behind by a previous run, oldest first.
+    pub async fn buffered_outbound(&self) -> Result<Vec<BufferedOutput>> {
+        let rows = sqlx::query(
+            "SELECT chat_id, topic_id, text, reply_to, updated_at
+             FROM outbound_buffer ORDER BY updated_at, topic_id",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| BufferedOutput {
+                chat_id: row.get(0),
+                topic_id: row.get(1),
+                text: row.get(2),
+                reply_to: row.get(3),
+                updated_at: row.get(4),
+            })
+            .collect())
+    }
+
+    #[allow(dead_code)]
+    // Used by future: manual cleanup and admin reporting
+    pub async fn get_stale_mappings(&self, older_than: Duration) -> Result<Vec<TopicMapping>> {
+        debug!("Looking up stale mappings");
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs() as i64;
+
+        let threshold = now - older_than.as_secs() as i64;
+
+        let rows = sqlx::query(
+            "SELECT topic_id, chat_id, project_path, session_id, instance_id,
+                    topic_name_updated, created_at, updated_at, read_only, output_topic_id,
+                    system_prompt_mtime, locale, last_activity_at
+             FROM topic_mappings WHERE updated_at < ?",
+        )
+        .bind(threshold)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mappings: Vec<TopicMapping> = rows
+            .into_iter()
+            .map(|row| TopicMapping {
+                topic_id: row.get(0),
+                chat_id: row.get(1),
+                project_path: row.get(2),
+                session_id: row.get(3),
+                instance_id: row.get(4),
+                topic_name_updated: row.get::<i32, _>(5) != 0,
+                created_at: row.get(6),
+                updated_at: row.get(7),
+                read_only: row.get::<i32, _>(8) != 0,
+                output_topic_id: row.get(9),
+                system_prompt_mtime: row.get(10),
+                locale: row.get(11),
+                last_activity_at: row.get(12),
+            })
+            .collect();
+
+        debug!(count = mappings.len(), "Stale mappings found");
+        Ok(mappings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn create_test_mapping(topic_id: i32, chat_id: i64) -> TopicMapping {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+
+        TopicMapping {
+            topic_id,
+            chat_id,
+            project_path: "/test/project".to_string(),
+            session_id: None,
+            instance_id: None,
+
+            topic_name_updated: false,
+            read_only: false,
+            output_topic_id: None,
+            system_prompt_mtime: None,
+            locale: None,
+            last_activity_at: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_new_creates_store_with_valid_db() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("topics.db");
+
+        let _store = TopicStore::new(&db_path).await.unwrap();
+
+        // Verify database file was created
+        assert!(db_path.exists());
+    }
+
+    #[tokio::test]
+    async fn test_save_mapping_inserts_new_mapping() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("topics.db");
+        let store = TopicStore::new(&db_path).await.unwrap();
+
+        let mapping = create_test_mapping(123, -1001234567890);
+        store.save_mapping(&mapping).await.unwrap();
+
+        // Verify mapping was saved
+        let retrieved = store.get_mapping(-1001234567890, 123).await.unwrap();
+        assert!(retrieved.is_some());
+        let retrieved = retrieved.unwrap();
+        assert_eq!(retrieved.topic_id, 123);
+        assert_eq!(retrieved.chat_id, -1001234567890);
+        assert_eq!(retrieved.project_path, "/test/project");
+    }
+
+    #[tokio::test]
+    async fn test_save_mapping_updates_existing_mapping() {
         let temp_dir = TempDir::new().unwrap();
         let db_path = temp_dir.path().join("topics.db");
         let store = TopicStore::new(&db_path).await.unwrap();
@@ -406,6 +1064,88 @@ mod tests {
         assert_eq!(results.len(), 0);
     }
 
+    #[tokio::test]
+    async fn test_count_mappings_by_chat_excludes_archived() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("topics.db");
+        let store = TopicStore::new(&db_path).await.unwrap();
+
+        let chat_id = -1001515151515;
+        store
+            .save_mapping(&create_test_mapping(1, chat_id))
+            .await
+            .unwrap();
+        store
+            .save_mapping(&create_test_mapping(2, chat_id))
+            .await
+            .unwrap();
+        store
+            .save_mapping(&create_test_mapping(3, -1002222222222))
+            .await
+            .unwrap();
+        assert_eq!(store.count_mappings_by_chat(chat_id).await.unwrap(), 2);
+
+        store.archive_mapping(chat_id, 2).await.unwrap();
+        assert_eq!(store.count_mappings_by_chat(chat_id).await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_get_active_mappings_excludes_archived() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("topics.db");
+        let store = TopicStore::new(&db_path).await.unwrap();
+
+        store
+            .save_mapping(&create_test_mapping(1, -1001515151515))
+            .await
+            .unwrap();
+        store
+            .save_mapping(&create_test_mapping(2, -1002222222222))
+            .await
+            .unwrap();
+        store.archive_mapping(-1002222222222, 2).await.unwrap();
+
+        let active = store.get_active_mappings().await.unwrap();
+        assert_eq!(active.len(), 1);
+        assert_eq!(active[0].topic_id, 1);
+        assert_eq!(store.get_all_mappings().await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_count_mappings_by_project_excludes_archived() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("topics.db");
+        let store = TopicStore::new(&db_path).await.unwrap();
+
+        store
+            .save_mapping(&create_test_mapping(1, -1001515151515))
+            .await
+            .unwrap();
+        store
+            .save_mapping(&create_test_mapping(2, -1002222222222))
+            .await
+            .unwrap();
+        let mut other = create_test_mapping(3, -1001515151515);
+        other.project_path = "/test/other".to_string();
+        store.save_mapping(&other).await.unwrap();
+        assert_eq!(
+            store
+                .count_mappings_by_project("/test/project")
+                .await
+                .unwrap(),
+            2
+        );
+
+        store.archive_mapping(-1002222222222, 2).await.unwrap();
+        assert_eq!(
+            store
+                .count_mappings_by_project("/test/project")
+                .await
+                .unwrap(),
+            1
+        );
+    }
+
     #[tokio::test]
     async fn test_get_mapping_by_session_finds_mapping() {
         let temp_dir = TempDir::new().unwrap();
@@ -498,6 +1238,119 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[tokio::test]
+    async fn test_set_read_only_toggles_flag() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("topics.db");
+        let store = TopicStore::new(&db_path).await.unwrap();
+
+        let mapping = create_test_mapping(778, -1006666666666);
+        store.save_mapping(&mapping).await.unwrap();
+        assert!(
+            !store
+                .get_mapping(-1006666666666, 778)
+                .await
+                .unwrap()
+                .unwrap()
+                .read_only
+        );
+
+        store
+            .set_read_only(-1006666666666, 778, true)
+            .await
+            .unwrap();
+        let retrieved = store
+            .get_mapping(-1006666666666, 778)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(retrieved.read_only);
+
+        store
+            .set_read_only(-1006666666666, 778, false)
+            .await
+            .unwrap();
+        let retrieved = store
+            .get_mapping(-1006666666666, 778)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(!retrieved.read_only);
+
+        assert!(store
+            .set_read_only(-1006666666666, 999, true)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_set_output_topic_persists() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("topics.db");
+        let store = TopicStore::new(&db_path).await.unwrap();
+
+        store
+            .save_mapping(&create_test_mapping(779, -1006666666666))
+            .await
+            .unwrap();
+
+        store
+            .set_output_topic(-1006666666666, 779, Some(780))
+            .await
+            .unwrap();
+        let retrieved = store
+            .get_mapping(-1006666666666, 779)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(retrieved.output_topic_id, Some(780));
+
+        store
+            .set_output_topic(-1006666666666, 779, None)
+            .await
+            .unwrap();
+        let retrieved = store
+            .get_mapping(-1006666666666, 779)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(retrieved.output_topic_id, None);
+    }
+
+    #[tokio::test]
+    async fn test_set_locale_persists() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("topics.db");
+        let store = TopicStore::new(&db_path).await.unwrap();
+
+        let mapping = create_test_mapping(780, -1006666666666);
+        store.save_mapping(&mapping).await.unwrap();
+
+        store
+            .set_locale(-1006666666666, 780, Some("de"))
+            .await
+            .unwrap();
+        let retrieved = store
+            .get_mapping(-1006666666666, 780)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(retrieved.locale.as_deref(), Some("de"));
+
+        store.set_locale(-1006666666666, 780, None).await.unwrap();
+        let retrieved = store
+            .get_mapping(-1006666666666, 780)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(retrieved.locale, None);
+
+        assert!(store
+            .set_locale(-1006666666666, 781, Some("de"))
+            .await
+            .is_err());
+    }
+
     #[tokio::test]
     async fn test_delete_mapping_removes_mapping() {
         let temp_dir = TempDir::new().unwrap();
@@ -524,6 +1377,90 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[tokio::test]
+    async fn test_archive_mapping_hides_mapping() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("topics.db");
+        let store = TopicStore::new(&db_path).await.unwrap();
+
+        let mut mapping = create_test_mapping(901, -1001212121212);
+        mapping.session_id = Some("session-archived".to_string());
+        store.save_mapping(&mapping).await.unwrap();
+
+        store.archive_mapping(-1001212121212, 901).await.unwrap();
+
+        assert!(store
+            .get_mapping(-1001212121212, 901)
+            .await
+            .unwrap()
+            .is_none());
+        assert!(store
+            .get_mappings_by_chat(-1001212121212)
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(store
+            .get_mapping_by_session("session-archived")
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_archive_mapping_fails_for_nonexistent_mapping() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("topics.db");
+        let store = TopicStore::new(&db_path).await.unwrap();
+
+        let result = store.archive_mapping(-1009999999999, 999).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_save_mapping_unarchives_topic() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("topics.db");
+        let store = TopicStore::new(&db_path).await.unwrap();
+
+        let mut mapping = create_test_mapping(902, -1001313131313);
+        store.save_mapping(&mapping).await.unwrap();
+        store.archive_mapping(-1001313131313, 902).await.unwrap();
+
+        mapping.project_path = "/test/other".to_string();
+        store.save_mapping(&mapping).await.unwrap();
+
+        let retrieved = store
+            .get_mapping(-1001313131313, 902)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(retrieved.project_path, "/test/other");
+    }
+
+    #[tokio::test]
+    async fn test_archived_flag_persists_across_reconnects() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("topics.db");
+
+        {
+            let store = TopicStore::new(&db_path).await.unwrap();
+            store
+                .save_mapping(&create_test_mapping(903, -1001414141414))
+                .await
+                .unwrap();
+            store.archive_mapping(-1001414141414, 903).await.unwrap();
+        }
+
+        {
+            let store = TopicStore::new(&db_path).await.unwrap();
+            assert!(store
+                .get_mapping(-1001414141414, 903)
+                .await
+                .unwrap()
+                .is_none());
+        }
+    }
+
     #[tokio::test]
     async fn test_get_stale_mappings_returns_old_mappings() {
         let temp_dir = TempDir::new().unwrap();
@@ -612,4 +1549,259 @@ mod tests {
 
         assert!(retrieved.topic_name_updated);
     }
+
+    #[tokio::test]
+    async fn test_export_then_import_into_fresh_store() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = TopicStore::new(&temp_dir.path().join("topics.db"))
+            .await
+            .unwrap();
+
+        let mut mapping = create_test_mapping(7, -1001234567890);
+        mapping.session_id = Some("ses_abc".to_string());
+        mapping.locale = Some("de".to_string());
+        mapping.read_only = true;
+        store.save_mapping(&mapping).await.unwrap();
+        store
+            .save_mapping(&create_test_mapping(3, -1001234567890))
+            .await
+            .unwrap();
+        store
+            .save_mapping(&create_test_mapping(9, -1009876543210))
+            .await
+            .unwrap();
+        store.archive_mapping(-1009876543210, 9).await.unwrap();
+
+        store
+            .save_mapping(&create_test_mapping(4, -1009876543210))
+            .await
+            .unwrap();
+
+        // Only this chat's active mappings
+        let exported = store.export_chat(-1001234567890).await.unwrap();
+        let keys: Vec<(i64, i32)> = exported.iter().map(|m| (m.chat_id, m.topic_id)).collect();
+        assert_eq!(keys, vec![(-1001234567890, 3), (-1001234567890, 7)]);
+
+        // Through JSON, as the /export_mappings file does
+        let json = serde_json::to_vec(&exported).unwrap();
+        let parsed: Vec<TopicMapping> = serde_json::from_slice(&json).unwrap();
+
+        let fresh = TopicStore::new(&temp_dir.path().join("fresh.db"))
+            .await
+            .unwrap();
+        let result = fresh
+            .import(-1001234567890, Path::new("/test"), &parsed)
+            .await
+            .unwrap();
+        assert_eq!(result.imported, 2);
+        assert!(result.conflicts.is_empty());
+
+        let restored = fresh.get_mapping(-1001234567890, 7).await.unwrap().unwrap();
+        assert_eq!(restored.project_path, "/test/project");
+        assert_eq!(restored.session_id.as_deref(), Some("ses_abc"));
+        assert_eq!(restored.locale.as_deref(), Some("de"));
+        assert!(restored.read_only);
+        assert_eq!(restored.created_at, mapping.created_at);
+        assert_eq!(fresh.export_chat(-1001234567890).await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_import_reports_conflicts_and_rejects_invalid_entries() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = TopicStore::new(&temp_dir.path().join("topics.db"))
+            .await
+            .unwrap();
+        store
+            .save_mapping(&create_test_mapping(5, -1001234567890))
+            .await
+            .unwrap();
+
+        let mut moved = create_test_mapping(5, -1001234567890);
+        moved.project_path = "/test/other".to_string();
+        let base = Path::new("/test");
+        let result = store
+            .import(-1001234567890, base, &[moved.clone()])
+            .await
+            .unwrap();
+        assert_eq!(result.imported, 1);
+        assert_eq!(
+            result.conflicts,
+            vec![MappingConflict {
+                chat_id: -1001234567890,
+                topic_id: 5,
+                existing_project: "/test/project".to_string(),
+                imported_project: "/test/other".to_string(),
+            }]
+        );
+
+        // Nothing is written when any entry is invalid
+        let mut invalid = create_test_mapping(6, -1001234567890);
+        invalid.project_path = String::new();
+        let new_topic = create_test_mapping(8, -1001234567890);
+        let err = store
+            .import(-1001234567890, base, &[new_topic.clone(), invalid])
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("no project path"));
+        assert!(store
+            .get_mapping(-1001234567890, 8)
+            .await
+            .unwrap()
+            .is_none());
+
+        let err = store
+            .import(
+                -1001234567890,
+                base,
+                &[new_topic.clone(), new_topic.clone()],
+            )
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("more than once"));
+
+        // Host paths outside PROJECT_BASE_PATH and other chats' topics are refused
+        for path in ["/etc", "/test/../root", "test/project", "/test"] {
+            let mut escape = create_test_mapping(6, -1001234567890);
+            escape.project_path = path.to_string();
+            let err = store
+                .import(-1001234567890, base, &[new_topic.clone(), escape])
+                .await
+                .unwrap_err();
+            assert!(err.to_string().contains("outside /test"), "{}", path);
+        }
+        let other_chat = create_test_mapping(6, -1009876543210);
+        let err = store
+            .import(-1001234567890, base, &[other_chat])
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("not this chat"));
+        assert!(store
+            .get_mapping(-1001234567890, 8)
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_outbound_queue_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = TopicStore::new(&temp_dir.path().join("topics.db"))
+            .await
+            .unwrap();
+
+        let first = store
+            .enqueue_outbound(-1001234567890, 42, 42, "<b>first</b>", Some(777))
+            .await
+            .unwrap();
+        store
+            .enqueue_outbound(-1001234567890, 43, 43, "second", None)
+            .await
+            .unwrap();
+
+        let pending = store.pending_outbound().await.unwrap();
+        assert_eq!(pending.len(), 2);
+        assert_eq!(pending[0].id, first);
+        assert_eq!(pending[0].text, "<b>first</b>");
+        assert_eq!(pending[0].reply_to, Some(777));
+        assert_eq!(pending[1].topic_id, 43);
+
+        store.delete_outbound(first).await.unwrap();
+        let pending = store.pending_outbound().await.unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].text, "second");
+    }
+
+    #[tokio::test]
+    async fn test_outbound_buffer_is_replaced_and_moved_to_queue() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = TopicStore::new(&temp_dir.path().join("topics.db"))
+            .await
+            .unwrap();
+
+        store
+            .buffer_outbound(-1001234567890, 42, "Hello", Some(777))
+            .await
+            .unwrap();
+        store
+            .buffer_outbound(-1001234567890, 42, "Hello world", Some(777))
+            .await
+            .unwrap();
+        store
+            .buffer_outbound(-1001234567890, 43, "Other topic", None)
+            .await
+            .unwrap();
+        let buffered = store.buffered_outbound().await.unwrap();
+        assert_eq!(buffered.len(), 2);
+        assert_eq!(buffered[0].text, "Hello world");
+        assert_eq!(buffered[0].reply_to, Some(777));
+
+        // Queueing the formatted text for an output topic drops the source buffer
+        store
+            .enqueue_outbound(-1001234567890, 42, 99, "<b>Hello world</b>", Some(777))
+            .await
+            .unwrap();
+        let buffered = store.buffered_outbound().await.unwrap();
+        assert_eq!(buffered.len(), 1);
+        assert_eq!(buffered[0].topic_id, 43);
+        let pending = store.pending_outbound().await.unwrap();
+        assert_eq!(pending[0].topic_id, 99);
+
+        store
+            .clear_outbound_buffer(-1001234567890, 43)
+            .await
+            .unwrap();
+        assert!(store.buffered_outbound().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_pending_generations_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("topics.db");
+        let pending = vec![
+            PendingGeneration {
+                chat_id: -1001234567890,
+                topic_id: 42,
+                session_id: "ses_a".to_string(),
+                last_message_id: Some(1001),
+                prompt: Some(r#"[{"type":"text","text":"hi"}]"#.to_string()),
+                saved_at: 1_700_000_000,
+            },
+            PendingGeneration {
+                chat_id: -1001234567890,
+                topic_id: 43,
+                session_id: "ses_b".to_string(),
+                last_message_id: None,
+                prompt: None,
+                saved_at: 1_700_000_000,
+            },
+        ];
+
+        {
+            let store = TopicStore::new(&db_path).await.unwrap();
+            for generation in &pending {
+                store.save_pending_generation(generation).await.unwrap();
+            }
+        }
+
+        // Markers survive a reconnect and are cleared once taken
+        let store = TopicStore::new(&db_path).await.unwrap();
+        assert_eq!(store.take_pending_generations().await.unwrap(), pending);
+        assert!(store.take_pending_generations().await.unwrap().is_empty());
+
+        // Saving replaces a topic's marker; deleting removes only that topic
+        for generation in &pending {
+            store.save_pending_generation(generation).await.unwrap();
+        }
+        let mut replaced = pending[0].clone();
+        replaced.session_id = "ses_c".to_string();
+        store.save_pending_generation(&replaced).await.unwrap();
+        store
+            .delete_pending_generation(-1001234567890, 43)
+            .await
+            .unwrap();
+        assert_eq!(
+            store.take_pending_generations().await.unwrap(),
+            vec![replaced]
+        );
+    }
 }
@@ -1,10 +1,17 @@
+pub mod api;
+pub mod backoff;
 pub mod bot;
 pub mod config;
+pub mod config_check;
 pub mod db;
 pub mod forum;
 pub mod git;
 pub mod integration;
 pub mod opencode;
 pub mod orchestrator;
+pub mod project_config;
 pub mod telegram;
+#[cfg(test)]
+mod test_utils;
+pub mod transcription;
 pub mod types;
@@ -1,29 +1,44 @@
 use anyhow::Result;
 use dptree::case;
+use oc_outpost::api::instances::AppState;
+use oc_outpost::api::server as api_server;
 use oc_outpost::bot::{
-    dispatch_callback, handle_close, handle_help, handle_new, handle_projects, handle_session,
-    handle_sessions, handle_status,
+    dispatch_callback, handle_archive, handle_close, handle_compare, handle_cordon, handle_debug,
+    handle_export, handle_export_mappings, handle_help, handle_import_mappings, handle_lang,
+    handle_new, handle_observe, handle_output, handle_ping, handle_projects, handle_reload,
+    handle_restart, handle_retry_clean, handle_session, handle_sessions, handle_stats,
+    handle_status, handle_switch, handle_uncordon, handle_whoami, is_allowed_sender,
+    reject_unauthorized_message,
 };
-use oc_outpost::bot::{BotState, Command};
-use oc_outpost::config::Config;
+use oc_outpost::bot::{parse_message_command, BotState, Command};
+use oc_outpost::config::{Config, SharedConfig};
+use oc_outpost::config_check;
 use oc_outpost::db::log_store::LogStore;
 use oc_outpost::db::tracing_layer::DatabaseLayer;
 use oc_outpost::forum::TopicStore;
 use oc_outpost::integration::Integration;
-use oc_outpost::opencode::stream_handler::StreamHandler;
+use oc_outpost::opencode::stream_handler::{StreamConfig, StreamHandler};
 use oc_outpost::opencode::OpenCodeClient;
-use oc_outpost::orchestrator::container::DockerRuntime;
+use oc_outpost::orchestrator::container::{ContainerRuntime, DockerRuntime};
 use oc_outpost::orchestrator::manager::InstanceManager;
 use oc_outpost::orchestrator::port_pool::PortPool;
 use oc_outpost::orchestrator::store::OrchestratorStore;
+use oc_outpost::telegram::polling::{PollingErrorHandler, AUTH_FAILURE_EXIT_CODE};
 use oc_outpost::types::error::OutpostError;
 use std::sync::Arc;
 use std::time::Instant;
 use teloxide::prelude::*;
+use teloxide::types::Me;
+use teloxide::update_listeners;
 use tokio::signal;
+use tokio_util::sync::CancellationToken;
 use tracing::{debug, error, info, warn};
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{reload, EnvFilter, Registry};
+
+/// Handle for swapping the log filter at runtime.
+type LogFilterHandle = reload::Handle<EnvFilter, Registry>;
 
 fn log_command_error(
     command: &str,
@@ -56,8 +71,112 @@ fn log_command_error(
     }
 }
 
+/// Reply sent when a command arrives as a media caption.
+const IGNORED_ATTACHMENT_MESSAGE: &str =
+    "Ran the command from the caption. The attachment was not sent to the agent.";
+
+/// Parse a command from the message text or a media caption.
+fn parse_command(msg: Message, me: Me) -> Option<Command> {
+    parse_message_command(&msg, me.username())
+}
+
+/// A command in a media caption runs as usual, but the attachment is not
+/// forwarded to the agent, so say so. `/import_mappings` reads its attachment.
+async fn note_ignored_attachment(bot: Bot, msg: Message, cmd: Command) {
+    if msg.text().is_some() || cmd == Command::ImportMappings {
+        return;
+    }
+    debug!(
+        chat_id = msg.chat.id.0,
+        topic_id = ?msg.thread_id.map(|t| t.0 .0),
+        "Command received as media caption, attachment not forwarded"
+    );
+    let mut request = bot.send_message(msg.chat.id, IGNORED_ATTACHMENT_MESSAGE);
+    if let Some(thread_id) = msg.thread_id {
+        request = request.message_thread_id(thread_id);
+    }
+    if let Err(e) = request.await {
+        warn!(error = %e, "Failed to note ignored attachment");
+    }
+}
+
+/// Re-read the environment after SIGHUP. Applies the hot-reloadable settings
+/// (as `/reload` does) and, when `RUST_LOG` is set, the log filter. Settings
+/// that need a restart are left alone with a warning.
+fn reload_on_hangup(config: &SharedConfig, log_filter: &LogFilterHandle) {
+    match Config::reload_from_env() {
+        Ok(fresh) => {
+            let ignored = config.get().restart_required_changes(&fresh);
+            if !ignored.is_empty() {
+                warn!(settings = ?ignored, "Changed settings require a restart; keeping startup values");
+            }
+            let changed = config.reload(&fresh);
+            info!(changed = ?changed, "Config reloaded on SIGHUP");
+        }
+        Err(e) => warn!(error = %e, "Config reload failed; keeping current config"),
+    }
+
+    if std::env::var_os("RUST_LOG").is_some() {
+        match EnvFilter::try_from_default_env() {
+            Ok(filter) => match log_filter.reload(filter) {
+                Ok(()) => info!("Log filter reloaded from RUST_LOG"),
+                Err(e) => warn!(error = %e, "Failed to apply new log filter"),
+            },
+            Err(e) => warn!(error = %e, "Invalid RUST_LOG; keeping current log filter"),
+        }
+    }
+}
+
+/// Reload config on every SIGHUP until the process exits.
+#[cfg(unix)]
+fn spawn_hangup_reload(config: SharedConfig, log_filter: LogFilterHandle) {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    tokio::spawn(async move {
+        let mut hangup = match signal(SignalKind::hangup()) {
+            Ok(hangup) => hangup,
+            Err(e) => {
+                warn!(error = %e, "Failed to install SIGHUP handler; use /reload instead");
+                return;
+            }
+        };
+        while hangup.recv().await.is_some() {
+            info!("Received SIGHUP, reloading config...");
+            reload_on_hangup(&config, &log_filter);
+        }
+    });
+}
+
+/// `--check-config`: validate the configuration, print a report and exit
+/// without starting the bot. Exits with 1 if any check fails.
+async fn check_config() -> Result<()> {
+    let config = match Config::from_env() {
+        Ok(config) => config,
+        Err(e) => {
+            println!("[FAIL] environment: {:#}", e);
+            std::process::exit(1);
+        }
+    };
+    let runtime = DockerRuntime::new().map_err(|e| format!("{:#}", e));
+    let runtime = match &runtime {
+        Ok(runtime) => Ok(runtime as &dyn ContainerRuntime),
+        Err(e) => Err(e.clone()),
+    };
+
+    let report = config_check::validate(&config, runtime).await;
+    println!("{}", report);
+    if !report.ok() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
+    if std::env::args().skip(1).any(|arg| arg == "--check-config") {
+        return check_config().await;
+    }
+
     let config = Config::from_env()?;
     debug!("Config loaded from environment");
 
@@ -81,10 +200,11 @@ async fn main() -> Result<()> {
     } else {
         "oc_outpost=info"
     };
-    let env_filter = tracing_subscriber::EnvFilter::try_from_default_env()
-        .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new(default_filter));
+    let env_filter =
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(default_filter));
+    let (env_filter, log_filter) = reload::Layer::new(env_filter);
 
-    let db_layer = DatabaseLayer::new(
+    let (db_layer, log_writer) = DatabaseLayer::new(
         log_store.clone(),
         tokio::runtime::Handle::current(),
         run_id.clone(),
@@ -114,55 +234,180 @@ async fn main() -> Result<()> {
         "Port pool created"
     );
     let runtime = Arc::new(DockerRuntime::new()?);
-    let instance_manager = InstanceManager::new(
-        Arc::new(config.clone()),
-        store_for_manager,
-        port_pool,
-        runtime,
-    )
-    .await?;
+    // Shared between the manager and bot state so /reload reaches both
+    let shared_config = SharedConfig::new(config.clone());
+    let (failure_tx, mut failure_rx) = tokio::sync::mpsc::unbounded_channel();
+    let instance_manager =
+        InstanceManager::new(shared_config.clone(), store_for_manager, port_pool, runtime)
+            .await?
+            .with_event_log(log_store.clone())
+            .with_failure_notifier(failure_tx);
     debug!("Instance manager created");
 
     info!("Recovering instances from database...");
     instance_manager.recover_from_db().await?;
 
+    if !instance_manager.probe_runtime().await {
+        warn!("Container runtime unavailable; new instances will fail until it recovers");
+    }
+
     info!("Reconciling containers...");
     if let Err(e) = instance_manager.reconcile_containers().await {
         warn!(error = %e, "Container reconciliation failed (Docker may not be available)");
     }
 
+    info!("Reserving ports for recovered instances...");
+    instance_manager.reserve_recovered_ports().await?;
+
     info!("Starting health check loop...");
     let _health_check_handle = instance_manager.start_health_check_loop();
 
     let bot_start_time = Instant::now();
 
-    let bot_state = Arc::new(BotState::new(
-        orchestrator_store,
-        topic_store,
-        config.clone(),
-        instance_manager,
-        bot_start_time,
-    ));
+    let bot_state = Arc::new(
+        BotState::new(
+            orchestrator_store,
+            topic_store,
+            shared_config,
+            instance_manager,
+            bot_start_time,
+        )
+        .with_log_store(log_store.clone()),
+    );
     debug!("Bot state initialized");
 
+    // Pull the image in the background so the first topic skips the
+    // download; a pull cut short by shutdown is retried on the next spawn
+    tokio::spawn({
+        let bot_state = Arc::clone(&bot_state);
+        async move {
+            match bot_state.instance_manager.prepull_image().await {
+                Ok(true) => info!("Pulled container image"),
+                Ok(false) => debug!("Container image already present"),
+                Err(e) => warn!(error = %e, "Failed to pull container image"),
+            }
+        }
+    });
+
+    let api_state = AppState {
+        instance_manager: Arc::clone(&bot_state.instance_manager),
+    };
+    let api_shutdown = CancellationToken::new();
+    let api_server = match api_server::spawn(&config, api_state, api_shutdown.clone()).await {
+        Ok(Some((addr, handle))) => {
+            info!(%addr, "API server listening");
+            Some(handle)
+        }
+        Ok(None) => {
+            debug!("API server disabled (API_PORT=0)");
+            None
+        }
+        Err(e) => {
+            warn!(error = %e, "Failed to start API server");
+            None
+        }
+    };
+
+    #[cfg(unix)]
+    spawn_hangup_reload(bot_state.config.clone(), log_filter);
+    #[cfg(not(unix))]
+    drop(log_filter);
+
     let bot = Bot::new(&config.telegram_bot_token);
 
-    let opencode_client =
-        OpenCodeClient::new(&format!("http://localhost:{}", config.opencode_port_start));
-    let stream_handler = Arc::new(StreamHandler::new(opencode_client));
+    let opencode_client = OpenCodeClient::for_port(&config, config.opencode_port_start)?;
+    let stream_handler = Arc::new(StreamHandler::new(
+        opencode_client,
+        StreamConfig::from_config(&config),
+    ));
 
-    let integration = Arc::new(Integration::new(bot_state.clone(), stream_handler));
+    let integration = Arc::new(Integration::new(
+        bot_state.clone(),
+        Arc::clone(&stream_handler),
+    ));
+
+    // Tell topics when their instance used up its restart attempts
+    tokio::spawn({
+        let integration = Arc::clone(&integration);
+        let bot = bot.clone();
+        async move {
+            while let Some(failure) = failure_rx.recv().await {
+                if let Err(e) = integration.notify_instance_failure(&bot, &failure).await {
+                    warn!(topic_id = failure.topic_id, error = %e, "Failed to report instance failure");
+                }
+            }
+        }
+    });
+
+    // Before streams resume, so output from the last run goes out first and
+    // new text for a topic is not mistaken for text the last run left behind
+    match integration.replay_outbound(&bot).await {
+        Ok(0) => {}
+        Ok(count) => info!(count, "Delivered queued outbound messages"),
+        Err(e) => warn!(error = %e, "Failed to replay queued outbound messages"),
+    }
+
+    info!("Restoring stream subscriptions...");
+    match integration.resubscribe_all(bot.clone()).await {
+        Ok(count) => info!(count, "Stream subscriptions restored"),
+        Err(e) => warn!(error = %e, "Failed to restore stream subscriptions"),
+    }
+    match integration.resume_in_flight(bot.clone()).await {
+        Ok(0) => {}
+        Ok(count) => info!(count, "Resent prompts interrupted by the restart"),
+        Err(e) => warn!(error = %e, "Failed to resend interrupted prompts"),
+    }
 
     let handler = dptree::entry()
+        // /whoami answers in any chat and for any user, so people can find
+        // the ids to put in TELEGRAM_CHAT_IDS and TELEGRAM_ALLOWED_USERS.
+        .branch(Update::filter_message().filter_map(parse_command).branch(
+            case![Command::Whoami].endpoint({
+                let state = Arc::clone(&bot_state);
+                move |bot: Bot, msg: Message, cmd: Command| {
+                    let state = Arc::clone(&state);
+                    async move {
+                        let chat_id = msg.chat.id.0;
+                        let topic_id = msg.thread_id.map(|t| t.0 .0);
+                        let sender_id = msg.from.as_ref().map(|u| u.id.0);
+                        let sender_username = msg.from.as_ref().and_then(|u| u.username.clone());
+                        if let Err(e) = handle_whoami(bot, msg, cmd, state).await {
+                            log_command_error(
+                                "/whoami",
+                                &e,
+                                chat_id,
+                                topic_id,
+                                sender_id,
+                                sender_username.as_deref(),
+                            );
+                        }
+                        respond(())
+                    }
+                }
+            }),
+        ))
         .branch(
             Update::filter_message()
                 .filter({
                     let config = config.clone();
                     move |msg: Message| config.is_whitelisted_chat(msg.chat.id.0)
                 })
+                .branch(
+                    dptree::filter({
+                        let config = config.clone();
+                        move |msg: Message| !is_allowed_sender(&config, msg.from.as_ref())
+                    })
+                    .endpoint(|bot: Bot, msg: Message| async move {
+                        if let Err(e) = reject_unauthorized_message(bot, msg).await {
+                            warn!(error = %e, "Failed to reply to unauthorized user");
+                        }
+                        respond(())
+                    }),
+                )
                 .branch(
                     dptree::entry()
-                        .filter_command::<Command>()
+                        .filter_map(parse_command)
+                        .inspect_async(note_ignored_attachment)
                         .branch(case![Command::New(name)].endpoint({
                             let state = Arc::clone(&bot_state);
                             move |bot: Bot, msg: Message, cmd: Command| {
@@ -235,17 +480,21 @@ async fn main() -> Result<()> {
                                 }
                             }
                         }))
-                        .branch(case![Command::Close].endpoint({
+                        .branch(case![Command::Close(args)].endpoint({
                             let state = Arc::clone(&bot_state);
+                            let stream_handler = Arc::clone(&stream_handler);
                             move |bot: Bot, msg: Message, cmd: Command| {
                                 let state = Arc::clone(&state);
+                                let stream_handler = Arc::clone(&stream_handler);
                                 async move {
                                     let chat_id = msg.chat.id.0;
                                     let topic_id = msg.thread_id.map(|t| t.0 .0);
                                     let sender_id = msg.from.as_ref().map(|u| u.id.0);
                                     let sender_username =
                                         msg.from.as_ref().and_then(|u| u.username.clone());
-                                    if let Err(e) = handle_close(bot, msg, cmd, state).await {
+                                    if let Err(e) =
+                                        handle_close(bot, msg, cmd, state, stream_handler).await
+                                    {
                                         log_command_error(
                                             "/close",
                                             &e,
@@ -259,6 +508,30 @@ async fn main() -> Result<()> {
                                 }
                             }
                         }))
+                        .branch(case![Command::Archive].endpoint({
+                            let state = Arc::clone(&bot_state);
+                            move |bot: Bot, msg: Message, cmd: Command| {
+                                let state = Arc::clone(&state);
+                                async move {
+                                    let chat_id = msg.chat.id.0;
+                                    let topic_id = msg.thread_id.map(|t| t.0 .0);
+                                    let sender_id = msg.from.as_ref().map(|u| u.id.0);
+                                    let sender_username =
+                                        msg.from.as_ref().and_then(|u| u.username.clone());
+                                    if let Err(e) = handle_archive(bot, msg, cmd, state).await {
+                                        log_command_error(
+                                            "/archive",
+                                            &e,
+                                            chat_id,
+                                            topic_id,
+                                            sender_id,
+                                            sender_username.as_deref(),
+                                        );
+                                    }
+                                    respond(())
+                                }
+                            }
+                        }))
                         .branch(case![Command::Session].endpoint({
                             let state = Arc::clone(&bot_state);
                             move |bot: Bot, msg: Message, cmd: Command| {
@@ -283,17 +556,134 @@ async fn main() -> Result<()> {
                                 }
                             }
                         }))
+                        .branch(case![Command::Export].endpoint({
+                            let state = Arc::clone(&bot_state);
+                            move |bot: Bot, msg: Message, cmd: Command| {
+                                let state = Arc::clone(&state);
+                                async move {
+                                    let chat_id = msg.chat.id.0;
+                                    let topic_id = msg.thread_id.map(|t| t.0 .0);
+                                    let sender_id = msg.from.as_ref().map(|u| u.id.0);
+                                    let sender_username =
+                                        msg.from.as_ref().and_then(|u| u.username.clone());
+                                    if let Err(e) = handle_export(bot, msg, cmd, state).await {
+                                        log_command_error(
+                                            "/export",
+                                            &e,
+                                            chat_id,
+                                            topic_id,
+                                            sender_id,
+                                            sender_username.as_deref(),
+                                        );
+                                    }
+                                    respond(())
+                                }
+                            }
+                        }))
+                        .branch(case![Command::RetryClean].endpoint({
+                            let state = Arc::clone(&bot_state);
+                            let stream_handler = Arc::clone(&stream_handler);
+                            let integration = Arc::clone(&integration);
+                            move |bot: Bot, msg: Message, cmd: Command| {
+                                let state = Arc::clone(&state);
+                                let stream_handler = Arc::clone(&stream_handler);
+                                let integration = Arc::clone(&integration);
+                                async move {
+                                    let chat_id = msg.chat.id.0;
+                                    let topic_id = msg.thread_id.map(|t| t.0 .0);
+                                    let sender_id = msg.from.as_ref().map(|u| u.id.0);
+                                    let sender_username =
+                                        msg.from.as_ref().and_then(|u| u.username.clone());
+                                    if let Err(e) = handle_retry_clean(
+                                        bot,
+                                        msg,
+                                        cmd,
+                                        state,
+                                        stream_handler,
+                                        integration,
+                                    )
+                                    .await
+                                    {
+                                        log_command_error(
+                                            "/retry_clean",
+                                            &e,
+                                            chat_id,
+                                            topic_id,
+                                            sender_id,
+                                            sender_username.as_deref(),
+                                        );
+                                    }
+                                    respond(())
+                                }
+                            }
+                        }))
+                        .branch(case![Command::Compare(args)].endpoint({
+                            let state = Arc::clone(&bot_state);
+                            move |bot: Bot, msg: Message, cmd: Command| {
+                                let state = Arc::clone(&state);
+                                async move {
+                                    let chat_id = msg.chat.id.0;
+                                    let topic_id = msg.thread_id.map(|t| t.0 .0);
+                                    let sender_id = msg.from.as_ref().map(|u| u.id.0);
+                                    let sender_username =
+                                        msg.from.as_ref().and_then(|u| u.username.clone());
+                                    if let Err(e) = handle_compare(bot, msg, cmd, state).await {
+                                        log_command_error(
+                                            "/compare",
+                                            &e,
+                                            chat_id,
+                                            topic_id,
+                                            sender_id,
+                                            sender_username.as_deref(),
+                                        );
+                                    }
+                                    respond(())
+                                }
+                            }
+                        }))
+                        .branch(case![Command::Switch].endpoint({
+                            let state = Arc::clone(&bot_state);
+                            let integration = Arc::clone(&integration);
+                            move |bot: Bot, msg: Message, cmd: Command| {
+                                let state = Arc::clone(&state);
+                                let integration = Arc::clone(&integration);
+                                async move {
+                                    let chat_id = msg.chat.id.0;
+                                    let topic_id = msg.thread_id.map(|t| t.0 .0);
+                                    let sender_id = msg.from.as_ref().map(|u| u.id.0);
+                                    let sender_username =
+                                        msg.from.as_ref().and_then(|u| u.username.clone());
+                                    if let Err(e) =
+                                        handle_switch(bot, msg, cmd, state, integration).await
+                                    {
+                                        log_command_error(
+                                            "/switch",
+                                            &e,
+                                            chat_id,
+                                            topic_id,
+                                            sender_id,
+                                            sender_username.as_deref(),
+                                        );
+                                    }
+                                    respond(())
+                                }
+                            }
+                        }))
                         .branch(case![Command::Status].endpoint({
                             let state = Arc::clone(&bot_state);
+                            let integration = Arc::clone(&integration);
                             move |bot: Bot, msg: Message, cmd: Command| {
                                 let state = Arc::clone(&state);
+                                let integration = Arc::clone(&integration);
                                 async move {
                                     let chat_id = msg.chat.id.0;
                                     let topic_id = msg.thread_id.map(|t| t.0 .0);
                                     let sender_id = msg.from.as_ref().map(|u| u.id.0);
                                     let sender_username =
                                         msg.from.as_ref().and_then(|u| u.username.clone());
-                                    if let Err(e) = handle_status(bot, msg, cmd, state).await {
+                                    if let Err(e) =
+                                        handle_status(bot, msg, cmd, state, integration).await
+                                    {
                                         log_command_error(
                                             "/status",
                                             &e,
@@ -307,6 +697,314 @@ async fn main() -> Result<()> {
                                 }
                             }
                         }))
+                        .branch(case![Command::Stats(args)].endpoint({
+                            let state = Arc::clone(&bot_state);
+                            move |bot: Bot, msg: Message, cmd: Command| {
+                                let state = Arc::clone(&state);
+                                async move {
+                                    let chat_id = msg.chat.id.0;
+                                    let topic_id = msg.thread_id.map(|t| t.0 .0);
+                                    let sender_id = msg.from.as_ref().map(|u| u.id.0);
+                                    let sender_username =
+                                        msg.from.as_ref().and_then(|u| u.username.clone());
+                                    if let Err(e) = handle_stats(bot, msg, cmd, state).await {
+                                        log_command_error(
+                                            "/stats",
+                                            &e,
+                                            chat_id,
+                                            topic_id,
+                                            sender_id,
+                                            sender_username.as_deref(),
+                                        );
+                                    }
+                                    respond(())
+                                }
+                            }
+                        }))
+                        .branch(case![Command::Observe(args)].endpoint({
+                            let state = Arc::clone(&bot_state);
+                            move |bot: Bot, msg: Message, cmd: Command| {
+                                let state = Arc::clone(&state);
+                                async move {
+                                    let chat_id = msg.chat.id.0;
+                                    let topic_id = msg.thread_id.map(|t| t.0 .0);
+                                    let sender_id = msg.from.as_ref().map(|u| u.id.0);
+                                    let sender_username =
+                                        msg.from.as_ref().and_then(|u| u.username.clone());
+                                    if let Err(e) = handle_observe(bot, msg, cmd, state).await {
+                                        log_command_error(
+                                            "/observe",
+                                            &e,
+                                            chat_id,
+                                            topic_id,
+                                            sender_id,
+                                            sender_username.as_deref(),
+                                        );
+                                    }
+                                    respond(())
+                                }
+                            }
+                        }))
+                        .branch(case![Command::Output(args)].endpoint({
+                            let state = Arc::clone(&bot_state);
+                            move |bot: Bot, msg: Message, cmd: Command| {
+                                let state = Arc::clone(&state);
+                                async move {
+                                    let chat_id = msg.chat.id.0;
+                                    let topic_id = msg.thread_id.map(|t| t.0 .0);
+                                    let sender_id = msg.from.as_ref().map(|u| u.id.0);
+                                    let sender_username =
+                                        msg.from.as_ref().and_then(|u| u.username.clone());
+                                    if let Err(e) = handle_output(bot, msg, cmd, state).await {
+                                        log_command_error(
+                                            "/output",
+                                            &e,
+                                            chat_id,
+                                            topic_id,
+                                            sender_id,
+                                            sender_username.as_deref(),
+                                        );
+                                    }
+                                    respond(())
+                                }
+                            }
+                        }))
+                        .branch(case![Command::Lang(args)].endpoint({
+                            let state = Arc::clone(&bot_state);
+                            let integration = Arc::clone(&integration);
+                            move |bot: Bot, msg: Message, cmd: Command| {
+                                let state = Arc::clone(&state);
+                                let integration = Arc::clone(&integration);
+                                async move {
+                                    let chat_id = msg.chat.id.0;
+                                    let topic_id = msg.thread_id.map(|t| t.0 .0);
+                                    let sender_id = msg.from.as_ref().map(|u| u.id.0);
+                                    let sender_username =
+                                        msg.from.as_ref().and_then(|u| u.username.clone());
+                                    if let Err(e) =
+                                        handle_lang(bot, msg, cmd, state, integration).await
+                                    {
+                                        log_command_error(
+                                            "/lang",
+                                            &e,
+                                            chat_id,
+                                            topic_id,
+                                            sender_id,
+                                            sender_username.as_deref(),
+                                        );
+                                    }
+                                    respond(())
+                                }
+                            }
+                        }))
+                        .branch(case![Command::Restart].endpoint({
+                            let state = Arc::clone(&bot_state);
+                            let integration = Arc::clone(&integration);
+                            move |bot: Bot, msg: Message, cmd: Command| {
+                                let state = Arc::clone(&state);
+                                let integration = Arc::clone(&integration);
+                                async move {
+                                    let chat_id = msg.chat.id.0;
+                                    let topic_id = msg.thread_id.map(|t| t.0 .0);
+                                    let sender_id = msg.from.as_ref().map(|u| u.id.0);
+                                    let sender_username =
+                                        msg.from.as_ref().and_then(|u| u.username.clone());
+                                    if let Err(e) =
+                                        handle_restart(bot, msg, cmd, state, integration).await
+                                    {
+                                        log_command_error(
+                                            "/restart",
+                                            &e,
+                                            chat_id,
+                                            topic_id,
+                                            sender_id,
+                                            sender_username.as_deref(),
+                                        );
+                                    }
+                                    respond(())
+                                }
+                            }
+                        }))
+                        .branch(case![Command::Ping].endpoint({
+                            let state = Arc::clone(&bot_state);
+                            let integration = Arc::clone(&integration);
+                            move |bot: Bot, msg: Message, cmd: Command| {
+                                let state = Arc::clone(&state);
+                                let integration = Arc::clone(&integration);
+                                async move {
+                                    let chat_id = msg.chat.id.0;
+                                    let topic_id = msg.thread_id.map(|t| t.0 .0);
+                                    let sender_id = msg.from.as_ref().map(|u| u.id.0);
+                                    let sender_username =
+                                        msg.from.as_ref().and_then(|u| u.username.clone());
+                                    if let Err(e) =
+                                        handle_ping(bot, msg, cmd, state, integration).await
+                                    {
+                                        log_command_error(
+                                            "/ping",
+                                            &e,
+                                            chat_id,
+                                            topic_id,
+                                            sender_id,
+                                            sender_username.as_deref(),
+                                        );
+                                    }
+                                    respond(())
+                                }
+                            }
+                        }))
+                        .branch(case![Command::Cordon(args)].endpoint({
+                            let state = Arc::clone(&bot_state);
+                            move |bot: Bot, msg: Message, cmd: Command| {
+                                let state = Arc::clone(&state);
+                                async move {
+                                    let chat_id = msg.chat.id.0;
+                                    let topic_id = msg.thread_id.map(|t| t.0 .0);
+                                    let sender_id = msg.from.as_ref().map(|u| u.id.0);
+                                    let sender_username =
+                                        msg.from.as_ref().and_then(|u| u.username.clone());
+                                    if let Err(e) = handle_cordon(bot, msg, cmd, state).await {
+                                        log_command_error(
+                                            "/cordon",
+                                            &e,
+                                            chat_id,
+                                            topic_id,
+                                            sender_id,
+                                            sender_username.as_deref(),
+                                        );
+                                    }
+                                    respond(())
+                                }
+                            }
+                        }))
+                        .branch(case![Command::Uncordon(args)].endpoint({
+                            let state = Arc::clone(&bot_state);
+                            move |bot: Bot, msg: Message, cmd: Command| {
+                                let state = Arc::clone(&state);
+                                async move {
+                                    let chat_id = msg.chat.id.0;
+                                    let topic_id = msg.thread_id.map(|t| t.0 .0);
+                                    let sender_id = msg.from.as_ref().map(|u| u.id.0);
+                                    let sender_username =
+                                        msg.from.as_ref().and_then(|u| u.username.clone());
+                                    if let Err(e) = handle_uncordon(bot, msg, cmd, state).await {
+                                        log_command_error(
+                                            "/uncordon",
+                                            &e,
+                                            chat_id,
+                                            topic_id,
+                                            sender_id,
+                                            sender_username.as_deref(),
+                                        );
+                                    }
+                                    respond(())
+                                }
+                            }
+                        }))
+                        .branch(case![Command::Reload].endpoint({
+                            let state = Arc::clone(&bot_state);
+                            move |bot: Bot, msg: Message, cmd: Command| {
+                                let state = Arc::clone(&state);
+                                async move {
+                                    let chat_id = msg.chat.id.0;
+                                    let topic_id = msg.thread_id.map(|t| t.0 .0);
+                                    let sender_id = msg.from.as_ref().map(|u| u.id.0);
+                                    let sender_username =
+                                        msg.from.as_ref().and_then(|u| u.username.clone());
+                                    if let Err(e) = handle_reload(bot, msg, cmd, state).await {
+                                        log_command_error(
+                                            "/reload",
+                                            &e,
+                                            chat_id,
+                                            topic_id,
+                                            sender_id,
+                                            sender_username.as_deref(),
+                                        );
+                                    }
+                                    respond(())
+                                }
+                            }
+                        }))
+                        .branch(case![Command::ExportMappings].endpoint({
+                            let state = Arc::clone(&bot_state);
+                            move |bot: Bot, msg: Message, cmd: Command| {
+                                let state = Arc::clone(&state);
+                                async move {
+                                    let chat_id = msg.chat.id.0;
+                                    let topic_id = msg.thread_id.map(|t| t.0 .0);
+                                    let sender_id = msg.from.as_ref().map(|u| u.id.0);
+                                    let sender_username =
+                                        msg.from.as_ref().and_then(|u| u.username.clone());
+                                    if let Err(e) =
+                                        handle_export_mappings(bot, msg, cmd, state).await
+                                    {
+                                        log_command_error(
+                                            "/export_mappings",
+                                            &e,
+                                            chat_id,
+                                            topic_id,
+                                            sender_id,
+                                            sender_username.as_deref(),
+                                        );
+                                    }
+                                    respond(())
+                                }
+                            }
+                        }))
+                        .branch(case![Command::ImportMappings].endpoint({
+                            let state = Arc::clone(&bot_state);
+                            move |bot: Bot, msg: Message, cmd: Command| {
+                                let state = Arc::clone(&state);
+                                async move {
+                                    let chat_id = msg.chat.id.0;
+                                    let topic_id = msg.thread_id.map(|t| t.0 .0);
+                                    let sender_id = msg.from.as_ref().map(|u| u.id.0);
+                                    let sender_username =
+                                        msg.from.as_ref().and_then(|u| u.username.clone());
+                                    if let Err(e) =
+                                        handle_import_mappings(bot, msg, cmd, state).await
+                                    {
+                                        log_command_error(
+                                            "/import_mappings",
+                                            &e,
+                                            chat_id,
+                                            topic_id,
+                                            sender_id,
+                                            sender_username.as_deref(),
+                                        );
+                                    }
+                                    respond(())
+                                }
+                            }
+                        }))
+                        .branch(case![Command::Debug(args)].endpoint({
+                            let state = Arc::clone(&bot_state);
+                            let stream_handler = Arc::clone(&stream_handler);
+                            move |bot: Bot, msg: Message, cmd: Command| {
+                                let state = Arc::clone(&state);
+                                let stream_handler = Arc::clone(&stream_handler);
+                                async move {
+                                    let chat_id = msg.chat.id.0;
+                                    let topic_id = msg.thread_id.map(|t| t.0 .0);
+                                    let sender_id = msg.from.as_ref().map(|u| u.id.0);
+                                    let sender_username =
+                                        msg.from.as_ref().and_then(|u| u.username.clone());
+                                    if let Err(e) =
+                                        handle_debug(bot, msg, cmd, state, stream_handler).await
+                                    {
+                                        log_command_error(
+                                            "/debug",
+                                            &e,
+                                            chat_id,
+                                            topic_id,
+                                            sender_id,
+                                            sender_username.as_deref(),
+                                        );
+                                    }
+                                    respond(())
+                                }
+                            }
+                        }))
                         .branch(case![Command::Help].endpoint({
                             let state = Arc::clone(&bot_state);
                             move |bot: Bot, msg: Message, cmd: Command| {
@@ -343,7 +1041,12 @@ async fn main() -> Result<()> {
                             let sender_username =
                                 msg.from.as_ref().and_then(|u| u.username.clone());
 
-                            if let Err(e) = integration.handle_message(bot, msg).await {
+                            let result = if msg.media_group_id().is_some() {
+                                integration.handle_media_group_message(bot, msg).await
+                            } else {
+                                integration.handle_message(bot, msg).await
+                            };
+                            if let Err(e) = result {
                                 if e.is_user_error() {
                                     warn!(
                                         chat_id = chat_id,
@@ -369,14 +1072,52 @@ async fn main() -> Result<()> {
                     }
                 })),
         )
+        .branch(
+            Update::filter_edited_message()
+                .filter({
+                    let config = config.clone();
+                    move |msg: Message| {
+                        config.is_whitelisted_chat(msg.chat.id.0)
+                            && is_allowed_sender(&config, msg.from.as_ref())
+                    }
+                })
+                .endpoint({
+                    let integration = Arc::clone(&integration);
+                    move |bot: Bot, msg: Message| {
+                        let integration = Arc::clone(&integration);
+                        async move {
+                            let chat_id = msg.chat.id.0;
+                            let topic_id = msg.thread_id.map(|t| t.0 .0);
+                            let sender_id = msg.from.as_ref().map(|u| u.id.0);
+
+                            if let Err(e) = integration.handle_edited_message(bot, msg).await {
+                                warn!(
+                                    chat_id = chat_id,
+                                    topic_id = ?topic_id,
+                                    sender_id = ?sender_id,
+                                    error = %e,
+                                    "Error handling edited message"
+                                );
+                            }
+                            respond(())
+                        }
+                    }
+                }),
+        )
         .branch(Update::filter_callback_query().endpoint({
             let state = Arc::clone(&bot_state);
+            let stream_handler = Arc::clone(&stream_handler);
+            let integration = Arc::clone(&integration);
             move |bot: Bot, q: CallbackQuery| {
                 let state = Arc::clone(&state);
+                let stream_handler = Arc::clone(&stream_handler);
+                let integration = Arc::clone(&integration);
                 async move {
                     let sender_id = q.from.id.0;
                     let sender_username = q.from.username.clone();
-                    if let Err(e) = dispatch_callback(bot, q, state).await {
+                    if let Err(e) =
+                        dispatch_callback(bot, q, state, stream_handler, integration).await
+                    {
                         error!(
                             sender_id = sender_id,
                             sender_username = ?sender_username,
@@ -394,31 +1135,56 @@ async fn main() -> Result<()> {
         .enable_ctrlc_handler()
         .build();
 
+    let auth_failed = Arc::new(tokio::sync::Notify::new());
+    let listener = update_listeners::polling_default(bot.clone()).await;
+    let listener_error_handler = PollingErrorHandler::new(Arc::clone(&auth_failed));
+
     info!("Bot connected. Press Ctrl+C to stop.");
     debug!("Starting Telegram dispatcher loop");
 
+    let mut exit_code = None;
     tokio::select! {
-        _ = dispatcher.dispatch() => {
+        _ = dispatcher.dispatch_with_listener(listener, listener_error_handler) => {
             info!("Dispatcher stopped");
         }
+        _ = auth_failed.notified() => {
+            error!("Telegram bot token rejected, shutting down...");
+            exit_code = Some(AUTH_FAILURE_EXIT_CODE);
+        }
         _ = signal::ctrl_c() => {
             info!("Received Ctrl+C, shutting down gracefully...");
         }
     }
 
-    info!("Stopping all OpenCode instances...");
-    if let Err(e) = bot_state.instance_manager.stop_all().await {
-        error!("Error stopping instances: {:?}", e);
+    if let Some(api_server) = api_server {
+        info!("Stopping API server...");
+        api_shutdown.cancel();
+        if let Err(e) = api_server.await {
+            error!("API server task failed: {:?}", e);
+        }
     }
 
+    // Streams go first: a stream that ended on its instance stopping would
+    // clear the record of a response still being generated, which the next
+    // start uses to report it lost and resend the prompt
     info!("Stopping active streams...");
     integration.stop_all_streams().await;
 
+    info!("Stopping all OpenCode instances...");
+    if let Err(e) = bot_state.instance_manager.stop_all().await {
+        error!("Error stopping instances: {:?}", e);
+    }
+
     info!("Finishing run log...");
     if let Err(e) = log_store.finish_run(&run_id).await {
         error!("Failed to finalize run log: {:?}", e);
     }
 
     info!("Shutdown complete.");
+    // The layer is installed globally and never dropped, so flush it here
+    log_writer.flush_and_join().await;
+    if let Some(code) = exit_code {
+        std::process::exit(code);
+    }
     Ok(())
 }
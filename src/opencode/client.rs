@@ -1,15 +1,47 @@
-use crate::types::opencode::{CreateMessageRequest, Message, MessagePart, SessionInfo};
+use crate::config::Config;
+use crate::types::opencode::{CreateMessageRequest, Message, MessagePart, ModelRef, SessionInfo};
 use anyhow::{Context, Result};
 use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
 use std::path::Path;
+use std::time::Duration;
 use tracing::debug;
 
+/// OpenCode reported that a session does not exist (HTTP 404), e.g. because
+/// it was deleted from the OpenCode CLI or web UI.
+#[derive(Debug, thiserror::Error)]
+#[error("Session not found: {0}")]
+pub struct SessionNotFound(pub String);
+
+/// Whether `err` is a [`SessionNotFound`] error.
+pub fn is_session_not_found(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<SessionNotFound>().is_some()
+}
+
+/// OpenCode did not accept a prompt within the client's request timeout,
+/// e.g. because the instance is busy.
+#[derive(Debug, thiserror::Error)]
+#[error("OpenCode did not accept the prompt within {0:?}")]
+pub struct PromptTimeout(pub Duration);
+
+/// Whether `err` is a [`PromptTimeout`] error.
+pub fn is_prompt_timeout(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<PromptTimeout>().is_some()
+}
+
+/// Base URL used over a Unix socket. The host is ignored by the transport but
+/// still required to form valid request URLs.
+const UNIX_SOCKET_BASE_URL: &str = "http://localhost";
+
 /// OpenCode REST API client
 #[derive(Clone)]
 pub struct OpenCodeClient {
     client: reqwest::Client,
     base_url: String,
+    max_retries: u32,
+    retry_base_delay: Duration,
+    /// Limit on sending a prompt; `None` waits as long as the server takes
+    request_timeout: Option<Duration>,
 }
 
 /// Metadata for a message response
@@ -34,6 +66,9 @@ pub struct MessageResponse {
 #[allow(dead_code)]
 struct CreateSessionRequest {
     project_path: String,
+    /// System instructions for the session, e.g. the topic's `/lang` hint
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    system: Option<String>,
 }
 
 /// Request body for permission reply
@@ -49,12 +84,123 @@ struct HealthResponse {
     status: String,
 }
 
+/// Parse a `provider/model` override for a prompt request.
+fn parse_model(model: &str) -> Result<ModelRef> {
+    ModelRef::parse(model)
+        .with_context(|| format!("Invalid model {:?}: expected provider/model", model))
+}
+
 impl OpenCodeClient {
     /// Create a new OpenCode client
     pub fn new(base_url: &str) -> Self {
+        Self::with_retry(base_url, 0, Duration::ZERO)
+    }
+
+    /// Create a client that retries transient failures.
+    ///
+    /// GET and DELETE requests are retried on connection errors, timeouts and
+    /// 5xx responses, up to `max_retries` times, doubling `retry_base_delay`
+    /// after each attempt. POSTs such as prompts and new sessions may already
+    /// have taken effect after a timeout or 5xx, so they are only retried when
+    /// the connection could not be made. 4xx responses are returned
+    /// immediately.
+    pub fn with_retry(base_url: &str, max_retries: u32, retry_base_delay: Duration) -> Self {
         Self {
             client: reqwest::Client::new(),
             base_url: base_url.trim_end_matches('/').to_string(),
+            max_retries,
+            retry_base_delay,
+            request_timeout: None,
+        }
+    }
+
+    /// Give up on sending a prompt after `timeout`, failing with
+    /// [`PromptTimeout`]. Zero disables the limit. Health checks and other
+    /// requests are unaffected.
+    pub fn with_request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = (!timeout.is_zero()).then_some(timeout);
+        self
+    }
+
+    /// Create a client that talks to an OpenCode server listening on a Unix
+    /// domain socket instead of a TCP port.
+    #[cfg(unix)]
+    pub fn with_unix_socket(socket_path: &Path) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .unix_socket(socket_path)
+            .build()
+            .with_context(|| {
+                format!(
+                    "Failed to build client for socket {}",
+                    socket_path.display()
+                )
+            })?;
+        Ok(Self {
+            client,
+            base_url: UNIX_SOCKET_BASE_URL.to_string(),
+            max_retries: 0,
+            retry_base_delay: Duration::ZERO,
+            request_timeout: None,
+        })
+    }
+
+    /// Create a client for the instance on `port`, or for the server on
+    /// `OPENCODE_SOCKET_PATH` when one is configured. Prompts are limited by
+    /// `OPENCODE_REQUEST_TIMEOUT_MS`.
+    pub fn for_port(config: &Config, port: u16) -> Result<Self> {
+        #[cfg(unix)]
+        if let Some(socket_path) = &config.opencode_socket_path {
+            return Ok(Self::with_unix_socket(socket_path)?
+                .with_request_timeout(config.opencode_request_timeout));
+        }
+        #[cfg(not(unix))]
+        if config.opencode_socket_path.is_some() {
+            anyhow::bail!("OPENCODE_SOCKET_PATH is only supported on Unix");
+        }
+        Ok(Self::new(&format!("http://localhost:{}", port))
+            .with_request_timeout(config.opencode_request_timeout))
+    }
+
+    /// The underlying HTTP client, shared with the SSE stream so both use the
+    /// same transport.
+    pub fn http_client(&self) -> &reqwest::Client {
+        &self.client
+    }
+
+    /// Send a request, retrying transient failures per the retry settings.
+    /// Only requests that are safe to repeat are retried after they may have
+    /// reached the server.
+    async fn send_with_retry<F>(&self, build: F) -> reqwest::Result<reqwest::Response>
+    where
+        F: Fn() -> reqwest::RequestBuilder,
+    {
+        let mut attempt = 0;
+        loop {
+            let request = build().build()?;
+            let idempotent = matches!(
+                *request.method(),
+                reqwest::Method::GET | reqwest::Method::DELETE
+            );
+            let result = self.client.execute(request).await;
+            let transient = match &result {
+                Ok(response) => idempotent && response.status().is_server_error(),
+                Err(e) => e.is_connect() || (idempotent && e.is_timeout()),
+            };
+            if !transient || attempt >= self.max_retries {
+                return result;
+            }
+
+            let delay = self
+                .retry_base_delay
+                .saturating_mul(2u32.saturating_pow(attempt));
+            attempt += 1;
+            debug!(
+                attempt = attempt,
+                max_retries = self.max_retries,
+                delay_ms = delay.as_millis() as u64,
+                "Retrying OpenCode request after transient failure"
+            );
+            tokio::time::sleep(delay).await;
         }
     }
 
@@ -92,9 +238,7 @@ impl OpenCodeClient {
         let url = format!("{}/sessions", self.base_url);
         debug!(url = %url, "Listing sessions");
         let response = self
-            .client
-            .get(&url)
-            .send()
+            .send_with_retry(|| self.client.get(&url))
             .await
             .context("Failed to send list sessions request")?;
 
@@ -121,9 +265,7 @@ impl OpenCodeClient {
         let url = format!("{}/session/{}", self.base_url, id);
         debug!(session_id = %id, url = %url, "Getting session");
         let response = self
-            .client
-            .get(&url)
-            .send()
+            .send_with_retry(|| self.client.get(&url))
             .await
             .context("Failed to send get session request")?;
 
@@ -136,9 +278,7 @@ impl OpenCodeClient {
                 debug!(session_id = %id, "Session retrieved");
                 Ok(session)
             }
-            StatusCode::NOT_FOUND => {
-                anyhow::bail!("Session not found: {}", id)
-            }
+            StatusCode::NOT_FOUND => Err(SessionNotFound(id.to_string()).into()),
             status => {
                 anyhow::bail!("Failed to get session: HTTP {}", status.as_u16())
             }
@@ -146,23 +286,28 @@ impl OpenCodeClient {
     }
 
     /// Create a new session
-    #[allow(dead_code)]
-    // Used by future: session creation feature
     pub async fn create_session(&self, project_path: &Path) -> Result<SessionInfo> {
+        self.create_session_with_system(project_path, None).await
+    }
+
+    /// Create a new session with optional system instructions.
+    pub async fn create_session_with_system(
+        &self,
+        project_path: &Path,
+        system: Option<&str>,
+    ) -> Result<SessionInfo> {
         let url = format!("{}/session", self.base_url);
         let request_body = CreateSessionRequest {
             project_path: project_path
                 .to_str()
                 .context("Invalid project path")?
                 .to_string(),
+            system: system.map(String::from),
         };
 
-        debug!(project_path = %request_body.project_path, url = %url, "Creating session");
+        debug!(project_path = %request_body.project_path, has_system = system.is_some(), url = %url, "Creating session");
         let response = self
-            .client
-            .post(&url)
-            .json(&request_body)
-            .send()
+            .send_with_retry(|| self.client.post(&url).json(&request_body))
             .await
             .context("Failed to send create session request")?;
 
@@ -182,12 +327,93 @@ impl OpenCodeClient {
         Ok(session)
     }
 
+    /// Fork a session, copying its message history into a new session
+    pub async fn fork_session(&self, session_id: &str) -> Result<SessionInfo> {
+        let url = format!("{}/session/{}/fork", self.base_url, session_id);
+        debug!(session_id = %session_id, url = %url, "Forking session");
+        let response = self
+            .send_with_retry(|| self.client.post(&url).json(&serde_json::json!({})))
+            .await
+            .context("Failed to send fork session request")?;
+
+        match response.status() {
+            status if status.is_success() => {
+                let session: SessionInfo = response
+                    .json()
+                    .await
+                    .context("Failed to parse fork session response")?;
+                debug!(session_id = %session_id, forked_session_id = %session.id, "Session forked");
+                Ok(session)
+            }
+            StatusCode::NOT_FOUND => Err(SessionNotFound(session_id.to_string()).into()),
+            status => {
+                anyhow::bail!("Failed to fork session: HTTP {}", status.as_u16())
+            }
+        }
+    }
+
+    /// Delete a session. A session that is already gone counts as deleted.
+    pub async fn delete_session(&self, session_id: &str) -> Result<()> {
+        let url = format!("{}/session/{}", self.base_url, session_id);
+        debug!(session_id = %session_id, url = %url, "Deleting session");
+        let response = self
+            .send_with_retry(|| self.client.delete(&url))
+            .await
+            .context("Failed to send delete session request")?;
+
+        match response.status() {
+            status if status.is_success() || status == StatusCode::NOT_FOUND => {
+                debug!(session_id = %session_id, "Session deleted");
+                Ok(())
+            }
+            status => {
+                anyhow::bail!("Failed to delete session: HTTP {}", status.as_u16())
+            }
+        }
+    }
+
+    /// List the messages of a session, oldest first
+    pub async fn list_messages(&self, session_id: &str) -> Result<Vec<Message>> {
+        let url = format!("{}/session/{}/message", self.base_url, session_id);
+        debug!(session_id = %session_id, url = %url, "Listing session messages");
+        let response = self
+            .send_with_retry(|| self.client.get(&url))
+            .await
+            .context("Failed to send list messages request")?;
+
+        match response.status() {
+            StatusCode::OK => {
+                let messages: Vec<Message> = response
+                    .json()
+                    .await
+                    .context("Failed to parse messages response")?;
+                debug!(session_id = %session_id, count = messages.len(), "Session messages listed");
+                Ok(messages)
+            }
+            StatusCode::NOT_FOUND => Err(SessionNotFound(session_id.to_string()).into()),
+            status => {
+                anyhow::bail!("Failed to list messages: HTTP {}", status.as_u16())
+            }
+        }
+    }
+
     /// Send a message and wait for response (synchronous)
     #[allow(dead_code)]
     // Used by future: synchronous message sending feature
     pub async fn send_message(&self, session_id: &str, text: &str) -> Result<MessageResponse> {
+        self.send_message_with_model(session_id, text, None).await
+    }
+
+    /// Send a message and wait for the response, optionally overriding the
+    /// model.
+    pub async fn send_message_with_model(
+        &self,
+        session_id: &str,
+        text: &str,
+        model: Option<&str>,
+    ) -> Result<MessageResponse> {
         let url = format!("{}/session/{}/prompt", self.base_url, session_id);
-        debug!(session_id = %session_id, text_len = text.len(), url = %url, "Sending message (sync)");
+        debug!(session_id = %session_id, text_len = text.len(), model = ?model, url = %url, "Sending message (sync)");
 
         // Create a proper message structure
         let message = Message {
@@ -200,13 +426,12 @@ impl OpenCodeClient {
         let request_body = CreateMessageRequest {
             message,
             stream: Some(false),
+            model: model.map(parse_model).transpose()?,
+            system: None,
         };
 
         let response = self
-            .client
-            .post(&url)
-            .json(&request_body)
-            .send()
+            .send_with_retry(|| self.client.post(&url).json(&request_body))
             .await
             .context("Failed to send message")?;
 
@@ -238,9 +463,40 @@ impl OpenCodeClient {
         &self,
         session_id: &str,
         parts: Vec<MessagePart>,
+    ) -> Result<()> {
+        self.send_message_parts_with_model_async(session_id, parts, None)
+            .await
+    }
+
+    /// Send message parts asynchronously, optionally overriding the model.
+    pub async fn send_message_parts_with_model_async(
+        &self,
+        session_id: &str,
+        parts: Vec<MessagePart>,
+        model: Option<&str>,
+    ) -> Result<()> {
+        self.prompt_async(session_id, parts, model, None).await
+    }
+
+    /// Send message parts asynchronously with optional system instructions.
+    pub async fn send_message_parts_with_system_async(
+        &self,
+        session_id: &str,
+        parts: Vec<MessagePart>,
+        system: Option<&str>,
+    ) -> Result<()> {
+        self.prompt_async(session_id, parts, None, system).await
+    }
+
+    async fn prompt_async(
+        &self,
+        session_id: &str,
+        parts: Vec<MessagePart>,
+        model: Option<&str>,
+        system: Option<&str>,
     ) -> Result<()> {
         let url = format!("{}/session/{}/prompt_async", self.base_url, session_id);
-        debug!(session_id = %session_id, parts_count = parts.len(), url = %url, "Sending message (async)");
+        debug!(session_id = %session_id, parts_count = parts.len(), model = ?model, has_system = system.is_some(), url = %url, "Sending message (async)");
 
         let message = Message {
             role: "user".to_string(),
@@ -250,16 +506,30 @@ impl OpenCodeClient {
         let request_body = CreateMessageRequest {
             message,
             stream: Some(false),
+            model: model.map(parse_model).transpose()?,
+            system: system.map(String::from),
         };
 
         let response = self
-            .client
-            .post(&url)
-            .json(&request_body)
-            .send()
-            .await
-            .context("Failed to send async message")?;
+            .send_with_retry(|| {
+                let request = self.client.post(&url).json(&request_body);
+                match self.request_timeout {
+                    Some(timeout) => request.timeout(timeout),
+                    None => request,
+                }
+            })
+            .await;
+        let response = match (response, self.request_timeout) {
+            (Err(e), Some(timeout)) if e.is_timeout() => {
+                debug!(session_id = %session_id, timeout_ms = timeout.as_millis() as u64, "Async message timed out");
+                return Err(PromptTimeout(timeout).into());
+            }
+            (response, _) => response.context("Failed to send async message")?,
+        };
 
+        if response.status() == StatusCode::NOT_FOUND {
+            return Err(SessionNotFound(session_id.to_string()).into());
+        }
         if !response.status().is_success() {
             anyhow::bail!(
                 "Failed to send async message: HTTP {}",
@@ -293,10 +563,7 @@ impl OpenCodeClient {
         let request_body = PermissionReplyRequest { allow };
 
         let response = self
-            .client
-            .post(&url)
-            .json(&request_body)
-            .send()
+            .send_with_retry(|| self.client.post(&url).json(&request_body))
             .await
             .context("Failed to send permission reply")?;
 
@@ -315,7 +582,7 @@ impl OpenCodeClient {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use wiremock::matchers::{method, path, path_regex};
+    use wiremock::matchers::{body_partial_json, method, path, path_regex};
     use wiremock::{Mock, MockServer, ResponseTemplate};
 
     #[tokio::test]
@@ -450,6 +717,9 @@ mod tests {
 
         Mock::given(method("POST"))
             .and(path("/session"))
+            .and(body_partial_json(serde_json::json!({
+                "project_path": "/tmp/test-project"
+            })))
             .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
                 "id": "new-session",
                 "title": "New Session",
@@ -467,6 +737,116 @@ mod tests {
         assert_eq!(session.id, "new-session");
     }
 
+    #[tokio::test]
+    async fn test_create_session_failure() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/session"))
+            .respond_with(ResponseTemplate::new(400))
+            .mount(&mock_server)
+            .await;
+
+        let client = OpenCodeClient::new(&mock_server.uri());
+        let err = client
+            .create_session(Path::new("/tmp/test-project"))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("HTTP 400"));
+    }
+
+    #[tokio::test]
+    async fn test_fork_session() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/session/ses_source/fork"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": "ses_forked",
+                "title": "Forked",
+                "created": 1640000000,
+                "updated": 1640000000
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let client = OpenCodeClient::new(&mock_server.uri());
+        let session = client.fork_session("ses_source").await.unwrap();
+        assert_eq!(session.id, "ses_forked");
+    }
+
+    #[tokio::test]
+    async fn test_fork_session_not_found() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/session/missing/fork"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&mock_server)
+            .await;
+
+        let client = OpenCodeClient::new(&mock_server.uri());
+        let result = client.fork_session("missing").await;
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("Session not found"));
+    }
+
+    #[tokio::test]
+    async fn test_delete_session_tolerates_missing_session() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("DELETE"))
+            .and(path("/session/ses_gone"))
+            .respond_with(ResponseTemplate::new(404))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let client = OpenCodeClient::new(&mock_server.uri());
+        client.delete_session("ses_gone").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_list_messages() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/session/session-123/message"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!([
+                {"role": "user", "content": [{"type": "text", "text": "Hi"}]},
+                {"role": "assistant", "content": [{"type": "text", "text": "Hello!"}]}
+            ])))
+            .mount(&mock_server)
+            .await;
+
+        let client = OpenCodeClient::new(&mock_server.uri());
+        let messages = client.list_messages("session-123").await.unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].role, "user");
+        assert_eq!(messages[1].role, "assistant");
+    }
+
+    #[tokio::test]
+    async fn test_list_messages_not_found() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/session/missing/message"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&mock_server)
+            .await;
+
+        let client = OpenCodeClient::new(&mock_server.uri());
+        let result = client.list_messages("missing").await;
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("Session not found"));
+    }
+
     #[tokio::test]
     async fn test_send_message_sync() {
         let mock_server = MockServer::start().await;
@@ -513,6 +893,126 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[tokio::test]
+    async fn test_send_message_async_session_not_found() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/session/ses_gone/prompt_async"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&mock_server)
+            .await;
+
+        let client = OpenCodeClient::new(&mock_server.uri());
+        let err = client
+            .send_message_async("ses_gone", "Hello")
+            .await
+            .unwrap_err();
+        assert!(is_session_not_found(&err));
+        assert_eq!(err.to_string(), "Session not found: ses_gone");
+
+        let other = anyhow::anyhow!("Failed to send async message: HTTP 500");
+        assert!(!is_session_not_found(&other));
+    }
+
+    #[tokio::test]
+    async fn test_send_message_async_times_out_on_slow_server() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/session/session-123/prompt_async"))
+            .respond_with(ResponseTemplate::new(204).set_delay(Duration::from_secs(5)))
+            .mount(&mock_server)
+            .await;
+
+        let client = OpenCodeClient::new(&mock_server.uri())
+            .with_request_timeout(Duration::from_millis(100));
+        let started = std::time::Instant::now();
+        let err = client
+            .send_message_async("session-123", "Hello")
+            .await
+            .unwrap_err();
+        assert!(is_prompt_timeout(&err));
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert!(!is_prompt_timeout(&anyhow::anyhow!("HTTP 500")));
+    }
+
+    #[tokio::test]
+    async fn test_send_message_with_model_override() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/session/session-123/prompt_async"))
+            .and(body_partial_json(serde_json::json!({
+                "model": {"providerID": "openai", "modelID": "gpt-4o"}
+            })))
+            .respond_with(ResponseTemplate::new(202))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let client = OpenCodeClient::new(&mock_server.uri());
+        let parts = vec![MessagePart::Text {
+            text: "Hello".to_string(),
+        }];
+        let result = client
+            .send_message_parts_with_model_async("session-123", parts, Some("openai/gpt-4o"))
+            .await;
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_send_message_sync_with_model_splits_at_first_slash() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/session/session-123/prompt"))
+            .and(body_partial_json(serde_json::json!({
+                "model": {
+                    "providerID": "openrouter",
+                    "modelID": "anthropic/claude-3.5-sonnet"
+                }
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "message": {"role": "assistant", "content": [{"type": "text", "text": "Hi"}]},
+                "metadata": {"id": "msg-1", "role": "assistant", "model": null}
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let client = OpenCodeClient::new(&mock_server.uri());
+        let response = client
+            .send_message_with_model(
+                "session-123",
+                "Hello",
+                Some("openrouter/anthropic/claude-3.5-sonnet"),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.message.role, "assistant");
+    }
+
+    #[tokio::test]
+    async fn test_send_message_rejects_model_without_provider() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(202))
+            .expect(0)
+            .mount(&mock_server)
+            .await;
+
+        let client = OpenCodeClient::new(&mock_server.uri());
+        let parts = vec![MessagePart::Text {
+            text: "Hello".to_string(),
+        }];
+        let err = client
+            .send_message_parts_with_model_async("session-123", parts, Some("gpt-4o"))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("expected provider/model"));
+    }
+
     #[tokio::test]
     async fn test_sse_url_generation() {
         let client = OpenCodeClient::new("http://localhost:4100");
@@ -584,4 +1084,154 @@ mod tests {
         let result = client.list_sessions().await;
         assert!(result.is_err());
     }
+
+    #[tokio::test]
+    async fn test_retry_succeeds_after_transient_failures() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/session"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(2)
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/session"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!([])))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let client = OpenCodeClient::with_retry(&mock_server.uri(), 3, Duration::from_millis(1));
+        let result = client.list_sessions().await;
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_retry_gives_up_after_max_retries() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("DELETE"))
+            .and(path("/session/session-123"))
+            .respond_with(ResponseTemplate::new(503))
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+
+        let client = OpenCodeClient::with_retry(&mock_server.uri(), 1, Duration::from_millis(1));
+        let result = client.delete_session("session-123").await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_retry_does_not_repeat_posts_that_reached_the_server() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/session/session-123/prompt_async"))
+            .respond_with(ResponseTemplate::new(503))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/session"))
+            .respond_with(ResponseTemplate::new(500))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let client = OpenCodeClient::with_retry(&mock_server.uri(), 3, Duration::from_millis(1));
+        assert!(client
+            .send_message_async("session-123", "Hello")
+            .await
+            .is_err());
+        assert!(client
+            .create_session(Path::new("/workspace"))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_retry_does_not_repeat_timed_out_prompts() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/session/session-123/prompt_async"))
+            .respond_with(ResponseTemplate::new(202).set_delay(Duration::from_millis(500)))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let client = OpenCodeClient::with_retry(&mock_server.uri(), 3, Duration::from_millis(1))
+            .with_request_timeout(Duration::from_millis(50));
+        assert!(client
+            .send_message_async("session-123", "Hello")
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_retry_skips_client_errors() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/session/missing"))
+            .respond_with(ResponseTemplate::new(404))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let client = OpenCodeClient::with_retry(&mock_server.uri(), 3, Duration::from_millis(1));
+        let result = client.get_session("missing").await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_default_client_does_not_retry() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/session/session-123/prompt_async"))
+            .respond_with(ResponseTemplate::new(503))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let client = OpenCodeClient::new(&mock_server.uri());
+        let result = client.send_message_async("session-123", "Hello").await;
+        assert!(result.is_err());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_unix_socket_transport() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        use tokio::net::UnixListener;
+
+        let dir = tempfile::tempdir().unwrap();
+        let socket_path = dir.path().join("opencode.sock");
+        let listener = UnixListener::bind(&socket_path).unwrap();
+
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = vec![0u8; 4096];
+            let n = stream.read(&mut buf).await.unwrap();
+            let request = String::from_utf8_lossy(&buf[..n]).to_string();
+            let body = r#"{"status":"ok"}"#;
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            stream.write_all(response.as_bytes()).await.unwrap();
+            request
+        });
+
+        let client = OpenCodeClient::with_unix_socket(&socket_path).unwrap();
+        assert!(client.health().await.unwrap());
+
+        let request = server.await.unwrap();
+        assert!(request.starts_with("GET /global/health HTTP/1.1"));
+    }
 }
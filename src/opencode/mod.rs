@@ -2,6 +2,9 @@ mod client;
 pub mod stream_handler;
 
 #[allow(unused_imports)]
-pub use client::{MessageResponse, OpenCodeClient};
+pub use client::{
+    is_prompt_timeout, is_session_not_found, MessageResponse, OpenCodeClient, PromptTimeout,
+    SessionNotFound,
+};
 #[allow(unused_imports)]
-pub use stream_handler::{OpenCodeMessage, StreamEvent, StreamHandler};
+pub use stream_handler::{OpenCodeMessage, StreamConfig, StreamEvent, StreamHandler};
@@ -5,12 +5,14 @@
 
 #![allow(dead_code)]
 
+use crate::backoff;
+use crate::config::Config;
 use crate::opencode::OpenCodeClient;
 use anyhow::{Context, Result};
 use futures::StreamExt;
 use reqwest_eventsource::{Event, EventSource};
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, VecDeque};
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 use tokio::sync::{mpsc, oneshot};
@@ -31,6 +33,57 @@ const BATCH_INTERVAL_SECS: u64 = 2;
 /// Deduplication message expiry (30 seconds)
 const DEDUP_EXPIRY_SECS: u64 = 30;
 
+/// Distinct Telegram messages remembered per session for deduplication
+const MAX_DEDUP_ENTRIES_PER_SESSION: usize = 32;
+
+/// Tunables for SSE reconnection and Telegram deduplication.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StreamConfig {
+    /// How long a Telegram-originated message is remembered for deduplication
+    pub dedup_expiry: Duration,
+    /// Reconnection attempts before emitting a `SessionError`
+    pub max_reconnect_attempts: u32,
+    /// Base delay for exponential backoff
+    pub base_reconnect_delay: Duration,
+    /// Upper bound on the backoff delay
+    pub max_reconnect_delay: Duration,
+}
+
+impl Default for StreamConfig {
+    fn default() -> Self {
+        Self {
+            dedup_expiry: Duration::from_secs(DEDUP_EXPIRY_SECS),
+            max_reconnect_attempts: MAX_RECONNECT_ATTEMPTS,
+            base_reconnect_delay: Duration::from_secs(BASE_RECONNECT_DELAY_SECS),
+            max_reconnect_delay: Duration::from_secs(MAX_RECONNECT_DELAY_SECS),
+        }
+    }
+}
+
+impl StreamConfig {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            dedup_expiry: config.dedup_expiry,
+            max_reconnect_attempts: config.max_reconnect_attempts,
+            base_reconnect_delay: config.base_reconnect_delay,
+            max_reconnect_delay: config.max_reconnect_delay,
+        }
+    }
+
+    /// Backoff delay before reconnection attempt `attempt` (0-based).
+    fn reconnect_delay(&self, attempt: u32) -> Duration {
+        self.base_reconnect_delay
+            .saturating_mul(2_u32.saturating_pow(attempt))
+            .min(self.max_reconnect_delay)
+    }
+
+    /// [`Self::reconnect_delay`] with jitter for `sample` (see
+    /// [`backoff::jittered`]), still capped at `max_reconnect_delay`.
+    fn jittered_reconnect_delay(&self, attempt: u32, sample: f64) -> Duration {
+        backoff::jittered(self.reconnect_delay(attempt), sample).min(self.max_reconnect_delay)
+    }
+}
+
 /// Events emitted by the stream handler.
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum StreamEvent {
@@ -47,8 +100,13 @@ pub enum StreamEvent {
     MessageComplete { message: OpenCodeMessage },
     /// Session is idle (ready for input)
     SessionIdle,
-    /// Session error occurred
-    SessionError { error: String },
+    /// Session error occurred. `kind` is OpenCode's error name (e.g.
+    /// `ProviderAuthError`) and `status` the provider's HTTP status, when given.
+    SessionError {
+        error: String,
+        kind: Option<String>,
+        status: Option<u16>,
+    },
     /// Permission requested
     PermissionRequest {
         id: String,
@@ -57,6 +115,19 @@ pub enum StreamEvent {
     },
     /// Permission reply received
     PermissionReply { id: String, allowed: bool },
+    /// A file was changed by the agent
+    FileEdited {
+        path: String,
+        change_type: String,
+        additions: Option<u64>,
+        deletions: Option<u64>,
+    },
+    /// Token usage and cost for a completed message
+    Usage {
+        input_tokens: u64,
+        output_tokens: u64,
+        cost: Option<f64>,
+    },
     /// Connection lost (for internal tracking)
     Disconnected,
     /// Connection restored
@@ -88,10 +159,32 @@ enum MessagePartData {
     },
 }
 
+/// Raw SSE event data for message.usage (also embedded in message.updated)
+#[derive(Clone, Debug, Deserialize)]
+struct UsageData {
+    #[serde(default)]
+    input_tokens: u64,
+    #[serde(default)]
+    output_tokens: u64,
+    #[serde(default)]
+    cost: Option<f64>,
+}
+
+/// Optional usage field carried by message.updated
+#[derive(Clone, Debug, Deserialize)]
+struct MessageUsageEnvelope {
+    #[serde(default)]
+    usage: Option<UsageData>,
+}
+
 /// Raw SSE event data for session.error
 #[derive(Clone, Debug, Deserialize)]
 struct SessionErrorData {
     message: String,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default, rename = "statusCode")]
+    status_code: Option<u16>,
 }
 
 /// Raw SSE event data for permission.updated
@@ -111,27 +204,197 @@ struct PermissionRepliedData {
     allowed: bool,
 }
 
+/// Raw SSE event data for file.edited
+#[derive(Clone, Debug, Deserialize)]
+struct FileEditedData {
+    #[serde(alias = "file")]
+    path: String,
+    #[serde(default = "default_change_type", alias = "type")]
+    change_type: String,
+    #[serde(default)]
+    additions: Option<u64>,
+    #[serde(default)]
+    deletions: Option<u64>,
+}
+
+fn default_change_type() -> String {
+    "edited".to_string()
+}
+
+/// Connection state of an SSE subscription.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectionState {
+    /// Initial connection in progress
+    Connecting,
+    /// Stream is open and receiving events
+    Connected,
+    /// Connection lost, waiting to retry
+    Reconnecting,
+    /// Gave up after exhausting reconnection attempts
+    Failed,
+}
+
+/// Diagnostic snapshot of a single SSE subscription.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct SubscriptionStatus {
+    pub session_id: String,
+    pub state: ConnectionState,
+    pub reconnect_attempts: u32,
+    /// SSE events received since subscribing, across reconnects
+    pub events_received: u64,
+    /// Total size of the event payloads in `events_received`
+    pub bytes_received: u64,
+    #[serde(skip)]
+    pub subscribed_at: Instant,
+    #[serde(skip)]
+    pub last_event_at: Option<Instant>,
+}
+
+impl SubscriptionStatus {
+    fn new(session_id: String) -> Self {
+        Self {
+            session_id,
+            state: ConnectionState::Connecting,
+            reconnect_attempts: 0,
+            events_received: 0,
+            bytes_received: 0,
+            subscribed_at: Instant::now(),
+            last_event_at: None,
+        }
+    }
+
+    fn record_event(&mut self, bytes: usize) {
+        self.events_received += 1;
+        self.bytes_received += bytes as u64;
+        self.last_event_at = Some(Instant::now());
+    }
+
+    /// Average event rate since subscribing.
+    pub fn events_per_sec(&self) -> f64 {
+        let elapsed = self.subscribed_at.elapsed().as_secs_f64();
+        if elapsed > 0.0 {
+            self.events_received as f64 / elapsed
+        } else {
+            0.0
+        }
+    }
+
+    /// Time since the last event, or `None` if nothing has arrived yet.
+    pub fn last_event_age(&self) -> Option<Duration> {
+        self.last_event_at.map(|at| at.elapsed())
+    }
+}
+
 /// Handle for a subscription (for cleanup)
 struct SubscriptionHandle {
     cancel_tx: oneshot::Sender<()>,
     #[allow(dead_code)]
     task_handle: tokio::task::JoinHandle<()>,
+    status: Arc<Mutex<SubscriptionStatus>>,
+}
+
+/// Collapse runs of whitespace and trim, so an echo that OpenCode reformatted
+/// slightly still matches the text sent from Telegram.
+fn normalize_dedup_text(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// A Telegram message waiting for its echo on the SSE stream.
+#[derive(Debug)]
+struct DedupEntry {
+    text: String,
+    /// Echoes still expected; one per time the text was sent
+    remaining: u32,
+    marked_at: Instant,
+}
+
+/// Messages recently sent from Telegram, per session. Each send swallows
+/// exactly one echo, so a user repeating themselves does not hide later
+/// identical output. Entries expire after `expiry`.
+#[derive(Debug)]
+struct TelegramEchoes {
+    expiry: Duration,
+    sessions: HashMap<String, VecDeque<DedupEntry>>,
+}
+
+impl TelegramEchoes {
+    fn new(expiry: Duration) -> Self {
+        Self {
+            expiry,
+            sessions: HashMap::new(),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.sessions.is_empty()
+    }
+
+    /// Drop expired entries and sessions left without any.
+    fn prune(&mut self, now: Instant) {
+        let expiry = self.expiry;
+        self.sessions.retain(|_, entries| {
+            entries.retain(|entry| now.duration_since(entry.marked_at) < expiry);
+            !entries.is_empty()
+        });
+    }
+
+    fn mark(&mut self, session_id: &str, text: &str, now: Instant) {
+        self.prune(now);
+        let text = normalize_dedup_text(text);
+        let entries = self.sessions.entry(session_id.to_string()).or_default();
+        if let Some(entry) = entries.iter_mut().find(|entry| entry.text == text) {
+            entry.remaining += 1;
+            entry.marked_at = now;
+            return;
+        }
+        if entries.len() >= MAX_DEDUP_ENTRIES_PER_SESSION {
+            entries.pop_front();
+        }
+        entries.push_back(DedupEntry {
+            text,
+            remaining: 1,
+            marked_at: now,
+        });
+    }
+
+    /// Consume one expected echo of `text`. Returns whether it was expected.
+    fn take(&mut self, session_id: &str, text: &str, now: Instant) -> bool {
+        self.prune(now);
+        let text = normalize_dedup_text(text);
+        let Some(entries) = self.sessions.get_mut(session_id) else {
+            return false;
+        };
+        let Some(index) = entries.iter().position(|entry| entry.text == text) else {
+            return false;
+        };
+        entries[index].remaining -= 1;
+        if entries[index].remaining == 0 {
+            entries.remove(index);
+            if entries.is_empty() {
+                self.sessions.remove(session_id);
+            }
+        }
+        true
+    }
 }
 
 /// SSE stream handler for OpenCode events.
 pub struct StreamHandler {
     client: OpenCodeClient,
+    config: StreamConfig,
     subscriptions: Arc<Mutex<HashMap<String, SubscriptionHandle>>>,
-    telegram_messages: Arc<Mutex<HashMap<String, HashSet<String>>>>,
+    telegram_messages: Arc<Mutex<TelegramEchoes>>,
 }
 
 impl StreamHandler {
     /// Create a new stream handler.
-    pub fn new(client: OpenCodeClient) -> Self {
+    pub fn new(client: OpenCodeClient, config: StreamConfig) -> Self {
         Self {
             client,
+            config,
             subscriptions: Arc::new(Mutex::new(HashMap::new())),
-            telegram_messages: Arc::new(Mutex::new(HashMap::new())),
+            telegram_messages: Arc::new(Mutex::new(TelegramEchoes::new(config.dedup_expiry))),
         }
     }
 
@@ -147,9 +410,23 @@ impl StreamHandler {
 
         let telegram_messages = Arc::clone(&self.telegram_messages);
         let session_id_clone = session_id.clone();
+        let status = Arc::new(Mutex::new(SubscriptionStatus::new(session_id.clone())));
+        let task_status = Arc::clone(&status);
+        let config = self.config;
+
+        let client = self.client.clone();
 
         let task_handle = tokio::spawn(async move {
-            Self::run_stream_loop(url, session_id_clone, tx, cancel_rx, telegram_messages).await;
+            Self::run_stream_loop(
+                client,
+                session_id_clone,
+                tx,
+                cancel_rx,
+                telegram_messages,
+                task_status,
+                config,
+            )
+            .await;
         });
 
         debug!(session_id = %session_id, "SSE stream task spawned");
@@ -161,6 +438,7 @@ impl StreamHandler {
                 SubscriptionHandle {
                     cancel_tx,
                     task_handle,
+                    status,
                 },
             );
         }
@@ -171,30 +449,10 @@ impl StreamHandler {
     /// Mark a message as sent from Telegram (for deduplication).
     pub fn mark_from_telegram(&self, session_id: &str, text: &str) {
         debug!(session_id = %session_id, text_len = text.len(), "Marking text as from Telegram for dedup");
-        let session_id = session_id.to_string();
-        let text = text.to_string();
-        let telegram_messages = Arc::clone(&self.telegram_messages);
-
-        {
-            let mut messages = telegram_messages.lock().unwrap();
-            messages
-                .entry(session_id.clone())
-                .or_default()
-                .insert(text.clone());
-        }
-
-        // Spawn cleanup task to remove after expiry
-        let cleanup_messages = Arc::clone(&self.telegram_messages);
-        tokio::spawn(async move {
-            tokio::time::sleep(Duration::from_secs(DEDUP_EXPIRY_SECS)).await;
-            let mut messages = cleanup_messages.lock().unwrap();
-            if let Some(set) = messages.get_mut(&session_id) {
-                set.remove(&text);
-                if set.is_empty() {
-                    messages.remove(&session_id);
-                }
-            }
-        });
+        self.telegram_messages
+            .lock()
+            .unwrap()
+            .mark(session_id, text, Instant::now());
     }
 
     /// Unsubscribe from a session's SSE stream.
@@ -211,27 +469,41 @@ impl StreamHandler {
         }
     }
 
-    /// Check if message should be skipped (sent from Telegram)
+    /// Snapshot the state of all active subscriptions, sorted by session ID.
+    pub fn subscription_status(&self) -> Vec<SubscriptionStatus> {
+        let subs = self.subscriptions.lock().unwrap();
+        let mut statuses: Vec<SubscriptionStatus> = subs
+            .values()
+            .map(|handle| handle.status.lock().unwrap().clone())
+            .collect();
+        statuses.sort_by(|a, b| a.session_id.cmp(&b.session_id));
+        statuses
+    }
+
+    /// Check if message should be skipped (sent from Telegram). A match
+    /// consumes one expected echo.
     fn should_skip(
-        telegram_messages: &Arc<Mutex<HashMap<String, HashSet<String>>>>,
+        telegram_messages: &Arc<Mutex<TelegramEchoes>>,
         session_id: &str,
         text: &str,
     ) -> bool {
-        let messages = telegram_messages.lock().unwrap();
-        messages
-            .get(session_id)
-            .map(|set| set.contains(text))
-            .unwrap_or(false)
+        telegram_messages
+            .lock()
+            .unwrap()
+            .take(session_id, text, Instant::now())
     }
 
     /// Run the main stream loop with reconnection logic
     async fn run_stream_loop(
-        url: String,
+        client: OpenCodeClient,
         session_id: String,
         tx: mpsc::Sender<StreamEvent>,
         mut cancel_rx: oneshot::Receiver<()>,
-        telegram_messages: Arc<Mutex<HashMap<String, HashSet<String>>>>,
+        telegram_messages: Arc<Mutex<TelegramEchoes>>,
+        status: Arc<Mutex<SubscriptionStatus>>,
+        config: StreamConfig,
     ) {
+        let url = client.sse_url(&session_id);
         let mut attempt = 0;
 
         loop {
@@ -242,11 +514,13 @@ impl StreamHandler {
             }
 
             match Self::connect_and_process(
+                client.http_client(),
                 &url,
                 &session_id,
                 &tx,
                 &mut cancel_rx,
                 &telegram_messages,
+                &status,
             )
             .await
             {
@@ -257,7 +531,8 @@ impl StreamHandler {
                 Err(e) => {
                     warn!("SSE stream error for session {}: {:?}", session_id, e);
 
-                    if attempt >= MAX_RECONNECT_ATTEMPTS {
+                    if attempt >= config.max_reconnect_attempts {
+                        status.lock().unwrap().state = ConnectionState::Failed;
                         error!(
                             "Max reconnection attempts reached for session: {}",
                             session_id
@@ -266,32 +541,37 @@ impl StreamHandler {
                             .send(StreamEvent::SessionError {
                                 error: format!(
                                     "Connection lost after {} attempts",
-                                    MAX_RECONNECT_ATTEMPTS
+                                    config.max_reconnect_attempts
                                 ),
+                                kind: None,
+                                status: None,
                             })
                             .await;
                         return;
                     }
 
+                    {
+                        let mut status = status.lock().unwrap();
+                        status.state = ConnectionState::Reconnecting;
+                        status.reconnect_attempts = attempt + 1;
+                    }
+
                     // Notify disconnect
                     let _ = tx.send(StreamEvent::Disconnected).await;
 
                     // Exponential backoff
-                    let delay_secs = std::cmp::min(
-                        BASE_RECONNECT_DELAY_SECS * 2_u64.pow(attempt),
-                        MAX_RECONNECT_DELAY_SECS,
-                    );
+                    let delay = config.jittered_reconnect_delay(attempt, backoff::random_sample());
                     info!(
-                        "Reconnecting to session {} in {}s (attempt {}/{})",
+                        "Reconnecting to session {} in {:?} (attempt {}/{})",
                         session_id,
-                        delay_secs,
+                        delay,
                         attempt + 1,
-                        MAX_RECONNECT_ATTEMPTS
+                        config.max_reconnect_attempts
                     );
 
                     // Wait with cancellation check
                     tokio::select! {
-                        _ = tokio::time::sleep(Duration::from_secs(delay_secs)) => {}
+                        _ = tokio::time::sleep(delay) => {}
                         _ = &mut cancel_rx => {
                             debug!("Stream cancelled during reconnect for session: {}", session_id);
                             return;
@@ -306,13 +586,14 @@ impl StreamHandler {
 
     /// Connect to SSE and process events
     async fn connect_and_process(
+        client: &reqwest::Client,
         url: &str,
         session_id: &str,
         tx: &mpsc::Sender<StreamEvent>,
         cancel_rx: &mut oneshot::Receiver<()>,
-        telegram_messages: &Arc<Mutex<HashMap<String, HashSet<String>>>>,
+        telegram_messages: &Arc<Mutex<TelegramEchoes>>,
+        status: &Arc<Mutex<SubscriptionStatus>>,
     ) -> Result<()> {
-        let client = reqwest::Client::new();
         let request = client.get(url);
         let mut es = EventSource::new(request).context("Failed to create EventSource")?;
 
@@ -326,10 +607,12 @@ impl StreamHandler {
                     match event {
                         Some(Ok(Event::Open)) => {
                             info!("SSE connected for session: {}", session_id);
+                            status.lock().unwrap().state = ConnectionState::Connected;
                             // Notify reconnection if this was a retry
                             let _ = tx.send(StreamEvent::Reconnected).await;
                         }
                         Some(Ok(Event::Message(msg))) => {
+                            status.lock().unwrap().record_event(msg.data.len());
                             // Handle the SSE event
                             if let Err(e) = Self::handle_sse_message(
                                 &msg.event,
@@ -386,7 +669,7 @@ impl StreamHandler {
         tx: &mpsc::Sender<StreamEvent>,
         text_batch: &mut String,
         last_batch_time: &mut Instant,
-        telegram_messages: &Arc<Mutex<HashMap<String, HashSet<String>>>>,
+        telegram_messages: &Arc<Mutex<TelegramEchoes>>,
     ) -> Result<()> {
         match event_type {
             "message.part.updated" => {
@@ -453,10 +736,46 @@ impl StreamHandler {
 
                 let message: OpenCodeMessage =
                     serde_json::from_str(data).context("Failed to parse message.updated")?;
+
+                // Emit usage ahead of completion so it can be attached to the final flush
+                if let Ok(MessageUsageEnvelope { usage: Some(usage) }) =
+                    serde_json::from_str::<MessageUsageEnvelope>(data)
+                {
+                    debug!(
+                        input_tokens = usage.input_tokens,
+                        output_tokens = usage.output_tokens,
+                        "Usage parsed from message.updated"
+                    );
+                    tx.send(StreamEvent::Usage {
+                        input_tokens: usage.input_tokens,
+                        output_tokens: usage.output_tokens,
+                        cost: usage.cost,
+                    })
+                    .await
+                    .ok();
+                }
+
                 debug!(message_id = %message.id, role = %message.role, "Message complete parsed");
                 tx.send(StreamEvent::MessageComplete { message }).await.ok();
             }
 
+            "message.usage" => {
+                let usage: UsageData =
+                    serde_json::from_str(data).context("Failed to parse message.usage")?;
+                debug!(
+                    input_tokens = usage.input_tokens,
+                    output_tokens = usage.output_tokens,
+                    "Usage parsed"
+                );
+                tx.send(StreamEvent::Usage {
+                    input_tokens: usage.input_tokens,
+                    output_tokens: usage.output_tokens,
+                    cost: usage.cost,
+                })
+                .await
+                .ok();
+            }
+
             "session.idle" => {
                 // Flush any pending text batch
                 if !text_batch.is_empty() {
@@ -473,9 +792,16 @@ impl StreamHandler {
             "session.error" => {
                 let error_data: SessionErrorData =
                     serde_json::from_str(data).context("Failed to parse session.error")?;
-                debug!(error = %error_data.message, "Session error parsed");
+                debug!(
+                    error = %error_data.message,
+                    kind = ?error_data.name,
+                    status = ?error_data.status_code,
+                    "Session error parsed"
+                );
                 tx.send(StreamEvent::SessionError {
                     error: error_data.message,
+                    kind: error_data.name,
+                    status: error_data.status_code,
                 })
                 .await
                 .ok();
@@ -506,6 +832,20 @@ impl StreamHandler {
                 .ok();
             }
 
+            "file.edited" => {
+                let edit: FileEditedData =
+                    serde_json::from_str(data).context("Failed to parse file.edited")?;
+                debug!(path = %edit.path, change_type = %edit.change_type, "File edit parsed");
+                tx.send(StreamEvent::FileEdited {
+                    path: edit.path,
+                    change_type: edit.change_type,
+                    additions: edit.additions,
+                    deletions: edit.deletions,
+                })
+                .await
+                .ok();
+            }
+
             _ => {
                 debug!("Unknown SSE event type: {}", event_type);
             }
@@ -568,7 +908,7 @@ mod tests {
     #[test]
     fn test_new_creates_handler() {
         let client = OpenCodeClient::new("http://localhost:4100");
-        let handler = StreamHandler::new(client);
+        let handler = StreamHandler::new(client, StreamConfig::default());
         assert!(handler.subscriptions.lock().unwrap().is_empty());
         assert!(handler.telegram_messages.lock().unwrap().is_empty());
     }
@@ -577,7 +917,7 @@ mod tests {
     async fn test_subscribe_creates_channel() {
         let base_url = create_mock_sse_server(vec![("session.idle", "{}")]).await;
         let client = OpenCodeClient::new(&base_url);
-        let handler = StreamHandler::new(client);
+        let handler = StreamHandler::new(client, StreamConfig::default());
 
         let rx = handler.subscribe("test-session").await.unwrap();
 
@@ -591,6 +931,38 @@ mod tests {
         handler.unsubscribe("test-session").await;
     }
 
+    #[tokio::test]
+    async fn test_subscription_status_tracks_lifecycle() {
+        let base_url = create_mock_sse_server(vec![("session.idle", "{}")]).await;
+        let client = OpenCodeClient::new(&base_url);
+        let handler = StreamHandler::new(client, StreamConfig::default());
+
+        assert!(handler.subscription_status().is_empty());
+
+        let mut rx = handler.subscribe("status-session").await.unwrap();
+
+        // Wait until the stream reports it is open
+        let connected = timeout(Duration::from_secs(5), async {
+            while let Some(event) = rx.recv().await {
+                if event == StreamEvent::Reconnected {
+                    return true;
+                }
+            }
+            false
+        })
+        .await;
+        assert!(connected.unwrap_or(false), "Expected stream to connect");
+
+        let statuses = handler.subscription_status();
+        assert_eq!(statuses.len(), 1);
+        assert_eq!(statuses[0].session_id, "status-session");
+        assert_eq!(statuses[0].state, ConnectionState::Connected);
+        assert_eq!(statuses[0].reconnect_attempts, 0);
+
+        handler.unsubscribe("status-session").await;
+        assert!(handler.subscription_status().is_empty());
+    }
+
     #[tokio::test]
     async fn test_parse_message_part_updated_text() {
         let events = vec![(
@@ -599,7 +971,7 @@ mod tests {
         )];
         let base_url = create_mock_sse_server(events).await;
         let client = OpenCodeClient::new(&base_url);
-        let handler = StreamHandler::new(client);
+        let handler = StreamHandler::new(client, StreamConfig::default());
 
         let mut rx = handler.subscribe("test-session").await.unwrap();
 
@@ -631,7 +1003,7 @@ mod tests {
         )];
         let base_url = create_mock_sse_server(events).await;
         let client = OpenCodeClient::new(&base_url);
-        let handler = StreamHandler::new(client);
+        let handler = StreamHandler::new(client, StreamConfig::default());
 
         let mut rx = handler.subscribe("test-session").await.unwrap();
 
@@ -663,7 +1035,7 @@ mod tests {
         )];
         let base_url = create_mock_sse_server(events).await;
         let client = OpenCodeClient::new(&base_url);
-        let handler = StreamHandler::new(client);
+        let handler = StreamHandler::new(client, StreamConfig::default());
 
         let mut rx = handler.subscribe("test-session").await.unwrap();
 
@@ -694,7 +1066,7 @@ mod tests {
         )];
         let base_url = create_mock_sse_server(events).await;
         let client = OpenCodeClient::new(&base_url);
-        let handler = StreamHandler::new(client);
+        let handler = StreamHandler::new(client, StreamConfig::default());
 
         let mut rx = handler.subscribe("test-session").await.unwrap();
 
@@ -723,7 +1095,7 @@ mod tests {
         let events = vec![("session.idle", "{}")];
         let base_url = create_mock_sse_server(events).await;
         let client = OpenCodeClient::new(&base_url);
-        let handler = StreamHandler::new(client);
+        let handler = StreamHandler::new(client, StreamConfig::default());
 
         let mut rx = handler.subscribe("test-session").await.unwrap();
 
@@ -743,20 +1115,59 @@ mod tests {
         handler.unsubscribe("test-session").await;
     }
 
+    #[tokio::test]
+    async fn test_subscription_counts_events_and_bytes() {
+        // Unrecognised events are still counted
+        let events = vec![("custom.event", r#"{"n":1}"#), ("session.idle", "{}")];
+        let base_url = create_mock_sse_server(events).await;
+        let client = OpenCodeClient::new(&base_url);
+        let handler = StreamHandler::new(client, StreamConfig::default());
+
+        let mut rx = handler.subscribe("metrics-session").await.unwrap();
+        let before = handler.subscription_status();
+        assert_eq!(before[0].events_received, 0);
+        assert!(before[0].last_event_at.is_none());
+
+        let result = timeout(Duration::from_secs(5), async {
+            while let Some(event) = rx.recv().await {
+                if event == StreamEvent::SessionIdle {
+                    return true;
+                }
+            }
+            false
+        })
+        .await;
+        assert!(result.unwrap_or(false), "Expected SessionIdle event");
+
+        let status = &handler.subscription_status()[0];
+        assert_eq!(status.events_received, 2);
+        assert_eq!(status.bytes_received, 9);
+        assert!(status.last_event_age().is_some());
+        assert!(status.events_per_sec() > 0.0);
+
+        handler.unsubscribe("metrics-session").await;
+    }
+
     #[tokio::test]
     async fn test_parse_session_error() {
         let events = vec![("session.error", r#"{"message":"Something went wrong"}"#)];
         let base_url = create_mock_sse_server(events).await;
         let client = OpenCodeClient::new(&base_url);
-        let handler = StreamHandler::new(client);
+        let handler = StreamHandler::new(client, StreamConfig::default());
 
         let mut rx = handler.subscribe("test-session").await.unwrap();
 
         let result = timeout(Duration::from_secs(5), async {
             while let Some(event) = rx.recv().await {
                 match event {
-                    StreamEvent::SessionError { error } => {
+                    StreamEvent::SessionError {
+                        error,
+                        kind,
+                        status,
+                    } => {
                         assert_eq!(error, "Something went wrong");
+                        assert!(kind.is_none());
+                        assert!(status.is_none());
                         return true;
                     }
                     StreamEvent::Reconnected => continue,
@@ -771,6 +1182,41 @@ mod tests {
         handler.unsubscribe("test-session").await;
     }
 
+    #[tokio::test]
+    async fn test_parse_session_error_kind_and_status() {
+        let events = vec![(
+            "session.error",
+            r#"{"message":"Too many requests","name":"APIError","statusCode":429}"#,
+        )];
+        let base_url = create_mock_sse_server(events).await;
+        let client = OpenCodeClient::new(&base_url);
+        let handler = StreamHandler::new(client, StreamConfig::default());
+
+        let mut rx = handler.subscribe("test-session").await.unwrap();
+
+        let event = timeout(Duration::from_secs(5), async {
+            loop {
+                match rx.recv().await {
+                    Some(event @ StreamEvent::SessionError { .. }) => return event,
+                    Some(_) => {}
+                    None => panic!("stream closed without SessionError"),
+                }
+            }
+        })
+        .await
+        .expect("Expected SessionError event");
+
+        assert_eq!(
+            event,
+            StreamEvent::SessionError {
+                error: "Too many requests".to_string(),
+                kind: Some("APIError".to_string()),
+                status: Some(429),
+            }
+        );
+        handler.unsubscribe("test-session").await;
+    }
+
     #[tokio::test]
     async fn test_parse_permission_updated() {
         let events = vec![(
@@ -779,7 +1225,7 @@ mod tests {
         )];
         let base_url = create_mock_sse_server(events).await;
         let client = OpenCodeClient::new(&base_url);
-        let handler = StreamHandler::new(client);
+        let handler = StreamHandler::new(client, StreamConfig::default());
 
         let mut rx = handler.subscribe("test-session").await.unwrap();
 
@@ -813,7 +1259,7 @@ mod tests {
         let events = vec![("permission.replied", r#"{"id":"perm_123","allowed":true}"#)];
         let base_url = create_mock_sse_server(events).await;
         let client = OpenCodeClient::new(&base_url);
-        let handler = StreamHandler::new(client);
+        let handler = StreamHandler::new(client, StreamConfig::default());
 
         let mut rx = handler.subscribe("test-session").await.unwrap();
 
@@ -837,6 +1283,127 @@ mod tests {
         handler.unsubscribe("test-session").await;
     }
 
+    #[tokio::test]
+    async fn test_parse_file_edited() {
+        let events = vec![(
+            "file.edited",
+            r#"{"file":"/workspace/src/main.rs","type":"modified","additions":12,"deletions":3}"#,
+        )];
+        let base_url = create_mock_sse_server(events).await;
+        let client = OpenCodeClient::new(&base_url);
+        let handler = StreamHandler::new(client, StreamConfig::default());
+
+        let mut rx = handler.subscribe("test-session").await.unwrap();
+
+        let result = timeout(Duration::from_secs(5), async {
+            while let Some(event) = rx.recv().await {
+                if let StreamEvent::FileEdited {
+                    path,
+                    change_type,
+                    additions,
+                    deletions,
+                } = event
+                {
+                    assert_eq!(path, "/workspace/src/main.rs");
+                    assert_eq!(change_type, "modified");
+                    assert_eq!(additions, Some(12));
+                    assert_eq!(deletions, Some(3));
+                    return true;
+                }
+            }
+            false
+        })
+        .await;
+
+        assert!(result.unwrap_or(false), "Expected FileEdited event");
+        handler.unsubscribe("test-session").await;
+    }
+
+    #[test]
+    fn test_file_edited_data_defaults() {
+        let edit: FileEditedData = serde_json::from_str(r#"{"path":"README.md"}"#).unwrap();
+        assert_eq!(edit.path, "README.md");
+        assert_eq!(edit.change_type, "edited");
+        assert!(edit.additions.is_none());
+        assert!(edit.deletions.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_parse_message_usage() {
+        let events = vec![(
+            "message.usage",
+            r#"{"input_tokens":1200,"output_tokens":345,"cost":0.0123}"#,
+        )];
+        let base_url = create_mock_sse_server(events).await;
+        let client = OpenCodeClient::new(&base_url);
+        let handler = StreamHandler::new(client, StreamConfig::default());
+
+        let mut rx = handler.subscribe("test-session").await.unwrap();
+
+        let result = timeout(Duration::from_secs(5), async {
+            while let Some(event) = rx.recv().await {
+                if let StreamEvent::Usage {
+                    input_tokens,
+                    output_tokens,
+                    cost,
+                } = event
+                {
+                    assert_eq!(input_tokens, 1200);
+                    assert_eq!(output_tokens, 345);
+                    assert_eq!(cost, Some(0.0123));
+                    return true;
+                }
+            }
+            false
+        })
+        .await;
+
+        assert!(result.unwrap_or(false), "Expected Usage event");
+        handler.unsubscribe("test-session").await;
+    }
+
+    #[tokio::test]
+    async fn test_parse_usage_embedded_in_message_updated() {
+        let events = vec![(
+            "message.updated",
+            r#"{"id":"msg_1","role":"assistant","content":[],"usage":{"input_tokens":10,"output_tokens":20}}"#,
+        )];
+        let base_url = create_mock_sse_server(events).await;
+        let client = OpenCodeClient::new(&base_url);
+        let handler = StreamHandler::new(client, StreamConfig::default());
+
+        let mut rx = handler.subscribe("test-session").await.unwrap();
+
+        // Usage must arrive before MessageComplete
+        let result = timeout(Duration::from_secs(5), async {
+            let mut usage_seen = false;
+            while let Some(event) = rx.recv().await {
+                match event {
+                    StreamEvent::Usage {
+                        input_tokens,
+                        output_tokens,
+                        cost,
+                    } => {
+                        assert_eq!(input_tokens, 10);
+                        assert_eq!(output_tokens, 20);
+                        assert_eq!(cost, None);
+                        usage_seen = true;
+                    }
+                    StreamEvent::MessageComplete { .. } => return usage_seen,
+                    _ => continue,
+                }
+            }
+            false
+        })
+        .await;
+
+        assert!(
+            result.unwrap_or(false),
+            "Expected Usage followed by MessageComplete"
+        );
+        handler.unsubscribe("test-session").await;
+    }
+
     #[tokio::test]
     async fn test_message_batching() {
         // Multiple text chunks should be batched
@@ -848,7 +1415,7 @@ mod tests {
         ];
         let base_url = create_mock_sse_server(events).await;
         let client = OpenCodeClient::new(&base_url);
-        let handler = StreamHandler::new(client);
+        let handler = StreamHandler::new(client, StreamConfig::default());
 
         let mut rx = handler.subscribe("test-session").await.unwrap();
 
@@ -882,7 +1449,7 @@ mod tests {
     #[tokio::test]
     async fn test_mark_from_telegram() {
         let client = OpenCodeClient::new("http://localhost:4100");
-        let handler = StreamHandler::new(client);
+        let handler = StreamHandler::new(client, StreamConfig::default());
 
         // Mark a message
         handler.mark_from_telegram("session-1", "Hello from Telegram");
@@ -890,11 +1457,61 @@ mod tests {
         // Verify it's tracked
         {
             let messages = handler.telegram_messages.lock().unwrap();
-            assert!(messages.contains_key("session-1"));
-            assert!(messages["session-1"].contains("Hello from Telegram"));
+            assert!(messages.sessions.contains_key("session-1"));
+            assert_eq!(
+                messages.sessions["session-1"][0].text,
+                "Hello from Telegram"
+            );
         }
     }
 
+    #[test]
+    fn test_dedup_normalizes_whitespace() {
+        let mut echoes = TelegramEchoes::new(Duration::from_secs(30));
+        let now = Instant::now();
+        echoes.mark("s", "  hello\n  world ", now);
+
+        assert!(!echoes.take("other", "hello world", now));
+        assert!(echoes.take("s", "hello world", now));
+        assert!(echoes.is_empty());
+    }
+
+    #[test]
+    fn test_dedup_counts_repeated_messages() {
+        let mut echoes = TelegramEchoes::new(Duration::from_secs(30));
+        let now = Instant::now();
+        echoes.mark("s", "yes", now);
+        echoes.mark("s", "yes", now);
+
+        // One echo swallowed per send; further identical text goes through
+        assert!(echoes.take("s", "yes", now));
+        assert!(echoes.take("s", "yes", now));
+        assert!(!echoes.take("s", "yes", now));
+    }
+
+    #[test]
+    fn test_dedup_entries_expire() {
+        let mut echoes = TelegramEchoes::new(Duration::from_secs(30));
+        let now = Instant::now();
+        echoes.mark("s", "hello", now);
+
+        assert!(!echoes.take("s", "hello", now + Duration::from_secs(31)));
+        assert!(echoes.is_empty());
+    }
+
+    #[test]
+    fn test_dedup_entries_are_bounded() {
+        let mut echoes = TelegramEchoes::new(Duration::from_secs(30));
+        let now = Instant::now();
+        for i in 0..=MAX_DEDUP_ENTRIES_PER_SESSION {
+            echoes.mark("s", &format!("message {i}"), now);
+        }
+
+        assert_eq!(echoes.sessions["s"].len(), MAX_DEDUP_ENTRIES_PER_SESSION);
+        assert!(!echoes.take("s", "message 0", now));
+        assert!(echoes.take("s", "message 1", now));
+    }
+
     #[tokio::test]
     async fn test_deduplication_skips_telegram_messages() {
         let events = vec![(
@@ -903,7 +1520,7 @@ mod tests {
         )];
         let base_url = create_mock_sse_server(events).await;
         let client = OpenCodeClient::new(&base_url);
-        let handler = StreamHandler::new(client);
+        let handler = StreamHandler::new(client, StreamConfig::default());
 
         // Mark the message as from Telegram BEFORE subscribing
         handler.mark_from_telegram("test-session", "Hello from Telegram");
@@ -943,7 +1560,7 @@ mod tests {
         ];
         let base_url = create_mock_sse_server(events).await;
         let client = OpenCodeClient::new(&base_url);
-        let handler = StreamHandler::new(client);
+        let handler = StreamHandler::new(client, StreamConfig::default());
 
         let _rx = handler.subscribe("test-session").await.unwrap();
 
@@ -972,9 +1589,9 @@ mod tests {
 
         // Use different clients for different sessions
         let client1 = OpenCodeClient::new(&base_url1);
-        let handler1 = StreamHandler::new(client1);
+        let handler1 = StreamHandler::new(client1, StreamConfig::default());
         let client2 = OpenCodeClient::new(&base_url2);
-        let handler2 = StreamHandler::new(client2);
+        let handler2 = StreamHandler::new(client2, StreamConfig::default());
 
         let _rx1 = handler1.subscribe("session-1").await.unwrap();
         let _rx2 = handler2.subscribe("session-2").await.unwrap();
@@ -1002,7 +1619,7 @@ mod tests {
         ];
         let base_url = create_mock_sse_server(events).await;
         let client = OpenCodeClient::new(&base_url);
-        let handler = StreamHandler::new(client);
+        let handler = StreamHandler::new(client, StreamConfig::default());
 
         let mut rx = handler.subscribe("test-session").await.unwrap();
 
@@ -1038,7 +1655,7 @@ mod tests {
         });
 
         let client = OpenCodeClient::new(&format!("http://{}", addr));
-        let handler = StreamHandler::new(client);
+        let handler = StreamHandler::new(client, StreamConfig::default());
 
         let mut rx = handler.subscribe("test").await.unwrap();
 
@@ -1049,6 +1666,101 @@ mod tests {
         assert!(result.is_ok() || result.is_err());
     }
 
+    async fn unreachable_url() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+        format!("http://{}", addr)
+    }
+
+    #[tokio::test]
+    async fn test_custom_max_reconnect_attempts_emits_session_error() {
+        let client = OpenCodeClient::new(&unreachable_url().await);
+        let config = StreamConfig {
+            max_reconnect_attempts: 1,
+            base_reconnect_delay: Duration::from_millis(10),
+            max_reconnect_delay: Duration::from_millis(10),
+            ..StreamConfig::default()
+        };
+        let handler = StreamHandler::new(client, config);
+        let mut rx = handler.subscribe("test").await.unwrap();
+
+        let mut disconnects = 0;
+        let error = timeout(Duration::from_secs(5), async {
+            loop {
+                match rx.recv().await {
+                    Some(StreamEvent::Disconnected) => disconnects += 1,
+                    Some(StreamEvent::SessionError { error, .. }) => return error,
+                    Some(_) => {}
+                    None => panic!("stream closed without SessionError"),
+                }
+            }
+        })
+        .await
+        .expect("SessionError should be emitted after one reconnect attempt");
+
+        assert_eq!(error, "Connection lost after 1 attempts");
+        assert_eq!(disconnects, 1);
+        assert_eq!(
+            handler.subscription_status()[0].state,
+            ConnectionState::Failed
+        );
+    }
+
+    #[tokio::test]
+    async fn test_default_reconnect_attempts_keep_retrying() {
+        let client = OpenCodeClient::new(&unreachable_url().await);
+        let handler = StreamHandler::new(client, StreamConfig::default());
+        let mut rx = handler.subscribe("test").await.unwrap();
+
+        // With the default 1s base delay, the first retry is still pending.
+        let first = timeout(Duration::from_millis(500), rx.recv())
+            .await
+            .unwrap();
+        assert_eq!(first, Some(StreamEvent::Disconnected));
+        assert!(timeout(Duration::from_millis(300), rx.recv())
+            .await
+            .is_err());
+
+        handler.unsubscribe("test").await;
+    }
+
+    #[test]
+    fn test_reconnect_delay_backoff_is_capped() {
+        let config = StreamConfig::default();
+        assert_eq!(config.reconnect_delay(0), Duration::from_secs(1));
+        assert_eq!(config.reconnect_delay(2), Duration::from_secs(4));
+        assert_eq!(config.reconnect_delay(10), Duration::from_secs(16));
+        assert_eq!(config.reconnect_delay(40), Duration::from_secs(16));
+    }
+
+    #[test]
+    fn test_jittered_reconnect_delay_bounds() {
+        let config = StreamConfig {
+            base_reconnect_delay: Duration::from_secs(1),
+            max_reconnect_delay: Duration::from_secs(16),
+            ..StreamConfig::default()
+        };
+
+        assert_eq!(
+            config.jittered_reconnect_delay(2, 0.0),
+            Duration::from_secs(3)
+        );
+        assert_eq!(
+            config.jittered_reconnect_delay(2, 1.0),
+            Duration::from_secs(5)
+        );
+        // Jitter never pushes past the cap
+        assert_eq!(
+            config.jittered_reconnect_delay(10, 1.0),
+            Duration::from_secs(16)
+        );
+        assert_eq!(
+            config.jittered_reconnect_delay(10, 0.0),
+            Duration::from_secs(12)
+        );
+    }
+
     #[test]
     fn test_stream_event_serialization() {
         let event = StreamEvent::TextChunk {
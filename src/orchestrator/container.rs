@@ -4,6 +4,20 @@ use std::collections::HashMap;
 use std::path::Path;
 use tracing::debug;
 
+/// Where the project is mounted inside the container unless
+/// `CONTAINER_WORKSPACE_MOUNT` says otherwise.
+pub const DEFAULT_WORKSPACE_MOUNT: &str = "/workspace";
+
+/// OpenCode's data directory inside the container.
+pub const CONTAINER_DATA_DIR: &str = "/home/user/.local/share/opencode";
+
+/// Container label holding the outpost instance id.
+pub const LABEL_INSTANCE_ID: &str = "oc-outpost.instance_id";
+/// Container label holding the forum topic id.
+pub const LABEL_TOPIC_ID: &str = "oc-outpost.topic_id";
+/// Container label holding the host project path.
+pub const LABEL_PROJECT: &str = "oc-outpost.project";
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum ContainerState {
     Running,
@@ -17,6 +31,18 @@ pub struct ContainerInfo {
     pub id: String,
     pub name: String,
     pub state: ContainerState,
+    pub labels: HashMap<String, String>,
+    /// The kernel killed the container for exceeding its memory limit.
+    /// Only reported by inspect; listed containers always have `false`.
+    pub oom_killed: bool,
+}
+
+impl ContainerInfo {
+    /// Instance id from the container's labels. Containers created before
+    /// labels were added have none.
+    pub fn instance_id(&self) -> Option<&str> {
+        self.labels.get(LABEL_INSTANCE_ID).map(String::as_str)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -30,6 +56,17 @@ pub struct ContainerConfig {
     pub opencode_data_path: String,
     pub topic_id: i32,
     pub env_vars: Vec<String>,
+    pub dns: Vec<String>,
+    pub dns_search: Vec<String>,
+    /// Extra per-project binds, already validated (`host:container:mode`)
+    pub extra_binds: Vec<String>,
+    /// Arguments appended to the base `opencode serve` command, in order
+    pub extra_args: Vec<String>,
+    /// Where the project is mounted inside the container. `opencode serve` is
+    /// pointed at it with `--project`, so both must stay in sync.
+    pub workspace_mount: String,
+    /// Memory limit in MiB; 0 leaves the container unlimited
+    pub memory_limit_mb: u64,
 }
 
 impl ContainerConfig {
@@ -37,26 +74,47 @@ impl ContainerConfig {
         format!("oc-{}", self.instance_id)
     }
 
+    /// Labels identifying the container, for filtering on the host with
+    /// `docker ps --filter label=...` and for reconciliation.
+    pub fn labels(&self) -> HashMap<String, String> {
+        HashMap::from([
+            (LABEL_INSTANCE_ID.to_string(), self.instance_id.clone()),
+            (LABEL_TOPIC_ID.to_string(), self.topic_id.to_string()),
+            (LABEL_PROJECT.to_string(), self.worktree_path.clone()),
+        ])
+    }
+
     pub fn cmd(&self) -> Vec<String> {
-        vec![
+        let mut cmd = vec![
             "opencode".to_string(),
             "serve".to_string(),
             "--port".to_string(),
             self.container_port.to_string(),
             "--project".to_string(),
-            "/workspace".to_string(),
-        ]
+            self.workspace_mount.clone(),
+        ];
+        cmd.extend(self.extra_args.iter().cloned());
+        cmd
+    }
+
+    /// Host directory mounted as OpenCode's data directory: the topic's own
+    /// subdirectory.
+    pub fn host_data_dir(&self) -> String {
+        format!("{}/{}", self.opencode_data_path, self.topic_id)
     }
 
     pub fn binds(&self) -> Vec<String> {
         let mut binds = vec![
-            format!("{}:/workspace", self.worktree_path),
+            format!("{}:{}", self.worktree_path, self.workspace_mount),
             format!("{}:/home/user/.config/opencode/:ro", self.config_mount_path),
         ];
 
         // Add OpenCode data directory mount (per-topic isolation)
-        let data_dir = format!("{}/{}", self.opencode_data_path, self.topic_id);
-        binds.push(format!("{}:/home/user/.local/share/opencode:rw", data_dir));
+        binds.push(format!(
+            "{}:{}:rw",
+            self.host_data_dir(),
+            CONTAINER_DATA_DIR
+        ));
 
         let home = std::env::var("HOME").unwrap_or_else(|_| "/root".to_string());
         let ssh_path = format!("{}/.ssh", home);
@@ -68,6 +126,8 @@ impl ContainerConfig {
             binds.push(format!("{}:/home/user/.gitconfig:ro", gitconfig_path));
         }
 
+        binds.extend(self.extra_binds.iter().cloned());
+
         binds
     }
 
@@ -83,6 +143,41 @@ impl ContainerConfig {
         bindings
     }
 
+    pub fn host_config(&self) -> bollard::models::HostConfig {
+        use bollard::models::{HostConfig, PortBinding as BollardPortBinding};
+
+        let port_bindings: HashMap<String, Option<Vec<BollardPortBinding>>> = self
+            .port_bindings()
+            .into_iter()
+            .map(|(k, v)| {
+                (
+                    k,
+                    Some(
+                        v.into_iter()
+                            .map(|pb| BollardPortBinding {
+                                host_ip: Some(pb.host_ip),
+                                host_port: Some(pb.host_port),
+                            })
+                            .collect(),
+                    ),
+                )
+            })
+            .collect();
+
+        // Leave DNS unset when not configured so Docker uses its defaults
+        let non_empty = |v: &Vec<String>| (!v.is_empty()).then(|| v.clone());
+
+        HostConfig {
+            binds: Some(self.binds()),
+            port_bindings: Some(port_bindings),
+            auto_remove: Some(false),
+            dns: non_empty(&self.dns),
+            dns_search: non_empty(&self.dns_search),
+            memory: (self.memory_limit_mb > 0).then(|| (self.memory_limit_mb * 1024 * 1024) as i64),
+            ..Default::default()
+        }
+    }
+
     pub fn env_passthrough(&self) -> Vec<String> {
         self.env_vars
             .iter()
@@ -109,6 +204,14 @@ pub trait ContainerRuntime: Send + Sync {
     async fn remove_container(&self, container_id: &str, force: bool) -> Result<()>;
     async fn inspect_container(&self, container_id: &str) -> Result<ContainerInfo>;
     async fn list_containers_by_prefix(&self, prefix: &str) -> Result<Vec<ContainerInfo>>;
+    /// All containers carrying `label`, whatever its value.
+    async fn list_containers_by_label(&self, label: &str) -> Result<Vec<ContainerInfo>>;
+    /// Last `tail` lines of the container's stdout and stderr, or `None` if
+    /// the container no longer exists.
+    async fn container_logs(&self, container_id: &str, tail: usize) -> Result<Option<String>>;
+    /// Pull `image` unless it is already present, so creating a container
+    /// does not fail or wait on the download. Returns whether it was pulled.
+    async fn ensure_image(&self, image: &str) -> Result<bool>;
 }
 
 pub struct DockerRuntime {
@@ -121,6 +224,58 @@ impl DockerRuntime {
             .map_err(|e| anyhow::anyhow!("Failed to connect to Docker: {}", e))?;
         Ok(Self { client })
     }
+
+    /// Containers (including stopped ones) matching a single Docker list filter.
+    async fn list_containers_filtered(
+        &self,
+        filter: &str,
+        value: &str,
+    ) -> Result<Vec<ContainerInfo>> {
+        use bollard::container::ListContainersOptions;
+
+        let mut filters = HashMap::new();
+        filters.insert(filter, vec![value]);
+
+        let options = ListContainersOptions {
+            all: true,
+            filters,
+            ..Default::default()
+        };
+
+        let containers = self
+            .client
+            .list_containers(Some(options))
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to list containers: {}", e))?;
+
+        let results = containers
+            .into_iter()
+            .map(|c| {
+                let state = match c.state.as_deref() {
+                    Some("running") => ContainerState::Running,
+                    Some("exited") => ContainerState::Exited(-1),
+                    Some("created") => ContainerState::Created,
+                    Some(other) => ContainerState::Unknown(other.to_string()),
+                    None => ContainerState::Unknown("unknown".to_string()),
+                };
+                let name = c
+                    .names
+                    .and_then(|n| n.first().cloned())
+                    .unwrap_or_default()
+                    .trim_start_matches('/')
+                    .to_string();
+                ContainerInfo {
+                    id: c.id.unwrap_or_default(),
+                    name,
+                    state,
+                    labels: c.labels.unwrap_or_default(),
+                    oom_killed: false,
+                }
+            })
+            .collect();
+
+        Ok(results)
+    }
 }
 
 #[async_trait]
@@ -128,7 +283,6 @@ impl ContainerRuntime for DockerRuntime {
     async fn create_container(&self, config: &ContainerConfig) -> Result<String> {
         use bollard::container::Config as ContainerCreateConfig;
         use bollard::container::CreateContainerOptions;
-        use bollard::models::{HostConfig, PortBinding as BollardPortBinding};
 
         debug!(
             instance_id = %config.instance_id,
@@ -138,7 +292,7 @@ impl ContainerRuntime for DockerRuntime {
         );
 
         // Create OpenCode data directory if it doesn't exist
-        let data_dir = format!("{}/{}", config.opencode_data_path, config.topic_id);
+        let data_dir = config.host_data_dir();
         tokio::fs::create_dir_all(&data_dir).await.map_err(|e| {
             anyhow::anyhow!(
                 "Failed to create OpenCode data directory {}: {}",
@@ -148,30 +302,7 @@ impl ContainerRuntime for DockerRuntime {
         })?;
         debug!(data_dir = %data_dir, "OpenCode data directory created");
 
-        let port_bindings: HashMap<String, Option<Vec<BollardPortBinding>>> = config
-            .port_bindings()
-            .into_iter()
-            .map(|(k, v)| {
-                (
-                    k,
-                    Some(
-                        v.into_iter()
-                            .map(|pb| BollardPortBinding {
-                                host_ip: Some(pb.host_ip),
-                                host_port: Some(pb.host_port),
-                            })
-                            .collect(),
-                    ),
-                )
-            })
-            .collect();
-
-        let host_config = HostConfig {
-            binds: Some(config.binds()),
-            port_bindings: Some(port_bindings),
-            auto_remove: Some(false),
-            ..Default::default()
-        };
+        let host_config = config.host_config();
 
         let mut exposed_ports = HashMap::new();
         exposed_ports.insert(
@@ -185,6 +316,7 @@ impl ContainerRuntime for DockerRuntime {
             env: Some(config.env_passthrough()),
             exposed_ports: Some(exposed_ports),
             host_config: Some(host_config),
+            labels: Some(config.labels()),
             ..Default::default()
         };
 
@@ -273,6 +405,11 @@ impl ContainerRuntime for DockerRuntime {
             .await
             .map_err(|e| anyhow::anyhow!("Failed to inspect container: {}", e))?;
 
+        let oom_killed = response
+            .state
+            .as_ref()
+            .and_then(|s| s.oom_killed)
+            .unwrap_or(false);
         let state = match response.state {
             Some(ref s) => match s.status {
                 Some(bollard::models::ContainerStateStatusEnum::RUNNING) => ContainerState::Running,
@@ -292,58 +429,79 @@ impl ContainerRuntime for DockerRuntime {
             .trim_start_matches('/')
             .to_string();
 
+        let labels = response.config.and_then(|c| c.labels).unwrap_or_default();
+
         Ok(ContainerInfo {
             id: response.id.unwrap_or_default(),
             name,
             state,
+            labels,
+            oom_killed,
         })
     }
 
     async fn list_containers_by_prefix(&self, prefix: &str) -> Result<Vec<ContainerInfo>> {
-        use bollard::container::ListContainersOptions;
-
         debug!(prefix = %prefix, "Listing containers by prefix");
+        self.list_containers_filtered("name", prefix).await
+    }
 
-        let mut filters = HashMap::new();
-        filters.insert("name", vec![prefix]);
+    async fn list_containers_by_label(&self, label: &str) -> Result<Vec<ContainerInfo>> {
+        debug!(label = %label, "Listing containers by label");
+        self.list_containers_filtered("label", label).await
+    }
 
-        let options = ListContainersOptions {
-            all: true,
-            filters,
+    async fn container_logs(&self, container_id: &str, tail: usize) -> Result<Option<String>> {
+        use bollard::container::LogsOptions;
+        use futures::StreamExt;
+
+        debug!(container_id = %container_id, tail = tail, "Fetching container logs");
+        let options = LogsOptions::<String> {
+            stdout: true,
+            stderr: true,
+            tail: tail.to_string(),
             ..Default::default()
         };
+        let mut stream = self.client.logs(container_id, Some(options));
+
+        let mut logs = String::new();
+        while let Some(chunk) = stream.next().await {
+            match chunk {
+                Ok(output) => logs.push_str(&output.to_string()),
+                Err(bollard::errors::Error::DockerResponseServerError {
+                    status_code: 404, ..
+                }) => {
+                    debug!(container_id = %container_id, "Container not found during logs");
+                    return Ok(None);
+                }
+                Err(e) => return Err(anyhow::anyhow!("Failed to fetch container logs: {}", e)),
+            }
+        }
+        Ok(Some(logs))
+    }
 
-        let containers = self
-            .client
-            .list_containers(Some(options))
-            .await
-            .map_err(|e| anyhow::anyhow!("Failed to list containers: {}", e))?;
+    async fn ensure_image(&self, image: &str) -> Result<bool> {
+        use bollard::image::CreateImageOptions;
+        use futures::TryStreamExt;
 
-        let results = containers
-            .into_iter()
-            .map(|c| {
-                let state = match c.state.as_deref() {
-                    Some("running") => ContainerState::Running,
-                    Some("exited") => ContainerState::Exited(-1),
-                    Some("created") => ContainerState::Created,
-                    Some(other) => ContainerState::Unknown(other.to_string()),
-                    None => ContainerState::Unknown("unknown".to_string()),
-                };
-                let name = c
-                    .names
-                    .and_then(|n| n.first().cloned())
-                    .unwrap_or_default()
-                    .trim_start_matches('/')
-                    .to_string();
-                ContainerInfo {
-                    id: c.id.unwrap_or_default(),
-                    name,
-                    state,
-                }
-            })
-            .collect();
+        match self.client.inspect_image(image).await {
+            Ok(_) => return Ok(false),
+            Err(bollard::errors::Error::DockerResponseServerError {
+                status_code: 404, ..
+            }) => {}
+            Err(e) => return Err(anyhow::anyhow!("Failed to inspect image {}: {}", image, e)),
+        }
 
-        Ok(results)
+        debug!(image = %image, "Pulling container image");
+        let options = CreateImageOptions {
+            from_image: image,
+            ..Default::default()
+        };
+        self.client
+            .create_image(Some(options), None, None)
+            .try_collect::<Vec<_>>()
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to pull image {}: {}", image, e))?;
+        Ok(true)
     }
 }
 
@@ -355,12 +513,37 @@ pub mod mock {
     #[derive(Debug, Clone)]
     #[allow(dead_code)]
     pub enum MockAction {
-        CreateContainer { config_name: String },
-        StartContainer { id: String },
-        StopContainer { id: String, timeout: u64 },
-        RemoveContainer { id: String, force: bool },
-        InspectContainer { id: String },
-        ListContainers { prefix: String },
+        CreateContainer {
+            config_name: String,
+            labels: HashMap<String, String>,
+        },
+        StartContainer {
+            id: String,
+        },
+        StopContainer {
+            id: String,
+            timeout: u64,
+        },
+        RemoveContainer {
+            id: String,
+            force: bool,
+        },
+        InspectContainer {
+            id: String,
+        },
+        ListContainers {
+            prefix: String,
+        },
+        ListContainersByLabel {
+            label: String,
+        },
+        ContainerLogs {
+            id: String,
+            tail: usize,
+        },
+        EnsureImage {
+            image: String,
+        },
     }
 
     pub struct MockRuntime {
@@ -370,6 +553,7 @@ pub mod mock {
         pub remove_result: Mutex<Result<(), String>>,
         pub inspect_result: Mutex<Result<ContainerInfo, String>>,
         pub list_result: Mutex<Result<Vec<ContainerInfo>, String>>,
+        pub logs_result: Mutex<Result<Option<String>, String>>,
         pub actions: Mutex<Vec<MockAction>>,
     }
 
@@ -390,8 +574,11 @@ pub mod mock {
                     id: "mock-container-id-abc123".to_string(),
                     name: "oc-test".to_string(),
                     state: ContainerState::Running,
+                    labels: HashMap::new(),
+                    oom_killed: false,
                 })),
                 list_result: Mutex::new(Ok(vec![])),
+                logs_result: Mutex::new(Ok(Some(String::new()))),
                 actions: Mutex::new(vec![]),
             }
         }
@@ -416,6 +603,11 @@ pub mod mock {
             self
         }
 
+        pub fn with_logs_result(self, result: Result<Option<String>, String>) -> Self {
+            *self.logs_result.lock().unwrap() = result;
+            self
+        }
+
         pub fn recorded_actions(&self) -> Vec<MockAction> {
             self.actions.lock().unwrap().clone()
         }
@@ -429,6 +621,7 @@ pub mod mock {
                 .unwrap()
                 .push(MockAction::CreateContainer {
                     config_name: config.container_name(),
+                    labels: config.labels(),
                 });
             self.create_result
                 .lock()
@@ -508,6 +701,50 @@ pub mod mock {
                 .clone()
                 .map_err(|e| anyhow::anyhow!(e))
         }
+
+        /// Like Docker's `label` filter, only containers carrying `label`
+        /// from `list_result` are returned.
+        async fn list_containers_by_label(&self, label: &str) -> Result<Vec<ContainerInfo>> {
+            self.actions
+                .lock()
+                .unwrap()
+                .push(MockAction::ListContainersByLabel {
+                    label: label.to_string(),
+                });
+            self.list_result
+                .lock()
+                .unwrap()
+                .clone()
+                .map(|containers| {
+                    containers
+                        .into_iter()
+                        .filter(|c| c.labels.contains_key(label))
+                        .collect()
+                })
+                .map_err(|e| anyhow::anyhow!(e))
+        }
+
+        async fn container_logs(&self, container_id: &str, tail: usize) -> Result<Option<String>> {
+            self.actions
+                .lock()
+                .unwrap()
+                .push(MockAction::ContainerLogs {
+                    id: container_id.to_string(),
+                    tail,
+                });
+            self.logs_result
+                .lock()
+                .unwrap()
+                .clone()
+                .map_err(|e| anyhow::anyhow!(e))
+        }
+
+        async fn ensure_image(&self, image: &str) -> Result<bool> {
+            self.actions.lock().unwrap().push(MockAction::EnsureImage {
+                image: image.to_string(),
+            });
+            Ok(false)
+        }
     }
 }
 
@@ -530,6 +767,12 @@ mod tests {
                 "ANTHROPIC_API_KEY".to_string(),
                 "OPENAI_API_KEY".to_string(),
             ],
+            dns: vec![],
+            dns_search: vec![],
+            extra_binds: vec![],
+            extra_args: vec![],
+            workspace_mount: "/workspace".to_string(),
+            memory_limit_mb: 0,
         }
     }
 
@@ -556,6 +799,31 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_cmd_appends_extra_args_in_order() {
+        let mut config = test_config();
+        config.extra_args = vec![
+            "--log-level".to_string(),
+            "debug".to_string(),
+            "--print-logs".to_string(),
+        ];
+        let cmd = config.cmd();
+        assert_eq!(
+            cmd,
+            vec![
+                "opencode",
+                "serve",
+                "--port",
+                "8080",
+                "--project",
+                "/workspace",
+                "--log-level",
+                "debug",
+                "--print-logs"
+            ]
+        );
+    }
+
     #[test]
     fn test_port_bindings_maps_host_to_container() {
         let config = test_config();
@@ -578,6 +846,31 @@ mod tests {
             .any(|b| b == "/tmp/projects/.worktrees/my-topic:/workspace"));
     }
 
+    #[test]
+    fn test_cmd_project_matches_workspace_bind() {
+        let config = test_config();
+        let cmd = config.cmd();
+        let project_idx = cmd.iter().position(|a| a == "--project").unwrap();
+        let project = &cmd[project_idx + 1];
+
+        let workspace_bind = format!("{}:{}", config.worktree_path, project);
+        assert!(config.binds().contains(&workspace_bind));
+    }
+
+    #[test]
+    fn test_custom_workspace_mount_used_for_cmd_and_binds() {
+        let mut config = test_config();
+        config.workspace_mount = "/home/user/app".to_string();
+
+        let cmd = config.cmd();
+        assert_eq!(cmd[4..], ["--project", "/home/user/app"]);
+        let binds = config.binds();
+        assert!(binds
+            .iter()
+            .any(|b| b == "/tmp/projects/.worktrees/my-topic:/home/user/app"));
+        assert!(!binds.iter().any(|b| b.ends_with(":/workspace")));
+    }
+
     #[test]
     fn test_binds_includes_config_ro() {
         let config = test_config();
@@ -596,6 +889,17 @@ mod tests {
             .any(|b| b == "/tmp/opencode-data/456:/home/user/.local/share/opencode:rw"));
     }
 
+    #[test]
+    fn test_binds_appends_extra_binds() {
+        let mut config = test_config();
+        config.extra_binds = vec!["/srv/projects/shared:/shared:ro".to_string()];
+        let binds = config.binds();
+        assert_eq!(
+            binds.last().map(String::as_str),
+            Some("/srv/projects/shared:/shared:ro")
+        );
+    }
+
     #[test]
     fn test_env_passthrough_filters_set_vars() {
         std::env::set_var("ANTHROPIC_API_KEY", "sk-test-key");
@@ -610,6 +914,50 @@ mod tests {
         std::env::remove_var("ANTHROPIC_API_KEY");
     }
 
+    #[test]
+    fn test_labels_identify_instance() {
+        let config = test_config();
+        let labels = config.labels();
+        assert_eq!(labels.get(LABEL_INSTANCE_ID).unwrap(), "test-123");
+        assert_eq!(labels.get(LABEL_TOPIC_ID).unwrap(), "456");
+        assert_eq!(
+            labels.get(LABEL_PROJECT).unwrap(),
+            "/tmp/projects/.worktrees/my-topic"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_create_container_sets_labels() {
+        let runtime = MockRuntime::new();
+        runtime.create_container(&test_config()).await.unwrap();
+
+        let actions = runtime.recorded_actions();
+        assert!(matches!(
+            &actions[0],
+            MockAction::CreateContainer { labels, .. }
+                if labels.get(LABEL_INSTANCE_ID).map(String::as_str) == Some("test-123")
+        ));
+    }
+
+    #[test]
+    fn test_container_info_instance_id_from_labels() {
+        let labeled = ContainerInfo {
+            id: "abc".to_string(),
+            name: "oc-test-123".to_string(),
+            state: ContainerState::Running,
+            labels: test_config().labels(),
+            oom_killed: false,
+        };
+        assert_eq!(labeled.instance_id(), Some("test-123"));
+
+        let unlabeled = ContainerInfo {
+            labels: HashMap::new(),
+            oom_killed: false,
+            ..labeled
+        };
+        assert_eq!(unlabeled.instance_id(), None);
+    }
+
     #[tokio::test]
     async fn test_mock_runtime_create_returns_id() {
         let runtime = MockRuntime::new();
@@ -633,7 +981,7 @@ mod tests {
         assert_eq!(actions.len(), 4);
 
         assert!(
-            matches!(&actions[0], MockAction::CreateContainer { config_name } if config_name == "oc-test-123")
+            matches!(&actions[0], MockAction::CreateContainer { config_name, .. } if config_name == "oc-test-123")
         );
         assert!(matches!(&actions[1], MockAction::StartContainer { id } if id == "abc123"));
         assert!(
@@ -660,6 +1008,8 @@ mod tests {
             id: "abc123".to_string(),
             name: "oc-test-123".to_string(),
             state: ContainerState::Running,
+            labels: HashMap::new(),
+            oom_killed: false,
         }));
 
         let info = runtime.inspect_container("abc123").await.unwrap();
@@ -673,6 +1023,8 @@ mod tests {
             id: "abc123".to_string(),
             name: "oc-test-123".to_string(),
             state: ContainerState::Exited(1),
+            labels: HashMap::new(),
+            oom_killed: false,
         }));
 
         let info = runtime.inspect_container("abc123").await.unwrap();
@@ -686,11 +1038,15 @@ mod tests {
                 id: "id1".to_string(),
                 name: "oc-instance-1".to_string(),
                 state: ContainerState::Running,
+                labels: HashMap::new(),
+                oom_killed: false,
             },
             ContainerInfo {
                 id: "id2".to_string(),
                 name: "oc-instance-2".to_string(),
                 state: ContainerState::Exited(0),
+                labels: HashMap::new(),
+                oom_killed: false,
             },
         ];
 
@@ -705,6 +1061,34 @@ mod tests {
         assert!(matches!(&actions[0], MockAction::ListContainers { prefix } if prefix == "oc-"));
     }
 
+    #[tokio::test]
+    async fn test_list_containers_by_label_skips_unlabeled() {
+        let containers = vec![
+            ContainerInfo {
+                id: "id1".to_string(),
+                name: "oc-instance-1".to_string(),
+                state: ContainerState::Running,
+                labels: HashMap::from([(LABEL_INSTANCE_ID.to_string(), "inst-1".to_string())]),
+                oom_killed: false,
+            },
+            ContainerInfo {
+                id: "id2".to_string(),
+                name: "oc-unrelated".to_string(),
+                state: ContainerState::Running,
+                labels: HashMap::new(),
+                oom_killed: false,
+            },
+        ];
+        let runtime = MockRuntime::new().with_list_result(Ok(containers));
+
+        let result = runtime
+            .list_containers_by_label(LABEL_INSTANCE_ID)
+            .await
+            .unwrap();
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].instance_id(), Some("inst-1"));
+    }
+
     #[tokio::test]
     async fn test_stop_then_remove_sequence() {
         let runtime = MockRuntime::new();
@@ -738,6 +1122,12 @@ mod tests {
             opencode_data_path: "/tmp/opencode-data".to_string(),
             topic_id: 789,
             env_vars: vec![],
+            dns: vec![],
+            dns_search: vec![],
+            extra_binds: vec![],
+            extra_args: vec![],
+            workspace_mount: "/workspace".to_string(),
+            memory_limit_mb: 0,
         };
 
         assert_eq!(config.container_name(), "oc-custom");
@@ -749,6 +1139,39 @@ mod tests {
         assert_eq!(bindings["3000/tcp"][0].host_port, "9999");
     }
 
+    #[test]
+    fn test_host_config_includes_dns_when_configured() {
+        let mut config = test_config();
+        config.dns = vec!["10.0.0.2".to_string(), "10.0.0.3".to_string()];
+        config.dns_search = vec!["corp.example.com".to_string()];
+
+        let host_config = config.host_config();
+        assert_eq!(
+            host_config.dns,
+            Some(vec!["10.0.0.2".to_string(), "10.0.0.3".to_string()])
+        );
+        assert_eq!(
+            host_config.dns_search,
+            Some(vec!["corp.example.com".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_host_config_omits_dns_by_default() {
+        let host_config = test_config().host_config();
+        assert!(host_config.dns.is_none());
+        assert!(host_config.dns_search.is_none());
+        assert!(host_config.port_bindings.unwrap().contains_key("8080/tcp"));
+        assert!(host_config.memory.is_none());
+    }
+
+    #[test]
+    fn test_host_config_sets_memory_limit() {
+        let mut config = test_config();
+        config.memory_limit_mb = 512;
+        assert_eq!(config.host_config().memory, Some(512 * 1024 * 1024));
+    }
+
     #[test]
     fn test_container_state_equality() {
         assert_eq!(ContainerState::Running, ContainerState::Running);
@@ -0,0 +1,176 @@
+//! Component health checks.
+//!
+//! Produces a JSON-serializable report of the components the orchestrator
+//! depends on. The database and container runtime are critical: if either is
+//! unreachable the report is unhealthy and maps to HTTP 503.
+
+use crate::orchestrator::container::ContainerRuntime;
+use crate::orchestrator::manager::ManagerStatus;
+use crate::orchestrator::store::OrchestratorStore;
+use serde::Serialize;
+use tracing::{debug, warn};
+
+/// Error reported for a failed component. The cause is only logged, since
+/// the report is served to unauthenticated clients.
+const UNAVAILABLE: &str = "unavailable";
+
+/// Result of probing a single component.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ComponentCheck {
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl ComponentCheck {
+    fn from_result<T>(component: &str, result: anyhow::Result<T>) -> Self {
+        match result {
+            Ok(_) => Self {
+                ok: true,
+                error: None,
+            },
+            Err(e) => {
+                warn!(component = component, error = %format!("{:#}", e), "Health check failed");
+                Self {
+                    ok: false,
+                    error: Some(UNAVAILABLE.to_string()),
+                }
+            }
+        }
+    }
+}
+
+/// Instance counts included in the health report.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct InstanceCounts {
+    pub total: usize,
+    pub running: usize,
+    pub stopped: usize,
+    pub error: usize,
+}
+
+impl From<&ManagerStatus> for InstanceCounts {
+    fn from(status: &ManagerStatus) -> Self {
+        Self {
+            total: status.total_instances,
+            running: status.running_instances,
+            stopped: status.stopped_instances,
+            error: status.error_instances,
+        }
+    }
+}
+
+/// Health of all components.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HealthReport {
+    pub healthy: bool,
+    pub database: ComponentCheck,
+    pub docker: ComponentCheck,
+    pub instances: InstanceCounts,
+}
+
+impl HealthReport {
+    /// HTTP status code for this report: 200 when healthy, 503 otherwise.
+    pub fn status_code(&self) -> u16 {
+        if self.healthy {
+            200
+        } else {
+            503
+        }
+    }
+}
+
+/// Probe the database and container runtime and combine with instance counts.
+pub async fn check_health(
+    store: &OrchestratorStore,
+    runtime: &dyn ContainerRuntime,
+    status: &ManagerStatus,
+) -> HealthReport {
+    let database = ComponentCheck::from_result("database", store.ping().await);
+    let docker =
+        ComponentCheck::from_result("docker", runtime.list_containers_by_prefix("oc-").await);
+    let healthy = database.ok && docker.ok;
+
+    debug!(
+        healthy = healthy,
+        database_ok = database.ok,
+        docker_ok = docker.ok,
+        "Health check complete"
+    );
+
+    HealthReport {
+        healthy,
+        database,
+        docker,
+        instances: InstanceCounts::from(status),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::orchestrator::container::mock::MockRuntime;
+    use tempfile::TempDir;
+
+    fn test_status() -> ManagerStatus {
+        ManagerStatus {
+            total_instances: 3,
+            running_instances: 2,
+            stopped_instances: 1,
+            error_instances: 0,
+            available_ports: 7,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_check_health_all_components_ok() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = OrchestratorStore::new(&temp_dir.path().join("orch.db"))
+            .await
+            .unwrap();
+        let runtime = MockRuntime::new();
+
+        let report = check_health(&store, &runtime, &test_status()).await;
+        assert!(report.healthy);
+        assert_eq!(report.status_code(), 200);
+        assert!(report.database.ok);
+        assert!(report.docker.ok);
+        assert_eq!(report.instances.running, 2);
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["database"]["ok"], true);
+        assert!(json["database"].get("error").is_none());
+        assert_eq!(json["instances"]["total"], 3);
+    }
+
+    #[tokio::test]
+    async fn test_check_health_reports_closed_database() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = OrchestratorStore::new(&temp_dir.path().join("orch.db"))
+            .await
+            .unwrap();
+        store.close().await;
+        let runtime = MockRuntime::new();
+
+        let report = check_health(&store, &runtime, &test_status()).await;
+        assert!(!report.healthy);
+        assert_eq!(report.status_code(), 503);
+        assert!(!report.database.ok);
+        assert_eq!(report.database.error.as_deref(), Some(UNAVAILABLE));
+        assert!(report.docker.ok);
+    }
+
+    #[tokio::test]
+    async fn test_check_health_reports_docker_failure() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = OrchestratorStore::new(&temp_dir.path().join("orch.db"))
+            .await
+            .unwrap();
+        let runtime = MockRuntime::new().with_list_result(Err("daemon unreachable".to_string()));
+
+        let report = check_health(&store, &runtime, &test_status()).await;
+        assert!(!report.healthy);
+        // The cause is logged, not served
+        assert_eq!(report.docker.error.as_deref(), Some(UNAVAILABLE));
+    }
+}
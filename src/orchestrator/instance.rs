@@ -4,7 +4,7 @@
 //! of OpenCode processes, including spawning, health checks, and graceful shutdown.
 
 use crate::orchestrator::container::{ContainerConfig, ContainerRuntime, ContainerState};
-use crate::types::instance::{InstanceConfig, InstanceState};
+use crate::types::instance::{HealthMethod, InstanceConfig, InstanceState};
 use anyhow::{anyhow, Result};
 use std::fmt;
 use std::sync::Arc;
@@ -13,11 +13,14 @@ use tokio::sync::Mutex;
 use tracing::debug;
 
 /// Default timeout for graceful shutdown before SIGKILL.
-const GRACEFUL_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+pub(crate) const GRACEFUL_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
 
 /// Default timeout for health check HTTP requests.
 const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
 
+/// Recorded as the last error when the container was OOM-killed.
+pub const OOM_KILLED_ERROR: &str = "Instance ran out of memory; increase CONTAINER_MEMORY_MB";
+
 /// Manages the lifecycle of a single OpenCode instance.
 ///
 /// Handles container spawning, health checks, and graceful shutdown.
@@ -32,13 +35,18 @@ pub struct OpenCodeInstance {
     #[allow(dead_code)]
     // Used by future: session tracking feature
     session_id: Arc<Mutex<Option<String>>>,
+    /// Why the container last exited abnormally, if it did
+    last_error: Arc<Mutex<Option<String>>>,
     http_client: reqwest::Client,
 }
 
 impl OpenCodeInstance {
     /// Spawn a new OpenCode instance with the given configuration and port.
     ///
-    /// The instance will be started with `opencode serve --port PORT --project PATH`.
+    /// Instances always run in a container: the project is bind-mounted at
+    /// the container config's `workspace_mount` and started with
+    /// `opencode serve --port PORT --project <workspace_mount>` (see
+    /// [`ContainerConfig::cmd`]). There is no local-process spawn path.
     /// Initial state is `Starting`, transitioning to `Running` after successful spawn.
     ///
     /// # Arguments
@@ -114,31 +122,56 @@ impl OpenCodeInstance {
                 runtime: Some(runtime),
                 container_id: container_id_holder,
                 session_id,
+                last_error: Arc::new(Mutex::new(None)),
                 http_client,
             },
             container_id,
         ))
     }
 
+    /// URL of the instance's health endpoint, e.g.
+    /// `http://localhost:{port}/global/health`.
+    pub fn health_url(&self) -> String {
+        format!("http://localhost:{}{}", self.port, self.config.health_path)
+    }
+
     /// Perform a health check by polling the instance's health endpoint.
     ///
-    /// Sends a GET request to `http://localhost:{port}/global/health`.
+    /// Sends the configured method (GET by default) to [`Self::health_url`].
+    /// The instance is healthy when the status matches the expected codes
+    /// (any 2xx if none are configured) and, when an expected body is set,
+    /// the response body contains it.
     ///
     /// # Returns
     /// * `Ok(true)` - Instance is healthy
     /// * `Ok(false)` - Health check failed (instance not ready or unhealthy)
     /// * `Err(_)` - HTTP request failed
     pub async fn health_check(&self) -> Result<bool> {
-        let url = format!("http://localhost:{}/global/health", self.port);
-        debug!(instance_id = %self.id, url = %url, "Checking instance health");
+        let url = self.health_url();
+        let check = &self.config.health_check;
+        debug!(instance_id = %self.id, url = %url, method = ?check.method, "Checking instance health");
 
-        match self.http_client.get(&url).send().await {
+        let request = match check.method {
+            HealthMethod::Get => self.http_client.get(&url),
+            HealthMethod::Head => self.http_client.head(&url),
+        };
+        match request.send().await {
             Ok(response) => {
-                let is_healthy = response.status().is_success();
+                let status = response.status().as_u16();
+                let mut is_healthy = check.status_ok(status);
+                if is_healthy && check.expect_body.is_some() {
+                    is_healthy = match response.text().await {
+                        Ok(body) => check.body_ok(&body),
+                        Err(e) => {
+                            debug!(instance_id = %self.id, error = %e, "Failed to read health response body");
+                            false
+                        }
+                    };
+                }
                 debug!(
                     instance_id = %self.id,
                     healthy = is_healthy,
-                    status = response.status().as_u16(),
+                    status = status,
                     "Health check result"
                 );
                 Ok(is_healthy)
@@ -167,12 +200,19 @@ impl OpenCodeInstance {
     /// 1. Sends SIGTERM to the process
     /// 2. Waits up to 5 seconds for graceful exit
     /// 3. If still running, sends SIGKILL
+    /// 4. Removes the container
     ///
     /// # Returns
     /// * `Ok(())` - Instance stopped successfully
     /// * `Err(_)` - Failed to stop instance
     pub async fn stop(&self) -> Result<()> {
-        debug!(instance_id = %self.id, "Stopping instance");
+        self.stop_with(true).await
+    }
+
+    /// Stop the instance like [`Self::stop`], leaving the stopped container
+    /// in place for debugging unless `remove_container` is set.
+    pub async fn stop_with(&self, remove_container: bool) -> Result<()> {
+        debug!(instance_id = %self.id, remove_container = remove_container, "Stopping instance");
 
         {
             let mut state_guard = self.state.lock().await;
@@ -186,7 +226,11 @@ impl OpenCodeInstance {
             runtime
                 .stop_container(&container_id, GRACEFUL_SHUTDOWN_TIMEOUT.as_secs())
                 .await?;
-            runtime.remove_container(&container_id, true).await?;
+            if remove_container {
+                runtime.remove_container(&container_id, true).await?;
+            } else {
+                debug!(instance_id = %self.id, container_id = %container_id, "Keeping stopped container");
+            }
         }
 
         {
@@ -224,6 +268,16 @@ impl OpenCodeInstance {
         &self.id
     }
 
+    /// Get the ID of the container running this instance, if any.
+    pub async fn container_id(&self) -> Option<String> {
+        self.container_id.lock().await.clone()
+    }
+
+    /// Why the container last exited abnormally, e.g. [`OOM_KILLED_ERROR`].
+    pub async fn last_error(&self) -> Option<String> {
+        self.last_error.lock().await.clone()
+    }
+
     /// Get the session ID if set.
     #[allow(dead_code)]
     // Used by future: session tracking feature
@@ -248,10 +302,14 @@ impl OpenCodeInstance {
         *guard = new_state;
     }
 
-    /// Check if the process has crashed (exited unexpectedly).
+    /// Check if the container has crashed (exited unexpectedly).
     ///
-    /// Returns true if the process was running but has now exited.
-    /// Updates state to Error if crash detected.
+    /// Inspects the container through the runtime rather than the HTTP health
+    /// endpoint, so a dead container is caught even if its port is reused.
+    /// Returns true if the container exited with a non-zero code or was
+    /// OOM-killed and updates state to Error, recording the cause in
+    /// [`last_error`](Self::last_error); a clean exit moves the instance to
+    /// Stopped.
     pub async fn check_for_crash(&self) -> Result<bool> {
         let runtime = self.runtime.as_ref().map(Arc::clone);
         let container_id = { self.container_id.lock().await.clone() };
@@ -271,8 +329,9 @@ impl OpenCodeInstance {
                 Ok(false)
             }
             ContainerState::Exited(code) => {
+                let crashed = code != 0 || info.oom_killed;
                 let mut state_guard = self.state.lock().await;
-                if code == 0 {
+                if !crashed {
                     debug!(
                         instance_id = %self.id,
                         exit_code = code,
@@ -283,16 +342,22 @@ impl OpenCodeInstance {
                     debug!(
                         instance_id = %self.id,
                         exit_code = code,
+                        oom_killed = info.oom_killed,
                         "Container crash detected"
                     );
                     *state_guard = InstanceState::Error;
+                    *self.last_error.lock().await = Some(if info.oom_killed {
+                        OOM_KILLED_ERROR.to_string()
+                    } else {
+                        format!("Container exited with code {}", code)
+                    });
                 }
                 drop(state_guard);
 
                 let mut container_guard = self.container_id.lock().await;
                 *container_guard = None;
 
-                Ok(code != 0)
+                Ok(crashed)
             }
             ContainerState::Created | ContainerState::Unknown(_) => Ok(false),
         }
@@ -369,6 +434,10 @@ mod tests {
     use super::*;
     use crate::orchestrator::container::mock::{MockAction, MockRuntime};
     use crate::orchestrator::container::{ContainerConfig, ContainerInfo, ContainerState};
+    use crate::types::instance::HealthCheck;
+    use std::collections::HashMap;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
 
     /// Helper to create a test InstanceConfig
     fn test_config(id: &str, project_path: &str) -> InstanceConfig {
@@ -378,6 +447,8 @@ mod tests {
             port: 0,
             auto_start: true,
             opencode_path: "opencode".to_string(),
+            health_path: "/global/health".to_string(),
+            health_check: Default::default(),
         }
     }
 
@@ -392,6 +463,12 @@ mod tests {
             opencode_data_path: "/tmp/opencode".to_string(),
             topic_id: 1,
             env_vars: vec![],
+            dns: vec![],
+            dns_search: vec![],
+            extra_binds: vec![],
+            extra_args: vec![],
+            workspace_mount: "/workspace".to_string(),
+            memory_limit_mb: 0,
         }
     }
 
@@ -439,6 +516,77 @@ mod tests {
         assert!(matches!(actions[3], MockAction::RemoveContainer { .. }));
     }
 
+    #[tokio::test]
+    async fn test_health_url_uses_configured_path() {
+        let mut config = test_config("health-path-test", "/tmp/project");
+        config.health_path = "/api/health".to_string();
+        let container_config = test_container_config("health-path-test", 4306);
+        let runtime: Arc<dyn ContainerRuntime> = Arc::new(MockRuntime::new());
+
+        let (instance, _) = OpenCodeInstance::spawn(config, 4306, runtime, container_config)
+            .await
+            .unwrap();
+
+        assert_eq!(instance.health_url(), "http://localhost:4306/api/health");
+    }
+
+    /// Spawn an instance whose health URL points at `server`.
+    async fn spawn_against(server: &MockServer, config: InstanceConfig) -> OpenCodeInstance {
+        let port = server.address().port();
+        let container_config = test_container_config(&config.id, port);
+        let runtime: Arc<dyn ContainerRuntime> = Arc::new(MockRuntime::new());
+        let (instance, _) = OpenCodeInstance::spawn(config, port, runtime, container_config)
+            .await
+            .unwrap();
+        instance
+    }
+
+    #[tokio::test]
+    async fn test_health_check_accepts_expected_204() {
+        let server = MockServer::start().await;
+        Mock::given(method("HEAD"))
+            .and(path("/global/health"))
+            .respond_with(ResponseTemplate::new(204))
+            .mount(&server)
+            .await;
+
+        let mut config = test_config("health-204-test", "/tmp/project");
+        config.health_check = HealthCheck {
+            method: HealthMethod::Head,
+            expect_status: vec![204],
+            expect_body: None,
+        };
+        let instance = spawn_against(&server, config).await;
+        assert!(instance.health_check().await.unwrap());
+
+        // A 200 is not healthy once only 204 is expected
+        server.reset().await;
+        Mock::given(method("HEAD"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+        assert!(!instance.health_check().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_health_check_matches_expected_body() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/global/health"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(r#"{"status":"ok"}"#))
+            .mount(&server)
+            .await;
+
+        let mut config = test_config("health-body-test", "/tmp/project");
+        config.health_check.expect_body = Some(r#""status":"ok""#.to_string());
+        let instance = spawn_against(&server, config.clone()).await;
+        assert!(instance.health_check().await.unwrap());
+
+        config.health_check.expect_body = Some(r#""status":"ready""#.to_string());
+        let instance = spawn_against(&server, config).await;
+        assert!(!instance.health_check().await.unwrap());
+    }
+
     #[tokio::test]
     async fn test_crash_detection_running() {
         let mut config = test_config("running-test", "/tmp/project");
@@ -448,6 +596,8 @@ mod tests {
             id: "mock-container-id-abc123".to_string(),
             name: "oc-running-test".to_string(),
             state: ContainerState::Running,
+            labels: HashMap::new(),
+            oom_killed: false,
         })));
         let runtime_arc: Arc<dyn ContainerRuntime> = runtime.clone();
 
@@ -469,6 +619,8 @@ mod tests {
             id: "mock-container-id-abc123".to_string(),
             name: "oc-crash-test".to_string(),
             state: ContainerState::Exited(1),
+            labels: HashMap::new(),
+            oom_killed: false,
         })));
         let runtime_arc: Arc<dyn ContainerRuntime> = runtime.clone();
 
@@ -479,6 +631,38 @@ mod tests {
         let crashed = instance.check_for_crash().await.unwrap();
         assert!(crashed);
         assert_eq!(instance.state().await, InstanceState::Error);
+        assert_eq!(
+            instance.last_error().await.as_deref(),
+            Some("Container exited with code 1")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_crash_detection_oom_killed() {
+        let mut config = test_config("oom-test", "/tmp/project");
+        config.port = 4307;
+        let container_config = test_container_config("oom-test", 4307);
+        let runtime = Arc::new(MockRuntime::new().with_inspect_result(Ok(ContainerInfo {
+            id: "mock-container-id-abc123".to_string(),
+            name: "oc-oom-test".to_string(),
+            state: ContainerState::Exited(137),
+            labels: HashMap::new(),
+            oom_killed: true,
+        })));
+        let runtime_arc: Arc<dyn ContainerRuntime> = runtime.clone();
+
+        let (instance, _) = OpenCodeInstance::spawn(config, 4307, runtime_arc, container_config)
+            .await
+            .unwrap();
+        assert_eq!(instance.last_error().await, None);
+
+        let crashed = instance.check_for_crash().await.unwrap();
+        assert!(crashed);
+        assert_eq!(instance.state().await, InstanceState::Error);
+        assert_eq!(
+            instance.last_error().await.as_deref(),
+            Some(OOM_KILLED_ERROR)
+        );
     }
 
     #[tokio::test]
@@ -490,6 +674,8 @@ mod tests {
             id: "mock-container-id-abc123".to_string(),
             name: "oc-exit-zero-test".to_string(),
             state: ContainerState::Exited(0),
+            labels: HashMap::new(),
+            oom_killed: false,
         })));
         let runtime_arc: Arc<dyn ContainerRuntime> = runtime.clone();
 
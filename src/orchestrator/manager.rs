@@ -9,40 +9,231 @@
 //! - Integration with OrchestratorStore for persistence
 //! - Integration with PortPool for port allocation
 
-use crate::config::Config;
-use crate::orchestrator::container::{ContainerConfig, ContainerRuntime};
-use crate::orchestrator::instance::OpenCodeInstance;
+use crate::backoff;
+use crate::config::{Config, SharedConfig};
+use crate::db::log_store::{InstanceEvent, LogStore};
+use crate::git::worktree::sanitize_branch_name;
+use crate::orchestrator::container::{ContainerConfig, ContainerRuntime, LABEL_INSTANCE_ID};
+use crate::orchestrator::health::{check_health, HealthReport};
+use crate::orchestrator::instance::{
+    OpenCodeInstance, GRACEFUL_SHUTDOWN_TIMEOUT, OOM_KILLED_ERROR,
+};
 use crate::orchestrator::port_pool::PortPool;
 use crate::orchestrator::store::OrchestratorStore;
+use crate::project_config::ProjectConfig;
+use crate::telegram::image_cache::cleanup_image_cache;
 use crate::types::instance::{InstanceConfig, InstanceInfo, InstanceState};
 use anyhow::{anyhow, Result};
 use std::collections::HashMap;
-use std::path::Path;
+use std::path::{Component, Path};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
-use tokio::sync::Mutex;
+use tokio::sync::{mpsc, Mutex};
 use tracing::debug;
 
 /// Maximum number of restart attempts before giving up.
 const MAX_RESTART_ATTEMPTS: usize = 5;
 
-/// Initial restart delay (doubles each attempt: 1s, 2s, 4s, 8s, 16s).
+/// Initial restart delay (doubles each attempt: 1s, 2s, 4s, 8s, 16s, ±25%).
 const INITIAL_RESTART_DELAY: Duration = Duration::from_secs(1);
 
+/// How long a project whose instance used up its restart attempts is not
+/// started again, unless the breaker is reset with `/restart`.
+pub const CIRCUIT_BREAKER_COOLDOWN: Duration = Duration::from_secs(10 * 60);
+
+/// Delay before restart attempt `attempt` (0-based): [`INITIAL_RESTART_DELAY`]
+/// doubled per attempt, jittered for `sample` (see [`backoff::jittered`]).
+fn restart_delay(attempt: usize, sample: f64) -> Duration {
+    backoff::jittered(
+        INITIAL_RESTART_DELAY.mul_f64(2_f64.powi(attempt as i32)),
+        sample,
+    )
+}
+
+/// The container runtime could not be reached, so instances cannot be
+/// spawned or restarted.
+#[derive(Debug, thiserror::Error)]
+#[error("Container runtime unavailable: {0}")]
+pub struct RuntimeUnavailable(pub String);
+
+/// Whether `err` is a [`RuntimeUnavailable`] error.
+pub fn is_runtime_unavailable(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<RuntimeUnavailable>().is_some()
+}
+
+/// The project's instance kept crashing and used up its restart attempts, so
+/// it is not started again until the cooldown passes or the breaker is reset.
+#[derive(Debug, thiserror::Error)]
+#[error("Instance for {project} is in a failed state after repeated crashes")]
+pub struct CircuitOpen {
+    pub project: String,
+    /// Time left until the instance may be started again.
+    pub retry_after: Duration,
+}
+
+/// Whether `err` is a [`CircuitOpen`] error.
+pub fn is_circuit_open(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<CircuitOpen>().is_some()
+}
+
+/// Why an [`InstanceFailure`] was sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureReason {
+    /// Used up its restart attempts; the circuit breaker tripped
+    RepeatedCrashes,
+    /// The container was OOM-killed; a restart is attempted as for any crash
+    OutOfMemory,
+}
+
+/// Sent when an instance's container was OOM-killed, or when it used up its
+/// restart attempts and its circuit breaker tripped, so the topic can be told.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstanceFailure {
+    pub project_path: String,
+    pub topic_id: i32,
+    pub reason: FailureReason,
+}
+
+/// Trip the circuit breaker for `project_path` and notify the listener, if any.
+async fn trip_circuit(
+    breakers: &Mutex<HashMap<String, Instant>>,
+    notifier: Option<&mpsc::UnboundedSender<InstanceFailure>>,
+    project_path: &str,
+    topic_id: i32,
+) {
+    tracing::warn!(project_path = %project_path, topic_id = topic_id, cooldown_secs = CIRCUIT_BREAKER_COOLDOWN.as_secs(), "Circuit breaker tripped");
+    breakers
+        .lock()
+        .await
+        .insert(project_path.to_string(), Instant::now());
+    if let Some(notifier) = notifier {
+        let _ = notifier.send(InstanceFailure {
+            project_path: project_path.to_string(),
+            topic_id,
+            reason: FailureReason::RepeatedCrashes,
+        });
+    }
+}
+
+/// Probe the container runtime and record the result in `available`.
+///
+/// Logs only on transitions so a long outage does not flood the log.
+async fn probe_runtime(runtime: &dyn ContainerRuntime, available: &AtomicBool) -> Result<()> {
+    let result = runtime.list_containers_by_prefix("oc-").await.map(|_| ());
+    let was_available = available.swap(result.is_ok(), Ordering::SeqCst);
+    match &result {
+        Ok(()) if !was_available => tracing::info!("Container runtime is available again"),
+        Err(e) if was_available => {
+            tracing::warn!(error = %e, "Container runtime is unavailable")
+        }
+        _ => {}
+    }
+    result
+}
+
+/// Record a lifecycle event for `/stats` if an event log is attached.
+async fn record_event(log: Option<&LogStore>, instance_id: &str, event: InstanceEvent) {
+    if let Some(log) = log {
+        if let Err(e) = log.record_instance_event(instance_id, event).await {
+            tracing::warn!(instance_id = %instance_id, event = event.as_str(), error = %e, "Failed to record instance event");
+        }
+    }
+}
+
+/// Extra time past the startup timeout before an instance still `Starting`
+/// is considered stuck.
+const STUCK_STARTING_GRACE: Duration = Duration::from_secs(30);
+
+/// Whether an instance that started at `started_at` (Unix seconds) has been
+/// `Starting` longer than `startup_timeout` plus [`STUCK_STARTING_GRACE`].
+/// Instances without a start time are never considered stuck.
+fn is_stuck_starting(started_at: Option<i64>, now: i64, startup_timeout: Duration) -> bool {
+    let limit = (startup_timeout + STUCK_STARTING_GRACE).as_secs() as i64;
+    started_at.is_some_and(|started| now - started > limit)
+}
+
+/// Whether a stopped instance's container is removed, or kept for debugging
+/// with `KEEP_STOPPED_CONTAINERS`. Every stop path goes through this.
+fn remove_stopped_container(config: &Config) -> bool {
+    !config.keep_stopped_containers
+}
+
+/// Mark instances stuck in `Starting` as `Error` and clean them up: stop the
+/// live instance or its container and release the port. The health checks
+/// only act on `Running` instances, so without this an interrupted startup
+/// would hold its port forever. Returns the number of instances reaped.
+async fn reap_stuck_starting(
+    store: &Mutex<OrchestratorStore>,
+    instances: &Mutex<HashMap<String, Arc<Mutex<OpenCodeInstance>>>>,
+    activity_trackers: &Mutex<HashMap<String, ActivityTracker>>,
+    port_pool: &PortPool,
+    runtime: &dyn ContainerRuntime,
+    startup_timeout: Duration,
+    remove_container: bool,
+) -> usize {
+    let all = match store.lock().await.get_all_instances().await {
+        Ok(all) => all,
+        Err(e) => {
+            tracing::warn!(error = %e, "Failed to load instances for stuck-starting check");
+            return 0;
+        }
+    };
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64;
+
+    let mut reaped = 0;
+    for info in all {
+        if info.state != InstanceState::Starting
+            || !is_stuck_starting(info.started_at, now, startup_timeout)
+        {
+            continue;
+        }
+        tracing::warn!(
+            instance_id = %info.id,
+            port = info.port,
+            "Instance stuck in Starting, marking error and cleaning up"
+        );
+
+        let instance = instances.lock().await.remove(&info.id);
+        match instance {
+            Some(instance) => {
+                let inst = instance.lock().await;
+                if let Err(e) = inst.stop_with(remove_container).await {
+                    tracing::warn!(instance_id = %info.id, error = %e, "Failed to stop stuck instance");
+                }
+            }
+            None => {
+                if let Some(container_id) = info.container_id.as_deref() {
+                    let _ = runtime
+                        .stop_container(container_id, GRACEFUL_SHUTDOWN_TIMEOUT.as_secs())
+                        .await;
+                    if remove_container {
+                        let _ = runtime.remove_container(container_id, true).await;
+                    }
+                }
+            }
+        }
+        port_pool.release(info.port).await;
+        activity_trackers.lock().await.remove(&info.id);
+
+        let store = store.lock().await;
+        if let Err(e) = store.update_state(&info.id, InstanceState::Error).await {
+            tracing::warn!(instance_id = %info.id, error = %e, "Failed to mark stuck instance as error");
+        }
+        reaped += 1;
+    }
+    reaped
+}
+
 /// Status information for the InstanceManager.
 #[derive(Debug, Clone)]
 pub struct ManagerStatus {
-    #[allow(dead_code)]
-    // Used by future: detailed status reporting feature
     pub total_instances: usize,
-    #[allow(dead_code)]
-    // Used by future: detailed status reporting feature
     pub running_instances: usize,
-    #[allow(dead_code)]
-    // Used by future: detailed status reporting feature
     pub stopped_instances: usize,
-    #[allow(dead_code)]
-    // Used by future: detailed status reporting feature
     pub error_instances: usize,
     pub available_ports: usize,
 }
@@ -58,14 +249,95 @@ struct RestartTracker {
 #[derive(Debug, Clone)]
 struct ActivityTracker {
     last_activity: Instant,
+    /// Per-project idle timeout; `None` uses the global default.
+    idle_timeout: Option<Duration>,
 }
 
 impl Default for ActivityTracker {
     fn default() -> Self {
         Self {
             last_activity: Instant::now(),
+            idle_timeout: None,
+        }
+    }
+}
+
+impl ActivityTracker {
+    fn with_idle_timeout(idle_timeout: Option<Duration>) -> Self {
+        Self {
+            idle_timeout,
+            ..Self::default()
+        }
+    }
+
+    /// Idle timeout in effect for this instance.
+    fn effective_idle_timeout(&self, default: Duration) -> Duration {
+        self.idle_timeout.unwrap_or(default)
+    }
+}
+
+/// Resolve the per-project idle timeout override, if any.
+///
+/// An unreadable project config is logged and treated as unset.
+fn project_idle_timeout(project_path: &Path) -> Option<Duration> {
+    match ProjectConfig::load(project_path) {
+        Ok(project_config) => project_config.idle_timeout(),
+        Err(e) => {
+            tracing::warn!(project_path = %project_path.display(), error = %e, "Ignoring invalid project config");
+            None
+        }
+    }
+}
+
+/// Resolve the per-project extra mounts as Docker bind strings.
+///
+/// Mounts must live inside the project; an invalid project config or mount
+/// list is logged and no extra mounts are added.
+fn project_mount_binds(project_path: &Path) -> Vec<String> {
+    let mounts = ProjectConfig::load(project_path)
+        .and_then(|project_config| project_config.validated_mounts(project_path));
+    match mounts {
+        Ok(mounts) => mounts.iter().map(|m| m.to_bind()).collect(),
+        Err(e) => {
+            tracing::warn!(project_path = %project_path.display(), error = %e, "Ignoring project mounts");
+            Vec::new()
+        }
+    }
+}
+
+/// Extra `opencode serve` arguments for a project's container: the global
+/// `OPENCODE_EXTRA_ARGS` followed by the project's `extra_args`.
+///
+/// An unreadable project config or a project arg outside the allowlist is
+/// logged and only the global args are used.
+fn container_extra_args(global: &[String], project_path: &Path) -> Vec<String> {
+    let mut args = global.to_vec();
+    match ProjectConfig::load(project_path).and_then(|c| c.validated_extra_args()) {
+        Ok(project_args) => args.extend(project_args),
+        Err(e) => {
+            tracing::warn!(project_path = %project_path.display(), error = %e, "Ignoring project extra args");
         }
     }
+    args
+}
+
+/// Key under which a project is cordoned: the sanitized directory name below
+/// `base`, or the worktree name for paths under `base/.worktrees`.
+pub fn project_cordon_key(project_path: &Path, base: &Path) -> Option<String> {
+    let name = match project_path.strip_prefix(base) {
+        Ok(relative) => {
+            let mut names = relative.components().filter_map(|c| match c {
+                Component::Normal(s) => s.to_str(),
+                _ => None,
+            });
+            match names.next()? {
+                ".worktrees" => names.next()?,
+                name => name,
+            }
+        }
+        Err(_) => project_path.file_name()?.to_str()?,
+    };
+    Some(sanitize_branch_name(name)).filter(|key| !key.is_empty())
 }
 
 /// Manages the lifecycle of all OpenCode instances.
@@ -76,14 +348,27 @@ impl Default for ActivityTracker {
 /// - Auto-restart: Restarts crashed instances with exponential backoff
 /// - Health checks: Periodic health monitoring via background task
 /// - Persistence: Integrates with OrchestratorStore
+/// - Warm pool: Adopts pre-started containers for new projects
 pub struct InstanceManager {
-    config: Arc<Config>,
+    config: SharedConfig,
     runtime: Arc<dyn ContainerRuntime>,
     store: Arc<Mutex<OrchestratorStore>>,
     port_pool: Arc<PortPool>,
     instances: Arc<Mutex<HashMap<String, Arc<Mutex<OpenCodeInstance>>>>>,
+    /// Instances started outside the manager, keyed by id, with their port.
+    /// They count against the instance limit and hold their port.
+    external_instances: Arc<Mutex<HashMap<String, u16>>>,
     restart_trackers: Arc<Mutex<HashMap<String, RestartTracker>>>,
     activity_trackers: Arc<Mutex<HashMap<String, ActivityTracker>>>,
+    /// Projects whose instance used up its restart attempts, with when the
+    /// breaker tripped. They are not started again until the cooldown passes.
+    circuit_breakers: Arc<Mutex<HashMap<String, Instant>>>,
+    /// Told about tripped circuit breakers.
+    failure_notifier: Option<mpsc::UnboundedSender<InstanceFailure>>,
+    /// Result of the last container runtime probe.
+    runtime_available: Arc<AtomicBool>,
+    /// Where spawn/stop/crash events are recorded for `/stats`.
+    event_log: Option<LogStore>,
     shutdown_signal: Arc<Mutex<bool>>,
 }
 
@@ -95,23 +380,72 @@ impl InstanceManager {
     /// * `store` - OrchestratorStore for persistence
     /// * `port_pool` - PortPool for port allocation
     pub async fn new(
-        config: Arc<Config>,
+        config: impl Into<SharedConfig>,
         store: OrchestratorStore,
         port_pool: PortPool,
         runtime: Arc<dyn ContainerRuntime>,
     ) -> Result<Self> {
+        let config: SharedConfig = config.into();
+        let port_pool = Arc::new(port_pool);
+        let instances = Arc::new(Mutex::new(HashMap::new()));
+        let external_instances = Arc::new(Mutex::new(HashMap::new()));
         Ok(Self {
             config,
             runtime,
             store: Arc::new(Mutex::new(store)),
-            port_pool: Arc::new(port_pool),
-            instances: Arc::new(Mutex::new(HashMap::new())),
+            port_pool,
+            instances,
+            external_instances,
             restart_trackers: Arc::new(Mutex::new(HashMap::new())),
             activity_trackers: Arc::new(Mutex::new(HashMap::new())),
+            circuit_breakers: Arc::new(Mutex::new(HashMap::new())),
+            failure_notifier: None,
+            runtime_available: Arc::new(AtomicBool::new(true)),
+            event_log: None,
             shutdown_signal: Arc::new(Mutex::new(false)),
         })
     }
 
+    /// Record instance lifecycle events to `log`.
+    pub fn with_event_log(mut self, log: LogStore) -> Self {
+        self.event_log = Some(log);
+        self
+    }
+
+    /// Send an [`InstanceFailure`] to `notifier` whenever a circuit breaker
+    /// trips.
+    pub fn with_failure_notifier(
+        mut self,
+        notifier: mpsc::UnboundedSender<InstanceFailure>,
+    ) -> Self {
+        self.failure_notifier = Some(notifier);
+        self
+    }
+
+    /// Probe the container runtime now and cache the result.
+    pub async fn probe_runtime(&self) -> bool {
+        probe_runtime(self.runtime.as_ref(), &self.runtime_available)
+            .await
+            .is_ok()
+    }
+
+    /// Whether the last runtime probe succeeded.
+    pub fn runtime_available(&self) -> bool {
+        self.runtime_available.load(Ordering::SeqCst)
+    }
+
+    /// Fail with [`RuntimeUnavailable`] if the runtime is down. A cached
+    /// failure is re-probed so recovery is noticed without waiting for the
+    /// next health check.
+    async fn ensure_runtime_available(&self) -> Result<()> {
+        if self.runtime_available() {
+            return Ok(());
+        }
+        probe_runtime(self.runtime.as_ref(), &self.runtime_available)
+            .await
+            .map_err(|e| RuntimeUnavailable(format!("{:#}", e)).into())
+    }
+
     /// Get an existing instance or create a new one for the given project path.
     ///
     /// Logic:
@@ -131,19 +465,26 @@ impl InstanceManager {
 
         debug!(project_path = %path_str, topic_id = topic_id, "get_or_create: looking up instance");
 
+        if self.is_cordoned(project_path).await {
+            return Err(anyhow!("Project is cordoned for maintenance: {}", path_str));
+        }
+        self.check_circuit(project_path).await?;
+
         // Check if instance already exists in memory
         if let Some(instance) = self.get_instance_by_path(project_path).await {
             let inst = instance.lock().await;
             match inst.state().await {
                 InstanceState::Running | InstanceState::Starting => {
                     debug!(project_path = %path_str, "Returning existing running instance");
+                    let id = inst.id().to_string();
                     drop(inst);
-                    self.record_activity(path_str).await;
+                    self.record_activity(&id).await;
                     return Ok(instance);
                 }
                 InstanceState::Stopped | InstanceState::Error => {
                     debug!(project_path = %path_str, "Instance stopped/error, attempting restart");
                     drop(inst);
+                    self.ensure_runtime_available().await?;
                     // Try to restart
                     return self.restart_instance_by_path(project_path).await;
                 }
@@ -153,6 +494,8 @@ impl InstanceManager {
             }
         }
 
+        self.ensure_runtime_available().await?;
+
         // Check database for persisted instance
         let store = self.store.lock().await;
         if let Some(info) = store.get_instance_by_path(path_str).await? {
@@ -166,34 +509,252 @@ impl InstanceManager {
         }
 
         // Check max instances limit
-        let instances = self.instances.lock().await;
-        debug!(
-            current_count = instances.len(),
-            max = self.config.opencode_max_instances,
-            "Checking instance limit"
-        );
-        if instances.len() >= self.config.opencode_max_instances {
-            return Err(anyhow!(
-                "Maximum instances limit reached ({})",
-                self.config.opencode_max_instances
-            ));
-        }
-        drop(instances);
+        self.check_instance_limit().await?;
 
         // Create new instance
         debug!(project_path = %path_str, "Spawning new instance");
         self.spawn_new_instance(project_path, topic_id).await
     }
 
+    /// Number of instances counted against `OPENCODE_MAX_INSTANCES`: managed
+    /// instances plus registered external ones.
+    pub async fn instance_count(&self) -> usize {
+        let managed = self.instances.lock().await.len();
+        let external = self.external_instances.lock().await.len();
+        managed + external
+    }
+
+    /// Pull the configured container image if it is missing, so the first
+    /// topic does not wait on the download. Returns whether it was pulled.
+    pub async fn prepull_image(&self) -> Result<bool> {
+        let image = self.config.get().docker_image.clone();
+        self.runtime.ensure_image(&image).await
+    }
+
+    async fn check_instance_limit(&self) -> Result<()> {
+        let count = self.instance_count().await;
+        let max = self.config.get().opencode_max_instances;
+        debug!(current_count = count, max = max, "Checking instance limit");
+        if count >= max {
+            return Err(anyhow!("Maximum instances limit reached ({})", max));
+        }
+        Ok(())
+    }
+
+    /// Register an instance that runs outside the manager on `port`.
+    ///
+    /// External instances count against the instance limit, and a port inside
+    /// the pool range is reserved so it is not handed to a managed instance.
+    pub async fn register_external(&self, id: &str, port: u16) -> Result<()> {
+        debug!(instance_id = %id, port = port, "Registering external instance");
+        if self.external_instances.lock().await.contains_key(id)
+            || self.instances.lock().await.contains_key(id)
+        {
+            return Err(anyhow!("Instance already registered: {}", id));
+        }
+        self.check_instance_limit().await?;
+        if self.port_pool.contains(port) && !self.port_pool.reserve(port).await {
+            return Err(anyhow!("Port {} is already allocated", port));
+        }
+        self.external_instances
+            .lock()
+            .await
+            .insert(id.to_string(), port);
+        Ok(())
+    }
+
+    /// Forget an external instance and release its port.
+    ///
+    /// Returns `false` if no external instance has this id.
+    pub async fn unregister_external(&self, id: &str) -> bool {
+        let Some(port) = self.external_instances.lock().await.remove(id) else {
+            return false;
+        };
+        if self.port_pool.contains(port) {
+            self.port_pool.release(port).await;
+        }
+        debug!(instance_id = %id, port = port, "External instance unregistered");
+        true
+    }
+
+    /// Last `tail` lines of the container logs for instance `id`.
+    ///
+    /// Returns `None` if the instance is unknown, has no container, or its
+    /// container no longer exists.
+    pub async fn instance_logs(&self, id: &str, tail: usize) -> Result<Option<String>> {
+        let tracked = { self.instances.lock().await.get(id).cloned() };
+        let container_id = match tracked {
+            Some(instance) => instance.lock().await.container_id().await,
+            None => None,
+        };
+        let container_id = match container_id {
+            Some(container_id) => Some(container_id),
+            None => self
+                .store
+                .lock()
+                .await
+                .get_instance(id)
+                .await?
+                .and_then(|info| info.container_id),
+        };
+        let Some(container_id) = container_id else {
+            return Ok(None);
+        };
+
+        self.runtime.container_logs(&container_id, tail).await
+    }
+
     /// Get an instance by ID.
-    #[allow(dead_code)]
-    // Used by future: instance lookup feature
     pub async fn get_instance(&self, id: &str) -> Option<Arc<Mutex<OpenCodeInstance>>> {
         let instances = self.instances.lock().await;
         instances.get(id).cloned()
     }
 
+    /// Record of instance `id`, with the state, port, project path and
+    /// container taken from the in-memory instance when it is running and
+    /// the rest from the store.
+    ///
+    /// Returns `None` if the instance is neither running nor stored.
+    pub async fn get_instance_by_id(&self, id: &str) -> Result<Option<InstanceInfo>> {
+        let stored = self.store.lock().await.get_instance(id).await?;
+        let Some(instance) = self.get_instance(id).await else {
+            return Ok(stored);
+        };
+
+        let instance = instance.lock().await;
+        let mut info = stored.unwrap_or_else(|| InstanceInfo {
+            id: id.to_string(),
+            state: InstanceState::Stopped,
+            project_path: String::new(),
+            port: 0,
+            pid: None,
+            container_id: None,
+            started_at: None,
+            stopped_at: None,
+            topic_id: 0,
+        });
+        info.state = instance.state().await;
+        info.port = instance.port();
+        info.project_path = instance.project_path().to_string();
+        if let Some(container_id) = instance.container_id().await {
+            info.container_id = Some(container_id);
+        }
+        Ok(Some(info))
+    }
+
     /// Get an instance by project path.
+    /// Whether the project owning `project_path` is cordoned. Store errors
+    /// are logged and treated as not cordoned.
+    pub async fn is_cordoned(&self, project_path: &Path) -> bool {
+        let base = self.config.get().project_base_path.clone();
+        let Some(key) = project_cordon_key(project_path, &base) else {
+            return false;
+        };
+        let store = self.store.lock().await;
+        store.is_project_cordoned(&key).await.unwrap_or_else(|e| {
+            tracing::warn!(project = %key, error = %e, "Failed to read cordon state");
+            false
+        })
+    }
+
+    /// Cordon `project` for maintenance and stop its running instances.
+    /// Returns the IDs of the stopped instances.
+    pub async fn cordon_project(&self, project: &str) -> Result<Vec<String>> {
+        let key = sanitize_branch_name(project);
+        if key.is_empty() {
+            return Err(anyhow!("Invalid project name: {}", project));
+        }
+        self.store.lock().await.cordon_project(&key).await?;
+
+        let base = self.config.get().project_base_path.clone();
+        let mut matching = Vec::new();
+        {
+            let instances = self.instances.lock().await;
+            for (id, instance) in instances.iter() {
+                let inst = instance.lock().await;
+                if project_cordon_key(Path::new(inst.project_path()), &base).as_deref()
+                    == Some(key.as_str())
+                {
+                    matching.push(id.clone());
+                }
+            }
+        }
+
+        let mut stopped = Vec::new();
+        for id in matching {
+            match self.stop_instance(&id).await {
+                Ok(()) => stopped.push(id),
+                Err(e) => {
+                    tracing::warn!(instance_id = %id, error = %e, "Failed to stop cordoned instance")
+                }
+            }
+        }
+
+        tracing::info!(project = %key, stopped = ?stopped, "Project cordoned");
+        Ok(stopped)
+    }
+
+    /// Clear the cordon on `project`. Returns false if it was not cordoned.
+    pub async fn uncordon_project(&self, project: &str) -> Result<bool> {
+        let key = sanitize_branch_name(project);
+        let removed = self.store.lock().await.uncordon_project(&key).await?;
+        if removed {
+            tracing::info!(project = %key, "Project uncordoned");
+        }
+        Ok(removed)
+    }
+
+    /// Names of all cordoned projects.
+    pub async fn cordoned_projects(&self) -> Result<Vec<String>> {
+        self.store.lock().await.get_cordoned_projects().await
+    }
+
+    /// Fail with [`CircuitOpen`] while the project's circuit breaker is
+    /// tripped. Once the cooldown has passed the breaker closes and the
+    /// instance gets a fresh set of restart attempts.
+    async fn check_circuit(&self, project_path: &Path) -> Result<()> {
+        let path_str = project_path.to_string_lossy().to_string();
+        let mut breakers = self.circuit_breakers.lock().await;
+        let Some(tripped_at) = breakers.get(&path_str).copied() else {
+            return Ok(());
+        };
+        let elapsed = tripped_at.elapsed();
+        if elapsed < CIRCUIT_BREAKER_COOLDOWN {
+            return Err(CircuitOpen {
+                project: path_str,
+                retry_after: CIRCUIT_BREAKER_COOLDOWN - elapsed,
+            }
+            .into());
+        }
+        breakers.remove(&path_str);
+        drop(breakers);
+        debug!(project_path = %path_str, "Circuit breaker cooldown passed");
+        self.clear_restart_tracker(project_path).await;
+        Ok(())
+    }
+
+    /// Reset the project's circuit breaker and restart attempts, e.g. for a
+    /// manual `/restart`. Returns whether the breaker was tripped.
+    pub async fn reset_circuit(&self, project_path: &Path) -> bool {
+        let path_str = project_path.to_string_lossy().to_string();
+        let tripped = self
+            .circuit_breakers
+            .lock()
+            .await
+            .remove(&path_str)
+            .is_some();
+        self.clear_restart_tracker(project_path).await;
+        debug!(project_path = %path_str, tripped = tripped, "Circuit breaker reset");
+        tripped
+    }
+
+    async fn clear_restart_tracker(&self, project_path: &Path) {
+        if let Some(instance) = self.get_instance_by_path(project_path).await {
+            let id = instance.lock().await.id().to_string();
+            self.restart_trackers.lock().await.remove(&id);
+        }
+    }
+
     pub async fn get_instance_by_path(&self, path: &Path) -> Option<Arc<Mutex<OpenCodeInstance>>> {
         let path_str = path.to_str()?;
         let instances = self.instances.lock().await;
@@ -208,7 +769,15 @@ impl InstanceManager {
     }
 
     /// Stop a specific instance by ID.
+    ///
+    /// With `KEEP_STOPPED_CONTAINERS` the stopped container is left in place
+    /// for debugging; it is cleaned up as an orphan at the next startup.
     pub async fn stop_instance(&self, id: &str) -> Result<()> {
+        let remove_container = remove_stopped_container(&self.config.get());
+        self.stop_tracked_instance(id, remove_container).await
+    }
+
+    async fn stop_tracked_instance(&self, id: &str, remove_container: bool) -> Result<()> {
         debug!(instance_id = %id, "Stopping instance");
         let instance = {
             let instances = self.instances.lock().await;
@@ -218,7 +787,7 @@ impl InstanceManager {
         if let Some(instance) = instance {
             let inst = instance.lock().await;
             let port = inst.port();
-            inst.stop().await?;
+            inst.stop_with(remove_container).await?;
             drop(inst);
 
             debug!(instance_id = %id, port = port, "Instance stopped, releasing port");
@@ -229,6 +798,8 @@ impl InstanceManager {
             let store = self.store.lock().await;
             store.update_state(id, InstanceState::Stopped).await?;
             store.update_container_id(id, None).await?;
+            drop(store);
+            record_event(self.event_log.as_ref(), id, InstanceEvent::Stopped).await;
 
             let mut instances = self.instances.lock().await;
             instances.remove(id);
@@ -245,6 +816,44 @@ impl InstanceManager {
         }
     }
 
+    /// Stop an instance's container, release its port and delete its DB row.
+    ///
+    /// Instances that are only known to the store (e.g. stopped or left over
+    /// from a previous run) have their container removed best-effort.
+    /// Returns `Ok(false)` if no instance with this ID exists.
+    pub async fn remove_instance(&self, id: &str) -> Result<bool> {
+        debug!(instance_id = %id, "Removing instance");
+        if self.unregister_external(id).await {
+            return Ok(true);
+        }
+        let tracked = { self.instances.lock().await.contains_key(id) };
+        let info = { self.store.lock().await.get_instance(id).await? };
+
+        if !tracked && info.is_none() {
+            return Ok(false);
+        }
+
+        if tracked {
+            // The record goes away, so never keep the container
+            self.stop_tracked_instance(id, true).await?;
+        } else if let Some(container_id) = info.and_then(|i| i.container_id) {
+            if let Err(e) = self
+                .runtime
+                .stop_container(&container_id, GRACEFUL_SHUTDOWN_TIMEOUT.as_secs())
+                .await
+            {
+                tracing::warn!(instance_id = %id, error = %e, "Failed to stop untracked container");
+            }
+            if let Err(e) = self.runtime.remove_container(&container_id, true).await {
+                tracing::warn!(instance_id = %id, error = %e, "Failed to remove untracked container");
+            }
+        }
+
+        self.store.lock().await.delete_instance(id).await?;
+        debug!(instance_id = %id, "Instance deleted");
+        Ok(true)
+    }
+
     /// Stop all instances gracefully.
     pub async fn stop_all(&self) -> Result<()> {
         // Signal shutdown to background tasks
@@ -293,7 +902,7 @@ impl InstanceManager {
             }
         }
 
-        let total_ports = self.config.opencode_port_pool_size as usize;
+        let total_ports = self.config.get().opencode_port_pool_size as usize;
         let allocated_ports = self.port_pool.allocated_count();
 
         ManagerStatus {
@@ -305,6 +914,13 @@ impl InstanceManager {
         }
     }
 
+    /// Probe critical components and report instance counts.
+    pub async fn health_report(&self) -> HealthReport {
+        let status = self.get_status().await;
+        let store = self.store.lock().await;
+        check_health(&store, self.runtime.as_ref(), &status).await
+    }
+
     /// Recover instances from database after restart.
     ///
     /// Marks all running instances as stopped (containers don't survive bot restart).
@@ -331,24 +947,44 @@ impl InstanceManager {
         Ok(())
     }
 
+    /// Match containers owned by the bot against stored instances: mark
+    /// instances whose container is gone as errored and remove orphans.
+    ///
+    /// Only containers carrying the instance id label are considered, so
+    /// unrelated containers that happen to share the `oc-` name prefix are
+    /// never touched.
     pub async fn reconcile_containers(&self) -> Result<()> {
         debug!("Starting container reconciliation");
 
-        let containers = self.runtime.list_containers_by_prefix("oc-").await?;
+        let containers = self
+            .runtime
+            .list_containers_by_label(LABEL_INSTANCE_ID)
+            .await?;
         let store = self.store.lock().await;
         let instances = store.get_all_instances().await?;
         drop(store);
 
         let mut container_ids = std::collections::HashSet::new();
+        let mut labeled_instance_ids = std::collections::HashSet::new();
         for container in &containers {
             container_ids.insert(container.id.clone());
+            if let Some(instance_id) = container.instance_id() {
+                labeled_instance_ids.insert(instance_id.to_string());
+            }
         }
 
+        // Labeled containers are matched by instance id as well, so a live
+        // instance keeps its container even if the recorded id went stale.
+        let mut active_instance_ids = std::collections::HashSet::new();
         let mut db_container_ids = std::collections::HashSet::new();
         for info in &instances {
+            if matches!(info.state, InstanceState::Running | InstanceState::Starting) {
+                active_instance_ids.insert(info.id.as_str());
+            }
             if let Some(container_id) = info.container_id.as_ref() {
                 db_container_ids.insert(container_id.clone());
-                if !container_ids.contains(container_id) {
+                if !container_ids.contains(container_id) && !labeled_instance_ids.contains(&info.id)
+                {
                     tracing::warn!(
                         instance_id = %info.id,
                         container_id = %container_id,
@@ -361,10 +997,14 @@ impl InstanceManager {
         }
 
         for container in containers {
-            if !db_container_ids.contains(&container.id) {
+            let known_instance = container
+                .instance_id()
+                .is_some_and(|id| active_instance_ids.contains(id));
+            if !db_container_ids.contains(&container.id) && !known_instance {
                 tracing::info!(
                     container_id = %container.id,
                     name = %container.name,
+                    instance_id = ?container.instance_id(),
                     "Orphan container found, stopping and removing"
                 );
                 self.runtime.stop_container(&container.id, 5).await?;
@@ -375,9 +1015,44 @@ impl InstanceManager {
         Ok(())
     }
 
+    /// Reserve pool ports for instances still running after recovery.
+    ///
+    /// Run after `recover_from_db` and `reconcile_containers` so surviving
+    /// containers keep their ports and the pool does not hand them out again.
+    /// Returns the number of ports reserved.
+    pub async fn reserve_recovered_ports(&self) -> Result<usize> {
+        let store = self.store.lock().await;
+        let instances = store.get_all_instances().await?;
+        drop(store);
+
+        let mut reserved = 0;
+        for info in instances {
+            if info.state != InstanceState::Running && info.state != InstanceState::Starting {
+                continue;
+            }
+            if self.port_pool.reserve(info.port).await {
+                reserved += 1;
+            } else {
+                tracing::warn!(
+                    instance_id = %info.id,
+                    port = info.port,
+                    "Could not reserve port for recovered instance"
+                );
+            }
+        }
+
+        tracing::info!(
+            reserved = reserved,
+            "Reserved ports for recovered instances"
+        );
+        Ok(reserved)
+    }
+
     /// Start periodic health check monitoring.
     ///
-    /// Spawns a background task that checks instance health and handles:
+    /// Spawns a background task that refreshes runtime availability, checks
+    /// instance health and handles:
+    /// - Instances stuck in `Starting` (marked error and cleaned up)
     /// - Crashed instances (auto-restart with backoff)
     /// - Idle instances (stop after timeout)
     pub fn start_health_check_loop(&self) -> tokio::task::JoinHandle<()> {
@@ -386,20 +1061,37 @@ impl InstanceManager {
         let activity_trackers = self.activity_trackers.clone();
         let store = self.store.clone();
         let port_pool = self.port_pool.clone();
-        let config = self.config.clone();
+        let shared_config = self.config.clone();
         let runtime = self.runtime.clone();
+        let runtime_available = self.runtime_available.clone();
+        let event_log = self.event_log.clone();
         let shutdown_signal = self.shutdown_signal.clone();
+        let circuit_breakers = self.circuit_breakers.clone();
+        let failure_notifier = self.failure_notifier.clone();
 
         tokio::spawn(async move {
-            let mut interval = tokio::time::interval(config.opencode_health_check_interval);
+            let mut period = shared_config.get().opencode_health_check_interval;
+            let mut interval = tokio::time::interval(period);
             debug!(
-                interval_ms = config.opencode_health_check_interval.as_millis() as u64,
+                interval_ms = period.as_millis() as u64,
                 "Health check loop started"
             );
 
             loop {
                 interval.tick().await;
 
+                // Read on every tick so /reload reaches the timeouts and interval
+                let config = shared_config.get();
+                if config.opencode_health_check_interval != period {
+                    period = config.opencode_health_check_interval;
+                    interval =
+                        tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+                    debug!(
+                        interval_ms = period.as_millis() as u64,
+                        "Health check interval changed"
+                    );
+                }
+
                 // Check shutdown signal
                 {
                     let shutdown = shutdown_signal.lock().await;
@@ -408,6 +1100,19 @@ impl InstanceManager {
                     }
                 }
 
+                let _ = probe_runtime(runtime.as_ref(), &runtime_available).await;
+
+                reap_stuck_starting(
+                    &store,
+                    &instances,
+                    &activity_trackers,
+                    &port_pool,
+                    runtime.as_ref(),
+                    config.opencode_startup_timeout,
+                    remove_stopped_container(&config),
+                )
+                .await;
+
                 // Get all instance IDs
                 let instance_ids: Vec<String> = {
                     let instances = instances.lock().await;
@@ -434,16 +1139,37 @@ impl InstanceManager {
                         // Check for crash
                         match inst.check_for_crash().await {
                             Ok(true) => {
+                                let last_error = inst.last_error().await;
                                 drop(inst);
-                                tracing::warn!("Instance {} crashed, attempting restart", id);
+                                tracing::warn!(
+                                    "Instance {} crashed ({}), attempting restart",
+                                    id,
+                                    last_error.as_deref().unwrap_or("unknown cause")
+                                );
+                                record_event(event_log.as_ref(), &id, InstanceEvent::Crashed).await;
+                                let info = {
+                                    let store_guard = store.lock().await;
+                                    let _ =
+                                        store_guard.update_state(&id, InstanceState::Error).await;
+                                    store_guard.get_instance(&id).await
+                                };
+                                if let (Some(notifier), Ok(Some(info)), Some(OOM_KILLED_ERROR)) =
+                                    (failure_notifier.as_ref(), &info, last_error.as_deref())
+                                {
+                                    let _ = notifier.send(InstanceFailure {
+                                        project_path: info.project_path.clone(),
+                                        topic_id: info.topic_id,
+                                        reason: FailureReason::OutOfMemory,
+                                    });
+                                }
 
                                 // Attempt restart with backoff
                                 let mut trackers = restart_trackers.lock().await;
                                 let tracker = trackers.entry(id.clone()).or_default();
 
                                 if tracker.attempt < MAX_RESTART_ATTEMPTS {
-                                    let delay = INITIAL_RESTART_DELAY
-                                        .mul_f64(2_f64.powi(tracker.attempt as i32));
+                                    let delay =
+                                        restart_delay(tracker.attempt, backoff::random_sample());
                                     tracker.attempt += 1;
                                     tracker.last_attempt = Some(Instant::now());
                                     drop(trackers);
@@ -474,6 +1200,27 @@ impl InstanceManager {
                                         }
                                     };
 
+                                    let cordoned = match project_cordon_key(
+                                        Path::new(&project_path),
+                                        &config.project_base_path,
+                                    ) {
+                                        Some(key) => {
+                                            let store_guard = store.lock().await;
+                                            store_guard
+                                                .is_project_cordoned(&key)
+                                                .await
+                                                .unwrap_or(false)
+                                        }
+                                        None => false,
+                                    };
+                                    if cordoned {
+                                        tracing::info!(
+                                            "Not restarting {}: project is cordoned",
+                                            id
+                                        );
+                                        continue;
+                                    }
+
                                     let old_port = {
                                         let inst = instance.lock().await;
                                         inst.port()
@@ -514,6 +1261,8 @@ impl InstanceManager {
                                             .opencode_path
                                             .to_string_lossy()
                                             .to_string(),
+                                        health_path: config.health_path.clone(),
+                                        health_check: config.health_check.clone(),
                                     };
 
                                     let container_config = ContainerConfig {
@@ -532,6 +1281,15 @@ impl InstanceManager {
                                             .to_string(),
                                         topic_id,
                                         env_vars: config.env_passthrough.clone(),
+                                        dns: config.container_dns.clone(),
+                                        dns_search: config.container_dns_search.clone(),
+                                        extra_binds: project_mount_binds(Path::new(&project_path)),
+                                        extra_args: container_extra_args(
+                                            &config.container_extra_args,
+                                            Path::new(&project_path),
+                                        ),
+                                        workspace_mount: config.workspace_mount.clone(),
+                                        memory_limit_mb: config.container_memory_mb,
                                     };
 
                                     let spawn_result = OpenCodeInstance::spawn(
@@ -618,10 +1376,26 @@ impl InstanceManager {
                                                         at.remove(&id);
                                                         at.insert(
                                                             new_id.clone(),
-                                                            ActivityTracker::default(),
+                                                            ActivityTracker::with_idle_timeout(
+                                                                project_idle_timeout(Path::new(
+                                                                    &project_path,
+                                                                )),
+                                                            ),
                                                         );
                                                     }
 
+                                                    record_event(
+                                                        event_log.as_ref(),
+                                                        &new_id,
+                                                        InstanceEvent::Spawned,
+                                                    )
+                                                    .await;
+                                                    record_event(
+                                                        event_log.as_ref(),
+                                                        &new_id,
+                                                        InstanceEvent::Restarted,
+                                                    )
+                                                    .await;
                                                     tracing::info!(
                                                         "Successfully restarted instance {} as {}",
                                                         id,
@@ -634,7 +1408,11 @@ impl InstanceManager {
                                                         id
                                                     );
                                                     let inst = new_instance.lock().await;
-                                                    let _ = inst.stop().await;
+                                                    let _ = inst
+                                                        .stop_with(remove_stopped_container(
+                                                            &config,
+                                                        ))
+                                                        .await;
                                                     drop(inst);
                                                     port_pool.release(new_port).await;
                                                     let store_guard = store.lock().await;
@@ -663,9 +1441,22 @@ impl InstanceManager {
                                         "Instance {} exceeded max restart attempts, marking as error",
                                         id
                                     );
-                                    let store_guard = store.lock().await;
-                                    let _ =
-                                        store_guard.update_state(&id, InstanceState::Error).await;
+                                    let info = {
+                                        let store_guard = store.lock().await;
+                                        let _ = store_guard
+                                            .update_state(&id, InstanceState::Error)
+                                            .await;
+                                        store_guard.get_instance(&id).await
+                                    };
+                                    if let Ok(Some(info)) = info {
+                                        trip_circuit(
+                                            &circuit_breakers,
+                                            failure_notifier.as_ref(),
+                                            &info.project_path,
+                                            info.topic_id,
+                                        )
+                                        .await;
+                                    }
                                 }
                             }
                             Ok(false) => {
@@ -688,8 +1479,10 @@ impl InstanceManager {
                         };
 
                         if let Some(activity) = activity {
-                            if activity.last_activity.elapsed() > config.opencode_idle_timeout {
-                                debug!(instance_id = %id, idle_secs = activity.last_activity.elapsed().as_secs(), timeout_secs = config.opencode_idle_timeout.as_secs(), "Idle timeout check");
+                            let idle_timeout =
+                                activity.effective_idle_timeout(config.opencode_idle_timeout);
+                            if activity.last_activity.elapsed() > idle_timeout {
+                                debug!(instance_id = %id, idle_secs = activity.last_activity.elapsed().as_secs(), timeout_secs = idle_timeout.as_secs(), "Idle timeout check");
                                 tracing::info!("Instance {} idle timeout reached, stopping", id);
                                 let instance = {
                                     let instances = instances.lock().await;
@@ -699,13 +1492,32 @@ impl InstanceManager {
                                 if let Some(instance) = instance {
                                     let inst = instance.lock().await;
                                     let port = inst.port();
-                                    let _ = inst.stop().await;
+                                    let project_path = inst.project_path().to_string();
+                                    let _ = inst.stop_with(remove_stopped_container(&config)).await;
                                     drop(inst);
 
                                     port_pool.release(port).await;
 
-                                    let store = store.lock().await;
-                                    let _ = store.update_state(&id, InstanceState::Stopped).await;
+                                    if let Err(e) = cleanup_image_cache(
+                                        Path::new(&project_path),
+                                        config.image_cache_retention,
+                                    )
+                                    .await
+                                    {
+                                        tracing::warn!(
+                                            "Failed to clean image cache for {}: {}",
+                                            project_path,
+                                            e
+                                        );
+                                    }
+
+                                    {
+                                        let store = store.lock().await;
+                                        let _ =
+                                            store.update_state(&id, InstanceState::Stopped).await;
+                                    }
+                                    record_event(event_log.as_ref(), &id, InstanceEvent::Stopped)
+                                        .await;
 
                                     let mut instances = instances.lock().await;
                                     instances.remove(&id);
@@ -732,6 +1544,25 @@ impl InstanceManager {
         tracker.last_activity = Instant::now();
     }
 
+    /// Record activity for the running instance of `project_path`, if any.
+    /// Called for streamed output so a long generation without new prompts
+    /// is not idle-stopped mid-response.
+    pub async fn record_activity_by_path(&self, project_path: &Path) {
+        if let Some(instance) = self.get_instance_by_path(project_path).await {
+            let id = instance.lock().await.id().to_string();
+            self.record_activity(&id).await;
+        }
+    }
+
+    /// Snapshot of time elapsed since the last recorded activity, keyed by instance ID.
+    pub async fn activity_snapshot(&self) -> HashMap<String, Duration> {
+        let activity_trackers = self.activity_trackers.lock().await;
+        activity_trackers
+            .iter()
+            .map(|(id, tracker)| (id.clone(), tracker.last_activity.elapsed()))
+            .collect()
+    }
+
     /// Spawn a new OpenCode instance.
     async fn spawn_new_instance(
         &self,
@@ -742,6 +1573,12 @@ impl InstanceManager {
             .to_str()
             .ok_or_else(|| anyhow!("Invalid project path"))?;
 
+        // Creating a container fails outright when the image is missing
+        let image = self.config.get().docker_image.clone();
+        if self.runtime.ensure_image(&image).await? {
+            debug!(image = %image, "Pulled missing container image");
+        }
+
         // Allocate port
         let port = self.port_pool.allocate().await?;
         debug!(port = port, project_path = %path_str, "Port allocated for new instance");
@@ -759,24 +1596,43 @@ impl InstanceManager {
             project_path: path_str.to_string(),
             port,
             auto_start: true,
-            opencode_path: self.config.opencode_path.to_string_lossy().to_string(),
+            opencode_path: self
+                .config
+                .get()
+                .opencode_path
+                .to_string_lossy()
+                .to_string(),
+            health_path: self.config.get().health_path.clone(),
+            health_check: self.config.get().health_check.clone(),
         };
 
         let container_config = ContainerConfig {
             instance_id: id.clone(),
-            image: self.config.docker_image.clone(),
+            image: self.config.get().docker_image.clone(),
             host_port: port,
-            container_port: self.config.container_port,
+            container_port: self.config.get().container_port,
             worktree_path: path_str.to_string(),
             config_mount_path: self
                 .config
+                .get()
                 .opencode_config_path
                 .to_string_lossy()
                 .to_string(),
-            opencode_data_path: self.config.opencode_data_path.to_string_lossy().to_string(),
-            topic_id,
-            env_vars: self.config.env_passthrough.clone(),
-        };
+            opencode_data_path: self
+                .config
+                .get()
+                .opencode_data_path
+                .to_string_lossy()
+                .to_string(),
+            topic_id,
+            env_vars: self.config.get().env_passthrough.clone(),
+            dns: self.config.get().container_dns.clone(),
+            dns_search: self.config.get().container_dns_search.clone(),
+            extra_binds: project_mount_binds(project_path),
+            extra_args: container_extra_args(&self.config.get().container_extra_args, project_path),
+            workspace_mount: self.config.get().workspace_mount.clone(),
+            memory_limit_mb: self.config.get().container_memory_mb,
+        };
 
         // Spawn instance
         let spawn_result = OpenCodeInstance::spawn(
@@ -797,6 +1653,28 @@ impl InstanceManager {
 
         debug!(instance_id = %id, "Process spawned, waiting for readiness");
 
+        // Record the startup so an interrupted readiness wait is reaped by
+        // the health loop instead of holding the port and container forever
+        let starting = InstanceInfo {
+            id: id.clone(),
+            state: InstanceState::Starting,
+            project_path: path_str.to_string(),
+            port,
+            pid: None,
+            container_id: Some(container_id.clone()),
+            started_at: Some(
+                std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap()
+                    .as_secs() as i64,
+            ),
+            stopped_at: None,
+            topic_id,
+        };
+        if let Err(e) = self.store.lock().await.save_instance(&starting, None).await {
+            tracing::warn!(instance_id = %id, error = %e, "Failed to record starting instance");
+        }
+
         let instance = Arc::new(Mutex::new(instance));
 
         // Wait for instance to be ready
@@ -804,7 +1682,7 @@ impl InstanceManager {
             let inst = instance.lock().await;
             let ready = inst
                 .wait_for_ready(
-                    self.config.opencode_startup_timeout,
+                    self.config.get().opencode_startup_timeout,
                     Duration::from_millis(500),
                 )
                 .await?;
@@ -812,21 +1690,48 @@ impl InstanceManager {
             debug!(instance_id = %id, ready = ready, "Readiness check result");
 
             if !ready {
-                inst.stop().await?;
+                inst.stop_with(remove_stopped_container(&self.config.get()))
+                    .await?;
                 drop(inst);
                 self.port_pool.release(port).await;
+                let _ = self
+                    .store
+                    .lock()
+                    .await
+                    .update_state(&id, InstanceState::Error)
+                    .await;
                 return Err(anyhow!("Instance failed to start within timeout"));
             }
         }
 
+        self.track_instance(&instance, project_path, topic_id, &container_id)
+            .await?;
+        record_event(self.event_log.as_ref(), &id, InstanceEvent::Spawned).await;
+
+        Ok(instance)
+    }
+
+    /// Save a ready instance to the database and start tracking it.
+    async fn track_instance(
+        &self,
+        instance: &Arc<Mutex<OpenCodeInstance>>,
+        project_path: &Path,
+        topic_id: i32,
+        container_id: &str,
+    ) -> Result<()> {
+        let (id, port) = {
+            let inst = instance.lock().await;
+            (inst.id().to_string(), inst.port())
+        };
+
         // Save to database
         let info = InstanceInfo {
             id: id.clone(),
             state: InstanceState::Running,
-            project_path: path_str.to_string(),
+            project_path: project_path.to_string_lossy().to_string(),
             port,
             pid: None,
-            container_id: Some(container_id.clone()),
+            container_id: Some(container_id.to_string()),
             started_at: Some(
                 std::time::SystemTime::now()
                     .duration_since(std::time::UNIX_EPOCH)
@@ -839,7 +1744,7 @@ impl InstanceManager {
 
         let store = self.store.lock().await;
         store.save_instance(&info, None).await?;
-        store.update_container_id(&id, Some(&container_id)).await?;
+        store.update_container_id(&id, Some(container_id)).await?;
         drop(store);
         debug!(instance_id = %id, "Instance saved to database");
 
@@ -849,10 +1754,12 @@ impl InstanceManager {
         debug!(instance_id = %id, "Instance added to active map");
 
         // Initialize activity tracker
+        let idle_timeout = project_idle_timeout(project_path);
+        debug!(instance_id = %id, idle_timeout_ms = ?idle_timeout.map(|d| d.as_millis() as u64), "Activity tracker initialized");
         let mut activity_trackers = self.activity_trackers.lock().await;
-        activity_trackers.insert(id.clone(), ActivityTracker::default());
+        activity_trackers.insert(id.clone(), ActivityTracker::with_idle_timeout(idle_timeout));
 
-        Ok(instance)
+        Ok(())
     }
 
     /// Restart an instance by project path.
@@ -894,13 +1801,22 @@ impl InstanceManager {
         let tracker = trackers.entry(id.clone()).or_default();
 
         if tracker.attempt >= MAX_RESTART_ATTEMPTS {
-            return Err(anyhow!(
-                "Instance {} has exceeded maximum restart attempts",
-                id
-            ));
+            drop(trackers);
+            trip_circuit(
+                &self.circuit_breakers,
+                self.failure_notifier.as_ref(),
+                path_str,
+                topic_id,
+            )
+            .await;
+            return Err(CircuitOpen {
+                project: path_str.to_string(),
+                retry_after: CIRCUIT_BREAKER_COOLDOWN,
+            }
+            .into());
         }
 
-        let delay = INITIAL_RESTART_DELAY.mul_f64(2_f64.powi(tracker.attempt as i32));
+        let delay = restart_delay(tracker.attempt, backoff::random_sample());
         let attempt = tracker.attempt;
         tracker.attempt += 1;
         tracker.last_attempt = Some(Instant::now());
@@ -918,13 +1834,17 @@ impl InstanceManager {
 
         self.port_pool.release(old_port).await;
 
-        self.spawn_new_instance(project_path, topic_id).await
+        let instance = self.spawn_new_instance(project_path, topic_id).await?;
+        let new_id = instance.lock().await.id().to_string();
+        record_event(self.event_log.as_ref(), &new_id, InstanceEvent::Restarted).await;
+        Ok(instance)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::Config;
     use crate::orchestrator::container::mock::{MockAction, MockRuntime};
     use crate::orchestrator::container::{ContainerInfo, ContainerState};
     use tempfile::TempDir;
@@ -932,12 +1852,27 @@ mod tests {
     async fn create_test_manager() -> (InstanceManager, TempDir, Arc<MockRuntime>) {
         let temp_dir = TempDir::new().unwrap();
         let db_path = temp_dir.path().join("test.db");
+        let config = test_config(&temp_dir);
 
-        // Create minimal config
-        let config = Config {
+        let store = OrchestratorStore::new(&db_path).await.unwrap();
+        let port_pool = PortPool::new(14100, 10);
+        let runtime = Arc::new(MockRuntime::new());
+
+        let manager = InstanceManager::new(Arc::new(config), store, port_pool, runtime.clone())
+            .await
+            .unwrap();
+
+        (manager, temp_dir, runtime)
+    }
+
+    /// Minimal config with its databases and project base in `temp_dir`.
+    fn test_config(temp_dir: &TempDir) -> Config {
+        let db_path = temp_dir.path().join("test.db");
+        Config {
             telegram_bot_token: "test".to_string(),
             telegram_chat_ids: vec![-1001234567890],
             telegram_allowed_users: vec![],
+            telegram_admin_users: vec![],
             handle_general_topic: true,
             opencode_path: std::path::PathBuf::from("/nonexistent/opencode-test-binary"),
             opencode_max_instances: 5,
@@ -947,7 +1882,7 @@ mod tests {
             opencode_health_check_interval: Duration::from_secs(30),
             opencode_startup_timeout: Duration::from_secs(5),
             opencode_data_path: std::path::PathBuf::from("/tmp/opencode-data"),
-            orchestrator_db_path: db_path.clone(),
+            orchestrator_db_path: db_path,
             topic_db_path: temp_dir.path().join("topics.db"),
             log_db_path: temp_dir.path().join("logs.db"),
             project_base_path: temp_dir.path().to_path_buf(),
@@ -956,24 +1891,64 @@ mod tests {
             opencode_config_path: std::path::PathBuf::from("/tmp/oc-config"),
             container_port: 8080,
             env_passthrough: vec![],
-        };
-
-        let store = OrchestratorStore::new(&db_path).await.unwrap();
-        let port_pool = PortPool::new(14100, 10);
-        let runtime = Arc::new(MockRuntime::new());
-
-        let manager = InstanceManager::new(Arc::new(config), store, port_pool, runtime.clone())
-            .await
-            .unwrap();
-
-        (manager, temp_dir, runtime)
+            topic_name_strategy: crate::config::TopicNameStrategy::Leaf,
+            show_usage: false,
+            duplicate_response_window: Duration::from_secs(10),
+            container_dns: vec![],
+            container_dns_search: vec![],
+            fallback_model: None,
+            max_topics_per_chat: 50,
+            transcription_url: None,
+            transcription_api_key: None,
+            transcription_model: "whisper-1".to_string(),
+            audio_mode: crate::config::AudioMode::Transcribe,
+            permission_timeout: Duration::from_secs(600),
+            opencode_request_timeout: Duration::from_secs(30),
+            resurrection_timeout: Duration::from_secs(30),
+            resurrection_wake_delay: Duration::from_secs(3),
+            show_file_edits: false,
+            allowed_upload_mime: vec![],
+            dedup_expiry: Duration::from_secs(30),
+            max_reconnect_attempts: 5,
+            base_reconnect_delay: Duration::from_secs(1),
+            max_reconnect_delay: Duration::from_secs(16),
+            max_resume_age: Duration::ZERO,
+            max_concurrent_downloads: 4,
+            max_message_parts: 10,
+            api_max_body_bytes: 1_048_576,
+            api_port: 0,
+            api_host: [127, 0, 0, 1].into(),
+            api_key: None,
+            image_cache_retention: Duration::from_secs(86400),
+            slow_start_nudge: Duration::from_secs(15),
+            rewrite_workspace_paths: false,
+            opencode_socket_path: None,
+            health_path: "/global/health".to_string(),
+            health_check: Default::default(),
+            collect_feedback: false,
+            container_extra_args: vec![],
+            response_filters: vec![],
+            reply_to_prompts: false,
+            preferred_photo_size: crate::config::PhotoSizePreference::Largest,
+            max_image_dimension: 1280,
+            max_image_bytes: 1_048_576,
+            default_image_prompt: None,
+            validate_project: false,
+            max_response_chars: 0,
+            collapse_repeated_tools: false,
+            stream_mode: crate::config::StreamMode::Batched,
+            durable_outbound: false,
+            keep_stopped_containers: false,
+            container_memory_mb: 0,
+            workspace_mount: "/workspace".to_string(),
+        }
     }
 
     #[tokio::test]
     async fn test_new_creates_manager() {
         let (manager, _temp_dir, _runtime) = create_test_manager().await;
-        assert_eq!(manager.config.opencode_max_instances, 5);
-        assert_eq!(manager.config.opencode_port_start, 14100);
+        assert_eq!(manager.config.get().opencode_max_instances, 5);
+        assert_eq!(manager.config.get().opencode_port_start, 14100);
     }
 
     #[tokio::test]
@@ -983,6 +1958,96 @@ mod tests {
         assert!(result.is_none());
     }
 
+    #[tokio::test]
+    async fn test_get_instance_by_id_prefers_live_state_over_store() {
+        let (manager, _temp_dir, runtime) = create_test_manager().await;
+        manager
+            .store
+            .lock()
+            .await
+            .save_instance(
+                &InstanceInfo {
+                    id: "inst_lookup".to_string(),
+                    state: InstanceState::Stopped,
+                    project_path: "/test/lookup".to_string(),
+                    port: 4101,
+                    pid: None,
+                    container_id: Some("old-container".to_string()),
+                    started_at: Some(1_700_000_000),
+                    stopped_at: None,
+                    topic_id: 42,
+                },
+                None,
+            )
+            .await
+            .unwrap();
+
+        // Only the stored record exists
+        let info = manager
+            .get_instance_by_id("inst_lookup")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(info.state, InstanceState::Stopped);
+        assert_eq!(info.port, 4101);
+        assert_eq!(info.container_id.as_deref(), Some("old-container"));
+        assert_eq!(info.started_at, Some(1_700_000_000));
+
+        let inst_config = InstanceConfig {
+            id: "inst_lookup".to_string(),
+            project_path: "/test/lookup".to_string(),
+            port: 4102,
+            auto_start: true,
+            opencode_path: "opencode".to_string(),
+            health_path: "/global/health".to_string(),
+            health_check: Default::default(),
+        };
+        let container_config = ContainerConfig {
+            instance_id: "inst_lookup".to_string(),
+            image: "ghcr.io/sst/opencode".to_string(),
+            host_port: 4102,
+            container_port: 8080,
+            worktree_path: "/test/lookup".to_string(),
+            config_mount_path: "/tmp/oc-config".to_string(),
+            opencode_data_path: "/tmp/opencode-data".to_string(),
+            topic_id: 42,
+            env_vars: vec![],
+            dns: vec![],
+            dns_search: vec![],
+            extra_binds: vec![],
+            extra_args: vec![],
+            workspace_mount: "/workspace".to_string(),
+            memory_limit_mb: 0,
+        };
+        let (instance, container_id) =
+            OpenCodeInstance::spawn(inst_config, 4102, runtime, container_config)
+                .await
+                .unwrap();
+        manager
+            .instances
+            .lock()
+            .await
+            .insert("inst_lookup".to_string(), Arc::new(Mutex::new(instance)));
+
+        // The running instance overrides the stored state, port and container
+        let info = manager
+            .get_instance_by_id("inst_lookup")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(info.state, InstanceState::Running);
+        assert_eq!(info.port, 4102);
+        assert_eq!(info.container_id, Some(container_id));
+        assert_eq!(info.started_at, Some(1_700_000_000));
+        assert_eq!(info.topic_id, 42);
+
+        assert!(manager
+            .get_instance_by_id("nonexistent")
+            .await
+            .unwrap()
+            .is_none());
+    }
+
     #[tokio::test]
     async fn test_get_instance_by_path_returns_none_when_not_found() {
         let (manager, _temp_dir, _runtime) = create_test_manager().await;
@@ -992,6 +2057,15 @@ mod tests {
         assert!(result.is_none());
     }
 
+    #[tokio::test]
+    async fn test_health_report_healthy_with_mock_runtime() {
+        let (manager, _temp_dir, _runtime) = create_test_manager().await;
+
+        let report = manager.health_report().await;
+        assert!(report.healthy);
+        assert_eq!(report.instances.total, 0);
+    }
+
     #[tokio::test]
     async fn test_get_status_initial_empty() {
         let (manager, _temp_dir, _runtime) = create_test_manager().await;
@@ -1012,6 +2086,143 @@ mod tests {
         assert!(result.unwrap_err().to_string().contains("not found"));
     }
 
+    #[tokio::test]
+    async fn test_stop_instance_removes_container_unless_kept() {
+        for keep in [false, true] {
+            let (mut manager, _temp_dir, runtime) = create_test_manager().await;
+            let mut config = (*manager.config.get()).clone();
+            config.keep_stopped_containers = keep;
+            manager.config = SharedConfig::new(config);
+
+            let port = manager.port_pool.allocate().await.unwrap();
+            let inst_config = InstanceConfig {
+                id: "inst_keep".to_string(),
+                project_path: "/test/keep".to_string(),
+                port,
+                auto_start: true,
+                opencode_path: "opencode".to_string(),
+                health_path: "/global/health".to_string(),
+                health_check: Default::default(),
+            };
+            let container_config = ContainerConfig {
+                instance_id: "inst_keep".to_string(),
+                image: "ghcr.io/sst/opencode".to_string(),
+                host_port: port,
+                container_port: 8080,
+                worktree_path: "/test/keep".to_string(),
+                config_mount_path: "/tmp/oc-config".to_string(),
+                opencode_data_path: "/tmp/opencode-data".to_string(),
+                topic_id: 100,
+                env_vars: vec![],
+                dns: vec![],
+                dns_search: vec![],
+                extra_binds: vec![],
+                extra_args: vec![],
+                workspace_mount: "/workspace".to_string(),
+                memory_limit_mb: 0,
+            };
+            let (instance, _container_id) =
+                OpenCodeInstance::spawn(inst_config, port, runtime.clone(), container_config)
+                    .await
+                    .unwrap();
+            manager
+                .instances
+                .lock()
+                .await
+                .insert("inst_keep".to_string(), Arc::new(Mutex::new(instance)));
+
+            manager.stop_instance("inst_keep").await.unwrap();
+
+            let actions = runtime.recorded_actions();
+            assert!(actions
+                .iter()
+                .any(|a| matches!(a, MockAction::StopContainer { .. })));
+            assert_eq!(
+                actions
+                    .iter()
+                    .any(|a| matches!(a, MockAction::RemoveContainer { .. })),
+                !keep,
+                "keep_stopped_containers = {keep}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_remove_instance_stops_tracked_instance_and_deletes_record() {
+        let (manager, _temp_dir, runtime) = create_test_manager().await;
+        let port = manager.port_pool.allocate().await.unwrap();
+        assert_eq!(manager.port_pool.allocated_count(), 1);
+
+        let inst_config = InstanceConfig {
+            id: "inst_rm".to_string(),
+            project_path: "/test/rm".to_string(),
+            port,
+            auto_start: true,
+            opencode_path: "opencode".to_string(),
+            health_path: "/global/health".to_string(),
+            health_check: Default::default(),
+        };
+        let container_config = ContainerConfig {
+            instance_id: "inst_rm".to_string(),
+            image: "ghcr.io/sst/opencode".to_string(),
+            host_port: port,
+            container_port: 8080,
+            worktree_path: "/test/rm".to_string(),
+            config_mount_path: "/tmp/oc-config".to_string(),
+            opencode_data_path: "/tmp/opencode-data".to_string(),
+            topic_id: 100,
+            env_vars: vec![],
+            dns: vec![],
+            dns_search: vec![],
+            extra_binds: vec![],
+            extra_args: vec![],
+            workspace_mount: "/workspace".to_string(),
+            memory_limit_mb: 0,
+        };
+        let (instance, container_id) =
+            OpenCodeInstance::spawn(inst_config, port, runtime.clone(), container_config)
+                .await
+                .unwrap();
+        manager
+            .instances
+            .lock()
+            .await
+            .insert("inst_rm".to_string(), Arc::new(Mutex::new(instance)));
+        manager
+            .store
+            .lock()
+            .await
+            .save_instance(
+                &InstanceInfo {
+                    id: "inst_rm".to_string(),
+                    state: InstanceState::Running,
+                    project_path: "/test/rm".to_string(),
+                    port,
+                    pid: None,
+                    container_id: Some(container_id),
+                    started_at: None,
+                    stopped_at: None,
+                    topic_id: 100,
+                },
+                None,
+            )
+            .await
+            .unwrap();
+
+        assert!(manager.remove_instance("inst_rm").await.unwrap());
+
+        assert!(manager.get_instance("inst_rm").await.is_none());
+        assert_eq!(manager.port_pool.allocated_count(), 0);
+        let stored = manager.store.lock().await.get_instance("inst_rm").await;
+        assert!(stored.unwrap().is_none());
+        assert!(runtime
+            .recorded_actions()
+            .iter()
+            .any(|a| matches!(a, MockAction::StopContainer { .. })));
+
+        assert!(!manager.remove_instance("inst_rm").await.unwrap());
+    }
+
     #[tokio::test]
     async fn test_stop_all_succeeds_when_empty() {
         let (manager, _temp_dir, _runtime) = create_test_manager().await;
@@ -1034,6 +2245,8 @@ mod tests {
             id: "orphan-1".to_string(),
             name: "oc-orphan-1".to_string(),
             state: ContainerState::Running,
+            labels: HashMap::from([(LABEL_INSTANCE_ID.to_string(), "inst-orphan".to_string())]),
+            oom_killed: false,
         };
 
         *runtime.list_result.lock().unwrap() = Ok(vec![orphan]);
@@ -1041,7 +2254,9 @@ mod tests {
         manager.reconcile_containers().await.unwrap();
 
         let actions = runtime.recorded_actions();
-        assert!(matches!(actions[0], MockAction::ListContainers { .. }));
+        assert!(
+            matches!(actions[0], MockAction::ListContainersByLabel { ref label } if label == LABEL_INSTANCE_ID)
+        );
         assert!(matches!(actions[1], MockAction::StopContainer { ref id, .. } if id == "orphan-1"));
         assert!(
             matches!(actions[2], MockAction::RemoveContainer { ref id, .. } if id == "orphan-1")
@@ -1086,6 +2301,8 @@ mod tests {
             id: "match-1".to_string(),
             name: "oc-match-1".to_string(),
             state: ContainerState::Running,
+            labels: HashMap::from([(LABEL_INSTANCE_ID.to_string(), "inst-match".to_string())]),
+            oom_killed: false,
         };
 
         *runtime.list_result.lock().unwrap() = Ok(vec![container]);
@@ -1122,6 +2339,231 @@ mod tests {
         assert_eq!(updated.state, InstanceState::Running);
     }
 
+    #[tokio::test]
+    async fn test_reconcile_matches_labeled_container_by_instance_id() {
+        let (manager, _temp_dir, runtime) = create_test_manager().await;
+
+        let labeled = |id: &str, instance_id: &str| ContainerInfo {
+            id: id.to_string(),
+            name: format!("oc-{}", instance_id),
+            state: ContainerState::Running,
+            labels: HashMap::from([(LABEL_INSTANCE_ID.to_string(), instance_id.to_string())]),
+            oom_killed: false,
+        };
+        *runtime.list_result.lock().unwrap() = Ok(vec![
+            labeled("new-id", "inst-live"),
+            labeled("stale-id", "inst-gone"),
+        ]);
+
+        let info = InstanceInfo {
+            id: "inst-live".to_string(),
+            state: InstanceState::Running,
+            project_path: "/tmp/project".to_string(),
+            port: 14103,
+            pid: None,
+            container_id: Some("old-id".to_string()),
+            started_at: None,
+            stopped_at: None,
+            topic_id: 777,
+        };
+        {
+            let store = manager.store.lock().await;
+            store.save_instance(&info, None).await.unwrap();
+        }
+
+        manager.reconcile_containers().await.unwrap();
+
+        let removed: Vec<String> = runtime
+            .recorded_actions()
+            .into_iter()
+            .filter_map(|action| match action {
+                MockAction::RemoveContainer { id, .. } => Some(id),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(removed, vec!["stale-id".to_string()]);
+
+        let store = manager.store.lock().await;
+        let updated = store.get_instance("inst-live").await.unwrap().unwrap();
+        assert_eq!(updated.state, InstanceState::Running);
+    }
+
+    #[test]
+    fn test_is_stuck_starting() {
+        let timeout = Duration::from_secs(60);
+        assert!(!is_stuck_starting(Some(1_000), 1_090, timeout));
+        assert!(is_stuck_starting(Some(1_000), 1_091, timeout));
+        assert!(!is_stuck_starting(None, 1_000_000, timeout));
+    }
+
+    #[tokio::test]
+    async fn test_reap_stuck_starting_marks_error_and_cleans_up() {
+        let (manager, _temp_dir, runtime) = create_test_manager().await;
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+
+        let starting = |id: &str, port: u16, started_at: i64| InstanceInfo {
+            id: id.to_string(),
+            state: InstanceState::Starting,
+            project_path: format!("/tmp/{}", id),
+            port,
+            pid: None,
+            container_id: Some(format!("{}-container", id)),
+            started_at: Some(started_at),
+            stopped_at: None,
+            topic_id: 1,
+        };
+        {
+            let store = manager.store.lock().await;
+            store
+                .save_instance(&starting("inst-stuck", 14104, now - 3600), None)
+                .await
+                .unwrap();
+            store
+                .save_instance(&starting("inst-fresh", 14105, now), None)
+                .await
+                .unwrap();
+        }
+        assert!(manager.port_pool.reserve(14104).await);
+        assert!(manager.port_pool.reserve(14105).await);
+        assert_eq!(manager.port_pool.allocated_count(), 2);
+
+        let reaped = reap_stuck_starting(
+            &manager.store,
+            &manager.instances,
+            &manager.activity_trackers,
+            &manager.port_pool,
+            manager.runtime.as_ref(),
+            Duration::from_secs(5),
+            true,
+        )
+        .await;
+        assert_eq!(reaped, 1);
+
+        let store = manager.store.lock().await;
+        let stuck = store.get_instance("inst-stuck").await.unwrap().unwrap();
+        assert_eq!(stuck.state, InstanceState::Error);
+        let fresh = store.get_instance("inst-fresh").await.unwrap().unwrap();
+        assert_eq!(fresh.state, InstanceState::Starting);
+
+        assert_eq!(manager.port_pool.allocated_count(), 1);
+        assert!(runtime.recorded_actions().iter().any(|action| matches!(
+            action,
+            MockAction::RemoveContainer { id, .. } if id == "inst-stuck-container"
+        )));
+    }
+
+    #[tokio::test]
+    async fn test_reap_stuck_starting_keeps_container_when_configured() {
+        let (manager, _temp_dir, runtime) = create_test_manager().await;
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        let info = InstanceInfo {
+            id: "inst-stuck".to_string(),
+            state: InstanceState::Starting,
+            project_path: "/tmp/inst-stuck".to_string(),
+            port: 14106,
+            pid: None,
+            container_id: Some("inst-stuck-container".to_string()),
+            started_at: Some(now - 3600),
+            stopped_at: None,
+            topic_id: 1,
+        };
+        manager
+            .store
+            .lock()
+            .await
+            .save_instance(&info, None)
+            .await
+            .unwrap();
+
+        let reaped = reap_stuck_starting(
+            &manager.store,
+            &manager.instances,
+            &manager.activity_trackers,
+            &manager.port_pool,
+            manager.runtime.as_ref(),
+            Duration::from_secs(5),
+            false,
+        )
+        .await;
+        assert_eq!(reaped, 1);
+
+        let actions = runtime.recorded_actions();
+        assert!(actions.iter().any(
+            |a| matches!(a, MockAction::StopContainer { id, .. } if id == "inst-stuck-container")
+        ));
+        assert!(!actions
+            .iter()
+            .any(|a| matches!(a, MockAction::RemoveContainer { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_ignores_unlabeled_containers() {
+        let (manager, _temp_dir, runtime) = create_test_manager().await;
+
+        let decoy = ContainerInfo {
+            id: "decoy-1".to_string(),
+            name: "oc-something".to_string(),
+            state: ContainerState::Running,
+            labels: HashMap::from([("com.example.app".to_string(), "other".to_string())]),
+            oom_killed: false,
+        };
+        *runtime.list_result.lock().unwrap() = Ok(vec![decoy]);
+
+        manager.reconcile_containers().await.unwrap();
+
+        let actions = runtime.recorded_actions();
+        assert!(actions.iter().all(|action| !matches!(
+            action,
+            MockAction::StopContainer { .. } | MockAction::RemoveContainer { .. }
+        )));
+    }
+
+    #[tokio::test]
+    async fn test_reserve_recovered_ports_marks_running_instances_allocated() {
+        let (manager, _temp_dir, _runtime) = create_test_manager().await;
+
+        let running = InstanceInfo {
+            id: "inst-running".to_string(),
+            state: InstanceState::Running,
+            project_path: "/tmp/project-a".to_string(),
+            port: 14100,
+            pid: None,
+            container_id: Some("container-a".to_string()),
+            started_at: None,
+            stopped_at: None,
+            topic_id: 101,
+        };
+        let stopped = InstanceInfo {
+            id: "inst-stopped".to_string(),
+            state: InstanceState::Stopped,
+            project_path: "/tmp/project-b".to_string(),
+            port: 14101,
+            container_id: Some("container-b".to_string()),
+            topic_id: 102,
+            ..running.clone()
+        };
+
+        {
+            let store = manager.store.lock().await;
+            store.save_instance(&running, None).await.unwrap();
+            store.save_instance(&stopped, None).await.unwrap();
+        }
+
+        let reserved = manager.reserve_recovered_ports().await.unwrap();
+        assert_eq!(reserved, 1);
+        assert_eq!(manager.port_pool.allocated_count(), 1);
+
+        // The surviving instance's port is skipped by new allocations
+        let next = manager.port_pool.allocate().await.unwrap();
+        assert_eq!(next, 14101);
+    }
+
     #[tokio::test]
     async fn test_record_activity_creates_tracker() {
         let (manager, _temp_dir, _runtime) = create_test_manager().await;
@@ -1198,6 +2640,7 @@ mod tests {
             telegram_bot_token: "test".to_string(),
             telegram_chat_ids: vec![-1001234567890],
             telegram_allowed_users: vec![],
+            telegram_admin_users: vec![],
             handle_general_topic: true,
             opencode_path: std::path::PathBuf::from("opencode"),
             opencode_max_instances: 1,
@@ -1216,6 +2659,56 @@ mod tests {
             opencode_config_path: std::path::PathBuf::from("/tmp/oc-config"),
             container_port: 8080,
             env_passthrough: vec![],
+            topic_name_strategy: crate::config::TopicNameStrategy::Leaf,
+            show_usage: false,
+            duplicate_response_window: Duration::from_secs(10),
+            container_dns: vec![],
+            container_dns_search: vec![],
+            fallback_model: None,
+            max_topics_per_chat: 50,
+            transcription_url: None,
+            transcription_api_key: None,
+            transcription_model: "whisper-1".to_string(),
+            audio_mode: crate::config::AudioMode::Transcribe,
+            permission_timeout: Duration::from_secs(600),
+            opencode_request_timeout: Duration::from_secs(30),
+            resurrection_timeout: Duration::from_secs(30),
+            resurrection_wake_delay: Duration::from_secs(3),
+            show_file_edits: false,
+            allowed_upload_mime: vec![],
+            dedup_expiry: Duration::from_secs(30),
+            max_reconnect_attempts: 5,
+            base_reconnect_delay: Duration::from_secs(1),
+            max_reconnect_delay: Duration::from_secs(16),
+            max_resume_age: Duration::ZERO,
+            max_concurrent_downloads: 4,
+            max_message_parts: 10,
+            api_max_body_bytes: 1_048_576,
+            api_port: 0,
+            api_host: [127, 0, 0, 1].into(),
+            api_key: None,
+            image_cache_retention: Duration::from_secs(86400),
+            slow_start_nudge: Duration::from_secs(15),
+            rewrite_workspace_paths: false,
+            opencode_socket_path: None,
+            health_path: "/global/health".to_string(),
+            health_check: Default::default(),
+            collect_feedback: false,
+            container_extra_args: vec![],
+            response_filters: vec![],
+            reply_to_prompts: false,
+            preferred_photo_size: crate::config::PhotoSizePreference::Largest,
+            max_image_dimension: 1280,
+            max_image_bytes: 1_048_576,
+            default_image_prompt: None,
+            validate_project: false,
+            max_response_chars: 0,
+            collapse_repeated_tools: false,
+            stream_mode: crate::config::StreamMode::Batched,
+            durable_outbound: false,
+            keep_stopped_containers: false,
+            container_memory_mb: 0,
+            workspace_mount: "/workspace".to_string(),
         };
 
         let store = OrchestratorStore::new(&db_path).await.unwrap();
@@ -1232,6 +2725,8 @@ mod tests {
             port: 14200,
             auto_start: true,
             opencode_path: "opencode".to_string(),
+            health_path: "/global/health".to_string(),
+            health_check: Default::default(),
         };
         let container_config = ContainerConfig {
             instance_id: "inst_test".to_string(),
@@ -1243,6 +2738,12 @@ mod tests {
             opencode_data_path: "/tmp/opencode-data".to_string(),
             topic_id: 100,
             env_vars: vec![],
+            dns: vec![],
+            dns_search: vec![],
+            extra_binds: vec![],
+            extra_args: vec![],
+            workspace_mount: "/workspace".to_string(),
+            memory_limit_mb: 0,
         };
         let (instance, _container_id) =
             OpenCodeInstance::spawn(inst_config, 14200, runtime, container_config)
@@ -1266,31 +2767,106 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_concurrent_access_to_manager() {
-        let (manager, _temp_dir, _runtime) = create_test_manager().await;
-        let manager = Arc::new(manager);
+    async fn test_instance_limit_counts_external_instances() {
+        use crate::orchestrator::container::ContainerConfig;
+        use crate::orchestrator::instance::OpenCodeInstance;
+        use crate::types::instance::InstanceConfig;
 
-        // Spawn multiple concurrent tasks
-        let mut handles = vec![];
-        for i in 0..5 {
-            let manager_clone = manager.clone();
-            let handle = tokio::spawn(async move {
-                // Record activity from multiple tasks
-                manager_clone
-                    .record_activity(&format!("instance-{}", i))
-                    .await;
-                manager_clone.get_status().await
-            });
-            handles.push(handle);
-        }
+        // Limit is 5 instances, pool is 14100..14110
+        let (manager, _temp_dir, runtime) = create_test_manager().await;
 
-        for handle in handles {
-            let status = handle.await.unwrap();
-            // Status should be consistent
-            assert_eq!(status.total_instances, 0);
+        // One external instance holds a pool port, the others live outside it
+        manager.register_external("ext-0", 14100).await.unwrap();
+        for (i, port) in [9001, 9002, 9003].into_iter().enumerate() {
+            manager
+                .register_external(&format!("ext-{}", i + 1), port)
+                .await
+                .unwrap();
         }
+        assert_eq!(manager.port_pool.allocated_count(), 1);
+        assert!(manager.register_external("ext-0", 9004).await.is_err());
+        assert!(manager.register_external("ext-dup", 14100).await.is_err());
 
-        // All activity trackers should be present
+        let inst_config = InstanceConfig {
+            id: "managed".to_string(),
+            project_path: "/test/managed".to_string(),
+            port: 14101,
+            auto_start: true,
+            opencode_path: "opencode".to_string(),
+            health_path: "/global/health".to_string(),
+            health_check: Default::default(),
+        };
+        let container_config = ContainerConfig {
+            instance_id: "managed".to_string(),
+            image: "ghcr.io/sst/opencode".to_string(),
+            host_port: 14101,
+            container_port: 8080,
+            worktree_path: "/test/managed".to_string(),
+            config_mount_path: "/tmp/oc-config".to_string(),
+            opencode_data_path: "/tmp/opencode-data".to_string(),
+            topic_id: 100,
+            env_vars: vec![],
+            dns: vec![],
+            dns_search: vec![],
+            extra_binds: vec![],
+            extra_args: vec![],
+            workspace_mount: "/workspace".to_string(),
+            memory_limit_mb: 0,
+        };
+        let (instance, _container_id) =
+            OpenCodeInstance::spawn(inst_config, 14101, runtime, container_config)
+                .await
+                .unwrap();
+        manager.instances.lock().await.insert(
+            "managed".to_string(),
+            Arc::new(tokio::sync::Mutex::new(instance)),
+        );
+        assert_eq!(manager.instance_count().await, 5);
+
+        // Both managed spawns and new external registrations hit the limit
+        let err = manager
+            .get_or_create(Path::new("/test/another"), 999)
+            .await
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("Maximum instances limit"));
+        let err = manager.register_external("ext-4", 9005).await.unwrap_err();
+        assert!(err.to_string().contains("Maximum instances limit"));
+
+        // Removing an external instance frees a slot and its pool port
+        assert!(manager.remove_instance("ext-0").await.unwrap());
+        assert!(!manager.unregister_external("ext-0").await);
+        assert_eq!(manager.instance_count().await, 4);
+        assert_eq!(manager.port_pool.allocated_count(), 0);
+        manager.register_external("ext-4", 9005).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_access_to_manager() {
+        let (manager, _temp_dir, _runtime) = create_test_manager().await;
+        let manager = Arc::new(manager);
+
+        // Spawn multiple concurrent tasks
+        let mut handles = vec![];
+        for i in 0..5 {
+            let manager_clone = manager.clone();
+            let handle = tokio::spawn(async move {
+                // Record activity from multiple tasks
+                manager_clone
+                    .record_activity(&format!("instance-{}", i))
+                    .await;
+                manager_clone.get_status().await
+            });
+            handles.push(handle);
+        }
+
+        for handle in handles {
+            let status = handle.await.unwrap();
+            // Status should be consistent
+            assert_eq!(status.total_instances, 0);
+        }
+
+        // All activity trackers should be present
         let trackers = manager.activity_trackers.lock().await;
         assert_eq!(trackers.len(), 5);
     }
@@ -1313,6 +2889,610 @@ mod tests {
         handle.abort();
     }
 
+    #[tokio::test]
+    async fn test_idle_timeout_uses_per_instance_override() {
+        let (manager, _temp_dir, runtime) = create_test_manager().await;
+
+        // Both instances have been idle for 60s; the global timeout is 300s
+        let idle_since = Instant::now().checked_sub(Duration::from_secs(60)).unwrap();
+        for (id, port, idle_timeout) in [
+            ("inst_short", 14101, Duration::from_secs(10)),
+            ("inst_long", 14102, Duration::from_secs(3600)),
+        ] {
+            let inst_config = InstanceConfig {
+                id: id.to_string(),
+                project_path: format!("/test/{}", id),
+                port,
+                auto_start: true,
+                opencode_path: "opencode".to_string(),
+                health_path: "/global/health".to_string(),
+                health_check: Default::default(),
+            };
+            let container_config = ContainerConfig {
+                instance_id: id.to_string(),
+                image: "ghcr.io/sst/opencode".to_string(),
+                host_port: port,
+                container_port: 8080,
+                worktree_path: format!("/test/{}", id),
+                config_mount_path: "/tmp/oc-config".to_string(),
+                opencode_data_path: "/tmp/opencode-data".to_string(),
+                topic_id: 100,
+                env_vars: vec![],
+                dns: vec![],
+                dns_search: vec![],
+                extra_binds: vec![],
+                extra_args: vec![],
+                workspace_mount: "/workspace".to_string(),
+                memory_limit_mb: 0,
+            };
+            let (instance, _container_id) =
+                OpenCodeInstance::spawn(inst_config, port, runtime.clone(), container_config)
+                    .await
+                    .unwrap();
+            manager
+                .instances
+                .lock()
+                .await
+                .insert(id.to_string(), Arc::new(Mutex::new(instance)));
+            manager.activity_trackers.lock().await.insert(
+                id.to_string(),
+                ActivityTracker {
+                    last_activity: idle_since,
+                    idle_timeout: Some(idle_timeout),
+                },
+            );
+        }
+
+        // The first interval tick fires immediately
+        let handle = manager.start_health_check_loop();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        *manager.shutdown_signal.lock().await = true;
+        handle.abort();
+
+        let instances = manager.instances.lock().await;
+        assert!(!instances.contains_key("inst_short"));
+        assert!(instances.contains_key("inst_long"));
+    }
+
+    #[tokio::test]
+    async fn test_reloaded_idle_timeout_applies_to_running_loop() {
+        let (manager, _temp_dir, runtime) = create_test_manager().await;
+        let mut config = (*manager.config.get()).clone();
+        config.opencode_health_check_interval = Duration::from_millis(50);
+        manager.config.reload(&config);
+
+        let inst_config = InstanceConfig {
+            id: "inst_idle".to_string(),
+            project_path: "/test/inst_idle".to_string(),
+            port: 14105,
+            auto_start: true,
+            opencode_path: "opencode".to_string(),
+            health_path: "/global/health".to_string(),
+            health_check: Default::default(),
+        };
+        let container_config = ContainerConfig {
+            instance_id: "inst_idle".to_string(),
+            image: "ghcr.io/sst/opencode".to_string(),
+            host_port: 14105,
+            container_port: 8080,
+            worktree_path: "/test/inst_idle".to_string(),
+            config_mount_path: "/tmp/oc-config".to_string(),
+            opencode_data_path: "/tmp/opencode-data".to_string(),
+            topic_id: 100,
+            env_vars: vec![],
+            dns: vec![],
+            dns_search: vec![],
+            extra_binds: vec![],
+            extra_args: vec![],
+            workspace_mount: "/workspace".to_string(),
+            memory_limit_mb: 0,
+        };
+        let (instance, _container_id) =
+            OpenCodeInstance::spawn(inst_config, 14105, runtime.clone(), container_config)
+                .await
+                .unwrap();
+        manager
+            .instances
+            .lock()
+            .await
+            .insert("inst_idle".to_string(), Arc::new(Mutex::new(instance)));
+        // Idle for 60s, within the 300s global timeout
+        manager.activity_trackers.lock().await.insert(
+            "inst_idle".to_string(),
+            ActivityTracker {
+                last_activity: Instant::now().checked_sub(Duration::from_secs(60)).unwrap(),
+                idle_timeout: None,
+            },
+        );
+
+        let handle = manager.start_health_check_loop();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert!(manager.instances.lock().await.contains_key("inst_idle"));
+
+        config.opencode_idle_timeout = Duration::from_secs(30);
+        manager.config.reload(&config);
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        *manager.shutdown_signal.lock().await = true;
+        handle.abort();
+
+        assert!(!manager.instances.lock().await.contains_key("inst_idle"));
+    }
+
+    #[tokio::test]
+    async fn test_streaming_activity_prevents_idle_stop() {
+        let (manager, _temp_dir, runtime) = create_test_manager().await;
+
+        // Idle for 60s against a 10s timeout, but output is still streaming
+        let idle_since = Instant::now().checked_sub(Duration::from_secs(60)).unwrap();
+        for (id, port) in [("inst_streaming", 14103), ("inst_quiet", 14104)] {
+            let inst_config = InstanceConfig {
+                id: id.to_string(),
+                project_path: format!("/test/{}", id),
+                port,
+                auto_start: true,
+                opencode_path: "opencode".to_string(),
+                health_path: "/global/health".to_string(),
+                health_check: Default::default(),
+            };
+            let container_config = ContainerConfig {
+                instance_id: id.to_string(),
+                image: "ghcr.io/sst/opencode".to_string(),
+                host_port: port,
+                container_port: 8080,
+                worktree_path: format!("/test/{}", id),
+                config_mount_path: "/tmp/oc-config".to_string(),
+                opencode_data_path: "/tmp/opencode-data".to_string(),
+                topic_id: 100,
+                env_vars: vec![],
+                dns: vec![],
+                dns_search: vec![],
+                extra_binds: vec![],
+                extra_args: vec![],
+                workspace_mount: "/workspace".to_string(),
+                memory_limit_mb: 0,
+            };
+            let (instance, _container_id) =
+                OpenCodeInstance::spawn(inst_config, port, runtime.clone(), container_config)
+                    .await
+                    .unwrap();
+            manager
+                .instances
+                .lock()
+                .await
+                .insert(id.to_string(), Arc::new(Mutex::new(instance)));
+            manager.activity_trackers.lock().await.insert(
+                id.to_string(),
+                ActivityTracker {
+                    last_activity: idle_since,
+                    idle_timeout: Some(Duration::from_secs(10)),
+                },
+            );
+        }
+
+        manager
+            .record_activity_by_path(Path::new("/test/inst_streaming"))
+            .await;
+
+        let handle = manager.start_health_check_loop();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        *manager.shutdown_signal.lock().await = true;
+        handle.abort();
+
+        let instances = manager.instances.lock().await;
+        assert!(instances.contains_key("inst_streaming"));
+        assert!(!instances.contains_key("inst_quiet"));
+    }
+
+    #[tokio::test]
+    async fn test_health_check_loop_marks_exited_container_as_error() {
+        let (manager, _temp_dir, runtime) = create_test_manager().await;
+
+        let inst_config = InstanceConfig {
+            id: "inst_crash".to_string(),
+            project_path: "/test/crash".to_string(),
+            port: 14101,
+            auto_start: true,
+            opencode_path: "opencode".to_string(),
+            health_path: "/global/health".to_string(),
+            health_check: Default::default(),
+        };
+        let container_config = ContainerConfig {
+            instance_id: "inst_crash".to_string(),
+            image: "ghcr.io/sst/opencode".to_string(),
+            host_port: 14101,
+            container_port: 8080,
+            worktree_path: "/test/crash".to_string(),
+            config_mount_path: "/tmp/oc-config".to_string(),
+            opencode_data_path: "/tmp/opencode-data".to_string(),
+            topic_id: 100,
+            env_vars: vec![],
+            dns: vec![],
+            dns_search: vec![],
+            extra_binds: vec![],
+            extra_args: vec![],
+            workspace_mount: "/workspace".to_string(),
+            memory_limit_mb: 0,
+        };
+        let (instance, container_id) =
+            OpenCodeInstance::spawn(inst_config, 14101, runtime.clone(), container_config)
+                .await
+                .unwrap();
+        let instance = Arc::new(Mutex::new(instance));
+        manager
+            .instances
+            .lock()
+            .await
+            .insert("inst_crash".to_string(), Arc::clone(&instance));
+        manager
+            .store
+            .lock()
+            .await
+            .save_instance(
+                &InstanceInfo {
+                    id: "inst_crash".to_string(),
+                    state: InstanceState::Running,
+                    project_path: "/test/crash".to_string(),
+                    port: 14101,
+                    pid: None,
+                    container_id: Some(container_id.clone()),
+                    started_at: None,
+                    stopped_at: None,
+                    topic_id: 100,
+                },
+                None,
+            )
+            .await
+            .unwrap();
+
+        // The container dies behind the manager's back
+        *runtime.inspect_result.lock().unwrap() = Ok(ContainerInfo {
+            id: container_id,
+            name: "oc-inst_crash".to_string(),
+            state: ContainerState::Exited(1),
+            labels: HashMap::new(),
+            oom_killed: false,
+        });
+
+        // The first tick fires immediately; the restart then waits out its backoff
+        let handle = manager.start_health_check_loop();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        *manager.shutdown_signal.lock().await = true;
+        handle.abort();
+
+        assert_eq!(instance.lock().await.state().await, InstanceState::Error);
+        let stored = manager
+            .store
+            .lock()
+            .await
+            .get_instance("inst_crash")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.state, InstanceState::Error);
+        assert!(runtime
+            .recorded_actions()
+            .iter()
+            .any(|a| matches!(a, MockAction::InspectContainer { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_circuit_breaker_trips_after_max_restart_attempts() {
+        let (manager, _temp_dir, runtime) = create_test_manager().await;
+        let (tx, mut failures) = mpsc::unbounded_channel();
+        let manager = manager.with_failure_notifier(tx);
+
+        let inst_config = InstanceConfig {
+            id: "inst_flaky".to_string(),
+            project_path: "/test/flaky".to_string(),
+            port: 14102,
+            auto_start: true,
+            opencode_path: "opencode".to_string(),
+            health_path: "/global/health".to_string(),
+            health_check: Default::default(),
+        };
+        let container_config = ContainerConfig {
+            instance_id: "inst_flaky".to_string(),
+            image: "ghcr.io/sst/opencode".to_string(),
+            host_port: 14102,
+            container_port: 8080,
+            worktree_path: "/test/flaky".to_string(),
+            config_mount_path: "/tmp/oc-config".to_string(),
+            opencode_data_path: "/tmp/opencode-data".to_string(),
+            topic_id: 101,
+            env_vars: vec![],
+            dns: vec![],
+            dns_search: vec![],
+            extra_binds: vec![],
+            extra_args: vec![],
+            workspace_mount: "/workspace".to_string(),
+            memory_limit_mb: 0,
+        };
+        let (instance, container_id) =
+            OpenCodeInstance::spawn(inst_config, 14102, runtime.clone(), container_config)
+                .await
+                .unwrap();
+        manager
+            .instances
+            .lock()
+            .await
+            .insert("inst_flaky".to_string(), Arc::new(Mutex::new(instance)));
+        manager
+            .store
+            .lock()
+            .await
+            .save_instance(
+                &InstanceInfo {
+                    id: "inst_flaky".to_string(),
+                    state: InstanceState::Running,
+                    project_path: "/test/flaky".to_string(),
+                    port: 14102,
+                    pid: None,
+                    container_id: Some(container_id.clone()),
+                    started_at: None,
+                    stopped_at: None,
+                    topic_id: 101,
+                },
+                None,
+            )
+            .await
+            .unwrap();
+        // Every restart attempt has already been used up
+        manager.restart_trackers.lock().await.insert(
+            "inst_flaky".to_string(),
+            RestartTracker {
+                attempt: MAX_RESTART_ATTEMPTS,
+                last_attempt: Some(Instant::now()),
+            },
+        );
+
+        *runtime.inspect_result.lock().unwrap() = Ok(ContainerInfo {
+            id: container_id,
+            name: "oc-inst_flaky".to_string(),
+            state: ContainerState::Exited(137),
+            labels: HashMap::new(),
+            oom_killed: false,
+        });
+
+        let handle = manager.start_health_check_loop();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        *manager.shutdown_signal.lock().await = true;
+        handle.abort();
+
+        assert_eq!(
+            failures.try_recv().unwrap(),
+            InstanceFailure {
+                project_path: "/test/flaky".to_string(),
+                topic_id: 101,
+                reason: FailureReason::RepeatedCrashes,
+            }
+        );
+        let err = manager
+            .get_or_create(Path::new("/test/flaky"), 101)
+            .await
+            .unwrap_err();
+        assert!(is_circuit_open(&err));
+        assert!(err.to_string().contains("failed state"));
+    }
+
+    #[tokio::test]
+    async fn test_oom_killed_crash_notifies_topic() {
+        let (manager, _temp_dir, runtime) = create_test_manager().await;
+        let (tx, mut failures) = mpsc::unbounded_channel();
+        let manager = manager.with_failure_notifier(tx);
+
+        let inst_config = InstanceConfig {
+            id: "inst_oom".to_string(),
+            project_path: "/test/oom".to_string(),
+            port: 14103,
+            auto_start: true,
+            opencode_path: "opencode".to_string(),
+            health_path: "/global/health".to_string(),
+            health_check: Default::default(),
+        };
+        let container_config = ContainerConfig {
+            instance_id: "inst_oom".to_string(),
+            image: "ghcr.io/sst/opencode".to_string(),
+            host_port: 14103,
+            container_port: 8080,
+            worktree_path: "/test/oom".to_string(),
+            config_mount_path: "/tmp/oc-config".to_string(),
+            opencode_data_path: "/tmp/opencode-data".to_string(),
+            topic_id: 102,
+            env_vars: vec![],
+            dns: vec![],
+            dns_search: vec![],
+            extra_binds: vec![],
+            extra_args: vec![],
+            workspace_mount: "/workspace".to_string(),
+            memory_limit_mb: 0,
+        };
+        let (instance, container_id) =
+            OpenCodeInstance::spawn(inst_config, 14103, runtime.clone(), container_config)
+                .await
+                .unwrap();
+        manager
+            .instances
+            .lock()
+            .await
+            .insert("inst_oom".to_string(), Arc::new(Mutex::new(instance)));
+        manager
+            .store
+            .lock()
+            .await
+            .save_instance(
+                &InstanceInfo {
+                    id: "inst_oom".to_string(),
+                    state: InstanceState::Running,
+                    project_path: "/test/oom".to_string(),
+                    port: 14103,
+                    pid: None,
+                    container_id: Some(container_id.clone()),
+                    started_at: None,
+                    stopped_at: None,
+                    topic_id: 102,
+                },
+                None,
+            )
+            .await
+            .unwrap();
+        *runtime.inspect_result.lock().unwrap() = Ok(ContainerInfo {
+            id: container_id,
+            name: "oc-inst_oom".to_string(),
+            state: ContainerState::Exited(137),
+            labels: HashMap::new(),
+            oom_killed: true,
+        });
+
+        let handle = manager.start_health_check_loop();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        *manager.shutdown_signal.lock().await = true;
+        handle.abort();
+
+        assert_eq!(
+            failures.try_recv().unwrap(),
+            InstanceFailure {
+                project_path: "/test/oom".to_string(),
+                topic_id: 102,
+                reason: FailureReason::OutOfMemory,
+            }
+        );
+        // Attempts remain, so the breaker stays closed
+        assert!(failures.try_recv().is_err());
+        assert!(manager.check_circuit(Path::new("/test/oom")).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_reset_circuit_allows_start_again() {
+        let (manager, _temp_dir, _runtime) = create_test_manager().await;
+        let path = Path::new("/test/flaky");
+        manager
+            .circuit_breakers
+            .lock()
+            .await
+            .insert("/test/flaky".to_string(), Instant::now());
+
+        let err = manager.check_circuit(path).await.unwrap_err();
+        let open = err.downcast_ref::<CircuitOpen>().unwrap();
+        assert!(open.retry_after <= CIRCUIT_BREAKER_COOLDOWN);
+
+        assert!(manager.reset_circuit(path).await);
+        assert!(manager.check_circuit(path).await.is_ok());
+        assert!(!manager.reset_circuit(path).await);
+    }
+
+    #[test]
+    fn test_restart_delay_jitter_bounds() {
+        for (attempt, base) in [(0, 1000), (1, 2000), (4, 16000)] {
+            assert_eq!(
+                restart_delay(attempt, 0.0),
+                Duration::from_millis(base * 3 / 4)
+            );
+            assert_eq!(
+                restart_delay(attempt, 1.0),
+                Duration::from_millis(base * 5 / 4)
+            );
+            let delay = restart_delay(attempt, backoff::random_sample());
+            assert!(delay >= Duration::from_millis(base * 3 / 4));
+            assert!(delay <= Duration::from_millis(base * 5 / 4));
+        }
+    }
+
+    #[test]
+    fn test_activity_tracker_effective_idle_timeout() {
+        let default = Duration::from_secs(300);
+        assert_eq!(
+            ActivityTracker::default().effective_idle_timeout(default),
+            default
+        );
+        assert_eq!(
+            ActivityTracker::with_idle_timeout(Some(Duration::from_secs(5)))
+                .effective_idle_timeout(default),
+            Duration::from_secs(5)
+        );
+    }
+
+    #[test]
+    fn test_project_mount_binds_validates_against_project() {
+        let base = TempDir::new().unwrap();
+        let project = base.path().join("app");
+        std::fs::create_dir_all(project.join("data")).unwrap();
+        std::fs::create_dir_all(base.path().join("other")).unwrap();
+        assert!(project_mount_binds(&project).is_empty());
+
+        std::fs::write(
+            project.join(crate::project_config::PROJECT_CONFIG_FILE),
+            r#"{"mounts": ["data:/data:ro"]}"#,
+        )
+        .unwrap();
+        let data = project.join("data").canonicalize().unwrap();
+        assert_eq!(
+            project_mount_binds(&project),
+            vec![format!("{}:/data:ro", data.display())]
+        );
+
+        // A mount outside the project drops the whole list
+        for mounts in [
+            r#"{"mounts": ["data:/data", "/etc:/host-etc"]}"#,
+            r#"{"mounts": ["data:/data", "../other:/other"]}"#,
+        ] {
+            std::fs::write(
+                project.join(crate::project_config::PROJECT_CONFIG_FILE),
+                mounts,
+            )
+            .unwrap();
+            assert!(project_mount_binds(&project).is_empty());
+        }
+    }
+
+    #[test]
+    fn test_project_idle_timeout_reads_project_config() {
+        let dir = TempDir::new().unwrap();
+        assert_eq!(project_idle_timeout(dir.path()), None);
+
+        std::fs::write(
+            dir.path().join(crate::project_config::PROJECT_CONFIG_FILE),
+            r#"{"idle_timeout_ms": 1500}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            project_idle_timeout(dir.path()),
+            Some(Duration::from_millis(1500))
+        );
+
+        // Invalid files fall back to the global default
+        std::fs::write(
+            dir.path().join(crate::project_config::PROJECT_CONFIG_FILE),
+            "garbage",
+        )
+        .unwrap();
+        assert_eq!(project_idle_timeout(dir.path()), None);
+    }
+
+    #[test]
+    fn test_container_extra_args_appends_project_args() {
+        let dir = TempDir::new().unwrap();
+        let global = vec!["--log-level".to_string(), "info".to_string()];
+        assert_eq!(container_extra_args(&global, dir.path()), global);
+
+        std::fs::write(
+            dir.path().join(crate::project_config::PROJECT_CONFIG_FILE),
+            r#"{"extra_args": ["--print-logs"]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            container_extra_args(&global, dir.path()),
+            vec!["--log-level", "info", "--print-logs"]
+        );
+
+        // Flags outside the allowlist drop the project's args
+        std::fs::write(
+            dir.path().join(crate::project_config::PROJECT_CONFIG_FILE),
+            r#"{"extra_args": ["--print-logs", "--hostname", "0.0.0.0"]}"#,
+        )
+        .unwrap();
+        assert_eq!(container_extra_args(&global, dir.path()), global);
+    }
+
     #[tokio::test]
     async fn test_port_allocation_on_spawn_failure() {
         let (manager, temp_dir, runtime) = create_test_manager().await;
@@ -1339,4 +3519,248 @@ mod tests {
         let final_count = manager.port_pool.allocated_count();
         assert_eq!(final_count, 0);
     }
+
+    #[test]
+    fn test_project_cordon_key() {
+        let base = Path::new("/projects");
+        assert_eq!(
+            project_cordon_key(Path::new("/projects/My_App"), base).as_deref(),
+            Some("my-app")
+        );
+        assert_eq!(
+            project_cordon_key(Path::new("/projects/.worktrees/my-app"), base).as_deref(),
+            Some("my-app")
+        );
+        assert_eq!(
+            project_cordon_key(Path::new("/elsewhere/api"), base).as_deref(),
+            Some("api")
+        );
+        assert_eq!(project_cordon_key(Path::new("/projects"), base), None);
+    }
+
+    #[tokio::test]
+    async fn test_cordoned_project_rejects_resurrection_while_others_work() {
+        let (manager, temp_dir, runtime) = create_test_manager().await;
+        let cordoned = temp_dir.path().join("maintenance");
+        let other = temp_dir.path().join("other");
+        std::fs::create_dir_all(&cordoned).unwrap();
+        std::fs::create_dir_all(&other).unwrap();
+        {
+            let mut create_result = runtime.create_result.lock().unwrap();
+            *create_result = Err("image not found".to_string());
+        }
+
+        manager.cordon_project("maintenance").await.unwrap();
+        assert!(manager.is_cordoned(&cordoned).await);
+        assert!(!manager.is_cordoned(&other).await);
+
+        let err = manager
+            .get_or_create(&cordoned, 1)
+            .await
+            .err()
+            .unwrap()
+            .to_string();
+        assert!(err.contains("cordoned"));
+        assert!(runtime.recorded_actions().is_empty());
+
+        // Other projects still reach the container runtime
+        let err = manager
+            .get_or_create(&other, 2)
+            .await
+            .err()
+            .unwrap()
+            .to_string();
+        assert!(!err.contains("cordoned"));
+        assert!(runtime
+            .recorded_actions()
+            .iter()
+            .any(|a| matches!(a, MockAction::CreateContainer { .. })));
+
+        assert!(manager.uncordon_project("maintenance").await.unwrap());
+        assert!(!manager.is_cordoned(&cordoned).await);
+        assert_eq!(
+            manager.cordoned_projects().await.unwrap(),
+            Vec::<String>::new()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_runtime_outage_fails_get_or_create_until_recovered() {
+        let (manager, temp_dir, runtime) = create_test_manager().await;
+        let project = temp_dir.path().join("project");
+        std::fs::create_dir_all(&project).unwrap();
+        assert!(manager.runtime_available());
+
+        *runtime.list_result.lock().unwrap() = Err("docker socket missing".to_string());
+        assert!(!manager.probe_runtime().await);
+        assert!(!manager.runtime_available());
+
+        let err = manager.get_or_create(&project, 1).await.err().unwrap();
+        assert!(is_runtime_unavailable(&err));
+        assert!(err.to_string().contains("docker socket missing"));
+        assert!(!runtime
+            .recorded_actions()
+            .iter()
+            .any(|a| matches!(a, MockAction::CreateContainer { .. })));
+
+        // Recovery is picked up by the re-probe, without a health check tick
+        *runtime.list_result.lock().unwrap() = Ok(vec![]);
+        *runtime.create_result.lock().unwrap() = Err("image not found".to_string());
+        let err = manager.get_or_create(&project, 1).await.err().unwrap();
+        assert!(!is_runtime_unavailable(&err));
+        assert!(manager.runtime_available());
+        assert!(runtime
+            .recorded_actions()
+            .iter()
+            .any(|a| matches!(a, MockAction::CreateContainer { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_stop_instance_records_event() {
+        let (manager, temp_dir, runtime) = create_test_manager().await;
+        let log = LogStore::new(&temp_dir.path().join("logs.db"))
+            .await
+            .unwrap();
+        let manager = manager.with_event_log(log.clone());
+        let port = manager.port_pool.allocate().await.unwrap();
+
+        let inst_config = InstanceConfig {
+            id: "inst_ev".to_string(),
+            project_path: "/test/ev".to_string(),
+            port,
+            auto_start: true,
+            opencode_path: "opencode".to_string(),
+            health_path: "/global/health".to_string(),
+            health_check: Default::default(),
+        };
+        let container_config = ContainerConfig {
+            instance_id: "inst_ev".to_string(),
+            image: "ghcr.io/sst/opencode".to_string(),
+            host_port: port,
+            container_port: 8080,
+            worktree_path: "/test/ev".to_string(),
+            config_mount_path: "/tmp/oc-config".to_string(),
+            opencode_data_path: "/tmp/opencode-data".to_string(),
+            topic_id: 100,
+            env_vars: vec![],
+            dns: vec![],
+            dns_search: vec![],
+            extra_binds: vec![],
+            extra_args: vec![],
+            workspace_mount: "/workspace".to_string(),
+            memory_limit_mb: 0,
+        };
+        let (instance, _container_id) =
+            OpenCodeInstance::spawn(inst_config, port, runtime.clone(), container_config)
+                .await
+                .unwrap();
+        manager
+            .instances
+            .lock()
+            .await
+            .insert("inst_ev".to_string(), Arc::new(Mutex::new(instance)));
+
+        manager.stop_instance("inst_ev").await.unwrap();
+
+        let stats = log.usage_stats(0).await.unwrap();
+        assert_eq!(stats.stopped, 1);
+        assert_eq!(stats.spawned, 0);
+    }
+
+    /// Consecutive ports starting at the returned one, each answering the
+    /// health check, so spawned instances pass `wait_for_ready`.
+    async fn healthy_port_range(count: u16) -> (u16, Vec<wiremock::MockServer>) {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        'search: loop {
+            let first = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            let start = first.local_addr().unwrap().port();
+            let mut listeners = vec![first];
+            for offset in 1..count {
+                let Some(port) = start.checked_add(offset) else {
+                    continue 'search;
+                };
+                match std::net::TcpListener::bind(("127.0.0.1", port)) {
+                    Ok(listener) => listeners.push(listener),
+                    Err(_) => continue 'search,
+                }
+            }
+
+            let mut servers = Vec::new();
+            for listener in listeners {
+                let server = MockServer::builder().listener(listener).start().await;
+                Mock::given(method("GET"))
+                    .and(path("/global/health"))
+                    .respond_with(ResponseTemplate::new(200))
+                    .mount(&server)
+                    .await;
+                servers.push(server);
+            }
+            return (start, servers);
+        }
+    }
+
+    async fn create_healthy_test_manager(
+        ports: u16,
+    ) -> (
+        InstanceManager,
+        TempDir,
+        Arc<MockRuntime>,
+        Vec<wiremock::MockServer>,
+    ) {
+        let temp_dir = TempDir::new().unwrap();
+        let (port_start, servers) = healthy_port_range(ports).await;
+        let mut config = test_config(&temp_dir);
+        config.opencode_port_start = port_start;
+        config.opencode_port_pool_size = ports;
+        config.opencode_data_path = temp_dir.path().join("opencode-data");
+
+        let store = OrchestratorStore::new(&config.orchestrator_db_path)
+            .await
+            .unwrap();
+        let runtime = Arc::new(MockRuntime::new());
+        let manager = InstanceManager::new(
+            Arc::new(config),
+            store,
+            PortPool::new(port_start, ports),
+            runtime.clone(),
+        )
+        .await
+        .unwrap();
+
+        (manager, temp_dir, runtime, servers)
+    }
+
+    #[tokio::test]
+    async fn test_spawn_ensures_image_before_creating_container() {
+        let (manager, temp_dir, runtime, _servers) = create_healthy_test_manager(1).await;
+
+        let project = temp_dir.path().join("backend");
+        std::fs::create_dir_all(&project).unwrap();
+        manager.get_or_create(&project, 42).await.unwrap();
+
+        let actions = runtime.recorded_actions();
+        let ensured = actions
+            .iter()
+            .position(|a| matches!(a, MockAction::EnsureImage { .. }))
+            .expect("image should be ensured");
+        let created = actions
+            .iter()
+            .position(|a| matches!(a, MockAction::CreateContainer { .. }))
+            .expect("container should be created");
+        assert!(ensured < created);
+    }
+
+    #[tokio::test]
+    async fn test_prepull_image_uses_configured_image() {
+        let (manager, _temp_dir, runtime, _servers) = create_healthy_test_manager(1).await;
+        let image = manager.config.get().docker_image.clone();
+
+        assert!(!manager.prepull_image().await.unwrap());
+        assert!(runtime.recorded_actions().iter().any(|a| matches!(
+            a,
+            MockAction::EnsureImage { image: pulled } if *pulled == image
+        )));
+    }
 }
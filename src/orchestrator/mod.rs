@@ -1,4 +1,5 @@
 pub mod container;
+pub mod health;
 pub mod instance;
 pub mod manager;
 pub mod port_pool;
@@ -66,6 +66,32 @@ impl PortPool {
         ))
     }
 
+    /// Whether `port` falls inside the pool range.
+    pub fn contains(&self, port: u16) -> bool {
+        port >= self.start && port - self.start < self.size
+    }
+
+    /// Mark a specific port as allocated, e.g. for an instance that survived a restart.
+    ///
+    /// # Returns
+    /// * `true` - Port was reserved
+    /// * `false` - Port is outside the pool range or already allocated
+    pub async fn reserve(&self, port: u16) -> bool {
+        if !self.contains(port) {
+            debug!(port = port, "Port outside pool range, not reserved");
+            return false;
+        }
+
+        let mut allocated = self.allocated.lock().unwrap();
+        let reserved = allocated.insert(port);
+        debug!(
+            port = port,
+            reserved = reserved,
+            "Port reservation requested"
+        );
+        reserved
+    }
+
     /// Release a port back to the pool, making it available for reuse.
     ///
     /// # Arguments
@@ -211,6 +237,37 @@ mod tests {
         assert_eq!(pool.allocated_count(), 3);
     }
 
+    #[tokio::test]
+    async fn test_reserve_skips_port_for_allocation() {
+        let pool = PortPool::new(4100, 3);
+
+        assert!(pool.reserve(4100).await);
+        assert!(!pool.reserve(4100).await);
+        assert_eq!(pool.allocated_count(), 1);
+
+        let port = pool.allocate().await.unwrap();
+        assert_eq!(port, 4101);
+    }
+
+    #[tokio::test]
+    async fn test_reserve_rejects_out_of_range_ports() {
+        let pool = PortPool::new(4100, 3);
+
+        assert!(!pool.reserve(4099).await);
+        assert!(!pool.reserve(4103).await);
+        assert_eq!(pool.allocated_count(), 0);
+    }
+
+    #[test]
+    fn test_contains_matches_pool_range() {
+        let pool = PortPool::new(4100, 3);
+
+        assert!(!pool.contains(4099));
+        assert!(pool.contains(4100));
+        assert!(pool.contains(4102));
+        assert!(!pool.contains(4103));
+    }
+
     #[tokio::test]
     async fn test_allocate_fails_when_pool_exhausted() {
         let pool = PortPool::new(4100, 2);
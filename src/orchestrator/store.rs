@@ -1,4 +1,4 @@
-use crate::db::init_orchestrator_db;
+use crate::db::{init_orchestrator_db, retry_busy};
 use crate::types::instance::{InstanceInfo, InstanceState};
 use anyhow::Result;
 use sqlx::sqlite::SqlitePool;
@@ -17,6 +17,17 @@ impl OrchestratorStore {
         Ok(Self { pool })
     }
 
+    /// Check that the database is reachable.
+    pub async fn ping(&self) -> Result<()> {
+        sqlx::query("SELECT 1").execute(&self.pool).await?;
+        Ok(())
+    }
+
+    /// Close the connection pool. Subsequent queries fail.
+    pub async fn close(&self) {
+        self.pool.close().await;
+    }
+
     pub async fn save_instance(
         &self,
         instance: &InstanceInfo,
@@ -44,21 +55,23 @@ impl OrchestratorStore {
             now
         };
 
-        sqlx::query(
+        let state = serde_json::to_string(&instance.state)?;
+        retry_busy(|| sqlx::query(
             "INSERT OR REPLACE INTO instances 
-              (id, project_path, port, state, session_id, container_id, topic_id, created_at, updated_at)
-              VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+              (id, project_path, port, state, session_id, container_id, topic_id, started_at, created_at, updated_at)
+              VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
         )
         .bind(&instance.id)
         .bind(&instance.project_path)
         .bind(instance.port as i64)
-        .bind(serde_json::to_string(&instance.state)?)
+        .bind(&state)
         .bind(session_id)
         .bind(&instance.container_id)
         .bind(instance.topic_id)
+        .bind(instance.started_at)
         .bind(created_at)
         .bind(now)
-        .execute(&self.pool)
+        .execute(&self.pool))
         .await?;
 
         Ok(())
@@ -133,12 +146,15 @@ impl OrchestratorStore {
             .duration_since(std::time::UNIX_EPOCH)?
             .as_millis() as i64;
 
-        sqlx::query("UPDATE instances SET state = ?, updated_at = ? WHERE id = ?")
-            .bind(serde_json::to_string(&state)?)
-            .bind(now)
-            .bind(id)
-            .execute(&self.pool)
-            .await?;
+        let state = serde_json::to_string(&state)?;
+        retry_busy(|| {
+            sqlx::query("UPDATE instances SET state = ?, updated_at = ? WHERE id = ?")
+                .bind(&state)
+                .bind(now)
+                .bind(id)
+                .execute(&self.pool)
+        })
+        .await?;
 
         Ok(())
     }
@@ -154,28 +170,83 @@ impl OrchestratorStore {
             .duration_since(std::time::UNIX_EPOCH)?
             .as_millis() as i64;
 
-        sqlx::query("UPDATE instances SET container_id = ?, updated_at = ? WHERE id = ?")
-            .bind(container_id)
-            .bind(now)
-            .bind(id)
-            .execute(&self.pool)
-            .await?;
+        retry_busy(|| {
+            sqlx::query("UPDATE instances SET container_id = ?, updated_at = ? WHERE id = ?")
+                .bind(container_id)
+                .bind(now)
+                .bind(id)
+                .execute(&self.pool)
+        })
+        .await?;
 
         Ok(())
     }
 
-    #[allow(dead_code)]
     pub async fn delete_instance(&self, id: &str) -> Result<()> {
         debug!(instance_id = %id, "Deleting instance from DB");
 
-        sqlx::query("DELETE FROM instances WHERE id = ?")
-            .bind(id)
+        retry_busy(|| {
+            sqlx::query("DELETE FROM instances WHERE id = ?")
+                .bind(id)
+                .execute(&self.pool)
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    /// Mark `project` as cordoned. Cordoning an already cordoned project
+    /// keeps its original timestamp.
+    pub async fn cordon_project(&self, project: &str) -> Result<()> {
+        debug!(project = %project, "Cordoning project");
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs() as i64;
+
+        retry_busy(|| {
+            sqlx::query(
+                "INSERT INTO cordoned_projects (project, cordoned_at) VALUES (?, ?)
+             ON CONFLICT(project) DO NOTHING",
+            )
+            .bind(project)
+            .bind(now)
             .execute(&self.pool)
-            .await?;
+        })
+        .await?;
 
         Ok(())
     }
 
+    /// Clear the cordon on `project`. Returns false if it was not cordoned.
+    pub async fn uncordon_project(&self, project: &str) -> Result<bool> {
+        debug!(project = %project, "Uncordoning project");
+        let result = retry_busy(|| {
+            sqlx::query("DELETE FROM cordoned_projects WHERE project = ?")
+                .bind(project)
+                .execute(&self.pool)
+        })
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn is_project_cordoned(&self, project: &str) -> Result<bool> {
+        let row = sqlx::query("SELECT 1 FROM cordoned_projects WHERE project = ?")
+            .bind(project)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.is_some())
+    }
+
+    pub async fn get_cordoned_projects(&self) -> Result<Vec<String>> {
+        let rows = sqlx::query("SELECT project FROM cordoned_projects ORDER BY project")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.iter().map(|row| row.get("project")).collect())
+    }
+
     #[allow(dead_code)]
     // Used by future: active instance counting feature
     pub async fn get_active_count(&self) -> Result<usize> {
@@ -202,6 +273,7 @@ impl OrchestratorStore {
 
         let has_container_id = row.columns().iter().any(|c| c.name() == "container_id");
         let has_topic_id = row.columns().iter().any(|c| c.name() == "topic_id");
+        let has_started_at = row.columns().iter().any(|c| c.name() == "started_at");
 
         Ok(InstanceInfo {
             id: row.get("id"),
@@ -214,7 +286,11 @@ impl OrchestratorStore {
             } else {
                 None
             },
-            started_at: None,
+            started_at: if has_started_at {
+                row.try_get("started_at").ok().flatten()
+            } else {
+                None
+            },
             stopped_at: None,
             topic_id: if has_topic_id {
                 row.try_get("topic_id").unwrap_or(0)
@@ -274,6 +350,22 @@ mod tests {
         assert_eq!(retrieved.project_path, "/test/path");
     }
 
+    #[tokio::test]
+    async fn test_save_instance_persists_started_at() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let store = OrchestratorStore::new(&db_path).await.unwrap();
+
+        let mut instance = create_test_instance("test-1", 4100, "/test/path");
+        instance.state = InstanceState::Starting;
+        instance.started_at = Some(1_700_000_000);
+        store.save_instance(&instance, None).await.unwrap();
+
+        let retrieved = store.get_instance("test-1").await.unwrap().unwrap();
+        assert_eq!(retrieved.state, InstanceState::Starting);
+        assert_eq!(retrieved.started_at, Some(1_700_000_000));
+    }
+
     #[tokio::test]
     async fn test_save_instance_updates_existing_instance() {
         let temp_dir = TempDir::new().unwrap();
@@ -687,4 +779,28 @@ mod tests {
         let retrieved = store.get_instance("test-ucid").await.unwrap().unwrap();
         assert_eq!(retrieved.container_id, None);
     }
+
+    #[tokio::test]
+    async fn test_cordon_project_persists() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let store = OrchestratorStore::new(&db_path).await.unwrap();
+
+        assert!(!store.is_project_cordoned("api").await.unwrap());
+        store.cordon_project("api").await.unwrap();
+        store.cordon_project("api").await.unwrap();
+        store.cordon_project("web").await.unwrap();
+        drop(store);
+
+        let store = OrchestratorStore::new(&db_path).await.unwrap();
+        assert!(store.is_project_cordoned("api").await.unwrap());
+        assert_eq!(
+            store.get_cordoned_projects().await.unwrap(),
+            vec!["api".to_string(), "web".to_string()]
+        );
+
+        assert!(store.uncordon_project("api").await.unwrap());
+        assert!(!store.uncordon_project("api").await.unwrap());
+        assert!(!store.is_project_cordoned("api").await.unwrap());
+    }
 }
@@ -0,0 +1,614 @@
+//! Per-project configuration overrides.
+//!
+//! Projects may contain a `.opencode-outpost.json` file at their root to
+//! override selected global settings for that project only. Every field is
+//! optional; anything left unset falls back to the global `Config`.
+//!
+//! The file lives in the workspace the agent can write to, so the settings
+//! that shape the container are confined: mounts stay inside the project and
+//! `extra_args` are limited to [`ALLOWED_EXTRA_ARGS`].
+
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// File name of the per-project config, relative to the project root.
+pub const PROJECT_CONFIG_FILE: &str = ".opencode-outpost.json";
+
+/// File name of the per-project system prompt, relative to the project root.
+pub const SYSTEM_PROMPT_FILE: &str = ".opencode-outpost.system.md";
+
+/// Reply sent when a topic command is not in the project's allowlist.
+pub const COMMAND_DISABLED_MESSAGE: &str = "Command disabled for this project";
+
+/// `opencode serve` flags a project may add with `extra_args`, with the values
+/// each accepts (empty for a flag without a value). Anything else needs the
+/// operator's `OPENCODE_EXTRA_ARGS`.
+pub const ALLOWED_EXTRA_ARGS: [(&str, &[&str]); 2] = [
+    ("--print-logs", &[]),
+    ("--log-level", &["DEBUG", "INFO", "WARN", "ERROR"]),
+];
+
+/// Per-project overrides read from `.opencode-outpost.json`.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProjectConfig {
+    /// Idle timeout override in milliseconds
+    pub idle_timeout_ms: Option<u64>,
+    /// Topic commands allowed for this project, e.g. `["session", "close"]`.
+    /// Unset allows every command.
+    pub enabled_commands: Option<Vec<String>>,
+    /// Extra bind mounts for the project's container, as
+    /// `host:container[:ro]`. Host paths resolve against the project root and
+    /// must stay inside it.
+    pub mounts: Vec<String>,
+    /// Extra arguments appended to the container's `opencode serve` command,
+    /// after the global `OPENCODE_EXTRA_ARGS`; limited to [`ALLOWED_EXTRA_ARGS`]
+    pub extra_args: Vec<String>,
+    /// Prompt sent with captionless images, overriding `DEFAULT_IMAGE_PROMPT`.
+    /// Empty disables it for the project.
+    pub default_image_prompt: Option<String>,
+}
+
+/// A validated extra bind mount from the project config.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProjectMount {
+    pub host_path: PathBuf,
+    pub container_path: String,
+    pub read_only: bool,
+}
+
+impl ProjectMount {
+    /// Parse a `host:container[:ro|:rw]` entry without touching the filesystem.
+    pub fn parse(entry: &str) -> Result<Self> {
+        let parts: Vec<&str> = entry.split(':').collect();
+        let (host, container, read_only) = match parts.as_slice() {
+            [host, container] => (*host, *container, false),
+            [host, container, "ro"] => (*host, *container, true),
+            [host, container, "rw"] => (*host, *container, false),
+            _ => {
+                return Err(anyhow!(
+                    "Invalid mount '{}': expected host:container[:ro]",
+                    entry
+                ))
+            }
+        };
+
+        if host.is_empty() {
+            return Err(anyhow!("Invalid mount '{}': empty host path", entry));
+        }
+        if !container.starts_with('/') {
+            return Err(anyhow!(
+                "Invalid mount '{}': container path must be absolute",
+                entry
+            ));
+        }
+
+        Ok(Self {
+            host_path: PathBuf::from(host),
+            container_path: container.to_string(),
+            read_only,
+        })
+    }
+
+    /// Docker bind string for this mount.
+    pub fn to_bind(&self) -> String {
+        let mode = if self.read_only { "ro" } else { "rw" };
+        format!(
+            "{}:{}:{}",
+            self.host_path.display(),
+            self.container_path,
+            mode
+        )
+    }
+}
+
+impl ProjectConfig {
+    /// Load the project config from `project_path`.
+    ///
+    /// Returns the default (empty) config when the file does not exist.
+    pub fn load(project_path: &Path) -> Result<Self> {
+        let path = project_path.join(PROJECT_CONFIG_FILE);
+        let contents = match std::fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(anyhow!("Failed to read {}: {}", path.display(), e)),
+        };
+
+        serde_json::from_str(&contents).map_err(|e| anyhow!("Invalid {}: {}", path.display(), e))
+    }
+
+    /// Idle timeout override, if set.
+    pub fn idle_timeout(&self) -> Option<Duration> {
+        self.idle_timeout_ms.map(Duration::from_millis)
+    }
+
+    /// Parse and validate `mounts` for the project at `project_path`.
+    ///
+    /// Every host path must exist and resolve (after following symlinks) to a
+    /// location inside the project itself, so a project config cannot expose
+    /// other projects or host directories. Any invalid entry rejects the whole
+    /// list.
+    pub fn validated_mounts(&self, project_path: &Path) -> Result<Vec<ProjectMount>> {
+        if self.mounts.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let base = project_path.canonicalize().map_err(|e| {
+            anyhow!(
+                "Project {} is not accessible: {}",
+                project_path.display(),
+                e
+            )
+        })?;
+
+        self.mounts
+            .iter()
+            .map(|entry| {
+                let mut mount = ProjectMount::parse(entry)?;
+                let host = project_path.join(&mount.host_path);
+                let host = host.canonicalize().map_err(|e| {
+                    anyhow!("Mount host path {} does not exist: {}", host.display(), e)
+                })?;
+                if !host.starts_with(&base) {
+                    return Err(anyhow!(
+                        "Mount host path {} is outside {}",
+                        host.display(),
+                        base.display()
+                    ));
+                }
+                mount.host_path = host;
+                Ok(mount)
+            })
+            .collect()
+    }
+
+    /// Validate `extra_args` against [`ALLOWED_EXTRA_ARGS`]. Values may follow
+    /// their flag as the next argument or after `=`. Any other argument
+    /// rejects the whole list.
+    pub fn validated_extra_args(&self) -> Result<Vec<String>> {
+        let mut args = self.extra_args.iter();
+        while let Some(arg) = args.next() {
+            let (flag, inline_value) = match arg.split_once('=') {
+                Some((flag, value)) => (flag, Some(value)),
+                None => (arg.as_str(), None),
+            };
+            let Some((_, values)) = ALLOWED_EXTRA_ARGS.iter().find(|(f, _)| *f == flag) else {
+                return Err(anyhow!(
+                    "Extra arg '{}' is not allowed in {}",
+                    arg,
+                    PROJECT_CONFIG_FILE
+                ));
+            };
+            let value = match (values.is_empty(), inline_value) {
+                (true, None) => continue,
+                (true, Some(_)) => {
+                    return Err(anyhow!("Extra arg '{}' does not take a value", flag))
+                }
+                (false, Some(value)) => Some(value),
+                (false, None) => args.next().map(String::as_str),
+            };
+            match value {
+                Some(value) if values.iter().any(|v| v.eq_ignore_ascii_case(value)) => {}
+                _ => {
+                    return Err(anyhow!(
+                        "Extra arg '{}' needs one of: {}",
+                        flag,
+                        values.join(", ")
+                    ))
+                }
+            }
+        }
+        Ok(self.extra_args.clone())
+    }
+
+    /// Prompt for images sent without a caption: the project's override if
+    /// set, else `global`. `None` when disabled.
+    pub fn image_prompt(&self, global: Option<&str>) -> Option<String> {
+        self.default_image_prompt
+            .as_deref()
+            .or(global)
+            .map(str::trim)
+            .filter(|prompt| !prompt.is_empty())
+            .map(str::to_string)
+    }
+
+    /// Whether `command` (with or without the leading `/`) is allowed.
+    pub fn is_command_enabled(&self, command: &str) -> bool {
+        let command = command.trim_start_matches('/');
+        match &self.enabled_commands {
+            Some(enabled) => enabled
+                .iter()
+                .any(|c| c.trim_start_matches('/').eq_ignore_ascii_case(command)),
+            None => true,
+        }
+    }
+}
+
+/// Contents of a project's `.opencode-outpost.system.md` and its modification
+/// time, used to detect edits between prompts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SystemPrompt {
+    pub text: String,
+    /// Modification time in milliseconds since the Unix epoch
+    pub mtime_ms: i64,
+}
+
+impl SystemPrompt {
+    /// Load the system prompt file from `project_path`.
+    ///
+    /// Returns `None` when the file does not exist or is blank.
+    pub fn load(project_path: &Path) -> Result<Option<Self>> {
+        let path = project_path.join(SYSTEM_PROMPT_FILE);
+        let metadata = match std::fs::metadata(&path) {
+            Ok(metadata) => metadata,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(anyhow!("Failed to read {}: {}", path.display(), e)),
+        };
+        let mtime_ms = metadata
+            .modified()
+            .map_err(|e| anyhow!("No modification time for {}: {}", path.display(), e))?
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_millis() as i64)
+            .unwrap_or_default();
+        let text = std::fs::read_to_string(&path)
+            .map_err(|e| anyhow!("Failed to read {}: {}", path.display(), e))?;
+
+        if text.trim().is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(Self { text, mtime_ms }))
+    }
+}
+
+/// Whether `command` is allowed in topics for the project at `project_path`.
+///
+/// An unreadable project config is logged and treated as allowing everything,
+/// matching how other per-project overrides fall back to global behaviour.
+pub fn command_enabled(project_path: &Path, command: &str) -> bool {
+    match ProjectConfig::load(project_path) {
+        Ok(config) => config.is_command_enabled(command),
+        Err(e) => {
+            tracing::warn!(project_path = %project_path.display(), error = %e, "Ignoring invalid project config");
+            true
+        }
+    }
+}
+
+/// Entries whose presence at the project root suggests a directory holds a
+/// real project rather than an empty or mistyped one.
+const PROJECT_MARKERS: &[&str] = &[
+    ".git",
+    "AGENTS.md",
+    "opencode.json",
+    PROJECT_CONFIG_FILE,
+    "README.md",
+    "Cargo.toml",
+    "package.json",
+    "pyproject.toml",
+    "go.mod",
+    "Makefile",
+    "src",
+];
+
+/// Outcome of the optional `VALIDATE_PROJECT` check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProjectValidation {
+    /// At least one project marker is present
+    Valid,
+    /// The directory has no entries at all
+    Empty,
+    /// The directory has files, but none of the usual project markers
+    NoMarkers,
+}
+
+impl ProjectValidation {
+    /// Warning shown to the user before spawning, if the project looks off.
+    pub fn warning(self, name: &str) -> Option<String> {
+        match self {
+            Self::Valid => None,
+            Self::Empty => Some(format!(
+                "⚠️ '{}' is empty. OpenCode will start, but has nothing to work with.",
+                name
+            )),
+            Self::NoMarkers => Some(format!(
+                "⚠️ '{}' doesn't look like a project (no README, manifest or git repository). Continuing anyway.",
+                name
+            )),
+        }
+    }
+}
+
+/// Check the project at `project_path` for basic project indicators.
+/// An unreadable directory counts as empty.
+pub fn validate_project(project_path: &Path) -> ProjectValidation {
+    let Ok(mut entries) = std::fs::read_dir(project_path) else {
+        return ProjectValidation::Empty;
+    };
+    if entries.next().is_none() {
+        return ProjectValidation::Empty;
+    }
+    if PROJECT_MARKERS
+        .iter()
+        .any(|marker| project_path.join(marker).exists())
+    {
+        ProjectValidation::Valid
+    } else {
+        ProjectValidation::NoMarkers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_load_missing_file_returns_default() {
+        let dir = TempDir::new().unwrap();
+        let config = ProjectConfig::load(dir.path()).unwrap();
+        assert_eq!(config, ProjectConfig::default());
+        assert!(config.idle_timeout().is_none());
+    }
+
+    #[test]
+    fn test_load_idle_timeout() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(
+            dir.path().join(PROJECT_CONFIG_FILE),
+            r#"{"idle_timeout_ms": 7200000}"#,
+        )
+        .unwrap();
+
+        let config = ProjectConfig::load(dir.path()).unwrap();
+        assert_eq!(config.idle_timeout(), Some(Duration::from_secs(7200)));
+    }
+
+    #[test]
+    fn test_image_prompt_override() {
+        let dir = TempDir::new().unwrap();
+        assert_eq!(
+            ProjectConfig::default().image_prompt(Some("Describe these images.")),
+            Some("Describe these images.".to_string())
+        );
+
+        std::fs::write(
+            dir.path().join(PROJECT_CONFIG_FILE),
+            r#"{"default_image_prompt": "Review this UI screenshot."}"#,
+        )
+        .unwrap();
+        let config = ProjectConfig::load(dir.path()).unwrap();
+        assert_eq!(
+            config.image_prompt(Some("Describe these images.")),
+            Some("Review this UI screenshot.".to_string())
+        );
+
+        // An empty override disables the global prompt
+        std::fs::write(
+            dir.path().join(PROJECT_CONFIG_FILE),
+            r#"{"default_image_prompt": ""}"#,
+        )
+        .unwrap();
+        let config = ProjectConfig::load(dir.path()).unwrap();
+        assert_eq!(config.image_prompt(Some("Describe these images.")), None);
+    }
+
+    #[test]
+    fn test_load_extra_args() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(
+            dir.path().join(PROJECT_CONFIG_FILE),
+            r#"{"extra_args": ["--log-level", "debug"]}"#,
+        )
+        .unwrap();
+
+        let config = ProjectConfig::load(dir.path()).unwrap();
+        assert_eq!(config.extra_args, vec!["--log-level", "debug"]);
+        assert_eq!(
+            config.validated_extra_args().unwrap(),
+            vec!["--log-level", "debug"]
+        );
+    }
+
+    #[test]
+    fn test_validated_extra_args_rejects_unlisted_flags() {
+        let args = |list: &[&str]| ProjectConfig {
+            extra_args: list.iter().map(|a| a.to_string()).collect(),
+            ..Default::default()
+        };
+
+        assert!(args(&["--print-logs", "--log-level=WARN"])
+            .validated_extra_args()
+            .is_ok());
+        assert!(args(&[]).validated_extra_args().unwrap().is_empty());
+
+        for rejected in [
+            &["--hostname", "0.0.0.0"][..],
+            &["--print-logs", "--port=80"],
+            &["--log-level"],
+            &["--log-level", "--hostname=0.0.0.0"],
+            &["--print-logs=yes"],
+        ] {
+            assert!(
+                args(rejected).validated_extra_args().is_err(),
+                "{:?}",
+                rejected
+            );
+        }
+    }
+
+    #[test]
+    fn test_command_allowlist_is_per_project() {
+        let restricted = TempDir::new().unwrap();
+        std::fs::write(
+            restricted.path().join(PROJECT_CONFIG_FILE),
+            r#"{"enabled_commands": ["session", "/close"]}"#,
+        )
+        .unwrap();
+        let open = TempDir::new().unwrap();
+
+        assert!(!command_enabled(restricted.path(), "retry_clean"));
+        assert!(command_enabled(open.path(), "retry_clean"));
+
+        assert!(command_enabled(restricted.path(), "/session"));
+        assert!(command_enabled(restricted.path(), "close"));
+        assert!(command_enabled(restricted.path(), "CLOSE"));
+        assert!(!command_enabled(restricted.path(), "archive"));
+    }
+
+    #[test]
+    fn test_empty_allowlist_disables_everything() {
+        let config = ProjectConfig {
+            enabled_commands: Some(vec![]),
+            ..Default::default()
+        };
+        assert!(!config.is_command_enabled("session"));
+        assert!(ProjectConfig::default().is_command_enabled("session"));
+    }
+
+    #[test]
+    fn test_mount_parse_read_only_flag() {
+        let mount = ProjectMount::parse("data:/data:ro").unwrap();
+        assert_eq!(mount.host_path, PathBuf::from("data"));
+        assert_eq!(mount.container_path, "/data");
+        assert!(mount.read_only);
+
+        assert!(!ProjectMount::parse("data:/data").unwrap().read_only);
+        assert!(!ProjectMount::parse("data:/data:rw").unwrap().read_only);
+
+        assert!(ProjectMount::parse("data:/data:xx").is_err());
+        assert!(ProjectMount::parse("data").is_err());
+        assert!(ProjectMount::parse(":/data").is_err());
+        assert!(ProjectMount::parse("data:relative").is_err());
+    }
+
+    #[test]
+    fn test_validated_mounts_within_project() {
+        let base = TempDir::new().unwrap();
+        let project = base.path().join("app");
+        std::fs::create_dir_all(project.join("fixtures")).unwrap();
+        std::fs::create_dir_all(project.join("cache")).unwrap();
+
+        let config = ProjectConfig {
+            mounts: vec![
+                "fixtures:/fixtures:ro".to_string(),
+                format!("{}:/cache", project.join("cache").display()),
+            ],
+            ..Default::default()
+        };
+        let mounts = config.validated_mounts(&project).unwrap();
+
+        let canonical_project = project.canonicalize().unwrap();
+        assert_eq!(mounts.len(), 2);
+        assert_eq!(
+            mounts[0].to_bind(),
+            format!(
+                "{}:/fixtures:ro",
+                canonical_project.join("fixtures").display()
+            )
+        );
+        assert_eq!(
+            mounts[1].to_bind(),
+            format!("{}:/cache:rw", canonical_project.join("cache").display())
+        );
+    }
+
+    #[test]
+    fn test_validated_mounts_rejects_paths_outside_project() {
+        let base = TempDir::new().unwrap();
+        let outside = TempDir::new().unwrap();
+        let project = base.path().join("app");
+        std::fs::create_dir_all(&project).unwrap();
+        std::fs::create_dir_all(base.path().join("other")).unwrap();
+
+        let absolute = ProjectConfig {
+            mounts: vec![format!("{}:/outside", outside.path().display())],
+            ..Default::default()
+        };
+        let err = absolute.validated_mounts(&project).unwrap_err();
+        assert!(err.to_string().contains("outside"));
+
+        // Another project under the same base is off limits too
+        let sibling = ProjectConfig {
+            mounts: vec!["../other:/other".to_string()],
+            ..Default::default()
+        };
+        assert!(sibling
+            .validated_mounts(&project)
+            .unwrap_err()
+            .to_string()
+            .contains("outside"));
+
+        let escaping = ProjectConfig {
+            mounts: vec!["../..:/root".to_string()],
+            ..Default::default()
+        };
+        assert!(escaping.validated_mounts(&project).is_err());
+
+        let missing = ProjectConfig {
+            mounts: vec!["nope:/nope".to_string()],
+            ..Default::default()
+        };
+        assert!(missing
+            .validated_mounts(&project)
+            .unwrap_err()
+            .to_string()
+            .contains("does not exist"));
+    }
+
+    #[test]
+    fn test_system_prompt_load() {
+        let dir = TempDir::new().unwrap();
+        assert_eq!(SystemPrompt::load(dir.path()).unwrap(), None);
+
+        std::fs::write(dir.path().join(SYSTEM_PROMPT_FILE), "  \n").unwrap();
+        assert_eq!(SystemPrompt::load(dir.path()).unwrap(), None);
+
+        std::fs::write(dir.path().join(SYSTEM_PROMPT_FILE), "Answer tersely.").unwrap();
+        let prompt = SystemPrompt::load(dir.path()).unwrap().unwrap();
+        assert_eq!(prompt.text, "Answer tersely.");
+        assert!(prompt.mtime_ms > 0);
+    }
+
+    #[test]
+    fn test_load_invalid_json_errors() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join(PROJECT_CONFIG_FILE), "{not json").unwrap();
+
+        let result = ProjectConfig::load(dir.path());
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("Invalid"));
+    }
+
+    #[test]
+    fn test_load_rejects_unknown_fields() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(
+            dir.path().join(PROJECT_CONFIG_FILE),
+            r#"{"idle_timeout": 1000}"#,
+        )
+        .unwrap();
+
+        assert!(ProjectConfig::load(dir.path()).is_err());
+    }
+
+    #[test]
+    fn test_validate_project_empty_vs_populated() {
+        let empty = TempDir::new().unwrap();
+        assert_eq!(validate_project(empty.path()), ProjectValidation::Empty);
+        assert!(ProjectValidation::Empty.warning("demo").is_some());
+
+        let unmarked = TempDir::new().unwrap();
+        std::fs::write(unmarked.path().join("notes.txt"), "hi").unwrap();
+        assert_eq!(
+            validate_project(unmarked.path()),
+            ProjectValidation::NoMarkers
+        );
+
+        let populated = TempDir::new().unwrap();
+        std::fs::write(populated.path().join("Cargo.toml"), "[package]").unwrap();
+        assert_eq!(validate_project(populated.path()), ProjectValidation::Valid);
+        assert!(ProjectValidation::Valid.warning("demo").is_none());
+    }
+}
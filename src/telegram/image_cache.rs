@@ -0,0 +1,149 @@
+//! Cleanup of photos downloaded from Telegram.
+//!
+//! Photos are saved under `{project}/.opencode-images` so the container can
+//! read them. Nothing else removes them, so they are reaped when a topic is
+//! closed or its instance goes idle.
+
+use std::io;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+use tracing::{debug, warn};
+
+/// Directory (relative to the project) that downloaded photos are saved to.
+pub const IMAGE_CACHE_DIR: &str = ".opencode-images";
+
+/// Extensions of files written by photo downloads.
+const IMAGE_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "gif", "webp"];
+
+fn is_image_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| IMAGE_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+}
+
+/// Delete images in `{project_path}/.opencode-images` last modified more than
+/// `max_age` ago. Returns the number of files removed.
+///
+/// Only regular files with an image extension are touched; other files,
+/// subdirectories and symlinks are left alone. A missing cache directory is
+/// not an error.
+pub async fn cleanup_image_cache(project_path: &Path, max_age: Duration) -> io::Result<usize> {
+    let cache_dir = project_path.join(IMAGE_CACHE_DIR);
+    let mut entries = match tokio::fs::read_dir(&cache_dir).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e),
+    };
+
+    let now = SystemTime::now();
+    let mut removed = 0;
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        // symlink_metadata so a link is never followed out of the cache
+        let metadata = tokio::fs::symlink_metadata(&path).await?;
+        if !metadata.is_file() || !is_image_file(&path) {
+            continue;
+        }
+
+        let age = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| now.duration_since(modified).ok())
+            .unwrap_or_default();
+        if age <= max_age {
+            continue;
+        }
+
+        match tokio::fs::remove_file(&path).await {
+            Ok(()) => removed += 1,
+            Err(e) => warn!(path = %path.display(), error = %e, "Failed to remove cached image"),
+        }
+    }
+
+    debug!(
+        cache_dir = %cache_dir.display(),
+        removed = removed,
+        max_age_secs = max_age.as_secs(),
+        "Image cache cleaned"
+    );
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{self, File};
+    use tempfile::TempDir;
+
+    const HOUR: Duration = Duration::from_secs(3600);
+
+    fn write_file(path: &Path, age: Duration) {
+        fs::write(path, b"data").unwrap();
+        File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(SystemTime::now() - age)
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_removes_only_old_images() {
+        let project = TempDir::new().unwrap();
+        let cache = project.path().join(IMAGE_CACHE_DIR);
+        fs::create_dir(&cache).unwrap();
+        write_file(&cache.join("old.jpg"), 48 * HOUR);
+        write_file(&cache.join("old.PNG"), 48 * HOUR);
+        write_file(&cache.join("fresh.jpg"), Duration::ZERO);
+
+        let removed = cleanup_image_cache(project.path(), 24 * HOUR)
+            .await
+            .unwrap();
+
+        assert_eq!(removed, 2);
+        assert!(!cache.join("old.jpg").exists());
+        assert!(!cache.join("old.PNG").exists());
+        assert!(cache.join("fresh.jpg").exists());
+    }
+
+    #[tokio::test]
+    async fn test_never_removes_non_image_files() {
+        let project = TempDir::new().unwrap();
+        let cache = project.path().join(IMAGE_CACHE_DIR);
+        fs::create_dir_all(cache.join("nested.jpg")).unwrap();
+        write_file(&cache.join("notes.txt"), 48 * HOUR);
+        write_file(&cache.join("no_extension"), 48 * HOUR);
+        write_file(&project.path().join("main.jpg"), 48 * HOUR);
+
+        let removed = cleanup_image_cache(project.path(), HOUR).await.unwrap();
+
+        assert_eq!(removed, 0);
+        assert!(cache.join("nested.jpg").is_dir());
+        assert!(cache.join("notes.txt").exists());
+        assert!(cache.join("no_extension").exists());
+        // Files outside the cache directory are never considered
+        assert!(project.path().join("main.jpg").exists());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_does_not_follow_symlinks() {
+        let project = TempDir::new().unwrap();
+        let cache = project.path().join(IMAGE_CACHE_DIR);
+        fs::create_dir(&cache).unwrap();
+        let target = project.path().join("precious.jpg");
+        write_file(&target, 48 * HOUR);
+        std::os::unix::fs::symlink(&target, cache.join("link.jpg")).unwrap();
+
+        let removed = cleanup_image_cache(project.path(), HOUR).await.unwrap();
+
+        assert_eq!(removed, 0);
+        assert!(target.exists());
+    }
+
+    #[tokio::test]
+    async fn test_missing_cache_dir_is_ok() {
+        let project = TempDir::new().unwrap();
+        assert_eq!(cleanup_image_cache(project.path(), HOUR).await.unwrap(), 0);
+    }
+}
@@ -7,7 +7,10 @@ use tracing::debug;
 /// - Italic: *text* or _text_ → <i>text</i>
 /// - Inline code: `code` → <code>code</code>
 /// - Code blocks: ```lang\ncode\n``` → <pre><code class="language-lang">code</code></pre>
-/// - Links: [text](url) → <a href="url">text</a>
+/// - Tables: GitHub-style pipe tables → aligned monospace <pre> block
+/// - Links: [text](url) → <a href="url">text</a>, with formatting inside text
+/// - Autolinks: <https://example.com> → <a href="...">https://example.com</a>
+/// - Bare URLs and @mentions are kept verbatim for Telegram to link
 pub fn markdown_to_telegram_html(text: &str) -> String {
     debug!(
         input_len = text.len(),
@@ -20,44 +23,17 @@ pub fn markdown_to_telegram_html(text: &str) -> String {
     while i < chars.len() {
         // Check for code blocks first (```lang\ncode\n```)
         if i + 2 < chars.len() && chars[i] == '`' && chars[i + 1] == '`' && chars[i + 2] == '`' {
-            i += 3;
-
-            // Extract language (optional)
-            let mut lang = String::new();
-            while i < chars.len() && chars[i] != '\n' {
-                lang.push(chars[i]);
-                i += 1;
-            }
-            if i < chars.len() {
-                i += 1; // Skip newline
-            }
-
-            // Extract code content
-            let mut code = String::new();
-            while i + 2 < chars.len() {
-                if chars[i] == '`' && chars[i + 1] == '`' && chars[i + 2] == '`' {
-                    break;
-                }
-                code.push(chars[i]);
-                i += 1;
-            }
-
-            // Skip closing ```
-            if i + 2 < chars.len() {
-                i += 3;
-            }
+            i = push_code_block(&chars, i, &mut result);
+            continue;
+        }
 
-            // Build code block HTML
-            result.push_str("<pre><code");
-            if !lang.trim().is_empty() {
-                result.push_str(" class=\"language-");
-                result.push_str(&escape_html(lang.trim()));
-                result.push('"');
+        // Check for tables (only at the start of a line)
+        if i == 0 || chars[i - 1] == '\n' {
+            if let Some((table, next)) = parse_table(&chars, i) {
+                result.push_str(&table);
+                i = next;
+                continue;
             }
-            result.push('>');
-            result.push_str(&escape_html(&code));
-            result.push_str("</code></pre>");
-            continue;
         }
 
         // Check for inline code (`code`)
@@ -77,6 +53,38 @@ pub fn markdown_to_telegram_html(text: &str) -> String {
             continue;
         }
 
+        // Check for links ([text](url)); incomplete syntax is kept literally
+        if chars[i] == '[' {
+            if let Some((link_text, url, next)) = parse_link(&chars, i) {
+                push_link(&mut result, &url, &markdown_to_telegram_html(&link_text));
+                i = next;
+                continue;
+            }
+            result.push('[');
+            i += 1;
+            continue;
+        }
+
+        // Check for autolinks (<https://example.com>)
+        if chars[i] == '<' {
+            if let Some((url, next)) = parse_autolink(&chars, i) {
+                push_link(&mut result, &url, &escape_html(&url));
+                i = next;
+                continue;
+            }
+        }
+
+        // Bare URLs and @mentions are copied verbatim so underscores in them
+        // are not read as italics; Telegram makes both clickable itself
+        if i == 0 || !chars[i - 1].is_alphanumeric() {
+            if let Some(end) = bare_url_end(&chars, i).or_else(|| mention_end(&chars, i)) {
+                let verbatim: String = chars[i..end].iter().collect();
+                result.push_str(&escape_html(&verbatim));
+                i = end;
+                continue;
+            }
+        }
+
         // Check for bold (**text** or __text__)
         if i + 1 < chars.len()
             && ((chars[i] == '*' && chars[i + 1] == '*')
@@ -121,49 +129,6 @@ pub fn markdown_to_telegram_html(text: &str) -> String {
             continue;
         }
 
-        // Check for links ([text](url))
-        if chars[i] == '[' {
-            i += 1;
-            let mut link_text = String::new();
-            let mut found_closing_bracket = false;
-            while i < chars.len() && chars[i] != ']' {
-                link_text.push(chars[i]);
-                i += 1;
-            }
-            if i < chars.len() && chars[i] == ']' {
-                found_closing_bracket = true;
-                i += 1; // Skip ]
-            }
-
-            // Check for (url)
-            if found_closing_bracket && i < chars.len() && chars[i] == '(' {
-                i += 1;
-                let mut url = String::new();
-                while i < chars.len() && chars[i] != ')' {
-                    url.push(chars[i]);
-                    i += 1;
-                }
-                if i < chars.len() {
-                    i += 1; // Skip )
-                }
-
-                result.push_str("<a href=\"");
-                result.push_str(&escape_html(&url));
-                result.push_str("\">");
-                result.push_str(&escape_html(&link_text));
-                result.push_str("</a>");
-                continue;
-            } else {
-                // Not a valid link, output as-is
-                result.push('[');
-                result.push_str(&escape_html(&link_text));
-                if found_closing_bracket {
-                    result.push(']');
-                }
-                continue;
-            }
-        }
-
         // Regular character - escape HTML entities
         match chars[i] {
             '<' => result.push_str("&lt;"),
@@ -182,8 +147,382 @@ pub fn markdown_to_telegram_html(text: &str) -> String {
     result
 }
 
+/// Append `<a href="url">inner_html</a>`.
+fn push_link(result: &mut String, url: &str, inner_html: &str) {
+    result.push_str("<a href=\"");
+    result.push_str(&escape_attr(url));
+    result.push_str("\">");
+    result.push_str(inner_html);
+    result.push_str("</a>");
+}
+
+/// Parse an inline link `[text](url)` starting at the `[` at `start`.
+///
+/// Returns the raw link text, the URL and the index just past the closing
+/// `)`, or `None` if the syntax is incomplete or the text is empty. Brackets
+/// in the text and parentheses in the URL may nest; an optional quoted title
+/// after the URL is dropped.
+fn parse_link(chars: &[char], start: usize) -> Option<(String, String, usize)> {
+    let mut i = start + 1;
+    let mut depth = 0;
+    let mut text = String::new();
+    loop {
+        let c = *chars.get(i)?;
+        match c {
+            ']' if depth == 0 => break,
+            ']' => depth -= 1,
+            '[' => depth += 1,
+            '\n' => return None,
+            _ => {}
+        }
+        text.push(c);
+        i += 1;
+    }
+    i += 1; // Skip ]
+
+    if chars.get(i) != Some(&'(') {
+        return None;
+    }
+    i += 1;
+    let mut depth = 0;
+    let mut destination = String::new();
+    loop {
+        let c = *chars.get(i)?;
+        match c {
+            ')' if depth == 0 => break,
+            ')' => depth -= 1,
+            '(' => depth += 1,
+            '\n' => return None,
+            _ => {}
+        }
+        destination.push(c);
+        i += 1;
+    }
+
+    if text.trim().is_empty() {
+        return None;
+    }
+    let url = link_destination(&destination)?;
+    Some((text, url, i + 1))
+}
+
+/// URL of a link destination like `url`, `<url>` or `url "title"`.
+fn link_destination(destination: &str) -> Option<String> {
+    let destination = destination.trim();
+    let (url, title) = destination
+        .split_once(char::is_whitespace)
+        .map(|(url, title)| (url, title.trim()))
+        .unwrap_or((destination, ""));
+    let quoted = |q: char| title.len() >= 2 && title.starts_with(q) && title.ends_with(q);
+    if !(title.is_empty() || quoted('"') || quoted('\'')) {
+        return None;
+    }
+    let url = url
+        .strip_prefix('<')
+        .and_then(|u| u.strip_suffix('>'))
+        .unwrap_or(url);
+    (!url.is_empty()).then(|| url.to_string())
+}
+
+/// Whether `text` starts with a scheme Telegram turns into a link.
+fn has_link_scheme(text: &str) -> bool {
+    ["http://", "https://", "mailto:", "tg://"]
+        .iter()
+        .any(|scheme| text.len() > scheme.len() && text.starts_with(scheme))
+}
+
+/// Parse an autolink `<scheme:...>` starting at the `<` at `start`, returning
+/// the URL and the index just past the `>`.
+fn parse_autolink(chars: &[char], start: usize) -> Option<(String, usize)> {
+    let mut i = start + 1;
+    let mut url = String::new();
+    loop {
+        let c = *chars.get(i)?;
+        if c == '>' {
+            break;
+        }
+        if c.is_whitespace() || c == '<' {
+            return None;
+        }
+        url.push(c);
+        i += 1;
+    }
+    has_link_scheme(&url).then_some((url, i + 1))
+}
+
+/// End of a bare `http(s)://` URL starting at `start`. Trailing punctuation
+/// and unbalanced closing parentheses are left out, as in
+/// "see https://example.com/a_(b)."
+fn bare_url_end(chars: &[char], start: usize) -> Option<usize> {
+    if chars[start] != 'h' {
+        return None;
+    }
+    let rest: String = chars[start..chars.len().min(start + 8)].iter().collect();
+    if !rest.starts_with("http://") && !rest.starts_with("https://") {
+        return None;
+    }
+
+    let mut end = start;
+    while end < chars.len() && !chars[end].is_whitespace() && !matches!(chars[end], '<' | '>' | '"')
+    {
+        end += 1;
+    }
+    loop {
+        let last = chars[end - 1];
+        let unbalanced_paren = last == ')' && {
+            let url = &chars[start..end];
+            url.iter().filter(|&&c| c == ')').count() > url.iter().filter(|&&c| c == '(').count()
+        };
+        if matches!(last, '.' | ',' | ':' | ';' | '!' | '?' | '\'' | '*' | '_') || unbalanced_paren
+        {
+            end -= 1;
+        } else {
+            break;
+        }
+    }
+    has_link_scheme(&chars[start..end].iter().collect::<String>()).then_some(end)
+}
+
+/// End of an `@username` mention starting at `start`.
+fn mention_end(chars: &[char], start: usize) -> Option<usize> {
+    if chars[start] != '@' {
+        return None;
+    }
+    let mut end = start + 1;
+    while end < chars.len()
+        && (chars[end].is_ascii_alphanumeric() || matches!(chars[end], '_' | '-'))
+    {
+        end += 1;
+    }
+    // Trailing underscores or hyphens are more likely markup than name
+    while end > start + 1 && matches!(chars[end - 1], '_' | '-') {
+        end -= 1;
+    }
+    (end > start + 1).then_some(end)
+}
+
+/// Render a fenced code block starting at `start` (the first backtick) and
+/// return the index just past the closing fence.
+///
+/// The fence closes only on a line consisting of at least as many backticks as
+/// it opened with, so backticks inside the block are kept verbatim.
+fn push_code_block(chars: &[char], start: usize, result: &mut String) -> usize {
+    let mut i = start;
+    let mut fence_len = 0;
+    while i < chars.len() && chars[i] == '`' {
+        fence_len += 1;
+        i += 1;
+    }
+
+    // Info string (language hint) runs to the end of the line
+    let info_start = i;
+    while i < chars.len() && chars[i] != '\n' {
+        i += 1;
+    }
+    let info: String = chars[info_start..i].iter().collect();
+
+    // ```code``` on a single line is inline code, not a block
+    let fence: String = "`".repeat(fence_len);
+    if let Some(pos) = info.find(&fence) {
+        result.push_str("<code>");
+        result.push_str(&escape_html(&info[..pos]));
+        result.push_str("</code>");
+        return info_start + info[..pos].chars().count() + fence_len;
+    }
+
+    if i < chars.len() {
+        i += 1; // Skip newline
+    }
+
+    let content_start = i;
+    let mut content_end = chars.len();
+    let mut next = chars.len();
+    let mut line_start = i;
+    while line_start < chars.len() {
+        let line_end = chars[line_start..]
+            .iter()
+            .position(|&c| c == '\n')
+            .map_or(chars.len(), |p| line_start + p);
+        if let Some(after_fence) = closing_fence_end(&chars[line_start..line_end], fence_len) {
+            content_end = line_start;
+            next = line_start + after_fence;
+            break;
+        }
+        line_start = line_end + 1;
+    }
+    let code: String = chars[content_start..content_end.max(content_start)]
+        .iter()
+        .collect();
+
+    let lang: String = info
+        .split_whitespace()
+        .next()
+        .unwrap_or("")
+        .chars()
+        .filter(|c| c.is_alphanumeric() || matches!(c, '+' | '-' | '_' | '#' | '.'))
+        .collect();
+
+    result.push_str("<pre><code");
+    if !lang.is_empty() {
+        result.push_str(" class=\"language-");
+        result.push_str(&lang);
+        result.push('"');
+    }
+    result.push('>');
+    result.push_str(&escape_html(&code));
+    result.push_str("</code></pre>");
+    next
+}
+
+/// If `line` closes a fence of `fence_len` backticks, return the offset just
+/// past the closing backticks.
+fn closing_fence_end(line: &[char], fence_len: usize) -> Option<usize> {
+    let indent = line.iter().take_while(|c| **c == ' ').count();
+    if indent > 3 {
+        return None;
+    }
+    let ticks = line[indent..].iter().take_while(|c| **c == '`').count();
+    if ticks < fence_len || !line[indent + ticks..].iter().all(|c| c.is_whitespace()) {
+        return None;
+    }
+    Some(indent + ticks)
+}
+
+/// Column alignment from a table separator row.
+#[derive(Clone, Copy, PartialEq)]
+enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+/// Split a table row into trimmed cells, ignoring leading/trailing pipes.
+fn table_cells(line: &str) -> Vec<String> {
+    let line = line.trim();
+    let line = line.strip_prefix('|').unwrap_or(line);
+    let line = line.strip_suffix('|').unwrap_or(line);
+    line.split('|')
+        .map(|cell| cell.trim().replace("**", "").replace('`', ""))
+        .collect()
+}
+
+/// Parse a separator row (`|---|:---:|--:|`) into column alignments.
+fn table_alignments(line: &str) -> Option<Vec<Align>> {
+    let line = line.trim();
+    if !line.contains('-') {
+        return None;
+    }
+    let line = line.strip_prefix('|').unwrap_or(line);
+    let line = line.strip_suffix('|').unwrap_or(line);
+    line.split('|')
+        .map(|cell| {
+            let cell = cell.trim();
+            let dashes = cell.trim_start_matches(':').trim_end_matches(':');
+            if dashes.is_empty() || !dashes.chars().all(|c| c == '-') {
+                return None;
+            }
+            Some(match (cell.starts_with(':'), cell.ends_with(':')) {
+                (true, true) => Align::Center,
+                (false, true) => Align::Right,
+                _ => Align::Left,
+            })
+        })
+        .collect()
+}
+
+/// Detect a pipe table starting at `start` (a line start) and flatten it into
+/// a monospace `<pre>` block with padded columns. Returns the HTML and the
+/// index of the newline ending the last row.
+fn parse_table(chars: &[char], start: usize) -> Option<(String, usize)> {
+    let line_end = |from: usize| {
+        chars[from..]
+            .iter()
+            .position(|&c| c == '\n')
+            .map_or(chars.len(), |p| from + p)
+    };
+
+    let header_end = line_end(start);
+    let header: String = chars[start..header_end].iter().collect();
+    if !header.contains('|') || header_end >= chars.len() {
+        return None;
+    }
+    let sep_end = line_end(header_end + 1);
+    let separator: String = chars[header_end + 1..sep_end].iter().collect();
+    if !separator.contains('|') {
+        return None;
+    }
+    let aligns = table_alignments(&separator)?;
+    let header = table_cells(&header);
+    if header.len() != aligns.len() {
+        return None;
+    }
+
+    let mut rows = vec![header];
+    let mut end = sep_end;
+    while end < chars.len() {
+        let next_end = line_end(end + 1);
+        let line: String = chars[end + 1..next_end].iter().collect();
+        if line.trim().is_empty() || !line.contains('|') {
+            break;
+        }
+        rows.push(table_cells(&line));
+        end = next_end;
+    }
+
+    let columns = rows
+        .iter()
+        .map(Vec::len)
+        .max()
+        .unwrap_or(0)
+        .max(aligns.len());
+    let mut widths = vec![0; columns];
+    for row in &rows {
+        for (col, cell) in row.iter().enumerate() {
+            widths[col] = widths[col].max(cell.chars().count());
+        }
+    }
+
+    let render_row = |row: &[String]| {
+        (0..columns)
+            .map(|col| {
+                let cell = row.get(col).map(String::as_str).unwrap_or("");
+                let pad = widths[col] - cell.chars().count();
+                let (left, right) = match aligns.get(col).copied().unwrap_or(Align::Left) {
+                    Align::Left => (0, pad),
+                    Align::Right => (pad, 0),
+                    Align::Center => (pad / 2, pad - pad / 2),
+                };
+                format!("{}{}{}", " ".repeat(left), cell, " ".repeat(right))
+            })
+            .collect::<Vec<_>>()
+            .join(" | ")
+            .trim_end()
+            .to_string()
+    };
+
+    let mut lines = vec![render_row(&rows[0])];
+    lines.push(
+        widths
+            .iter()
+            .map(|w| "-".repeat(*w))
+            .collect::<Vec<_>>()
+            .join("-+-"),
+    );
+    lines.extend(rows[1..].iter().map(|row| render_row(row)));
+
+    Some((
+        format!("<pre>{}</pre>", escape_html(&lines.join("\n"))),
+        end,
+    ))
+}
+
+/// Escape text for a double-quoted HTML attribute
+fn escape_attr(text: &str) -> String {
+    escape_html(text).replace('"', "&quot;")
+}
+
 /// Escape HTML entities
-fn escape_html(text: &str) -> String {
+pub(crate) fn escape_html(text: &str) -> String {
     text.chars()
         .map(|c| match c {
             '<' => "&lt;".to_string(),
@@ -251,7 +590,10 @@ pub fn truncate_message(text: &str, max_len: usize) -> String {
 
 /// Split message into chunks of max_len characters
 ///
-/// Preserves code block integrity. Closes/reopens HTML tags across splits.
+/// Never splits inside a `<pre>` block that fits in one chunk: the split moves
+/// to just before the block instead. Blocks longer than a chunk are closed at a
+/// line boundary and reopened (with the same language class) in the next part.
+/// Splits also avoid landing inside an HTML tag or entity.
 pub fn split_message(text: &str, max_len: usize) -> Vec<String> {
     debug!(
         input_len = text.len(),
@@ -265,49 +607,57 @@ pub fn split_message(text: &str, max_len: usize) -> Vec<String> {
     let mut parts = Vec::new();
     let chars: Vec<char> = text.chars().collect();
     let mut start = 0;
+    // Opening tags of a <pre> block carried over from the previous part
+    let mut reopen = String::new();
 
     while start < chars.len() {
+        let budget = max_len.saturating_sub(reopen.chars().count()).max(1);
         let remaining = chars.len() - start;
-        if remaining <= max_len {
+        if remaining <= budget {
             // Last chunk
             let chunk: String = chars[start..].iter().collect();
-            parts.push(chunk);
+            parts.push(format!("{}{}", reopen, chunk));
             break;
         }
 
-        // Find safe split point
-        let mut end = start + max_len;
-
-        // Check if we're inside a code block
-        let chunk_text: String = chars[start..end].iter().collect();
-        if is_inside_code_block(&chunk_text) {
-            // Find the end of the code block
-            end = find_code_block_end(&chars, start, end);
-        }
+        let end = safe_split_point(&chars, start, start + budget);
+        let chunk: String = chars[start..end].iter().collect();
+        let part = format!("{}{}", reopen, chunk);
 
-        // Check if we're inside an HTML tag
-        let mut in_tag = false;
-        for &ch in chars.iter().skip(start).take(end.min(chars.len()) - start) {
-            if ch == '<' {
-                in_tag = true;
-            } else if ch == '>' {
-                in_tag = false;
+        match unclosed_pre(&part) {
+            // The block opens partway through this chunk: split before it
+            Some(open) if open > reopen.len() => {
+                let end = start + part[reopen.len()..open].chars().count();
+                let chunk: String = chars[start..end].iter().collect();
+                parts.push(format!("{}{}", reopen, chunk));
+                reopen.clear();
+                start = end;
             }
-        }
-
-        // If inside tag, backtrack
-        if in_tag {
-            for i in (start..end).rev() {
-                if chars[i] == '<' {
-                    end = i;
-                    break;
+            // The block itself is longer than a chunk: close it and reopen
+            Some(open) => {
+                let opening = pre_opening_tags(&part[open..]);
+                let closing = if opening.contains("<code") {
+                    "</code></pre>"
+                } else {
+                    "</pre>"
+                };
+                let body_budget = budget.saturating_sub(closing.len()).max(1);
+                let mut end = safe_split_point(&chars, start, start + body_budget);
+                let min_end = start + (end - start) / 2;
+                if let Some(newline) = (min_end..end).rev().find(|&i| chars[i] == '\n') {
+                    end = newline + 1;
                 }
+                let chunk: String = chars[start..end].iter().collect();
+                parts.push(format!("{}{}{}", reopen, chunk, closing));
+                reopen = opening;
+                start = end;
+            }
+            None => {
+                parts.push(part);
+                reopen.clear();
+                start = end;
             }
         }
-
-        let chunk: String = chars[start..end].iter().collect();
-        parts.push(chunk);
-        start = end;
     }
 
     // Add ellipsis between parts
@@ -319,21 +669,52 @@ pub fn split_message(text: &str, max_len: usize) -> Vec<String> {
     parts
 }
 
-/// Check if text is inside a code block
-fn is_inside_code_block(text: &str) -> bool {
-    let pre_open = text.matches("<pre>").count();
-    let pre_close = text.matches("</pre>").count();
-    pre_open > pre_close
+/// Move a split point at `end` back so it doesn't fall inside an HTML tag or
+/// entity. Always returns a point past `start`.
+fn safe_split_point(chars: &[char], start: usize, end: usize) -> usize {
+    let end = end.min(chars.len());
+    let window = &chars[start..end];
+
+    // Inside a tag: `<` with no `>` after it
+    if let Some(lt) = window.iter().rposition(|&c| c == '<') {
+        if !window[lt..].contains(&'>') && lt > 0 {
+            return start + lt;
+        }
+    }
+
+    // Inside an entity such as `&amp;`
+    if let Some(amp) = window.iter().rposition(|&c| c == '&') {
+        if window.len() - amp < 10 && !window[amp..].contains(&';') && amp > 0 {
+            return start + amp;
+        }
+    }
+
+    end
+}
+
+/// Byte offset of the last `<pre` in `text` that has no matching `</pre>`.
+fn unclosed_pre(text: &str) -> Option<usize> {
+    let open = text.rfind("<pre")?;
+    if text[open..].contains("</pre>") {
+        return None;
+    }
+    Some(open)
 }
 
-/// Find the end of a code block
-fn find_code_block_end(chars: &[char], start: usize, initial_end: usize) -> usize {
-    let text: String = chars[start..].iter().collect();
-    if let Some(pos) = text.find("</pre>") {
-        let end = start + pos + 6; // Include </pre>
-        return end.min(chars.len());
+/// The `<pre>` tag (and a directly following `<code ...>` tag) at the start of
+/// `block`, used to reopen a block in the next part.
+fn pre_opening_tags(block: &str) -> String {
+    let Some(pre_end) = block.find('>') else {
+        return "<pre>".to_string();
+    };
+    let mut opening = block[..=pre_end].to_string();
+    let rest = &block[pre_end + 1..];
+    if rest.starts_with("<code") {
+        if let Some(code_end) = rest.find('>') {
+            opening.push_str(&rest[..=code_end]);
+        }
     }
-    initial_end
+    opening
 }
 
 #[cfg(test)]
@@ -386,6 +767,61 @@ mod tests {
         assert_eq!(markdown_to_telegram_html(input), expected);
     }
 
+    #[test]
+    fn test_code_block_keeps_nested_inline_code_verbatim() {
+        let input = "```markdown\nRun `cargo test` or ```sh``` if a < b && c > d\n```\nafter";
+        let expected = "<pre><code class=\"language-markdown\">Run `cargo test` or ```sh``` if a &lt; b &amp;&amp; c &gt; d\n</code></pre>\nafter";
+        assert_eq!(markdown_to_telegram_html(input), expected);
+    }
+
+    #[test]
+    fn test_code_block_markdown_not_formatted() {
+        let input = "```rust\nlet s = **ptr; // __init__ *x*\n```";
+        let expected =
+            "<pre><code class=\"language-rust\">let s = **ptr; // __init__ *x*\n</code></pre>";
+        assert_eq!(markdown_to_telegram_html(input), expected);
+    }
+
+    #[test]
+    fn test_code_block_longer_fence_contains_backtick_fence() {
+        let input = "````md\n```rust\nfn main() {}\n```\n````";
+        let expected = "<pre><code class=\"language-md\">```rust\nfn main() {}\n```\n</code></pre>";
+        assert_eq!(markdown_to_telegram_html(input), expected);
+    }
+
+    #[test]
+    fn test_unclosed_code_block_keeps_all_content() {
+        assert_eq!(
+            markdown_to_telegram_html("```\nlet x = 1;"),
+            "<pre><code>let x = 1;</code></pre>"
+        );
+    }
+
+    #[test]
+    fn test_code_block_language_hint_sanitized() {
+        assert_eq!(
+            markdown_to_telegram_html("```c++ title=\"x\"\nint x;\n```"),
+            "<pre><code class=\"language-c++\">int x;\n</code></pre>"
+        );
+    }
+
+    #[test]
+    fn test_table_flattened_to_monospace() {
+        let input = "Results:\n| Name | Tests | Status |\n|------|------:|:------:|\n| parser | 12 | **ok** |\n| `lexer` | 3 | fail |\n| a<b | 100 | ok |\nDone";
+        let expected = "Results:\n<pre>Name   | Tests | Status\n-------+-------+-------\nparser |    12 |   ok\nlexer  |     3 |  fail\na&lt;b    |   100 |   ok</pre>\nDone";
+        assert_eq!(markdown_to_telegram_html(input), expected);
+    }
+
+    #[test]
+    fn test_pipe_text_without_separator_is_not_a_table() {
+        assert_eq!(
+            markdown_to_telegram_html("a | b\nplain text"),
+            "a | b\nplain text"
+        );
+        // A horizontal rule under a line with a pipe is not a table separator
+        assert_eq!(markdown_to_telegram_html("a | b\n---"), "a | b\n---");
+    }
+
     #[test]
     fn test_link_conversion() {
         assert_eq!(
@@ -394,6 +830,81 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_link_text_keeps_nested_formatting() {
+        assert_eq!(
+            markdown_to_telegram_html("[**bold** and `code`](https://example.com)"),
+            "<a href=\"https://example.com\"><b>bold</b> and <code>code</code></a>"
+        );
+    }
+
+    #[test]
+    fn test_link_url_and_text_are_escaped() {
+        assert_eq!(
+            markdown_to_telegram_html("[a < b & c](https://example.com/?q=\"x\"&y=<1>)"),
+            "<a href=\"https://example.com/?q=&quot;x&quot;&amp;y=&lt;1&gt;\">a &lt; b &amp; c</a>"
+        );
+    }
+
+    #[test]
+    fn test_link_url_with_parentheses_and_title() {
+        assert_eq!(
+            markdown_to_telegram_html("[Rust](https://en.wikipedia.org/wiki/Rust_(language))"),
+            "<a href=\"https://en.wikipedia.org/wiki/Rust_(language)\">Rust</a>"
+        );
+        assert_eq!(
+            markdown_to_telegram_html("[docs](https://docs.rs \"Docs\")"),
+            "<a href=\"https://docs.rs\">docs</a>"
+        );
+    }
+
+    #[test]
+    fn test_angle_bracket_autolink() {
+        assert_eq!(
+            markdown_to_telegram_html("See <https://example.com/a_b> now"),
+            "See <a href=\"https://example.com/a_b\">https://example.com/a_b</a> now"
+        );
+        // Not a URL: still escaped as text
+        assert_eq!(
+            markdown_to_telegram_html("<not a link>"),
+            "&lt;not a link&gt;"
+        );
+    }
+
+    #[test]
+    fn test_bare_urls_and_mentions_kept_verbatim() {
+        assert_eq!(
+            markdown_to_telegram_html("Open https://example.com/snake_case_path."),
+            "Open https://example.com/snake_case_path."
+        );
+        assert_eq!(
+            markdown_to_telegram_html("(see https://example.com/a_(b))"),
+            "(see https://example.com/a_(b))"
+        );
+        assert_eq!(
+            markdown_to_telegram_html("ping @some_user_name and @octo-cat"),
+            "ping @some_user_name and @octo-cat"
+        );
+    }
+
+    #[test]
+    fn test_malformed_links_pass_through_literally() {
+        assert_eq!(markdown_to_telegram_html("[text] (url)"), "[text] (url)");
+        assert_eq!(
+            markdown_to_telegram_html("[text](https://unclosed"),
+            "[text](https://unclosed"
+        );
+        assert_eq!(
+            markdown_to_telegram_html("[](https://x.com)"),
+            "[](https://x.com)"
+        );
+        assert_eq!(
+            markdown_to_telegram_html("[a](not a url) and **b**"),
+            "[a](not a url) and <b>b</b>"
+        );
+        assert_eq!(markdown_to_telegram_html("array[0] = x"), "array[0] = x");
+    }
+
     #[test]
     fn test_nested_formatting() {
         assert_eq!(
@@ -464,6 +975,51 @@ mod tests {
         assert!(parts[0].contains("<pre><code>"));
     }
 
+    #[test]
+    fn test_split_moves_before_pre_block() {
+        let block = format!("<pre><code>{}</code></pre>", "y".repeat(50));
+        let text = format!("{}\n{}", "a".repeat(60), block);
+        let parts = split_message(&text, 100);
+
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0], format!("{}\n...", "a".repeat(60)));
+        assert_eq!(parts[1], block);
+    }
+
+    #[test]
+    fn test_split_reopens_oversized_pre_block() {
+        let code = (0..40).map(|i| format!("line {}\n", i)).collect::<String>();
+        let text = format!("<pre><code class=\"language-rust\">{}</code></pre>", code);
+        let parts = split_message(&text, 120);
+
+        assert!(parts.len() > 1);
+        for part in &parts {
+            let part = part.trim_end_matches("...");
+            assert!(part.len() <= 120, "part too long: {}", part.len());
+            assert!(part.starts_with("<pre><code class=\"language-rust\">"));
+            assert!(part.ends_with("</code></pre>"));
+            // Split on line boundaries
+            assert!(part.ends_with("\n</code></pre>"));
+        }
+        let rejoined: String = parts
+            .iter()
+            .map(|p| {
+                p.trim_end_matches("...")
+                    .trim_start_matches("<pre><code class=\"language-rust\">")
+                    .trim_end_matches("</code></pre>")
+            })
+            .collect();
+        assert_eq!(rejoined, code);
+    }
+
+    #[test]
+    fn test_split_does_not_break_entities() {
+        let text = format!("{}&amp;{}", "a".repeat(97), "b".repeat(20));
+        let parts = split_message(&text, 100);
+        assert_eq!(parts[0], format!("{}...", "a".repeat(97)));
+        assert!(parts[1].starts_with("&amp;"));
+    }
+
     #[test]
     fn test_empty_string() {
         assert_eq!(markdown_to_telegram_html(""), "");
@@ -0,0 +1,144 @@
+//! MIME detection and allowlist checks for uploaded files.
+//!
+//! The MIME type Telegram reports is whatever the sender's client declared,
+//! so uploads are sniffed from their leading bytes before being trusted.
+
+/// Fallback type for binary content without a known signature.
+const OCTET_STREAM: &str = "application/octet-stream";
+
+/// Known file signatures, checked in order against the start of the file.
+const SIGNATURES: &[(&[u8], &str)] = &[
+    (b"\x89PNG\r\n\x1a\n", "image/png"),
+    (b"\xff\xd8\xff", "image/jpeg"),
+    (b"GIF87a", "image/gif"),
+    (b"GIF89a", "image/gif"),
+    (b"%PDF-", "application/pdf"),
+    (b"PK\x03\x04", "application/zip"),
+    (b"\x1f\x8b", "application/gzip"),
+    (b"7z\xbc\xaf\x27\x1c", "application/x-7z-compressed"),
+    (b"Rar!\x1a\x07", "application/vnd.rar"),
+    (b"\x7fELF", "application/x-executable"),
+    (b"MZ", "application/x-msdownload"),
+    (b"\xcf\xfa\xed\xfe", "application/x-mach-binary"),
+    (b"\xca\xfe\xba\xbe", "application/x-mach-binary"),
+];
+
+/// Detect the MIME type of `data`.
+///
+/// Binary signatures win over the declared type. Content without a known
+/// signature keeps a declared `text/*` or structured-text type if it is valid
+/// UTF-8, falls back to `text/plain` for other UTF-8 content and to
+/// `application/octet-stream` otherwise.
+pub fn detect_mime(data: &[u8], declared: Option<&str>) -> String {
+    if data.len() >= 12 && &data[..4] == b"RIFF" && &data[8..12] == b"WEBP" {
+        return "image/webp".to_string();
+    }
+    if let Some((_, mime)) = SIGNATURES.iter().find(|(sig, _)| data.starts_with(sig)) {
+        return mime.to_string();
+    }
+
+    if std::str::from_utf8(data).is_err() {
+        return OCTET_STREAM.to_string();
+    }
+
+    match declared.map(|d| d.trim().to_lowercase()) {
+        Some(declared) if is_textual(&declared) => declared,
+        _ => "text/plain".to_string(),
+    }
+}
+
+/// Whether a declared type describes text content.
+fn is_textual(mime: &str) -> bool {
+    mime.starts_with("text/")
+        || matches!(
+            mime,
+            "application/json"
+                | "application/xml"
+                | "application/x-yaml"
+                | "application/yaml"
+                | "application/toml"
+                | "application/javascript"
+                | "application/x-sh"
+        )
+}
+
+/// Whether `mime` matches an allowlist entry. Entries may be exact types
+/// (`application/json`) or wildcards (`text/*`, `*/*`).
+pub fn is_mime_allowed(mime: &str, allowlist: &[String]) -> bool {
+    let mime = mime.to_lowercase();
+    allowlist.iter().any(|entry| {
+        let entry = entry.trim().to_lowercase();
+        match entry.strip_suffix("/*") {
+            Some("*") => true,
+            Some(top_level) => mime
+                .split_once('/')
+                .is_some_and(|(top, _)| top == top_level),
+            None => entry == mime,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn allowlist(entries: &[&str]) -> Vec<String> {
+        entries.iter().map(|e| e.to_string()).collect()
+    }
+
+    #[test]
+    fn test_detect_mime_from_signature() {
+        assert_eq!(
+            detect_mime(b"\x89PNG\r\n\x1a\n....", Some("text/plain")),
+            "image/png"
+        );
+        assert_eq!(detect_mime(b"%PDF-1.7", None), "application/pdf");
+        assert_eq!(detect_mime(b"RIFF\0\0\0\0WEBPVP8 ", None), "image/webp");
+    }
+
+    #[test]
+    fn test_detect_mime_ignores_declared_type_for_executables() {
+        assert_eq!(
+            detect_mime(b"\x7fELF\x02\x01\x01", Some("text/plain")),
+            "application/x-executable"
+        );
+        assert_eq!(
+            detect_mime(b"PK\x03\x04rest", Some("text/x-python")),
+            "application/zip"
+        );
+    }
+
+    #[test]
+    fn test_detect_mime_text_content() {
+        assert_eq!(
+            detect_mime(b"{\"a\": 1}", Some("application/json")),
+            "application/json"
+        );
+        assert_eq!(
+            detect_mime(b"fn main() {}", Some("text/x-rust")),
+            "text/x-rust"
+        );
+        assert_eq!(
+            detect_mime(b"fn main() {}", Some("application/octet-stream")),
+            "text/plain"
+        );
+        assert_eq!(detect_mime(&[0xff, 0x00, 0xfe], None), OCTET_STREAM);
+    }
+
+    #[test]
+    fn test_is_mime_allowed_exact_and_wildcard() {
+        let list = allowlist(&["text/*", "image/png", "application/json"]);
+        assert!(is_mime_allowed("text/plain", &list));
+        assert!(is_mime_allowed("TEXT/X-RUST", &list));
+        assert!(is_mime_allowed("image/png", &list));
+        assert!(is_mime_allowed("application/json", &list));
+        assert!(!is_mime_allowed("image/gif", &list));
+        assert!(!is_mime_allowed("application/x-executable", &list));
+    }
+
+    #[test]
+    fn test_is_mime_allowed_any() {
+        assert!(is_mime_allowed("application/zip", &allowlist(&["*/*"])));
+        assert!(!is_mime_allowed("application/zip", &[]));
+    }
+}
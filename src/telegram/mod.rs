@@ -1 +1,4 @@
+pub mod image_cache;
 pub mod markdown;
+pub mod mime;
+pub mod polling;
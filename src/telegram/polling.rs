@@ -0,0 +1,135 @@
+//! Update listener error handling.
+//!
+//! When the bot token is rotated or revoked, every long-poll fails with
+//! `Unauthorized` and the bot silently stops receiving updates. The handler
+//! here counts consecutive auth failures and signals the main loop, which
+//! shuts down and exits with [`AUTH_FAILURE_EXIT_CODE`] so a supervisor can
+//! restart the bot with the new token.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use teloxide::error_handlers::ErrorHandler;
+use teloxide::{ApiError, RequestError};
+use tokio::sync::Notify;
+use tracing::{error, warn};
+
+/// Process exit code after Telegram keeps rejecting the bot token
+/// (`EX_CONFIG` from sysexits.h).
+pub const AUTH_FAILURE_EXIT_CODE: i32 = 78;
+
+/// Consecutive auth failures before the token is considered invalid.
+pub const AUTH_FAILURE_THRESHOLD: u32 = 3;
+
+/// Whether `error` means Telegram rejected the bot token.
+pub fn is_auth_failure(error: &RequestError) -> bool {
+    matches!(error, RequestError::Api(ApiError::InvalidToken))
+}
+
+/// Counts consecutive auth failures. Any other error resets the count.
+#[derive(Debug)]
+pub struct AuthFailureTracker {
+    consecutive: AtomicU32,
+    threshold: u32,
+}
+
+impl AuthFailureTracker {
+    pub fn new(threshold: u32) -> Self {
+        Self {
+            consecutive: AtomicU32::new(0),
+            threshold,
+        }
+    }
+
+    /// Record a polling error. Returns `true` once the threshold of
+    /// consecutive auth failures is reached.
+    pub fn record(&self, error: &RequestError) -> bool {
+        if is_auth_failure(error) {
+            self.consecutive.fetch_add(1, Ordering::SeqCst) + 1 >= self.threshold
+        } else {
+            self.consecutive.store(0, Ordering::SeqCst);
+            false
+        }
+    }
+}
+
+/// Error handler for the polling update listener. Logs every error and
+/// notifies `auth_failed` when the token is persistently rejected.
+pub struct PollingErrorHandler {
+    tracker: AuthFailureTracker,
+    auth_failed: Arc<Notify>,
+}
+
+impl PollingErrorHandler {
+    pub fn new(auth_failed: Arc<Notify>) -> Arc<Self> {
+        Arc::new(Self {
+            tracker: AuthFailureTracker::new(AUTH_FAILURE_THRESHOLD),
+            auth_failed,
+        })
+    }
+}
+
+impl ErrorHandler<RequestError> for PollingErrorHandler {
+    fn handle_error(
+        self: Arc<Self>,
+        error: RequestError,
+    ) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        Box::pin(async move {
+            if self.tracker.record(&error) {
+                error!(
+                    error = %error,
+                    "Telegram keeps rejecting the bot token; it was probably rotated or revoked. \
+                     Shutting down so the bot can be restarted with a new TELEGRAM_BOT_TOKEN"
+                );
+                self.auth_failed.notify_one();
+            } else {
+                warn!(error = %error, "Error while polling Telegram for updates");
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_auth_failure() {
+        assert!(is_auth_failure(&RequestError::Api(ApiError::InvalidToken)));
+        assert!(!is_auth_failure(&RequestError::Api(ApiError::BotBlocked)));
+        assert!(!is_auth_failure(&RequestError::RetryAfter(
+            teloxide::types::Seconds::from_seconds(5)
+        )));
+    }
+
+    #[test]
+    fn test_tracker_trips_after_consecutive_auth_failures() {
+        let tracker = AuthFailureTracker::new(3);
+        let auth = || RequestError::Api(ApiError::InvalidToken);
+
+        assert!(!tracker.record(&auth()));
+        assert!(!tracker.record(&auth()));
+        // Another error in between resets the count
+        assert!(!tracker.record(&RequestError::Api(ApiError::BotBlocked)));
+        assert!(!tracker.record(&auth()));
+        assert!(!tracker.record(&auth()));
+        assert!(tracker.record(&auth()));
+    }
+
+    #[tokio::test]
+    async fn test_handler_notifies_on_persistent_auth_failure() {
+        let auth_failed = Arc::new(Notify::new());
+        let handler = PollingErrorHandler::new(Arc::clone(&auth_failed));
+
+        for _ in 0..AUTH_FAILURE_THRESHOLD {
+            Arc::clone(&handler)
+                .handle_error(RequestError::Api(ApiError::InvalidToken))
+                .await;
+        }
+
+        tokio::time::timeout(std::time::Duration::from_secs(1), auth_failed.notified())
+            .await
+            .expect("auth failure should be signalled");
+    }
+}
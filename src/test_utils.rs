@@ -0,0 +1,84 @@
+//! Helpers shared by unit tests.
+
+use crate::config::{AudioMode, Config, PhotoSizePreference, StreamMode, TopicNameStrategy};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// A valid config with every path under `dir` and optional features off.
+/// Tests override the fields they exercise.
+pub fn test_config(dir: &Path) -> Config {
+    Config {
+        telegram_bot_token: "test_token".to_string(),
+        telegram_chat_ids: vec![-1001234567890],
+        telegram_allowed_users: vec![],
+        telegram_admin_users: vec![],
+        handle_general_topic: true,
+        opencode_path: PathBuf::from("opencode"),
+        opencode_max_instances: 10,
+        opencode_idle_timeout: Duration::from_secs(1800),
+        opencode_port_start: 4100,
+        opencode_port_pool_size: 10,
+        opencode_health_check_interval: Duration::from_secs(30),
+        opencode_startup_timeout: Duration::from_secs(60),
+        opencode_data_path: dir.join("opencode-data"),
+        orchestrator_db_path: dir.join("orchestrator.db"),
+        topic_db_path: dir.join("topics.db"),
+        log_db_path: dir.join("logs.db"),
+        project_base_path: dir.to_path_buf(),
+        auto_create_project_dirs: true,
+        docker_image: "ghcr.io/sst/opencode".to_string(),
+        opencode_config_path: dir.join("oc-config"),
+        container_port: 8080,
+        env_passthrough: vec![],
+        topic_name_strategy: TopicNameStrategy::Leaf,
+        show_usage: false,
+        duplicate_response_window: Duration::from_secs(10),
+        container_dns: vec![],
+        container_dns_search: vec![],
+        fallback_model: None,
+        max_topics_per_chat: 50,
+        transcription_url: None,
+        transcription_api_key: None,
+        transcription_model: "whisper-1".to_string(),
+        audio_mode: AudioMode::Transcribe,
+        permission_timeout: Duration::from_secs(600),
+        opencode_request_timeout: Duration::from_secs(30),
+        resurrection_timeout: Duration::from_secs(30),
+        resurrection_wake_delay: Duration::from_secs(3),
+        show_file_edits: false,
+        allowed_upload_mime: vec![],
+        dedup_expiry: Duration::from_secs(30),
+        max_reconnect_attempts: 5,
+        base_reconnect_delay: Duration::from_secs(1),
+        max_reconnect_delay: Duration::from_secs(16),
+        max_resume_age: Duration::ZERO,
+        max_concurrent_downloads: 4,
+        max_message_parts: 10,
+        api_max_body_bytes: 1_048_576,
+        api_port: 0,
+        api_host: [127, 0, 0, 1].into(),
+        api_key: None,
+        image_cache_retention: Duration::from_secs(86400),
+        slow_start_nudge: Duration::from_secs(15),
+        rewrite_workspace_paths: false,
+        opencode_socket_path: None,
+        health_path: "/global/health".to_string(),
+        health_check: Default::default(),
+        collect_feedback: false,
+        container_extra_args: vec![],
+        response_filters: vec![],
+        reply_to_prompts: false,
+        preferred_photo_size: PhotoSizePreference::Largest,
+        max_image_dimension: 1280,
+        max_image_bytes: 1_048_576,
+        default_image_prompt: None,
+        validate_project: false,
+        max_response_chars: 0,
+        collapse_repeated_tools: false,
+        stream_mode: StreamMode::Batched,
+        durable_outbound: false,
+        keep_stopped_containers: false,
+        container_memory_mb: 0,
+        workspace_mount: "/workspace".to_string(),
+    }
+}
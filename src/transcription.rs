@@ -0,0 +1,183 @@
+//! Voice message transcription.
+//!
+//! Sends audio to a Whisper-compatible HTTP endpoint (`POST` multipart with
+//! `file` and `model` fields, JSON `{"text": ...}` response) and returns the
+//! transcript so it can be routed to OpenCode as a normal text prompt.
+
+use crate::config::Config;
+use anyhow::{anyhow, Result};
+use reqwest::multipart::{Form, Part};
+use serde::Deserialize;
+use std::time::Duration;
+use tracing::debug;
+
+/// Request timeout for transcription calls.
+const TRANSCRIPTION_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Deserialize)]
+struct TranscriptionResponse {
+    text: String,
+}
+
+/// Client for a Whisper-compatible transcription API.
+#[derive(Debug, Clone)]
+pub struct Transcriber {
+    client: reqwest::Client,
+    url: String,
+    api_key: Option<String>,
+    model: String,
+}
+
+impl Transcriber {
+    pub fn new(url: &str, api_key: Option<&str>, model: &str) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(TRANSCRIPTION_TIMEOUT)
+            .build()?;
+
+        Ok(Self {
+            client,
+            url: url.to_string(),
+            api_key: api_key.map(str::to_string),
+            model: model.to_string(),
+        })
+    }
+
+    /// Build a transcriber from config. Returns `None` when `TRANSCRIPTION_URL` is unset.
+    pub fn from_config(config: &Config) -> Result<Option<Self>> {
+        config
+            .transcription_url
+            .as_deref()
+            .map(|url| {
+                Self::new(
+                    url,
+                    config.transcription_api_key.as_deref(),
+                    &config.transcription_model,
+                )
+            })
+            .transpose()
+    }
+
+    /// Transcribe `audio` and return the trimmed text.
+    pub async fn transcribe(&self, audio: Vec<u8>, filename: &str, mime: &str) -> Result<String> {
+        debug!(
+            url = %self.url,
+            model = %self.model,
+            bytes = audio.len(),
+            "Sending audio for transcription"
+        );
+
+        let file = Part::bytes(audio)
+            .file_name(filename.to_string())
+            .mime_str(mime)?;
+        let form = Form::new()
+            .part("file", file)
+            .text("model", self.model.clone());
+
+        let mut request = self.client.post(&self.url).multipart(form);
+        if let Some(key) = &self.api_key {
+            request = request.bearer_auth(key);
+        }
+
+        let response = request.send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(anyhow!("Transcription failed ({}): {}", status, body));
+        }
+
+        let result: TranscriptionResponse = response.json().await?;
+        let text = result.text.trim().to_string();
+        if text.is_empty() {
+            return Err(anyhow!("Transcription returned no text"));
+        }
+
+        debug!(chars = text.len(), "Transcription complete");
+        Ok(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::test_config;
+    use std::path::Path;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn transcriber(server: &MockServer, api_key: Option<&str>) -> Transcriber {
+        Transcriber::new(
+            &format!("{}/v1/audio/transcriptions", server.uri()),
+            api_key,
+            "whisper-1",
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_transcribe_returns_text() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/audio/transcriptions"))
+            .and(header("authorization", "Bearer sk-test"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({"text": "  fix the failing test \n"})),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let text = transcriber(&server, Some("sk-test"))
+            .transcribe(vec![1, 2, 3], "voice.ogg", "audio/ogg")
+            .await
+            .unwrap();
+        assert_eq!(text, "fix the failing test");
+    }
+
+    #[tokio::test]
+    async fn test_transcribe_error_status() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(401).set_body_string("invalid api key"))
+            .mount(&server)
+            .await;
+
+        let err = transcriber(&server, None)
+            .transcribe(vec![1, 2, 3], "voice.ogg", "audio/ogg")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("401"));
+        assert!(err.to_string().contains("invalid api key"));
+    }
+
+    #[tokio::test]
+    async fn test_transcribe_empty_text_is_error() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"text": ""})))
+            .mount(&server)
+            .await;
+
+        let result = transcriber(&server, None)
+            .transcribe(vec![1, 2, 3], "voice.ogg", "audio/ogg")
+            .await;
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_from_config_disabled_without_url() {
+        let config = Config {
+            transcription_url: None,
+            ..test_config(Path::new("/tmp"))
+        };
+        assert!(Transcriber::from_config(&config).unwrap().is_none());
+
+        let config = Config {
+            transcription_url: Some("http://localhost:9000/transcribe".to_string()),
+            ..test_config(Path::new("/tmp"))
+        };
+        let transcriber = Transcriber::from_config(&config).unwrap().unwrap();
+        assert_eq!(transcriber.url, "http://localhost:9000/transcribe");
+        assert_eq!(transcriber.model, "whisper-1");
+    }
+}
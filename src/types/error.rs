@@ -64,6 +64,9 @@ pub enum OutpostError {
     // Used by future: instance limit enforcement
     MaxInstancesReached { limit: usize },
 
+    #[error("Container runtime unavailable: {message}")]
+    RuntimeUnavailable { message: String },
+
     #[error("IO error: {message}")]
     IoError { message: String },
 
@@ -187,6 +190,12 @@ impl OutpostError {
         Self::MaxInstancesReached { limit }
     }
 
+    pub fn runtime_unavailable(message: impl Into<String>) -> Self {
+        Self::RuntimeUnavailable {
+            message: message.into(),
+        }
+    }
+
     pub fn io_error(message: impl Into<String>) -> Self {
         Self::IoError {
             message: message.into(),
@@ -329,6 +338,15 @@ mod tests {
         assert_eq!(err.to_string(), "IO error: File not found");
     }
 
+    #[test]
+    fn test_runtime_unavailable_error() {
+        let err = OutpostError::runtime_unavailable("docker socket missing");
+        assert_eq!(
+            err.to_string(),
+            "Container runtime unavailable: docker socket missing"
+        );
+    }
+
     #[test]
     fn test_serialization_error() {
         let err = OutpostError::serialization_error("Invalid JSON");
@@ -352,6 +370,7 @@ mod tests {
 
         assert!(!OutpostError::database_error("test").is_user_error());
         assert!(!OutpostError::io_error("test").is_user_error());
+        assert!(!OutpostError::runtime_unavailable("test").is_user_error());
         assert!(!OutpostError::opencode_api_error("test").is_user_error());
         assert!(!OutpostError::instance_not_found("test").is_user_error());
         assert!(!OutpostError::port_allocation_error(3000, 3100).is_user_error());
@@ -10,6 +10,72 @@ pub struct TopicMapping {
     pub topic_name_updated: bool,
     pub created_at: i64,
     pub updated_at: i64,
+    /// Observer mode: mirror the session's output but ignore new prompts
+    #[serde(default)]
+    pub read_only: bool,
+    /// Topic that receives the agent's output, if not this one
+    #[serde(default)]
+    pub output_topic_id: Option<i32>,
+    /// Modification time (ms since epoch) of the project's system prompt file
+    /// when it was last sent to the session
+    #[serde(default)]
+    pub system_prompt_mtime: Option<i64>,
+    /// Language code set with `/lang`; the session is asked to respond in it
+    #[serde(default)]
+    pub locale: Option<String>,
+    /// When a prompt was last sent from the topic (seconds since epoch)
+    #[serde(default)]
+    pub last_activity_at: Option<i64>,
+}
+
+impl TopicMapping {
+    /// When the topic was last used: its last prompt, or for topics without
+    /// one recorded, its last update.
+    pub fn last_activity(&self) -> i64 {
+        self.last_activity_at.unwrap_or(self.updated_at)
+    }
+}
+
+/// A topic whose response is still being generated. Saved when the prompt is
+/// sent and removed when the response ends, so a marker found on start means
+/// the response was cut off by a restart or crash.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PendingGeneration {
+    pub chat_id: i64,
+    pub topic_id: i32,
+    pub session_id: String,
+    /// The Telegram message that triggered the response, if known
+    pub last_message_id: Option<i32>,
+    /// The prompt's message parts as JSON, resent when the response is lost
+    pub prompt: Option<String>,
+    pub saved_at: i64,
+}
+
+/// Formatted output recorded before it is sent to Telegram and removed once
+/// the send is confirmed, so a crash cannot lose it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutboundMessage {
+    pub id: i64,
+    pub chat_id: i64,
+    pub topic_id: i32,
+    /// Telegram HTML, ready to send
+    pub text: String,
+    /// Message the first part replies to, if any
+    pub reply_to: Option<i32>,
+    pub queued_at: i64,
+}
+
+/// Response text batched for a topic, recorded as it arrives so text that
+/// was never flushed survives a crash.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BufferedOutput {
+    pub chat_id: i64,
+    /// Topic the response belongs to; its output topic is resolved on replay
+    pub topic_id: i32,
+    /// Markdown as received from OpenCode
+    pub text: String,
+    pub reply_to: Option<i32>,
+    pub updated_at: i64,
 }
 
 #[cfg(test)]
@@ -68,6 +134,11 @@ mod tests {
             session_id: Some("sess-789".to_string()),
             instance_id: None,
             topic_name_updated: false,
+            read_only: false,
+            output_topic_id: None,
+            system_prompt_mtime: None,
+            locale: None,
+            last_activity_at: None,
             created_at: 1650000000,
             updated_at: 1650000200,
         };
@@ -94,6 +165,11 @@ mod tests {
             session_id: Some("test-session".to_string()),
             instance_id: Some("test-instance".to_string()),
             topic_name_updated: true,
+            read_only: false,
+            output_topic_id: None,
+            system_prompt_mtime: None,
+            locale: None,
+            last_activity_at: None,
             created_at: 1660000000,
             updated_at: 1660000300,
         };
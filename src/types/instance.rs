@@ -18,12 +18,78 @@ pub struct InstanceConfig {
     pub auto_start: bool,
     #[serde(default = "default_opencode_path")]
     pub opencode_path: String,
+    /// Path polled for readiness, e.g. `/global/health`.
+    #[serde(default = "default_health_path")]
+    pub health_path: String,
+    /// How the health endpoint is requested and what counts as healthy.
+    #[serde(default)]
+    pub health_check: HealthCheck,
 }
 
+/// HTTP method used for health checks.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum HealthMethod {
+    #[default]
+    Get,
+    Head,
+}
+
+impl std::str::FromStr for HealthMethod {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_uppercase().as_str() {
+            "GET" => Ok(Self::Get),
+            "HEAD" => Ok(Self::Head),
+            _ => Err(format!("unsupported health check method '{}'", s)),
+        }
+    }
+}
+
+/// What a health check sends and expects back. The default is a GET where
+/// any 2xx status is healthy.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct HealthCheck {
+    #[serde(default)]
+    pub method: HealthMethod,
+    /// Statuses that count as healthy; empty accepts any 2xx.
+    #[serde(default)]
+    pub expect_status: Vec<u16>,
+    /// Substring the response body must contain, if set.
+    #[serde(default)]
+    pub expect_body: Option<String>,
+}
+
+impl HealthCheck {
+    /// Whether a response with `status` passes the status check.
+    pub fn status_ok(&self, status: u16) -> bool {
+        if self.expect_status.is_empty() {
+            (200..300).contains(&status)
+        } else {
+            self.expect_status.contains(&status)
+        }
+    }
+
+    /// Whether `body` passes the body check.
+    pub fn body_ok(&self, body: &str) -> bool {
+        self.expect_body
+            .as_deref()
+            .is_none_or(|expected| body.contains(expected))
+    }
+}
+
+/// Health endpoint exposed by current OpenCode releases.
+pub const DEFAULT_HEALTH_PATH: &str = "/global/health";
+
 fn default_opencode_path() -> String {
     "opencode".to_string()
 }
 
+fn default_health_path() -> String {
+    DEFAULT_HEALTH_PATH.to_string()
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct InstanceInfo {
     pub id: String,
@@ -84,6 +150,29 @@ mod tests {
         assert_eq!(config.project_path, "/path/to/project");
         assert_eq!(config.port, 3000);
         assert!(config.auto_start);
+        assert_eq!(config.health_path, DEFAULT_HEALTH_PATH);
+        assert_eq!(config.health_check, HealthCheck::default());
+    }
+
+    #[test]
+    fn test_health_check_expectations() {
+        let default = HealthCheck::default();
+        assert!(default.status_ok(200));
+        assert!(default.status_ok(204));
+        assert!(!default.status_ok(503));
+        assert!(default.body_ok(""));
+
+        let strict = HealthCheck {
+            method: HealthMethod::Get,
+            expect_status: vec![200],
+            expect_body: Some("\"healthy\":true".to_string()),
+        };
+        assert!(!strict.status_ok(204));
+        assert!(strict.body_ok(r#"{"healthy":true,"version":"1.0"}"#));
+        assert!(!strict.body_ok(r#"{"healthy":false}"#));
+
+        assert_eq!("head".parse::<HealthMethod>(), Ok(HealthMethod::Head));
+        assert!("POST".parse::<HealthMethod>().is_err());
     }
 
     #[test]
@@ -94,6 +183,8 @@ mod tests {
             port: 8080,
             auto_start: false,
             opencode_path: "opencode".to_string(),
+            health_path: "/global/health".to_string(),
+            health_check: Default::default(),
         };
 
         let json = serde_json::to_string(&config).unwrap();
@@ -47,10 +47,40 @@ pub struct Message {
     pub content: Vec<MessagePart>,
 }
 
+/// Model selection in the shape OpenCode expects, e.g. from `provider/model`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModelRef {
+    #[serde(rename = "providerID")]
+    pub provider_id: String,
+    #[serde(rename = "modelID")]
+    pub model_id: String,
+}
+
+impl ModelRef {
+    /// Split `provider/model` at the first `/`; the model id may contain more.
+    /// `None` unless both halves are non-empty.
+    pub fn parse(model: &str) -> Option<Self> {
+        let (provider_id, model_id) = model.trim().split_once('/')?;
+        if provider_id.is_empty() || model_id.is_empty() {
+            return None;
+        }
+        Some(Self {
+            provider_id: provider_id.to_string(),
+            model_id: model_id.to_string(),
+        })
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct CreateMessageRequest {
     pub message: Message,
     pub stream: Option<bool>,
+    /// Model override; the session default when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<ModelRef>,
+    /// Extra system instructions for this prompt
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system: Option<String>,
 }
 
 #[cfg(test)]
@@ -148,6 +178,24 @@ mod tests {
         assert_eq!(request.message.role, "user");
     }
 
+    #[test]
+    fn test_model_ref_parse_and_serialize() {
+        let model = ModelRef::parse("openrouter/anthropic/claude-3.5-sonnet").unwrap();
+        assert_eq!(model.provider_id, "openrouter");
+        assert_eq!(model.model_id, "anthropic/claude-3.5-sonnet");
+        assert_eq!(
+            serde_json::to_value(&model).unwrap(),
+            serde_json::json!({
+                "providerID": "openrouter",
+                "modelID": "anthropic/claude-3.5-sonnet"
+            })
+        );
+
+        for invalid in ["gpt-4o", "/gpt-4o", "openai/", ""] {
+            assert!(ModelRef::parse(invalid).is_none(), "{:?}", invalid);
+        }
+    }
+
     #[test]
     fn test_session_info_serialization_roundtrip() {
         let session = SessionInfo {